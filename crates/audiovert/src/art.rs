@@ -0,0 +1,243 @@
+use core::fmt;
+use core::str::FromStr;
+
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{Context, Error, Result, anyhow};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat};
+use lofty::picture::{MimeType, Picture, PictureType};
+use lofty::tag::Tag;
+
+/// The quality used when encoding artwork as JPEG.
+const JPEG_QUALITY: u8 = 90;
+
+/// The names of images next to a source which are used as its cover with
+/// `--cover-from-folder`, in order of preference.
+const COVER_NAMES: [&str; 3] = ["cover", "folder", "front"];
+
+/// The format to store embedded artwork in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ArtFormat {
+    /// Baseline JPEG.
+    Jpeg,
+    /// PNG.
+    Png,
+}
+
+impl ArtFormat {
+    fn ffmpeg_codec(&self) -> &'static str {
+        match self {
+            ArtFormat::Jpeg => "mjpeg",
+            ArtFormat::Png => "png",
+        }
+    }
+}
+
+impl FromStr for ArtFormat {
+    type Err = Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "jpeg" | "jpg" => Ok(ArtFormat::Jpeg),
+            "png" => Ok(ArtFormat::Png),
+            _ => Err(anyhow!("Invalid artwork format '{s}'")),
+        }
+    }
+}
+
+impl fmt::Display for ArtFormat {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArtFormat::Jpeg => write!(f, "jpeg"),
+            ArtFormat::Png => write!(f, "png"),
+        }
+    }
+}
+
+/// How artwork is transformed when it is embedded in converted files.
+pub(crate) struct Art {
+    /// The maximum width or height of artwork.
+    pub(crate) max_size: Option<u32>,
+    /// The format to store artwork in.
+    pub(crate) format: Option<ArtFormat>,
+}
+
+impl Art {
+    /// Add arguments to an ffmpeg conversion which transform the artwork it
+    /// copies from the source.
+    pub(crate) fn ffmpeg_args(&self, command: &mut Command) {
+        if let Some(max) = self.max_size {
+            command.arg("-vf");
+            command.arg(format!(
+                "scale='min({max},iw)':'min({max},ih)':force_original_aspect_ratio=decrease"
+            ));
+        }
+
+        if let Some(format) = self.format {
+            command.args(["-c:v", format.ffmpeg_codec()]);
+        }
+    }
+
+    /// Transform the artwork stored in a tag.
+    pub(crate) fn apply(&self, tag: &mut Tag) -> Result<()> {
+        if self.max_size.is_none() && self.format.is_none() {
+            return Ok(());
+        }
+
+        for index in 0..tag.pictures().len() {
+            if let Some(picture) = self.transform(&tag.pictures()[index])? {
+                tag.set_picture(index, picture);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Transform a single picture, returning `None` if it can be kept as-is.
+    fn transform(&self, picture: &Picture) -> Result<Option<Picture>> {
+        let data = picture.data();
+
+        let current = match picture.mime_type() {
+            Some(MimeType::Jpeg) => Some(ArtFormat::Jpeg),
+            Some(MimeType::Png) => Some(ArtFormat::Png),
+            _ => None,
+        };
+
+        let format = self.format.or(current).unwrap_or(ArtFormat::Jpeg);
+
+        let image = image::load_from_memory(data).context("Decoding artwork")?;
+
+        let oversized = self
+            .max_size
+            .is_some_and(|max| image.width() > max || image.height() > max);
+
+        let reformat = match self.format {
+            Some(ArtFormat::Jpeg) => current != Some(ArtFormat::Jpeg) || is_progressive_jpeg(data),
+            Some(format) => current != Some(format),
+            None => false,
+        };
+
+        if !oversized && !reformat {
+            return Ok(None);
+        }
+
+        let image = match self.max_size {
+            Some(max) if oversized => image.resize(max, max, FilterType::Lanczos3),
+            _ => image,
+        };
+
+        let (mime_type, data) = encode(&image, format)?;
+
+        Ok(Some(Picture::new_unchecked(
+            picture.pic_type(),
+            Some(mime_type),
+            picture.description().map(str::to_owned),
+            data,
+        )))
+    }
+}
+
+/// Find the cover in a directory, which is an image named like `cover.jpg` or
+/// `folder.png`.
+pub(crate) fn folder_cover(dir: &Path) -> Result<Option<Picture>> {
+    let mut found = None::<(usize, MimeType, _)>;
+
+    let entries = fs::read_dir(dir).with_context(|| anyhow!("Failed to read {}", dir.display()))?;
+
+    for e in entries {
+        let path = e?.path();
+
+        let (Some(stem), Some(ext)) = (
+            path.file_stem().and_then(|s| s.to_str()),
+            path.extension().and_then(|s| s.to_str()),
+        ) else {
+            continue;
+        };
+
+        let mime_type = match ext.to_ascii_lowercase().as_str() {
+            "jpg" | "jpeg" => MimeType::Jpeg,
+            "png" => MimeType::Png,
+            _ => continue,
+        };
+
+        let Some(rank) = COVER_NAMES
+            .iter()
+            .position(|name| stem.eq_ignore_ascii_case(name))
+        else {
+            continue;
+        };
+
+        // Ties are broken by path, since directories are read in no
+        // particular order.
+        if found
+            .as_ref()
+            .is_none_or(|(r, _, p)| (rank, &path) < (*r, p))
+        {
+            found = Some((rank, mime_type, path));
+        }
+    }
+
+    let Some((_, mime_type, path)) = found else {
+        return Ok(None);
+    };
+
+    let data = fs::read(&path).with_context(|| anyhow!("Failed to read {}", path.display()))?;
+
+    Ok(Some(Picture::new_unchecked(
+        PictureType::CoverFront,
+        Some(mime_type),
+        None,
+        data,
+    )))
+}
+
+fn encode(image: &DynamicImage, format: ArtFormat) -> Result<(MimeType, Vec<u8>)> {
+    let mut out = Vec::new();
+
+    match format {
+        ArtFormat::Jpeg => {
+            // The encoder only produces baseline images.
+            JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY)
+                .encode_image(&image.to_rgb8())
+                .context("Encoding artwork as jpeg")?;
+            Ok((MimeType::Jpeg, out))
+        }
+        ArtFormat::Png => {
+            image
+                .write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
+                .context("Encoding artwork as png")?;
+            Ok((MimeType::Png, out))
+        }
+    }
+}
+
+/// Test if JPEG data is encoded progressively, which is signalled by the
+/// marker of the frame it contains.
+fn is_progressive_jpeg(data: &[u8]) -> bool {
+    let mut rest = data.get(2..).unwrap_or_default();
+
+    while let [0xff, marker, hi, lo, tail @ ..] = rest {
+        match marker {
+            // Progressive frames, with Huffman or arithmetic coding.
+            0xc2 | 0xc6 | 0xca | 0xce => return true,
+            // Huffman tables and arithmetic coding conditioning share the
+            // range of frame markers.
+            0xc4 | 0xc8 | 0xcc => {}
+            // Other frames, or the start of the scan.
+            0xc0..=0xcf | 0xda => return false,
+            _ => {}
+        }
+
+        let len = usize::from(u16::from_be_bytes([*hi, *lo]));
+        rest = tail.get(len.saturating_sub(2)..).unwrap_or_default();
+    }
+
+    false
+}
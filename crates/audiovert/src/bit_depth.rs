@@ -0,0 +1,44 @@
+use core::fmt;
+use core::str::FromStr;
+
+use anyhow::{Error, Result, anyhow};
+
+/// The number of bits per sample converted files are reduced to with
+/// `--max-bit-depth`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BitDepth {
+    /// 16 bits per sample, which is dithered.
+    Sixteen,
+    /// 24 bits per sample.
+    TwentyFour,
+}
+
+impl BitDepth {
+    /// The number of bits per sample.
+    pub(crate) fn bits(&self) -> u32 {
+        match self {
+            BitDepth::Sixteen => 16,
+            BitDepth::TwentyFour => 24,
+        }
+    }
+}
+
+impl FromStr for BitDepth {
+    type Err = Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "16" => Ok(BitDepth::Sixteen),
+            "24" => Ok(BitDepth::TwentyFour),
+            _ => Err(anyhow!("Invalid bit depth '{s}', expected 16 or 24")),
+        }
+    }
+}
+
+impl fmt::Display for BitDepth {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.bits().fmt(f)
+    }
+}
@@ -2,14 +2,16 @@ use std::collections::HashMap;
 
 use crate::format::Format;
 
-const DEFAULT_BITRATES: [(Format, u32); 3] = [
+const DEFAULT_BITRATES: [(Format, u32); 4] = [
     (Format::Aac, Format::DEFAULT_BITRATE_AAC),
+    (Format::M4a, Format::DEFAULT_BITRATE_AAC),
     (Format::Mp3, Format::DEFAULT_BITRATE_MP3),
     (Format::Ogg, Format::DEFAULT_BITRATE_OGG),
 ];
 
 pub(crate) struct Bitrates {
     map: HashMap<Format, u32>,
+    quality: HashMap<Format, f32>,
 }
 
 impl Bitrates {
@@ -18,6 +20,24 @@ impl Bitrates {
         Some(*self.map.get(format)?)
     }
 
+    /// Get the quality level for variable bitrate encoding of a format, which
+    /// is used instead of its bitrate if set.
+    #[inline]
+    pub(crate) fn quality(&self, format: &Format) -> Option<f32> {
+        Some(*self.quality.get(format)?)
+    }
+
+    #[inline]
+    pub(crate) fn set_quality(&mut self, format: Format, quality: f32) {
+        self.quality.insert(format, quality);
+    }
+
+    /// Iterate over formats which have a bitrate.
+    #[inline]
+    pub(crate) fn formats(&self) -> impl Iterator<Item = Format> + '_ {
+        self.map.keys().copied()
+    }
+
     #[inline]
     pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = (Format, &mut u32)> + '_ {
         self.map.iter_mut().map(|(f, v)| (*f, v))
@@ -29,6 +49,7 @@ impl Default for Bitrates {
     fn default() -> Self {
         Self {
             map: HashMap::from(DEFAULT_BITRATES),
+            quality: HashMap::new(),
         }
     }
 }
@@ -0,0 +1,249 @@
+use core::fmt::{self, Write};
+use core::str::FromStr;
+use core::time::Duration;
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Error, Result, anyhow};
+
+use crate::format::Format;
+
+/// The format of a sidecar file which chapters are written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ChaptersFormat {
+    /// A cue sheet with one track per chapter.
+    Cue,
+    /// A text file with one line per chapter, prefixed with its start time.
+    Txt,
+}
+
+impl ChaptersFormat {
+    /// The path of the sidecar file of the given destination.
+    pub(crate) fn sidecar_path(&self, to_path: &Path) -> PathBuf {
+        match self {
+            ChaptersFormat::Cue => to_path.with_extension("cue"),
+            ChaptersFormat::Txt => to_path.with_extension("chapters.txt"),
+        }
+    }
+
+    /// Render chapters of the file `name` in the given format.
+    pub(crate) fn render(&self, chapters: &[Chapter], name: &str, format: Format) -> String {
+        let mut out = String::new();
+
+        match self {
+            ChaptersFormat::Cue => {
+                let file_type = match format {
+                    Format::Mp3 => "MP3",
+                    _ => "WAVE",
+                };
+
+                _ = writeln!(out, "FILE \"{}\" {file_type}", cue_text(name));
+
+                for (n, chapter) in chapters.iter().enumerate() {
+                    let n = n + 1;
+                    let millis = chapter.start.as_millis();
+                    let frames = millis % 1000 * 75 / 1000;
+                    let seconds = millis / 1000 % 60;
+                    let minutes = millis / 60000;
+
+                    _ = writeln!(out, "  TRACK {n:02} AUDIO");
+                    _ = writeln!(out, "    TITLE \"{}\"", cue_text(&chapter.title(n)));
+                    _ = writeln!(out, "    INDEX 01 {minutes:02}:{seconds:02}:{frames:02}");
+                }
+            }
+            ChaptersFormat::Txt => {
+                for (n, chapter) in chapters.iter().enumerate() {
+                    let millis = chapter.start.as_millis();
+
+                    _ = writeln!(
+                        out,
+                        "{:02}:{:02}:{:02}.{:03} {}",
+                        millis / 3_600_000,
+                        millis / 60000 % 60,
+                        millis / 1000 % 60,
+                        millis % 1000,
+                        chapter.title(n + 1)
+                    );
+                }
+            }
+        }
+
+        out
+    }
+}
+
+impl FromStr for ChaptersFormat {
+    type Err = Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "cue" => Ok(ChaptersFormat::Cue),
+            "txt" => Ok(ChaptersFormat::Txt),
+            _ => Err(anyhow!("Invalid chapters format '{s}'")),
+        }
+    }
+}
+
+impl fmt::Display for ChaptersFormat {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChaptersFormat::Cue => write!(f, "cue"),
+            ChaptersFormat::Txt => write!(f, "txt"),
+        }
+    }
+}
+
+/// A chapter of a file.
+pub(crate) struct Chapter {
+    start: Duration,
+    title: Option<String>,
+}
+
+impl Chapter {
+    fn title(&self, n: usize) -> String {
+        match &self.title {
+            Some(title) => title.clone(),
+            None => format!("Chapter {n}"),
+        }
+    }
+}
+
+/// Parse the chapters out of metadata written by ffmpeg in the `ffmetadata`
+/// format.
+pub(crate) fn parse(input: &str) -> Vec<Chapter> {
+    struct Partial {
+        num: u64,
+        den: u64,
+        start: u64,
+        title: Option<String>,
+    }
+
+    let mut chapters = Vec::new();
+    let mut current = None::<Partial>;
+
+    for line in lines(input) {
+        if line.starts_with([';', '#']) {
+            continue;
+        }
+
+        if line.starts_with('[') {
+            chapters.extend(current.take());
+
+            if line == "[CHAPTER]" {
+                current = Some(Partial {
+                    num: 1,
+                    den: 1000,
+                    start: 0,
+                    title: None,
+                });
+            }
+
+            continue;
+        }
+
+        let Some(chapter) = &mut current else {
+            continue;
+        };
+
+        let Some((key, value)) = split_key(&line) else {
+            continue;
+        };
+
+        match key.as_str() {
+            "TIMEBASE" => {
+                if let Some((num, den)) = value.split_once('/')
+                    && let (Ok(num), Ok(den)) = (num.parse(), den.parse())
+                    && den > 0
+                {
+                    chapter.num = num;
+                    chapter.den = den;
+                }
+            }
+            "START" => {
+                chapter.start = value.parse().unwrap_or_default();
+            }
+            "title" => {
+                // Neither sidecar format can represent titles spanning
+                // multiple lines.
+                let title = value.replace(['\r', '\n'], " ");
+                let title = title.trim();
+                chapter.title = (!title.is_empty()).then(|| title.to_owned());
+            }
+            _ => {}
+        }
+    }
+
+    chapters.extend(current);
+
+    chapters
+        .into_iter()
+        .map(|c| Chapter {
+            start: Duration::from_millis(
+                c.start.saturating_mul(c.num).saturating_mul(1000) / c.den,
+            ),
+            title: c.title,
+        })
+        .collect()
+}
+
+/// Split input into lines, where a newline escaped with a backslash is part of
+/// the line.
+fn lines(input: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                line.push(c);
+                line.extend(chars.next());
+            }
+            '\n' => {
+                lines.push(line.trim_end_matches('\r').to_owned());
+                line.clear();
+            }
+            c => line.push(c),
+        }
+    }
+
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Split a line into an unescaped key and value at the first unescaped `=`.
+fn split_key(line: &str) -> Option<(String, String)> {
+    let mut key = String::new();
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => key.extend(chars.next()),
+            '=' => {
+                let mut value = String::new();
+
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => value.extend(chars.next()),
+                        c => value.push(c),
+                    }
+                }
+
+                return Some((key, value));
+            }
+            c => key.push(c),
+        }
+    }
+
+    None
+}
+
+/// Cue sheets have no way of escaping quotes, so they're replaced.
+fn cue_text(s: &str) -> String {
+    s.replace('"', "'")
+}
@@ -0,0 +1,418 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use anyhow::{Context, Result, anyhow, bail};
+use mediavert_core::archive::Archive;
+use mediavert_core::effects::Effects;
+use mediavert_core::link::{Link, MaybeLink};
+use relative_path::RelativePathBuf;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Config, Source, SourceArchive};
+use crate::cue::Split;
+use crate::format::Format;
+use crate::meta::Meta;
+use crate::tasks::{PathError, Task, TaskKind, Tasks, TransferKind};
+
+/// The version of the saved plan.
+const VERSION: u32 = 1;
+
+/// A saved plan.
+#[derive(Serialize, Deserialize)]
+struct Plan {
+    version: u32,
+    tasks: Vec<PlannedTask>,
+}
+
+/// A task in a saved plan.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct PlannedTask {
+    source: PlannedSource,
+    to: PathBuf,
+    kind: PlannedKind,
+    completed: bool,
+    /// Whether an existing destination has to be replaced, like with `--force`
+    /// or `--sync`, in which case it existing doesn't mean that the task has
+    /// completed.
+    #[serde(default)]
+    replace: bool,
+    /// When the source was last modified as the task was planned. Completed
+    /// tasks are redone if their source has been modified since.
+    modified: Option<SystemTime>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum PlannedSource {
+    File(PathBuf),
+    Archive { archive: PathBuf, path: String },
+}
+
+impl PlannedSource {
+    /// The path on disk the source is read from.
+    fn path(&self) -> &Path {
+        match self {
+            PlannedSource::File(file) => file,
+            PlannedSource::Archive { archive, .. } => archive,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum PlannedKind {
+    Convert {
+        from: String,
+        to: String,
+        bitrate: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        split: Option<Split>,
+    },
+    External {
+        ext: String,
+    },
+    Transfer {
+        kind: String,
+    },
+}
+
+/// A checkpoint of a run, which records the tasks that have been completed.
+///
+/// The plan is stored at the checkpoint path, and the indices of completed
+/// tasks are appended to a file next to it with a `.done` extension.
+pub(crate) struct Checkpoint {
+    effects: Effects,
+    path: PathBuf,
+    done_path: PathBuf,
+    /// Where completed tasks are appended, which is `None` in a dry run.
+    done: Option<File>,
+    /// The index in the saved plan of each loaded task.
+    indexes: Vec<usize>,
+}
+
+impl Checkpoint {
+    /// Record that the task at the given position has completed.
+    pub(crate) fn done(&mut self, n: usize) -> Result<()> {
+        let (Some(index), Some(done)) = (self.indexes.get(n), &mut self.done) else {
+            return Ok(());
+        };
+
+        writeln!(done, "{index}")?;
+        done.flush()?;
+        Ok(())
+    }
+
+    /// Remove the checkpoint, since the run it belongs to has completed.
+    pub(crate) fn remove(self) -> Result<()> {
+        self.effects
+            .remove_file(&self.path)
+            .with_context(|| anyhow!("Failed to remove {}", self.path.display()))?;
+        self.effects
+            .remove_file(&self.done_path)
+            .with_context(|| anyhow!("Failed to remove {}", self.done_path.display()))?;
+        Ok(())
+    }
+}
+
+/// Save the planned tasks to a new checkpoint.
+pub(crate) fn save(path: &Path, config: &Config, tasks: &Tasks) -> Result<Checkpoint> {
+    let mut plan = Plan {
+        version: VERSION,
+        tasks: Vec::with_capacity(tasks.tasks.len()),
+    };
+
+    for c in &tasks.tasks {
+        let source = match &c.source {
+            Source::File { file } => PlannedSource::File(tasks.db.file(*file)?.to_path_buf()),
+            Source::Archive { archive, path } => PlannedSource::Archive {
+                archive: tasks.db.archive(*archive)?.path.to_path_buf(),
+                path: path.to_string(),
+            },
+        };
+
+        let kind = match &c.kind {
+            TaskKind::Convert {
+                from,
+                to,
+                bitrate,
+                split,
+                ..
+            } => PlannedKind::Convert {
+                from: from.to_string(),
+                to: to.to_string(),
+                bitrate: *bitrate,
+                split: split.as_deref().cloned(),
+            },
+            TaskKind::External { external, .. } => PlannedKind::External {
+                ext: external.ext.clone(),
+            },
+            TaskKind::Transfer { kind } => PlannedKind::Transfer {
+                kind: kind.symbolic_command().to_owned(),
+            },
+        };
+
+        let modified = modified(source.path());
+        let replace = c.pre_remove.iter().any(|(_, path)| **path == *c.to_path);
+
+        plan.tasks.push(PlannedTask {
+            source,
+            to: c.to_path.to_path_buf(),
+            kind,
+            completed: c.is_completed(),
+            replace,
+            modified,
+        });
+    }
+
+    let part = path.with_added_extension("part");
+    let contents = serde_json::to_vec(&plan)?;
+
+    config
+        .effects
+        .write(&part, contents)
+        .with_context(|| anyhow!("Failed to write {}", part.display()))?;
+    config
+        .effects
+        .rename(&part, path)
+        .with_context(|| anyhow!("Failed to write {}", path.display()))?;
+
+    let done_path = path.with_added_extension("done");
+
+    let done = config
+        .effects
+        .create(&done_path)
+        .with_context(|| anyhow!("Failed to create {}", done_path.display()))?;
+
+    Ok(Checkpoint {
+        effects: config.effects,
+        path: path.to_owned(),
+        done_path,
+        done: Some(done),
+        indexes: (0..tasks.tasks.len()).collect(),
+    })
+}
+
+/// Load the tasks of a checkpoint.
+///
+/// Tasks which have been recorded as completed are loaded as completed and
+/// their destinations are not checked again, unless their source has been
+/// modified since the checkpoint was saved. They are kept so that their
+/// sources are still trashed with `--trash-source`.
+pub(crate) fn load(path: &Path, config: &Config, tasks: &mut Tasks) -> Result<Checkpoint> {
+    let contents = fs::read(path).with_context(|| anyhow!("Failed to read {}", path.display()))?;
+
+    let plan: Plan = serde_json::from_slice(&contents)
+        .with_context(|| anyhow!("{}: Invalid checkpoint", path.display()))?;
+
+    if plan.version != VERSION {
+        bail!(
+            "{}: Unsupported checkpoint version {}",
+            path.display(),
+            plan.version
+        );
+    }
+
+    let done_path = path.with_added_extension("done");
+    let mut done = HashSet::new();
+
+    if done_path.exists() {
+        let file = File::open(&done_path)
+            .with_context(|| anyhow!("Failed to open {}", done_path.display()))?;
+
+        for line in BufReader::new(file).lines() {
+            // A partially written last line is ignored.
+            if let Ok(index) = line?.trim().parse::<usize>() {
+                done.insert(index);
+            }
+        }
+    }
+
+    let mut archives = HashMap::new();
+    let mut indexes = Vec::new();
+
+    for (index, planned) in plan.tasks.into_iter().enumerate() {
+        // Sources which are missing are assumed to be unchanged, since moving
+        // them is how some tasks complete.
+        let changed = match (planned.modified, modified(planned.source.path())) {
+            (Some(then), Some(now)) => then != now,
+            _ => false,
+        };
+
+        let is_done = (planned.completed || done.contains(&index)) && !changed;
+
+        // There's nothing left to do for a completed task whose source is
+        // gone, like when it was moved.
+        if is_done && !planned.source.path().exists() {
+            continue;
+        }
+
+        let source = match planned.source {
+            PlannedSource::File(file) => {
+                let file = Link::new(&file)
+                    .with_context(|| anyhow!("{}: Source is missing", file.display()))?;
+
+                Source::File {
+                    file: tasks.db.push_file(file),
+                }
+            }
+            PlannedSource::Archive { archive, path } => {
+                let id = match archives.get(&archive) {
+                    Some(id) => *id,
+                    None => {
+                        let Some(kind) = archive
+                            .extension()
+                            .and_then(|e| e.to_str())
+                            .and_then(Archive::from_ext)
+                        else {
+                            bail!("{}: Unsupported archive", archive.display());
+                        };
+
+                        let link = Link::new(&archive)
+                            .with_context(|| anyhow!("{}: Source is missing", archive.display()))?;
+
+                        let id = tasks.db.push_archive(SourceArchive { kind, path: link });
+                        archives.insert(archive, id);
+                        id
+                    }
+                };
+
+                Source::Archive {
+                    archive: id,
+                    path: RelativePathBuf::from(path),
+                }
+            }
+        };
+
+        let to_path = MaybeLink::new(planned.to);
+
+        // A destination which exists was moved into place before the run
+        // stopped, but after its completion could be recorded. Unless it's
+        // the one which was planned to be replaced.
+        let mut completed = is_done || (!planned.replace && to_path.exists());
+        let part_path = MaybeLink::new(to_path.with_added_extension(&config.part_ext));
+        let mut pre_remove = Vec::new();
+
+        if !completed && planned.replace && to_path.exists() {
+            pre_remove.push(("destination to replace", to_path.clone()));
+        }
+
+        // Like when planning, an existing destination is only replaced with
+        // --force or --sync.
+        if changed && completed && (config.force || config.sync) {
+            pre_remove.push(("destination of modified source", to_path.clone()));
+            completed = false;
+        }
+
+        if !completed && part_path.exists() {
+            pre_remove.push(("partial conversion file", part_path.clone()));
+        }
+
+        let kind = match planned.kind {
+            PlannedKind::Convert {
+                from,
+                to,
+                bitrate,
+                split,
+            } => {
+                let (Ok(from), Ok(to)) = (from.parse::<Format>(), to.parse::<Format>()) else {
+                    bail!("{}: Unsupported conversion {from} to {to}", path.display());
+                };
+
+                if !completed
+                    && let Some(chapters_part) = config.chapters_part(&to_path)
+                    && chapters_part.exists()
+                {
+                    pre_remove.push(("partial chapters file", chapters_part));
+                }
+
+                if !completed && from.has_tags() {
+                    match Meta::read(&source, &tasks.db) {
+                        Ok(meta) => {
+                            tasks.db.insert_meta(source.clone(), meta);
+                        }
+                        Err(e) => {
+                            tasks.errors.push(PathError {
+                                source: source.clone(),
+                                messages: vec![e.to_string()],
+                            });
+                        }
+                    }
+                }
+
+                TaskKind::Convert {
+                    part_path,
+                    from,
+                    to,
+                    bitrate,
+                    converted: completed,
+                    tagged: false,
+                    split: split.map(Arc::new),
+                }
+            }
+            PlannedKind::External { ext } => {
+                let Some(external) = config.externals.get(&ext) else {
+                    bail!("{}: No plugin is configured for {ext}", path.display());
+                };
+
+                TaskKind::External {
+                    part_path,
+                    external: external.clone(),
+                    converted: completed,
+                }
+            }
+            PlannedKind::Transfer { kind } => TaskKind::Transfer {
+                kind: match kind.as_str() {
+                    "cp" => TransferKind::Copy,
+                    "ln" => TransferKind::Link,
+                    "mv" => TransferKind::Move,
+                    _ => bail!("{}: Unsupported transfer {kind}", path.display()),
+                },
+            },
+        };
+
+        tasks.tasks.push(Task {
+            index: tasks.tasks.len(),
+            kind,
+            source,
+            to_path,
+            moved: completed,
+            pre_remove,
+            after: Vec::new(),
+            gain: None,
+            replaygain: None,
+        });
+
+        indexes.push(index);
+    }
+
+    tasks.link();
+
+    let done = if config.dry_run {
+        None
+    } else {
+        let done = config
+            .effects
+            .append(&done_path)
+            .with_context(|| anyhow!("Failed to open {}", done_path.display()))?;
+
+        Some(done)
+    };
+
+    Ok(Checkpoint {
+        effects: config.effects,
+        path: path.to_owned(),
+        done_path,
+        done,
+        indexes,
+    })
+}
+
+/// Get when the file at the given path was last modified.
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
@@ -1,44 +1,144 @@
 use core::cell::Cell;
+use core::num::NonZeroUsize;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::ffi::{OsStr, OsString};
 use std::fs;
-use std::io::Write;
-use std::path::PathBuf;
-use std::process::{Command, ExitStatus, Stdio};
-
-use anyhow::{self, Context, Result, bail};
-use clap::Parser;
-use relative_path::RelativePath;
-use termcolor::{ColorChoice, StandardStream};
-
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow, bail};
+use clap::{ArgAction, CommandFactory, Parser};
+use mediavert_core::cancel::Cancel;
+use mediavert_core::collate::Collation;
+use mediavert_core::config::{self, ColorMode};
+use mediavert_core::control::Control;
+use mediavert_core::effects::Effects;
+use mediavert_core::events::{Event, Events, OutputFormat};
+use mediavert_core::link::MaybeLink;
+use mediavert_core::log::Verbosity;
+use mediavert_core::notify::{self, NotifyFormat};
+use mediavert_core::out::{Colors, Elapsed, Out, blank, error, info, warn};
+use mediavert_core::plugin;
+use mediavert_core::remote::{self, Spool};
+use mediavert_core::report::{Report, ReportTarget};
+use mediavert_core::shell::{self, FormatCommand};
+use mediavert_core::temp::{self, RunDir};
+use mediavert_core::trash;
+use relative_path::{RelativePath, RelativePathBuf};
+use termcolor::{Buffer, NoColor, StandardStream, WriteColor};
+use tracing::{debug, info_span};
+
+use crate::art::{self, Art, ArtFormat};
+use crate::bit_depth::BitDepth;
 use crate::bitrates::Bitrates;
+use crate::chapters::{self, ChaptersFormat};
+use crate::checkpoint::{self, Checkpoint};
 use crate::condition::{Condition, FromCondition, ToCondition};
-use crate::config::{ArchiveId, Config, Db, Source};
+use crate::config::{ArchiveId, Config, Db, External, Source};
+use crate::dedup::Dedup;
+use crate::disc::DiscTemplate;
+use crate::encoder::Encoder;
+use crate::ext_override::ExtOverride;
 use crate::format::Format;
-use crate::link::MaybeLink;
-use crate::out::{Colors, Out, blank, error, info, warn};
+use crate::input_args::InputArgs;
+use crate::interactive::{App, State, Styles};
+use crate::meta::{self, Meta};
+use crate::meta_pattern::MetaPattern;
+use crate::meta_template::MetaTemplate;
+use crate::musicbrainz::MusicBrainz;
+use crate::normalize::{self, Gain, Loudness, Normalize};
+use crate::output_arg::OutputArg;
+use crate::plan;
+use crate::pool;
+use crate::prune::{self, PruneEmpty};
+use crate::replaygain::{self, ReplayGain};
+use crate::report::{self, Outcome};
 use crate::set_bit_rate::SetBitRate;
-use crate::shell::{self, FormatCommand};
+use crate::set_quality::SetQuality;
+use crate::settings::Settings;
+use crate::sidecar::Sidecar;
+use crate::space::{self, LowSpace};
+use crate::stats;
+use crate::stream::{self, Stream};
+use crate::summary::Summary;
+use crate::sync::SyncTarget;
 use crate::tasks::{
-    Exists, MatchingConversion, TaskKind, Tasks, TransferKind, Trash, TrashWhat, Unsupported,
+    Collision, Exists, MatchingConversion, Priority, Task, TaskKind, Tasks, TransferKind, Trash,
+    TrashWhat, Unsupported,
 };
+use crate::timeout::Timeout;
+use crate::verify;
+use crate::warning::Warning;
+use crate::watch::Watch;
 
 const PART: &str = "part";
+/// The delay before the first retry of a failed command, which doubles with
+/// each attempt.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+/// The number of times the retry delay doubles at most, which caps it at
+/// about a minute.
+const RETRY_MAX_DOUBLINGS: u32 = 6;
+
+/// Extended documentation of the conversion grammar included in `--help` and
+/// the man page.
+const AFTER_LONG_HELP: &str = "\
+Conversions:
+  A conversion passed to --conversion has one of the following forms:
+    same         Keep every file in its current format.
+    <to>         Convert every file to <to>.
+    <from>=<to>  Convert files matching <from> to <to>.
+
+  <from> is either lossless, lossy, or an exact format.
+
+  <to> is either an exact format, or same to keep the format of the file.
+
+  An exact <to> format can be followed by @<kbps> to set the bitrate of the
+  conversion, which takes precedence over --bitrates. Like flac=ogg@128 and
+  flac=mp3@320 together produce both a small and a large copy of each file.
+
+  The supported formats are aac, alac, flac, m4a, mp3, ogg, and wav, where
+  alac, flac, and wav are lossless. Both m4a (AAC) and alac are stored in .m4a
+  files. Files in ac3, dts, and mp2 can be converted from, but not to.
+
+Bitrates:
+  A bitrate passed to --bitrates has the form <from>=<kbps>, where <from> is
+  the same as for conversions, like mp3=256 or lossy=192. A bitrate of 0 uses
+  the default bitrate of the format.
+
+  A quality passed to --quality has the form <from>=<quality>, like mp3=V0 or
+  ogg=6, and encodes with a variable bitrate instead.";
 
 /// A tool to perform batch conversion of audio.
 #[derive(Parser)]
+#[command(after_long_help = AFTER_LONG_HELP)]
 pub struct Audiovert {
     /// If set, forces overwriting of existing files if a source file exists and
     /// the destination file also exists.
     #[arg(short = 'f', long)]
     force: bool,
-    /// If set, enables verbose output.
-    #[arg(short = 'v', long)]
-    verbose: bool,
+    /// If set, destination files which already exist are only produced again
+    /// if their source has been modified since, or if a copied or linked
+    /// source differs in size. This keeps the destination a one-way mirror of
+    /// the sources across repeated runs.
+    #[arg(long, conflicts_with = "force")]
+    sync: bool,
+    /// Increase the verbosity of the output, can be specified multiple times.
+    ///
+    /// With `-v` matching conversions and existing files are shown, `-vv` adds
+    /// full command lines and how paths were resolved, and `-vvv` adds traces
+    /// of tag probing and archive enumeration.
+    #[arg(short = 'v', long, action = ArgAction::Count)]
+    verbose: u8,
     /// Removed files will be moved to this location instead of being
     /// deleted [default: ~/trash].
+    ///
+    /// This overrides the `trash` setting in the configuration file.
     #[arg(long)]
     trash: Option<PathBuf>,
     /// If set, source files are trashed after successful conversion.
@@ -58,16 +158,85 @@ pub struct Audiovert {
     /// multiple target files will be produced.
     #[arg(short = 'c', long)]
     conversion: Vec<Condition>,
+    /// Carry files next to the converted ones, whose names match the given
+    /// pattern, along to the same directory in the destination, like `*.cue`
+    /// or `cover.jpg`.
+    ///
+    /// A `*` matches any number of characters and a `?` exactly one, without
+    /// regard to case. They're transferred the same way as files which don't
+    /// need to be converted.
+    #[arg(long, value_name = "GLOB", requires = "to")]
+    copy_sidecar: Vec<Sidecar>,
     /// If set, performs a dry run without making any changes. This also implies
     /// verbose.
     #[arg(short = 'D', long)]
     dry_run: bool,
+    /// If set, performs a dry run where any attempt to modify the filesystem
+    /// is refused with an error. This implies `--dry-run`.
+    ///
+    /// Temporary files, reports, events and exported plans are still written.
+    #[arg(long)]
+    read_only: bool,
+    /// Sync converted and transferred files along with the directory they're
+    /// in to disk once they're complete, so that a power loss right after
+    /// can't leave an empty file behind which later runs would skip.
+    #[arg(long)]
+    durable: bool,
+    /// Replace outputs which are byte-identical to an earlier output of the
+    /// run with hard links to it, like when duplicate sources or multiple
+    /// conversions produce the same file in different places.
+    #[arg(long)]
+    dedup_outputs: bool,
+    /// Verify converted files before they are renamed into place by decoding
+    /// them in full, checking that they decode without errors and that their
+    /// duration matches the source. Files which fail are removed and their
+    /// tasks are marked as failed.
+    #[arg(long)]
+    verify: bool,
     /// If set, continues processing files even if errors are encountered.
     #[arg(short = 'k', long)]
     keep_going: bool,
+    /// Retry conversions which fail up to this many times, waiting a second
+    /// before the first retry and twice as long before each one after it.
+    /// This helps with network mounts which occasionally make ffmpeg fail
+    /// midway.
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    retries: u32,
+    /// Kill conversions which run for longer than this, like `10m` or
+    /// `1h30m`, and mark their tasks as failed. This keeps a source which
+    /// makes ffmpeg hang from stalling the rest of the run.
+    #[arg(long, value_name = "DURATION")]
+    task_timeout: Option<Timeout>,
+    /// Refuse to start a run which would overwrite, trash, or move away more
+    /// than the given number of files.
+    #[arg(long, value_name = "N")]
+    max_removals: Option<usize>,
+    /// Ask for confirmation before starting a run which would overwrite, trash,
+    /// or move away more than the given number of files. This is only done when
+    /// running in a terminal.
+    #[arg(long, value_name = "N", default_value_t = 100)]
+    confirm_above: usize,
+    /// Don't ask for confirmation before starting a run.
+    #[arg(short = 'y', long)]
+    yes: bool,
+    /// Check the free space on the target before each task, and stop or pause
+    /// the run according to `--on-low-space` when less than the given number of
+    /// megabytes is free.
+    #[arg(long, value_name = "MB")]
+    min_free_space: Option<f64>,
+    /// What to do when free space on the target drops below `--min-free-space`.
+    /// Either `stop` to stop the run cleanly so that it can be resumed, or
+    /// `pause` to wait until space has been freed.
+    #[arg(long, value_name = "POLICY", default_value_t)]
+    on_low_space: LowSpace,
     /// Output base directory for converted files.
     #[arg(short = 'o', long)]
     to: Option<PathBuf>,
+    /// Convert a single source file to this path instead of deriving the name
+    /// of the output. The format is taken from the extension of the path if it
+    /// is a known one, otherwise the conversion rules decide it.
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["to", "meta"])]
+    output: Option<PathBuf>,
     /// If set, uses metadata to determine the output path. This can only makes
     /// sense when used with `--to`. If all the required metadata are missing,
     /// the file will be ignored.
@@ -75,8 +244,54 @@ pub struct Audiovert {
     /// The output path will be:
     ///
     /// {Artist} / {Album} ({Year}) / {Artist} - {Track Number} - {Title}.{ext}
+    ///
+    /// Each disc of a multi-disc album is put in a folder of its own, which is
+    /// named according to `--disc-template`.
     #[arg(long)]
     meta: bool,
+    /// The name of the folder which holds each disc of a multi-disc album with
+    /// `--meta`. This may use the placeholders `{disc}`, `{total}`, and
+    /// `{media_type}`, like `CD{disc}`.
+    ///
+    /// If the template contains `{track}` discs are not put in folders, and the
+    /// template is used in place of the track number in the file name instead,
+    /// like `{disc}-{track}`.
+    ///
+    /// Defaults to `{media_type} {disc}`.
+    #[arg(long, value_name = "TEMPLATE")]
+    disc_template: Option<DiscTemplate>,
+    /// The smallest number of discs an album has to have for them to be put in
+    /// folders of their own with `--meta`. Discs of albums with fewer discs are
+    /// kept in the same folder, with the disc number prefixed to the track
+    /// number in file names. Use 3 to avoid disc folders for double albums.
+    #[arg(long, value_name = "N", default_value_t = 2)]
+    min_discs: u32,
+    /// The path of files organized with `--meta`, relative to the directory
+    /// they're written to and without extension, like
+    /// `{artist}/{album} ({year})/[CD{disc}/]{track:02} - {title}`.
+    ///
+    /// Placeholders are `{artist}`, `{album}`, `{year}`, `{track}`, `{title}`,
+    /// `{disc}`, `{total}`, and `{media_type}`, where numbers can be padded
+    /// with zeros like `{track:02}`. Segments in brackets are left out unless
+    /// all of their placeholders have a value, where `{disc}` and `{total}`
+    /// only have one for albums with at least `--min-discs` discs.
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        requires = "meta",
+        conflicts_with = "disc_template"
+    )]
+    meta_template: Option<MetaTemplate>,
+    /// Fill in tags which are missing for `--meta` by matching the path of the
+    /// source against a pattern, like `{artist}/{album} ({year})/{track} -
+    /// {title}`.
+    ///
+    /// The pattern is matched against the trailing components of the path,
+    /// with the extension of the file removed. It takes the same placeholders
+    /// as `--meta-template`, which match as little text as possible. Tags
+    /// always take precedence over values taken from the path.
+    #[arg(long, value_name = "PATTERN", requires = "meta")]
+    meta_from_path: Option<MetaPattern>,
     /// If set, dumps metadata for each file processed with `--meta`.
     #[arg(long)]
     meta_dump: bool,
@@ -87,7 +302,29 @@ pub struct Audiovert {
     /// errors.
     #[arg(long)]
     meta_dump_error: bool,
+    /// Look up files which are missing tags for `--meta` on MusicBrainz.
+    ///
+    /// Files are searched for by the tags they have if their title is known,
+    /// otherwise by their AcoustID fingerprint if `--acoustid-key` is set.
+    /// Requests are rate limited, and their results cached in the user's
+    /// cache directory.
+    #[arg(long, requires = "meta")]
+    musicbrainz: bool,
+    /// The AcoustID API key used to look up files by fingerprint with
+    /// `--musicbrainz`.
+    ///
+    /// This can also be set with `acoustid-key` in the `[audiovert]` section
+    /// of the configuration file.
+    #[arg(long, value_name = "KEY", requires = "musicbrainz")]
+    acoustid_key: Option<String>,
+    /// Path to fpcalc binary to use when fingerprinting files for
+    /// `--acoustid-key`.
+    #[arg(long, default_value = "fpcalc")]
+    fpcalc_bin: PathBuf,
     /// If set, moves files instead of creating hard links when transferring.
+    ///
+    /// A file is only moved once every other task using it as a source has
+    /// completed.
     #[arg(long)]
     r#move: bool,
     /// Bitrates to use when performing conversions. This has the format
@@ -97,9 +334,113 @@ pub struct Audiovert {
     /// Default bitrates are 320kbps for mp3 and 192kbps for ogg and aac.
     #[arg(long)]
     bitrates: Vec<SetBitRate>,
-    /// If set, forces re-encoding of the formats specified in --bitrates.
+    /// Use variable bitrate encoding at the given quality instead of a
+    /// constant bitrate. This has the format <format>=<quality>, like mp3=V0
+    /// or ogg=6.
+    ///
+    /// The quality ranges from V0 (best) to V9 for mp3, -1 to 10 (best) for
+    /// ogg, and 0.1 to 2 (best) for aac. It takes precedence over --bitrates,
+    /// but not over the bitrate of a conversion like flac=mp3@320.
+    #[arg(long)]
+    quality: Vec<SetQuality>,
+    /// If set, forces re-encoding of the formats specified in --bitrates or
+    /// --quality.
     #[arg(long)]
     force_bitrates: bool,
+    /// Override the extension of files produced in a format, like ogg=oga or
+    /// aac=m4b. This only affects the name of the produced file and not the
+    /// format it is written in.
+    #[arg(long, value_name = "FORMAT=EXT")]
+    ext_override: Vec<ExtOverride>,
+    /// Arguments passed to ffmpeg before the input when converting sources in
+    /// a format, like wav="-ignore_length 1" for files with broken headers.
+    /// Arguments are separated by whitespace, and arguments given for the same
+    /// format multiple times are combined.
+    #[arg(long, value_name = "FORMAT=ARGS")]
+    input_args: Vec<InputArgs>,
+    /// The ffmpeg encoder used to produce files in a format, like
+    /// mp3=libmp3lame or aac=libfdk_aac.
+    #[arg(long, value_name = "FORMAT=ENCODER")]
+    encoder: Vec<Encoder>,
+    /// An argument passed to ffmpeg before the output when producing files in
+    /// a format, like flac=-compression_level. Each argument is passed as-is,
+    /// so an option and its value are given separately, in order:
+    /// --ffmpeg-arg flac=-compression_level --ffmpeg-arg flac=12.
+    #[arg(long, value_name = "FORMAT=ARG", allow_hyphen_values = true)]
+    ffmpeg_arg: Vec<OutputArg>,
+    /// What to do when multiple sources would produce the same file, like two
+    /// files with identical tags or a file which is both inside of an archive
+    /// and extracted next to it. Either `error` to report it, `skip` to only
+    /// produce it from the first source, or `rename` to add a number to the
+    /// name of the file, like `Song (2).mp3`.
+    #[arg(long, value_name = "POLICY", default_value_t)]
+    on_collision: Collision,
+    /// Downscale embedded artwork which is wider or taller than the given
+    /// number of pixels when converting, keeping its aspect ratio.
+    #[arg(long, value_name = "PIXELS", visible_alias = "max-cover-size")]
+    art_max_size: Option<u32>,
+    /// Store embedded artwork in the given format when converting, either
+    /// `jpeg` or `png`. Artwork stored as jpeg is always baseline, since some
+    /// players refuse to display progressive images.
+    #[arg(long, value_name = "FORMAT", visible_alias = "cover-format")]
+    art_format: Option<ArtFormat>,
+    /// Embed an image next to the source, like `cover.jpg` or `folder.png`,
+    /// in converted files when the source has no artwork of its own.
+    #[arg(long)]
+    cover_from_folder: bool,
+    /// Write the chapters of converted files to a sidecar file next to them,
+    /// either `cue` for a cue sheet like `Book.cue` or `txt` for a list of
+    /// start times like `Book.chapters.txt`. This is useful for players which
+    /// ignore chapters embedded in mp3 and ogg files, or for formats which
+    /// can't store them.
+    ///
+    /// Chapters are always copied into converted files which can store them.
+    #[arg(long, value_name = "FORMAT")]
+    chapters_sidecar: Option<ChaptersFormat>,
+    /// The audio stream to convert from sources which have more than one, like
+    /// alternate languages or commentary. This is either the index of the
+    /// audio stream counting from 0, or a language like `eng`.
+    ///
+    /// If unset, the first audio stream is converted with a warning when
+    /// there are others.
+    #[arg(long, value_name = "INDEX|LANG")]
+    stream: Option<Stream>,
+    /// Downsample converted files from sources with a higher sample rate, like
+    /// `48000` for hi-res sources converted to mp3.
+    #[arg(long, value_name = "HZ")]
+    max_sample_rate: Option<u32>,
+    /// Reduce the number of bits per sample of converted files from sources
+    /// with more, either `16` or `24`. Reducing to 16 bits is dithered.
+    #[arg(long, value_name = "BITS")]
+    max_bit_depth: Option<BitDepth>,
+    /// Split lossless files which have a cue sheet next to them, like
+    /// `album.cue` for `album.flac`, into one file per track.
+    ///
+    /// Tracks are tagged from the cue sheet, and named like `01 - Title`
+    /// unless `--meta` is used.
+    #[arg(long, conflicts_with = "output")]
+    split_cue: bool,
+    /// Normalize the loudness of converted files, as `ebur128` or
+    /// `ebur128:TARGET` where the target is the integrated loudness in LUFS,
+    /// like `ebur128:-16`. Defaults to -23 LUFS as recommended by EBU R128.
+    ///
+    /// Sources are analyzed before they are converted, which means that each
+    /// of them is decoded twice.
+    #[arg(long, value_name = "METHOD[:TARGET]")]
+    normalize: Option<Normalize>,
+    /// Normalize albums as a whole with `--normalize`, so that their tracks
+    /// keep their loudness relative to each other. An album is the files
+    /// converted into the same directory.
+    #[arg(long, requires = "normalize")]
+    normalize_album: bool,
+    /// Write ReplayGain tags to converted files, either `track` for the gain
+    /// of each file or `album` to also write the gain of the album it belongs
+    /// to. An album is the files converted into the same directory.
+    ///
+    /// Sources are analyzed before they are converted, which means that each
+    /// of them is decoded twice.
+    #[arg(long, value_name = "MODE", conflicts_with = "normalize")]
+    replaygain: Option<ReplayGain>,
     /// Path to ffmpeg binary to use when performing conversions.
     #[arg(long, default_value = "ffmpeg")]
     ffmpeg_bin: PathBuf,
@@ -112,14 +453,211 @@ pub struct Audiovert {
     /// removed.
     #[arg(long, default_value = PART)]
     part_ext: String,
-    /// Paths to process.
+    /// Send a summary to this URL with a POST request once the run has
+    /// finished, which is useful for unattended runs.
+    #[arg(long, value_name = "URL")]
+    notify_url: Option<String>,
+    /// The payload to send to `--notify-url`. Either `json` for the summary as
+    /// JSON, or `ntfy`, `discord`, or `matrix` for a message understood by
+    /// the corresponding service.
+    #[arg(long, value_name = "FORMAT", default_value_t = NotifyFormat::Json)]
+    notify_format: NotifyFormat,
+    /// Run this shell command once the run has finished, with a summary of the
+    /// run as JSON written to its stdin.
+    #[arg(long, value_name = "COMMAND")]
+    notify_cmd: Option<String>,
+    /// Write a stream of JSON events describing the progress of the run to
+    /// this path, one per line. This can be a named pipe or a file descriptor
+    /// like `/dev/fd/3`.
+    #[arg(long, value_name = "PATH")]
+    events: Option<PathBuf>,
+    /// How the progress of the run is written to stdout. Either `human` for
+    /// colored output, or `json` for the events of `--events` one per line
+    /// instead, so that the outcome can be fed into scripts.
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        default_value_t,
+        conflicts_with_all = ["events", "stats", "clean_temp"]
+    )]
+    output_format: OutputFormat,
+    /// Export the planned tasks to this path as CSV, with one row per task,
+    /// before running them. Combine with `--dry-run` to review a large
+    /// migration in a spreadsheet before performing it.
+    #[arg(long, value_name = "PATH")]
+    export_plan: Option<PathBuf>,
+    /// Only scan the sources and report the composition of the library, like
+    /// the number and size of files in each format, bitrate, and top-level
+    /// directory, without planning or running anything.
+    #[arg(long)]
+    stats: bool,
+    /// Save the plan to this path before running it and record tasks as they
+    /// complete, so that an interrupted run can be continued with `--resume`.
+    /// The checkpoint is removed once every task has completed.
+    #[arg(long, value_name = "PATH")]
+    checkpoint: Option<PathBuf>,
+    /// Continue the run saved with `--checkpoint` without planning it again.
+    #[arg(long, requires = "checkpoint")]
+    resume: bool,
+    /// Review the planned tasks in an interactive interface before running
+    /// them, grouped by the directory they're written to. Individual tasks or
+    /// whole albums can be deselected, and the format of an album can be
+    /// changed.
+    #[arg(
+        short = 'i',
+        long,
+        conflicts_with_all = ["checkpoint", "watch", "stats", "output_format"]
+    )]
+    interactive: bool,
+    /// Keep running after converting the sources, and convert files which are
+    /// added to the source paths as they appear.
+    ///
+    /// New files are converted once they have stopped growing for
+    /// `--watch-settle` seconds, so that files which are still being copied
+    /// aren't picked up early.
+    #[arg(long, conflicts_with_all = ["output", "stats", "checkpoint", "dry_run"])]
+    watch: bool,
+    /// How many seconds new files have to stop growing for before they are
+    /// converted with `--watch`.
+    #[arg(long, value_name = "SECONDS", default_value_t = 5)]
+    watch_settle: u64,
+    /// The order unsupported files, existing files and errors are listed in,
+    /// either `natural` which ignores case and accents and compares numbers by
+    /// value, or `bytewise`.
+    #[arg(long, value_name = "ORDER", default_value_t)]
+    sort_order: Collation,
+    /// Don't print warnings in the given category, like `unsupported` for
+    /// files with an unsupported extension such as images in album folders.
+    /// Suppressed warnings are still counted at the end of the run. Either
+    /// `unsupported`, `already-exists`, `multiple-streams`, or `probe-failed`.
+    #[arg(long, value_name = "CODE")]
+    suppress: Vec<Warning>,
+    /// The order to run tasks in. Either `planned` for the order they were
+    /// planned in, `smallest-first` or `largest-first` by the size of their
+    /// source, or `lossless-first` to start conversions from lossless formats
+    /// first.
+    ///
+    /// Tasks still run after the tasks they depend on.
+    #[arg(long, value_name = "ORDER", default_value_t)]
+    order: Priority,
+    /// The number of conversions to run in parallel, and threads to plan
+    /// with. Defaults to the `jobs` setting.
+    ///
+    /// Without either, planning uses as many threads as there are available
+    /// CPUs while conversions are run one at a time.
+    ///
+    /// The output of each conversion is written once it has finished, so that
+    /// the output of different conversions isn't interleaved.
+    #[arg(short = 'j', long, value_name = "N")]
+    jobs: Option<NonZeroUsize>,
+    /// Remove directories left empty once sources have been trashed or moved,
+    /// walking upwards from each source but never past the path it was found
+    /// in. Either a number of directory levels to remove at most, or `all`.
+    #[arg(long, value_name = "DEPTH|all")]
+    prune_empty: Option<PruneEmpty>,
+    /// Trash files in the `--to` directory which don't correspond to any
+    /// source once the run has finished, along with directories left empty,
+    /// so that the destination stays an exact mirror of the converted sources.
+    /// Hidden files are left alone.
+    ///
+    /// This can't be combined with `--trash-source` or `--move`, since the
+    /// outputs of sources which are gone would be pruned by the next run.
+    #[arg(long, requires = "to", conflicts_with_all = ["trash_source", "move"])]
+    prune: bool,
+    /// Write a self-contained report of the planned or performed run, like
+    /// `html:report.html` or `text:report.txt`, with a table of tasks per album
+    /// and the outcome of each along with any warnings and errors.
+    #[arg(long, value_name = "FORMAT:PATH")]
+    report: Option<ReportTarget>,
+    /// Synchronize each album to this target once all of its files have been
+    /// converted into the `--to` directory. This is either an rsync target
+    /// like `phone:/sdcard/Music`, or an rclone remote prefixed with
+    /// `rclone:` like `rclone:drive:Music`.
+    #[arg(long, value_name = "TARGET")]
+    sync_to: Option<SyncTarget>,
+    /// Listen for control commands on a unix-domain socket at this path while
+    /// running, through which a client can query progress, pause after the
+    /// current task, resume, or stop the run gracefully.
+    #[arg(long, value_name = "PATH")]
+    control: Option<PathBuf>,
+    /// Path to the configuration file to use instead of
+    /// `mediavert/config.toml` in the user's configuration directory.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Directory to store temporary files in, such as downloaded sources.
+    ///
+    /// Each run uses its own directory inside of it which is removed when the
+    /// run finishes. This overrides the `tmp-dir` setting in the configuration
+    /// file.
+    #[arg(long, value_name = "DIR")]
+    tmp_dir: Option<PathBuf>,
+    /// Remove temporary directories left behind by earlier runs which are no
+    /// longer running, and exit.
+    #[arg(long)]
+    clean_temp: bool,
+    /// When to use colored output, either `auto`, `always`, or `never`.
+    ///
+    /// This overrides the `color` setting in the configuration file.
+    #[arg(long, value_name = "WHEN")]
+    color: Option<ColorMode>,
+    /// Prefix every line of output with the wall-clock time, and report how
+    /// long each task took when it completes.
+    #[arg(long)]
+    timestamps: bool,
+    /// Write a man page for audiovert to stdout and exit.
+    #[arg(long, hide = true)]
+    generate_man: bool,
+    /// Paths to process, or URLs which are downloaded before being processed.
     paths: Vec<PathBuf>,
 }
 
+impl Audiovert {
+    /// The verbosity requested through the `-v` flags.
+    #[inline]
+    pub fn verbosity(&self) -> Verbosity {
+        Verbosity::new(self.verbose)
+    }
+}
+
 /// Entry for `audiovert`.
 ///
-/// See [`crate`] documentation.
+/// The first Ctrl-C stops the run after the current task and the second aborts
+/// it immediately. See [`crate`] documentation.
 pub fn entry(opts: &Audiovert) -> Result<()> {
+    if opts.generate_man {
+        let man = clap_mangen::Man::new(Audiovert::command());
+        man.render(&mut io::stdout())?;
+        return Ok(());
+    }
+
+    entry_with(opts, Cancel::ctrl_c()?)
+}
+
+/// Entry for `audiovert` which is stopped through the given cancellation
+/// token instead of Ctrl-C.
+pub fn entry_with(opts: &Audiovert, cancel: Cancel) -> Result<()> {
+    let settings = config::load::<Settings>(opts.config.as_deref())?;
+    let shared = settings.shared();
+
+    let mut externals = HashMap::new();
+
+    for (ext, plugin) in settings.audiovert.plugins {
+        let Ok(to) = plugin.to.parse::<Format>() else {
+            bail!(
+                "Unsupported format '{}' produced by plugin for '{ext}'",
+                plugin.to
+            );
+        };
+
+        let external = External {
+            ext: ext.clone(),
+            to,
+            plugin: plugin.plugin,
+        };
+
+        externals.insert(ext, Arc::new(external));
+    }
+
     // Current indentation level for output.
     let indent = Cell::new(0);
 
@@ -147,47 +685,164 @@ pub fn entry(opts: &Audiovert) -> Result<()> {
         }
     }
 
-    let trash = match &opts.trash {
-        Some(p) => p.clone(),
-        None => 'trash: {
-            let mut trash = env::home_dir().context("Get home directory")?;
+    for quality in &opts.quality {
+        let mut formats = bitrates
+            .formats()
+            .filter(|&format| quality.from.matches(format))
+            .collect::<Vec<_>>();
 
-            for d in ["trash", "Trash"] {
-                trash.push(d);
+        formats.sort();
 
-                if trash.is_dir() {
-                    break 'trash trash;
-                }
+        if formats.is_empty() {
+            bail!("Cannot set quality for: {}", quality.from);
+        }
+
+        for format in formats {
+            let Some(level) = format.parse_quality(&quality.quality) else {
+                bail!("Invalid quality '{}' for format: {format}", quality.quality);
+            };
 
-                trash.pop();
+            if opts.force_bitrates {
+                forced_bitrates.insert(format);
             }
 
-            trash.push("trash");
-            trash
+            bitrates.set_quality(format, level);
         }
+    }
+
+    let encoders = opts
+        .encoder
+        .iter()
+        .map(|e| (e.format, e.encoder.clone()))
+        .collect::<HashMap<_, _>>();
+
+    // Arguments passed before the output, by target format.
+    let mut output_args = HashMap::<_, Vec<_>>::new();
+
+    for a in &opts.ffmpeg_arg {
+        output_args.entry(a.format).or_default().push(a.arg.clone());
+    }
+
+    // Arguments passed before the input, by source format.
+    let mut input_args = HashMap::<_, Vec<_>>::new();
+
+    for a in &opts.input_args {
+        input_args
+            .entry(a.format)
+            .or_default()
+            .extend(a.args.iter().cloned());
+    }
+
+    let musicbrainz = if opts.musicbrainz {
+        let acoustid_key = opts
+            .acoustid_key
+            .clone()
+            .or(settings.audiovert.acoustid_key);
+
+        Some(MusicBrainz::new(
+            opts.fpcalc_bin.clone(),
+            acoustid_key,
+            cancel.clone(),
+            opts.dry_run || opts.read_only,
+        )?)
+    } else {
+        None
+    };
+
+    let trash = match opts.trash.clone().or(shared.trash) {
+        Some(p) => p,
+        None => trash::default_dir()?,
     };
 
     let mut config = Config {
+        art: Art {
+            max_size: opts.art_max_size,
+            format: opts.art_format,
+        },
         bitrates,
+        cancel,
+        chapters_sidecar: opts.chapters_sidecar,
+        checkpoint: opts.checkpoint.clone(),
+        collision: opts.on_collision,
+        confirm_above: opts.confirm_above,
         conversion: opts.conversion.clone(),
-        dry_run: opts.dry_run,
+        copy_sidecars: opts.copy_sidecar.clone(),
+        cover_from_folder: opts.cover_from_folder,
+        dedup_outputs: opts.dedup_outputs,
+        disc_template: opts.disc_template.clone().unwrap_or_default(),
+        dry_run: opts.dry_run || opts.read_only,
+        effects: Effects::new(opts.read_only).with_durable(opts.durable),
+        encoders,
+        export_plan: opts.export_plan.clone(),
+        ext_overrides: opts
+            .ext_override
+            .iter()
+            .map(|o| (o.format, o.ext.clone()))
+            .collect(),
+        externals,
         ffmpeg: opts.ffmpeg_bin.clone(),
         force: opts.force,
         forced_bitrates,
+        input_args,
+        interactive: opts.interactive,
+        jobs: opts
+            .jobs
+            .or(shared.jobs)
+            .or_else(|| thread::available_parallelism().ok())
+            .unwrap_or(NonZeroUsize::MIN),
         keep_going: opts.keep_going,
+        max_bit_depth: opts.max_bit_depth,
+        max_removals: opts.max_removals,
+        max_sample_rate: opts.max_sample_rate,
         meta_dump_error: opts.meta_dump_error,
         meta_dump: opts.meta_dump,
         meta_internal: opts.meta_internal,
         meta: opts.meta,
+        meta_from_path: opts.meta_from_path.clone(),
+        meta_template: opts.meta_template.clone(),
+        min_discs: opts.min_discs,
+        min_free_space: opts.min_free_space.map(|mb| (mb * 1024.0 * 1024.0) as u64),
+        musicbrainz,
+        normalize: opts.normalize,
+        normalize_album: opts.normalize_album,
+        on_low_space: opts.on_low_space,
+        order: opts.order,
+        output: opts.output.clone(),
+        output_args,
+        output_format: opts.output_format,
         part_ext: opts.part_ext.clone(),
         paths: opts.paths.clone(),
+        prune: opts.prune,
+        prune_empty: opts.prune_empty,
         r#move: opts.r#move,
+        replaygain: opts.replaygain,
+        report: opts.report.clone(),
+        resume: opts.resume,
+        retries: opts.retries,
+        sort_order: opts.sort_order,
+        split_cue: opts.split_cue,
+        stream: opts.stream.clone(),
+        suppress: opts.suppress.iter().copied().collect(),
+        sync: opts.sync,
+        sync_to: opts.sync_to.clone(),
+        task_timeout: opts.task_timeout,
         to_dir: opts.to.clone(),
         trash_source: opts.trash_source,
         trash,
-        verbose: opts.verbose,
+        verbosity: opts.verbosity(),
+        verify: opts.verify,
+        workers: opts.jobs.or(shared.jobs).unwrap_or(NonZeroUsize::MIN),
+        yes: opts.yes,
     };
 
+    if config.sync_to.is_some() && config.to_dir.is_none() {
+        bail!("--sync-to requires --to");
+    }
+
+    if config.output.is_some() && !matches!(config.paths.as_slice(), [path] if path.is_file()) {
+        bail!("--output requires a single source file");
+    }
+
     if config.paths.is_empty() {
         config.paths.push(PathBuf::from("."));
     }
@@ -196,35 +851,262 @@ pub fn entry(opts: &Audiovert) -> Result<()> {
         config.conversion.push(Condition::FromTo {
             from: FromCondition::Lossless,
             to: ToCondition::Exact(Format::Mp3),
+            bitrate: None,
         });
 
         config.conversion.push(Condition::FromTo {
             from: FromCondition::Lossy,
             to: ToCondition::Same,
+            bitrate: None,
         });
     }
 
     let cols = Colors::new();
 
-    let o = StandardStream::stdout(ColorChoice::Auto);
-    let mut o = o.lock();
-    let mut o = Out::new(&indent, &cols, &mut o);
-    run(&mut o, &config)
+    let color = opts.color.or(shared.color).unwrap_or_default();
+
+    let stdout = StandardStream::stdout(color.choice());
+
+    // Human output is discarded when stdout is used for events.
+    let mut o: Box<dyn WriteColor> = match opts.output_format {
+        OutputFormat::Human => Box::new(stdout.lock()),
+        OutputFormat::Json => Box::new(NoColor::new(io::sink())),
+    };
+
+    let mut o = Out::new(&indent, &cols, &mut *o).with_timestamps(opts.timestamps);
+
+    let tmp_dir = opts
+        .tmp_dir
+        .clone()
+        .or(shared.tmp_dir)
+        .unwrap_or_else(env::temp_dir);
+
+    if opts.clean_temp {
+        for path in temp::clean(&tmp_dir)? {
+            info!(o, "Removed {}", shell::path(&path));
+        }
+
+        return Ok(());
+    }
+
+    let run_dir = RunDir::new(&tmp_dir, "audiovert");
+    let mut spool = Spool::new(&run_dir, shared.remote, config.cancel.clone())?;
+
+    for path in &mut config.paths {
+        let Some(url) = path.to_str().filter(|p| remote::is_url(p)) else {
+            continue;
+        };
+
+        if config.to_dir.is_none() {
+            bail!("Converting {url} requires --to");
+        }
+
+        info!(o, "Fetching {url}");
+        let url = url.to_owned();
+        *path = spool.fetch(&url)?;
+    }
+
+    if opts.stats {
+        return stats::run(&mut o, &config);
+    }
+
+    let mut events = match opts.output_format {
+        OutputFormat::Human => Events::open(opts.events.as_deref())?,
+        OutputFormat::Json => Events::stdout(),
+    };
+    let control = Control::bind(opts.control.as_deref())?;
+
+    if !opts.watch {
+        return run_once(opts, &mut o, &config, &run_dir, &mut events, &control);
+    }
+
+    // The watcher is started before the first run, so that files which are
+    // added while it's running aren't missed.
+    let watch = Watch::new(&config, Duration::from_secs(opts.watch_settle))?;
+
+    loop {
+        if let Err(e) = run_once(opts, &mut o, &config, &run_dir, &mut events, &control) {
+            error!(o, "{e:#}");
+        }
+
+        if config.cancel.is_cancelled() {
+            return Ok(());
+        }
+
+        info!(o, "Watching for new files (Ctrl-C to stop)");
+
+        if !watch.wait(&config.cancel)? {
+            return Ok(());
+        }
+    }
+}
+
+/// Run conversions once, and report the outcome.
+fn run_once(
+    opts: &Audiovert,
+    o: &mut Out<'_>,
+    config: &Config,
+    run_dir: &RunDir,
+    events: &mut Events,
+    control: &Control,
+) -> Result<()> {
+    let mut summary = Summary {
+        dry_run: config.dry_run,
+        ..Summary::default()
+    };
+
+    control.reset();
+    let result = run(o, config, run_dir, events, control, &mut summary);
+
+    if let Err(e) = &result {
+        let message = format!("{e:#}");
+
+        events.emit(&Event::Error {
+            index: None,
+            path: None,
+            message: &message,
+        })?;
+
+        summary.error = Some(message);
+    }
+
+    events.emit(&Event::Summary {
+        summary: serde_json::to_value(&summary)?,
+    })?;
+
+    if let Err(e) = notify(opts, &summary) {
+        error!(o, "Failed to send notification: {e:#}");
+    }
+
+    result
+}
+
+/// Send notifications of the outcome of a run.
+fn notify(opts: &Audiovert, summary: &Summary) -> Result<()> {
+    if let Some(url) = &opts.notify_url {
+        notify::url(url, opts.notify_format, "audiovert", summary)?;
+    }
+
+    if let Some(cmd) = &opts.notify_cmd {
+        notify::command(cmd, summary)?;
+    }
+
+    Ok(())
 }
 
-fn run(o: &mut Out<'_>, config: &Config) -> Result<()> {
+fn run(
+    o: &mut Out<'_>,
+    config: &Config,
+    run_dir: &RunDir,
+    events: &mut Events,
+    control: &Control,
+    summary: &mut Summary,
+) -> Result<()> {
     let mut tasks = Tasks::new();
 
-    config.populate(&mut tasks)?;
+    // Nothing is converted in read-only mode, so entries are read straight
+    // from their archives instead.
+    if !config.effects.is_read_only() {
+        tasks
+            .db
+            .set_extract_dir(run_dir.allocate("archives")?, config.effects);
+    }
+
+    let checkpoint = match &config.checkpoint {
+        Some(path) if config.resume => {
+            let checkpoint = checkpoint::load(path, config, &mut tasks)?;
+            info!(
+                o,
+                "Resuming {} tasks from {}",
+                tasks.tasks.iter().filter(|c| !c.is_completed()).count(),
+                shell::path(path)
+            );
+            Some(checkpoint)
+        }
+        // A dry run doesn't save a checkpoint, since nothing it plans is
+        // performed.
+        Some(path) if !config.dry_run => {
+            config.populate(&mut tasks)?;
+            Some(checkpoint::save(path, config, &tasks)?)
+        }
+        _ => {
+            config.populate(&mut tasks)?;
+            None
+        }
+    };
+
+    let mut report = config.report.as_ref().map(|_| {
+        if config.dry_run {
+            Report::new("audiovert dry run")
+        } else {
+            Report::new("audiovert run")
+        }
+    });
+
+    sort_listing(&tasks.db, config.sort_order, &mut tasks.unsupported, |u| {
+        &u.source
+    })?;
+    sort_listing(
+        &tasks.db,
+        config.sort_order,
+        &mut tasks.already_exists,
+        |e| &e.source,
+    )?;
+    sort_listing(&tasks.db, config.sort_order, &mut tasks.errors, |e| {
+        &e.source
+    })?;
+
+    summary.unsupported = tasks.unsupported.len();
+    summary.already_exists = tasks.already_exists.len();
+    summary.failed = tasks.errors.len();
 
     for Unsupported { source, ext } in tasks.unsupported.drain(..) {
+        summary.warn(Warning::Unsupported);
+
+        events.emit(&Event::Warning {
+            code: Some(Warning::Unsupported.code()),
+            path: Some(&tasks.db.describe(&source)?),
+            message: &format!("Unsupported extension: {ext}"),
+        })?;
+
+        if config.suppress.contains(&Warning::Unsupported) {
+            continue;
+        }
+
         warn!(o, "Unsupported extension: {ext}");
         let mut o = o.indent(1);
         tasks.db.dump(&mut o, &source)?;
+
+        if let Some(report) = &mut report {
+            let path = tasks.db.describe(&source)?;
+            report.warning(format_args!("{path}: Unsupported extension: {ext}"));
+        }
     }
 
-    if config.verbose {
-        for Exists { source, path } in tasks.already_exists.drain(..) {
+    for Exists { source, path } in tasks.already_exists.drain(..) {
+        summary.warn(Warning::AlreadyExists);
+
+        if events.is_enabled() {
+            events.emit(&Event::Warning {
+                code: Some(Warning::AlreadyExists.code()),
+                path: Some(&tasks.db.describe(&source)?),
+                message: &format!("Already exists: {}", shell::path(&path)),
+            })?;
+        }
+
+        if config.suppress.contains(&Warning::AlreadyExists) {
+            continue;
+        }
+
+        if let Some(report) = &mut report {
+            let source = tasks.db.describe(&source)?;
+            report.warning(format_args!(
+                "{source}: Already exists: {}",
+                shell::path(&path)
+            ));
+        }
+
+        if config.verbosity.is_verbose() {
             warn!(o, "already exists (--force to remove):");
             let mut o = o.indent(1);
             tasks.db.dump(&mut o, &source)?;
@@ -239,10 +1121,21 @@ fn run(o: &mut Out<'_>, config: &Config) -> Result<()> {
 
         for m in &e.messages {
             error!(o, "{m}");
+
+            events.emit(&Event::Error {
+                index: None,
+                path: Some(&tasks.db.describe(&e.source)?),
+                message: m,
+            })?;
+
+            if let Some(report) = &mut report {
+                let path = tasks.db.describe(&e.source)?;
+                report.error(format_args!("{path}: {m}"));
+            }
         }
 
         if config.meta_dump_error
-            && let Some(m) = tasks.meta.get(&e.source)
+            && let Some(m) = tasks.db.meta(&e.source)
         {
             let mut o = o.indent(1);
             m.dump(&mut o)?;
@@ -253,7 +1146,7 @@ fn run(o: &mut Out<'_>, config: &Config) -> Result<()> {
         for task in &tasks.tasks {
             tasks.db.dump(o, &task.source)?;
 
-            if let Some(m) = tasks.meta.get(&task.source) {
+            if let Some(m) = tasks.db.meta(&task.source) {
                 info!(o, "Meta:");
                 let mut o = o.indent(1);
                 m.dump(&mut o)?;
@@ -267,7 +1160,27 @@ fn run(o: &mut Out<'_>, config: &Config) -> Result<()> {
         bail!("Aborting due to previous errors, use --keep-going to ignore.");
     }
 
-    if config.verbose {
+    // The destinations of sources which couldn't be planned are unknown, so
+    // they can't be told apart from orphans.
+    if tasks.errors.is_empty() {
+        let orphans = prune::orphans(config, &tasks)?;
+        tasks.to_trash.extend(orphans);
+    } else if config.prune {
+        warn!(o, "Not pruning destination due to previous errors");
+    }
+
+    if config.interactive && !tasks.tasks.is_empty() {
+        let mut state = State::new(config, &tasks);
+        let mut app = App::new(Styles::new(o.supports_color()));
+
+        if !app.run(&mut state)? {
+            bail!("Aborting due to user cancellation.");
+        }
+
+        state.apply(config, &mut tasks)?;
+    }
+
+    if config.verbosity.is_verbose() {
         for MatchingConversion {
             source,
             from,
@@ -286,219 +1199,278 @@ fn run(o: &mut Out<'_>, config: &Config) -> Result<()> {
         }
     }
 
+    if let Some(path) = &config.export_plan {
+        plan::export_csv(path, config, &tasks)?;
+        info!(o, "Exported plan to {}", shell::path(path));
+    }
+
+    if !config.dry_run {
+        confirm_removals(o, config, &tasks)?;
+    }
+
     let total = tasks.tasks.len();
 
-    for c in &mut tasks.tasks {
-        if c.is_completed() {
-            continue;
+    events.emit(&Event::Plan { tasks: total })?;
+
+    if events.is_enabled() {
+        for c in &tasks.tasks {
+            events.emit(&Event::TaskPlanned {
+                index: c.index,
+                kind: &c.kind.to_string(),
+                from: &tasks.db.describe(&c.source)?,
+                to: &shell::path(&c.to_path).to_string(),
+            })?;
         }
+    }
+    control.set_total(total);
 
-        info!(
-            o,
-            "Task #{}/#{total}: {}",
-            c.index.saturating_add(1),
-            c.kind
-        );
-        let mut o = o.indent(1);
+    // Measuring loudness decodes every source, which is too much for a dry
+    // run.
+    if !config.dry_run && (config.normalize.is_some() || config.replaygain.is_some()) {
+        analyze_loudness(o, config, events, &mut tasks)?;
+    }
 
-        tasks.db.dump(&mut o, &c.source)?;
-        o.link("to", &c.to_path)?;
+    let order = tasks.schedule(config.order)?;
 
-        for (reason, path) in c.pre_remove.drain(..) {
-            info!(o, "removing {reason}");
-            let mut o = o.indent(1);
+    // The album each task belongs to, relative to the destination directory.
+    let albums = tasks
+        .tasks
+        .iter()
+        .map(|c| album(config, c))
+        .collect::<Vec<_>>();
 
-            if config.verbose {
-                blank!(o, "rm {}", shell::path(&path));
-            } else {
-                blank!(o, "rm <to>.{}", config.part_ext);
+    // Tasks which were completed before the run started.
+    let pre_completed = tasks
+        .tasks
+        .iter()
+        .map(|c| c.is_completed())
+        .collect::<Vec<_>>();
+
+    let mut remaining = HashMap::<_, usize>::new();
+
+    for (c, album) in tasks.tasks.iter().zip(&albums) {
+        if let Some(album) = album
+            && !c.is_completed()
+        {
+            *remaining.entry(album.as_path()).or_default() += 1;
+        }
+    }
+
+    let mut finish = Finish {
+        config,
+        control,
+        albums: &albums,
+        remaining,
+        checkpoint,
+        dedup: Dedup::default(),
+        stopped_at: None,
+        total,
+    };
+
+    // Conversions are only run on workers when they do something, since the
+    // output of a dry run should be the same every time.
+    let parallel = config.workers.get() > 1 && !config.dry_run;
+
+    thread::scope(|s| -> Result<()> {
+        let (tx, rx) = mpsc::channel::<Done>();
+
+        // Tasks which are running on workers.
+        let mut running = vec![false; tasks.tasks.len()];
+        let mut workers = 0;
+
+        for (i, &n) in order.iter().enumerate() {
+            // Tasks depending on a conversion which is running on a worker
+            // have to wait for it to finish.
+            while tasks.tasks[n].after.iter().any(|&m| running[m]) {
+                let done = rx.recv()?;
+                running[done.worker.n] = false;
+                workers -= 1;
+                finish.done(o, events, summary, &mut tasks.tasks, done)?;
             }
 
-            if !config.dry_run
-                && let Err(e) = fs::remove_file(&path)
-            {
-                error!(o, "{e}");
+            if finish.stopped_at.is_some() {
+                break;
             }
-        }
 
-        match c.kind {
-            TaskKind::Convert {
-                ref part_path,
-                to,
-                ref mut converted,
-                ref mut tagged,
-                ..
-            } => {
-                if !*converted {
-                    let (argument, archive) = match &c.source {
-                        Source::File { file } => {
-                            let file = tasks.db.file(*file)?;
-                            (file.as_os_str(), None)
-                        }
-                        Source::Archive { archive, path } => {
-                            (OsStr::new("pipe:"), Some((*archive, path)))
-                        }
-                    };
+            let blocked = tasks.tasks[n]
+                .after
+                .iter()
+                .any(|&m| !tasks.tasks[m].is_completed());
 
-                    let mut command = Command::new(&config.ffmpeg);
-                    command.args(["-hide_banner", "-loglevel", "error"]);
-                    command.args([OsStr::new("-i"), argument]);
+            let c = &mut tasks.tasks[n];
 
-                    if !config.meta_internal {
-                        command.args(["-map_metadata", "0"]);
-                    }
+            if c.is_completed() {
+                control.finish_task();
+                continue;
+            }
 
-                    to.bitrate(config, &mut command);
-                    command.args(["-f", to.ffmpeg_format()]);
-                    command.arg(part_path);
+            if config.cancel.is_cancelled() {
+                warn!(
+                    o,
+                    "Stopping since the run was cancelled, run again to resume"
+                );
+                finish.stop(i);
+                break;
+            }
 
-                    let mut f = FormatCommand::new(&command);
+            if !control.checkpoint(&config.cancel) {
+                if config.cancel.is_cancelled() {
+                    warn!(
+                        o,
+                        "Stopping since the run was cancelled, run again to resume"
+                    );
+                } else {
+                    warn!(o, "Stopping as requested through the control socket");
+                }
 
-                    if !config.verbose {
-                        f.replace(config.ffmpeg.as_os_str(), "<ffmpeg>");
+                finish.stop(i);
+                break;
+            }
 
-                        if archive.is_none() {
-                            f.replace(argument, "<from>");
-                        }
+            if !config.dry_run && !space::check(o, config, &c.to_path)? {
+                finish.stop(i);
+                break;
+            }
 
-                        f.replace(part_path.as_os_str(), format!("<to>.{}", config.part_ext));
-                    }
+            control.start_task(format!("{} to {}", c.kind, shell::path(&c.to_path)));
+
+            let start = Instant::now();
+
+            if blocked {
+                warn!(
+                    o,
+                    "Task #{}/#{total}: Skipping {} since a task it depends on didn't complete",
+                    c.index.saturating_add(1),
+                    c.kind
+                );
+            } else if parallel && matches!(c.kind, TaskKind::Convert { .. }) {
+                let worker = Worker {
+                    i,
+                    n,
+                    task: c.clone(),
+                    buffer: o.buffer(),
+                    timestamps: o.timestamps(),
+                    start,
+                };
+
+                let db = &tasks.db;
+                let mut worker_events = events.clone();
+                let tx = tx.clone();
+
+                s.spawn(move || {
+                    _ = tx.send(worker.run(config, db, &mut worker_events, total));
+                });
+
+                running[n] = true;
+                workers += 1;
+
+                // Wait for a worker to be free before starting anything else.
+                if workers == config.workers.get() {
+                    let done = rx.recv()?;
+                    running[done.worker.n] = false;
+                    workers -= 1;
+                    finish.done(o, events, summary, &mut tasks.tasks, done)?;
+                }
 
-                    if !config.make_dir(&mut o, "partial", part_path)? {
-                        continue;
-                    }
+                continue;
+            } else {
+                run_task(o, config, &tasks.db, events, summary, c, total)?;
+            }
 
-                    {
-                        blank!(o, "{f}");
-                        let mut o = o.indent(1);
+            if config.cancel.is_aborted() {
+                remove_part(o, config, c)?;
+                finish.stop(i);
+                break;
+            }
 
-                        if !config.dry_run {
-                            if let Some((archive, path)) = archive {
-                                command.stdin(Stdio::piped());
-
-                                let status = match write_source_to_stdin(
-                                    &mut command,
-                                    &tasks.db,
-                                    archive,
-                                    path,
-                                ) {
-                                    Ok(status) => status,
-                                    Err(e) => {
-                                        error!(o, "{e}");
-                                        continue;
-                                    }
-                                };
-
-                                *converted = status.success();
-                            } else {
-                                let status = match command.status() {
-                                    Ok(s) => s,
-                                    Err(e) => {
-                                        error!(o, "{e}");
-                                        continue;
-                                    }
-                                };
-
-                                *converted = status.success();
-                            }
-                        } else {
-                            *converted = true;
-                        }
+            finish.task(o, events, summary, n, c, start)?;
+        }
 
-                        if !config.meta_internal {
-                            *tagged = true;
-                        }
-                    }
+        while workers > 0 {
+            let done = rx.recv()?;
+            running[done.worker.n] = false;
+            workers -= 1;
+            finish.done(o, events, summary, &mut tasks.tasks, done)?;
+        }
 
-                    if !*tagged {
-                        if let Some(meta) = tasks.meta.get(&c.source) {
-                            blank!(o, "tag <to>.{} ({} tags)", config.part_ext, meta.len());
+        Ok(())
+    })?;
 
-                            if !config.dry_run {
-                                if let Err(e) = meta.tag_file(to, part_path) {
-                                    error!(o, "{e}");
-                                } else {
-                                    *tagged = true;
-                                }
-                            } else {
-                                *tagged = true;
-                            }
-                        } else {
-                            blank!(o, "tag <to>.{} (no tags)", config.part_ext);
-                            *tagged = true;
-                        }
-                    }
+    let stopped_at = finish.stopped_at;
 
-                    if *converted && *tagged && !c.moved {
-                        if !config.make_dir(&mut o, "rename", &c.to_path)? {
-                            continue;
-                        }
+    if let Some(checkpoint) = finish.checkpoint
+        && !config.dry_run
+        && tasks.tasks.iter().all(Task::is_completed)
+    {
+        checkpoint.remove()?;
+    }
 
-                        blank!(o, "mv <to>.{} <to>", config.part_ext);
-                        let mut o = o.indent(1);
+    let mut stopped = vec![false; tasks.tasks.len()];
 
-                        if config.verbose {
-                            o.link("from", part_path)?;
-                            o.link("to", &c.to_path)?;
-                        }
+    if let Some(s) = stopped_at {
+        for &n in &order[s..] {
+            stopped[n] = true;
+        }
+    }
 
-                        if !config.dry_run {
-                            if let Err(e) = fs::rename(part_path, &c.to_path) {
-                                error!(o, "{e}");
-                            } else {
-                                c.moved = true;
-                            }
-                        } else {
-                            c.moved = true;
-                        }
-                    }
-                }
-            }
-            TaskKind::Transfer { kind } => {
-                if !c.moved {
-                    if !config.make_dir(&mut o, kind, &c.to_path)? {
-                        continue;
-                    }
+    let mut outcomes = Vec::with_capacity(tasks.tasks.len());
 
-                    if config.verbose {
-                        tasks.db.dump(&mut o, &c.source)?;
-                        o.link("to", &c.to_path)?;
-                    } else {
-                        blank!(o, "{} <from> <to>", kind.symbolic_command());
-                    }
+    for (n, c) in tasks.tasks.iter().enumerate() {
+        if pre_completed[n] {
+            outcomes.push(Outcome::Exists);
+            continue;
+        }
 
-                    if !config.dry_run {
-                        let result = tasks.db.move_to(&c.source, &c.to_path, kind);
+        if stopped[n] && !c.is_completed() {
+            summary.stopped += 1;
+            outcomes.push(Outcome::Stopped);
+            continue;
+        }
 
-                        if let Err(e) = result {
-                            error!(o, "{e}");
-                        } else {
-                            c.moved = true;
-                        }
-                    } else {
-                        c.moved = true;
-                    }
-                }
-            }
+        match (c.is_completed(), &c.kind) {
+            (false, _) => summary.failed += 1,
+            (true, TaskKind::Convert { .. } | TaskKind::External { .. }) => summary.converted += 1,
+            (true, TaskKind::Transfer { .. }) => summary.transferred += 1,
         }
+
+        outcomes.push(match (c.is_completed(), config.dry_run) {
+            (false, _) => Outcome::Failed,
+            (true, true) => Outcome::Planned,
+            (true, false) => Outcome::Done,
+        });
     }
 
     let mut n = 0u32;
 
-    for c in tasks.tasks.iter().filter(|c| c.is_completed()) {
-        if !config.trash_source {
-            continue;
-        }
+    // Several tasks can share a source, like every track split from it with
+    // --split-cue, so a source is only trashed once all of them have
+    // completed. That includes tasks which weren't run because the run was
+    // stopped. Trashing is meaningless for moved sources.
+    let mut keep = HashSet::new();
 
-        // NB: Trashing is meaningless for moved files.
-        if matches!(
+    for (c, outcome) in tasks.tasks.iter().zip(&outcomes) {
+        let moved = matches!(
             c.kind,
             TaskKind::Transfer {
                 kind: TransferKind::Move
             }
-        ) {
-            continue;
-        }
+        );
+
+        if moved || matches!(outcome, Outcome::Failed | Outcome::Stopped) {
+            keep.insert(&c.source);
+        }
+    }
+
+    let mut trashed = HashSet::new();
+
+    for c in tasks.tasks.iter().filter(|c| c.is_completed()) {
+        if !config.trash_source {
+            continue;
+        }
+
+        if keep.contains(&c.source) || !trashed.insert(&c.source) {
+            continue;
+        }
 
         let path = match &c.source {
             Source::Archive { .. } => continue,
@@ -532,13 +1504,29 @@ fn run(o: &mut Out<'_>, config: &Config) -> Result<()> {
         blank!(o, "path: {}", shell::path(&config.trash));
 
         if !config.dry_run
-            && let Err(e) = fs::create_dir_all(&config.trash)
+            && let Err(e) = config.effects.create_dir_all(&config.trash)
         {
             error!(o, "{e}");
         }
     }
 
-    let mut check_empty = Vec::new();
+    // Sources which are no longer where they were found.
+    let mut removed = Vec::new();
+
+    for c in tasks.tasks.iter().filter(|c| c.is_completed()) {
+        if let (
+            TaskKind::Transfer {
+                kind: TransferKind::Move,
+            },
+            Source::File { file },
+        ) = (&c.kind, &c.source)
+        {
+            removed.push(tasks.db.file(*file)?.to_path_buf());
+        }
+    }
+
+    // Orphaned destinations which have been trashed.
+    let mut orphaned = Vec::new();
 
     // Move files to trash.
     for Trash { what, path, name } in tasks.to_trash.drain(..) {
@@ -550,61 +1538,1405 @@ fn run(o: &mut Out<'_>, config: &Config) -> Result<()> {
         o.link("to", &trash_path)?;
 
         if !config.dry_run
-            && let Err(e) = fs::rename(&path, &trash_path)
+            && let Err(e) = config.effects.rename(&path, &trash_path)
         {
             error!(o, "{e}");
+            continue;
+        }
 
-            if let Some(path) = path.parent() {
-                check_empty.push(path.to_path_buf());
-            }
+        summary.trashed += 1;
+
+        if events.is_enabled() {
+            events.emit(&Event::Trashed {
+                what: &what.to_string(),
+                from: &shell::path(&path).to_string(),
+                to: &shell::path(&trash_path).to_string(),
+            })?;
+        }
+
+        match what {
+            TrashWhat::SourceFile => removed.push(path.to_path_buf()),
+            TrashWhat::Orphan => orphaned.push(path.to_path_buf()),
         }
     }
 
-    // Recursively check for empty directories and remove them.
-    for mut path in check_empty {
-        if !is_empty_dir(&path) {
-            continue;
+    prune::prune(o, config, &removed)?;
+    prune::prune_orphans(o, config, &orphaned)?;
+
+    if !summary.warnings.is_empty() {
+        info!(o, "Warnings:");
+        let mut o = o.indent(1);
+
+        for (warning, count) in &summary.warnings {
+            if config.suppress.contains(warning) {
+                blank!(o, "{warning}: {count} (suppressed)");
+            } else {
+                blank!(o, "{warning}: {count}");
+            }
         }
+    }
 
-        info!(o, "removing empty directory:");
+    if config.cancel.is_cancelled() {
+        info!(o, "Interrupted:");
         let mut o = o.indent(1);
-        blank!(o, "path: {}", shell::path(&path));
+        blank!(o, "completed: {}", summary.converted + summary.transferred);
+        blank!(o, "failed: {}", summary.failed);
+        blank!(o, "remaining: {}", summary.stopped);
+
+        if config.verbosity.is_verbose() {
+            let mut o = o.indent(1);
+
+            for (c, outcome) in tasks.tasks.iter().zip(&outcomes) {
+                if matches!(outcome, Outcome::Stopped) {
+                    blank!(o, "{}", shell::path(&c.to_path));
+                }
+            }
+        }
+    }
+
+    if let (Some(target), Some(mut report)) = (&config.report, report) {
+        report::summary(&mut report, summary);
+        report::tasks(&mut report, config, &tasks, &outcomes)?;
+        report.write(target)?;
+        info!(o, "Wrote report to {}", shell::path(&target.path));
+    }
+
+    if config.cancel.is_aborted() {
+        bail!("Aborted, run again to resume");
+    }
+
+    Ok(())
+}
+
+/// A conversion to run on a worker, with its output collected in a buffer.
+struct Worker {
+    /// Position of the task in the order tasks are run in.
+    i: usize,
+    /// Index of the task.
+    n: usize,
+    task: Task,
+    buffer: Buffer,
+    timestamps: bool,
+    start: Instant,
+}
+
+impl Worker {
+    fn run(mut self, config: &Config, db: &Db, events: &mut Events, total: usize) -> Done {
+        let indent = Cell::new(0);
+        let colors = Colors::new();
+        let mut summary = Summary::default();
+
+        let mut o = Out::new(&indent, &colors, &mut self.buffer).with_timestamps(self.timestamps);
+        let result = run_task(
+            &mut o,
+            config,
+            db,
+            events,
+            &mut summary,
+            &mut self.task,
+            total,
+        );
+        drop(o);
+
+        Done {
+            worker: self,
+            summary,
+            result,
+        }
+    }
+}
+
+/// A conversion which has finished running on a worker.
+struct Done {
+    worker: Worker,
+    summary: Summary,
+    result: Result<()>,
+}
+
+/// Bookkeeping done as tasks finish, which with conversions running on workers
+/// isn't necessarily the order they were started in.
+struct Finish<'a> {
+    config: &'a Config,
+    control: &'a Control,
+    albums: &'a [Option<PathBuf>],
+    /// The number of tasks in each album which have yet to complete.
+    remaining: HashMap<&'a Path, usize>,
+    checkpoint: Option<Checkpoint>,
+    /// Outputs of the run, used to link identical outputs.
+    dedup: Dedup,
+    /// Position in the order of the task the run stopped at.
+    stopped_at: Option<usize>,
+    total: usize,
+}
+
+impl Finish<'_> {
+    /// Stop the run at the given position in the order, or an earlier one if
+    /// it has already been stopped.
+    fn stop(&mut self, i: usize) {
+        self.stopped_at = Some(self.stopped_at.map_or(i, |s| s.min(i)));
+    }
+
+    /// Finish a conversion which was run on a worker, writing its output.
+    fn done(
+        &mut self,
+        o: &mut Out<'_>,
+        events: &mut Events,
+        summary: &mut Summary,
+        tasks: &mut [Task],
+        done: Done,
+    ) -> Result<()> {
+        let Done {
+            worker,
+            summary: worker_summary,
+            result,
+        } = done;
+
+        o.write_buffer(&worker.buffer)?;
+        summary.merge_warnings(&worker_summary);
+        tasks[worker.n] = worker.task;
+        result?;
+
+        let c = &tasks[worker.n];
+
+        if self.config.cancel.is_aborted() {
+            remove_part(o, self.config, c)?;
+            self.stop(worker.i);
+            return Ok(());
+        }
+
+        self.task(o, events, summary, worker.n, c, worker.start)
+    }
+
+    /// Finish a task which has been run.
+    fn task(
+        &mut self,
+        o: &mut Out<'_>,
+        events: &mut Events,
+        summary: &mut Summary,
+        n: usize,
+        c: &Task,
+        start: Instant,
+    ) -> Result<()> {
+        let config = self.config;
+
+        if let Some(checkpoint) = &mut self.checkpoint
+            && !config.dry_run
+            && c.is_completed()
+        {
+            checkpoint.done(n)?;
+        }
+
+        if config.dedup_outputs
+            && !config.dry_run
+            && c.is_completed()
+            && self.dedup.check(o, config, &c.to_path)?
+        {
+            summary.deduplicated += 1;
+        }
+
+        if o.timestamps() {
+            let status = if c.is_completed() { "done" } else { "failed" };
+
+            info!(
+                o,
+                "Task #{}/#{}: {status} in {}",
+                c.index.saturating_add(1),
+                self.total,
+                Elapsed(start.elapsed())
+            );
+        }
+
+        // Tasks of an album might not run next to each other, so it's synced
+        // once the last of them has completed.
+        if let Some(album) = &self.albums[n]
+            && c.is_completed()
+            && let Some(remaining) = self.remaining.get_mut(album.as_path())
+        {
+            *remaining = remaining.saturating_sub(1);
+
+            if *remaining == 0
+                && let Some(sync_to) = &config.sync_to
+            {
+                sync_album(o, config, events, summary, sync_to, n, album)?;
+            }
+        }
+
+        events.emit(&Event::TaskDone {
+            index: c.index,
+            ok: c.is_completed(),
+        })?;
+
+        self.control.finish_task();
+        Ok(())
+    }
+}
+
+/// Get the album a task belongs to, which is the directory of its destination
+/// relative to the destination directory.
+fn album(config: &Config, c: &Task) -> Option<PathBuf> {
+    let to_dir = config.to_dir.as_ref()?;
+    let parent = c.to_path.parent()?;
+    Some(parent.strip_prefix(to_dir).ok()?.to_owned())
+}
+
+/// Measure the loudness of the sources of conversions with `--normalize` or
+/// `--replaygain`, which decides the gain they are converted or tagged with.
+fn analyze_loudness(
+    o: &mut Out<'_>,
+    config: &Config,
+    events: &mut Events,
+    tasks: &mut Tasks,
+) -> Result<()> {
+    let normalize = config.normalize.unwrap_or_default();
+    let by_album = config.normalize_album || config.replaygain == Some(ReplayGain::Album);
+
+    let is_pending = |c: &Task| {
+        matches!(
+            c.kind,
+            TaskKind::Convert {
+                converted: false,
+                ..
+            }
+        )
+    };
+
+    // Every track of an album has to be measured to find its gain, including
+    // the ones which have already been converted.
+    let pending_albums = tasks
+        .tasks
+        .iter()
+        .filter(|c| by_album && is_pending(c))
+        .filter_map(|c| c.to_path.parent())
+        .collect::<HashSet<_>>();
+
+    let analyzed = tasks
+        .tasks
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| {
+            is_pending(c)
+                || matches!(c.kind, TaskKind::Convert { .. })
+                    && c.to_path
+                        .parent()
+                        .is_some_and(|p| pending_albums.contains(p))
+        })
+        .map(|(n, _)| n)
+        .collect::<Vec<_>>();
+
+    if analyzed.is_empty() {
+        return Ok(());
+    }
+
+    info!(o, "Analyzing loudness of {} files", analyzed.len());
+
+    let results = pool::map(config.workers, &analyzed, |&n| {
+        if config.cancel.is_cancelled() {
+            return None;
+        }
+
+        let c = &tasks.tasks[n];
+
+        let TaskKind::Convert { from, split, .. } = &c.kind else {
+            return None;
+        };
+
+        Some(normalize.analyze(config, &tasks.db, &c.source, *from, split.as_deref()))
+    });
+
+    let mut measured = Vec::new();
+
+    for (n, result) in analyzed.into_iter().zip(results) {
+        match result {
+            Some(Ok(loudness)) => {
+                measured.push((n, loudness));
+            }
+            Some(Err(e)) => {
+                let c = &tasks.tasks[n];
+
+                warn!(
+                    o,
+                    "Task #{}: Failed to analyze loudness: {e}",
+                    c.index.saturating_add(1)
+                );
+
+                events.emit(&Event::Error {
+                    index: Some(c.index),
+                    path: None,
+                    message: &e.to_string(),
+                })?;
+            }
+            None => {}
+        }
+    }
+
+    let mut albums = HashMap::new();
 
-        if !config.dry_run {
-            if let Err(e) = fs::remove_dir(&path) {
-                error!(o, "{e}");
+    if by_album {
+        let mut tracks = HashMap::<&Path, Vec<&Loudness>>::new();
+
+        for (n, loudness) in &measured {
+            if let Some(album) = tasks.tasks[*n].to_path.parent() {
+                tracks.entry(album).or_default().push(loudness);
             }
+        }
+
+        for (album, tracks) in tracks {
+            if let Some(loudness) = normalize::album(tracks) {
+                albums.insert(album.to_owned(), loudness);
+            }
+        }
+    }
+
+    for (n, loudness) in measured {
+        let c = &mut tasks.tasks[n];
+        let album = c.to_path.parent().and_then(|p| albums.get(p)).copied();
 
-            path.pop();
+        if let Some(normalize) = &config.normalize {
+            c.gain = match album {
+                Some(album) if config.normalize_album => {
+                    Some(Gain::Album(normalize.album_gain(album)))
+                }
+                _ => Some(Gain::Track(loudness.clone())),
+            };
+        }
+
+        if let Some(mode) = config.replaygain {
+            let album = album.filter(|_| mode == ReplayGain::Album);
+            c.replaygain = Some(replaygain::Values::new(&loudness, album));
+        }
+    }
+
+    Ok(())
+}
+
+/// Files which a run would remove from where they currently are.
+#[derive(Default)]
+struct Removals {
+    overwritten: usize,
+    trashed: usize,
+    moved: usize,
+}
+
+impl Removals {
+    fn count(config: &Config, tasks: &Tasks) -> Self {
+        let mut removals = Removals::default();
+        let mut trashed = HashSet::new();
+
+        for c in &tasks.tasks {
+            if c.pre_remove.iter().any(|(_, path)| **path == *c.to_path) {
+                removals.overwritten += 1;
+            }
+
+            let is_move = matches!(
+                c.kind,
+                TaskKind::Transfer {
+                    kind: TransferKind::Move
+                }
+            );
+
+            if is_move {
+                if !c.is_completed() {
+                    removals.moved += 1;
+                }
+            } else if config.trash_source
+                && let Source::File { file } = c.source
+            {
+                trashed.insert(file);
+            }
+        }
+
+        removals.trashed = trashed.len() + tasks.to_trash.len();
+        removals
+    }
+
+    fn total(&self) -> usize {
+        self.overwritten + self.trashed + self.moved
+    }
+}
+
+/// Check the number of files a run would remove against `--max-removals`, and
+/// ask for confirmation if it exceeds `--confirm-above`.
+fn confirm_removals(o: &mut Out<'_>, config: &Config, tasks: &Tasks) -> Result<()> {
+    let removals = Removals::count(config, tasks);
+    let total = removals.total();
+
+    if total == 0 {
+        return Ok(());
+    }
+
+    let describe = format!(
+        "overwrite {}, trash {}, and move away {} files",
+        removals.overwritten, removals.trashed, removals.moved
+    );
+
+    if let Some(max) = config.max_removals
+        && total > max
+    {
+        bail!("Refusing to {describe}, which is more than --max-removals {max}");
+    }
+
+    if config.yes || total <= config.confirm_above || !io::stdin().is_terminal() {
+        return Ok(());
+    }
+
+    // With JSON output stdout carries events, so the question is asked on
+    // stderr instead.
+    if config.output_format == OutputFormat::Json {
+        eprint!("This run will {describe}. Continue? [y/N] ");
+        io::stderr().flush()?;
+    } else {
+        warn!(o, "This run will {describe}");
+        print!("Continue? [y/N] ");
+        io::stdout().flush()?;
+    }
+
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+
+    if !matches!(line.trim(), "y" | "Y" | "yes") {
+        bail!("Cancelled");
+    }
+
+    Ok(())
+}
+
+/// Synchronize an album to the `--sync-to` target.
+fn sync_album(
+    o: &mut Out<'_>,
+    config: &Config,
+    events: &mut Events,
+    summary: &mut Summary,
+    sync_to: &SyncTarget,
+    index: usize,
+    album: &Path,
+) -> Result<()> {
+    let Some(to_dir) = &config.to_dir else {
+        return Ok(());
+    };
+
+    let dir = to_dir.join(album);
+    let mut command = sync_to.command(&dir, album);
+
+    info!(o, "Syncing album {}", shell::path(&dir));
+    let mut o = o.indent(1);
+
+    events.emit(&Event::TaskProgress {
+        index,
+        step: "sync",
+    })?;
+
+    blank!(o, "{}", FormatCommand::new(&command));
+    debug!(command = %FormatCommand::new(&command), "syncing");
+
+    if config.dry_run {
+        summary.synced += 1;
+        return Ok(());
+    }
+
+    let result = match config.effects.status(&mut command) {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(anyhow!("sync exited with {status}")),
+        Err(e) => Err(anyhow::Error::from(e).context("spawning sync")),
+    };
+
+    if let Err(e) = result {
+        error!(o, "{e:#}");
+        summary.sync_failed += 1;
+
+        events.emit(&Event::Error {
+            index: Some(index),
+            path: Some(&shell::path(&dir).to_string()),
+            message: &format!("{e:#}"),
+        })?;
+    } else {
+        summary.synced += 1;
+    }
+
+    Ok(())
+}
+
+/// Sort a listing by how the source of each item is shown to the user.
+fn sort_listing<T>(
+    db: &Db,
+    collation: Collation,
+    items: &mut Vec<T>,
+    source: impl Fn(&T) -> &Source,
+) -> Result<()> {
+    let mut keyed = items
+        .drain(..)
+        .map(|item| Ok((db.describe(source(&item))?, item)))
+        .collect::<Result<Vec<_>>>()?;
+
+    keyed.sort_by(|a, b| collation.compare(&a.0, &b.0));
+    items.extend(keyed.into_iter().map(|(_, item)| item));
+    Ok(())
+}
+
+/// Run a single task.
+fn run_task(
+    o: &mut Out<'_>,
+    config: &Config,
+    db: &Db,
+    events: &mut Events,
+    summary: &mut Summary,
+    c: &mut Task,
+    total: usize,
+) -> Result<()> {
+    info!(
+        o,
+        "Task #{}/#{total}: {}",
+        c.index.saturating_add(1),
+        c.kind
+    );
+    let mut o = o.indent(1);
+
+    let span = info_span!("task", index = c.index, kind = %c.kind, to = %shell::path(&c.to_path));
+    let _enter = span.enter();
+
+    let index = c.index;
+
+    events.emit(&Event::TaskStart {
+        index,
+        kind: &c.kind.to_string(),
+        from: &db.describe(&c.source)?,
+        to: &shell::path(&c.to_path).to_string(),
+    })?;
+
+    db.dump(&mut o, &c.source)?;
+    o.link("to", &c.to_path)?;
+
+    for (reason, path) in c.pre_remove.drain(..) {
+        info!(o, "removing {reason}");
+        let mut o = o.indent(1);
+
+        events.emit(&Event::TaskProgress {
+            index,
+            step: "remove",
+        })?;
+
+        if config.verbosity.is_detailed() {
+            blank!(o, "rm {}", shell::path(&path));
         } else {
-            continue;
+            blank!(o, "rm <to>.{}", config.part_ext);
+        }
+
+        if !config.dry_run
+            && let Err(e) = config.effects.remove_file(&path)
+        {
+            error!(o, "{e}");
+            events.emit(&Event::Error {
+                index: Some(index),
+                path: None,
+                message: &e.to_string(),
+            })?;
+        }
+    }
+
+    match c.kind {
+        TaskKind::Convert {
+            ref part_path,
+            from,
+            to,
+            bitrate,
+            ref mut converted,
+            ref mut tagged,
+            ref split,
+        } => {
+            if !*converted {
+                let (argument, archive) = match &c.source {
+                    Source::File { file } => {
+                        let file = db.file(*file)?;
+                        (file.as_os_str(), None)
+                    }
+                    Source::Archive { archive, path } => {
+                        (OsStr::new("pipe:"), Some((*archive, path)))
+                    }
+                };
+
+                // Probing runs ffmpeg on the source, so it's only done when
+                // something depends on its streams.
+                let probe = !config.dry_run
+                    && (config.stream.is_some()
+                        || config.max_sample_rate.is_some()
+                        || config.max_bit_depth.is_some()
+                        || config.verify
+                        || !config.suppress.contains(&Warning::MultipleStreams));
+
+                let probed = probe.then(|| stream::probe(config, db, &c.source));
+
+                let selected = match (&probed, &config.stream) {
+                    (None, _) => None,
+                    (Some(Ok(streams)), Some(stream)) => match stream.select(streams) {
+                        Ok(index) => Some(index),
+                        Err(e) => {
+                            error!(o, "{e}");
+                            events.emit(&Event::Error {
+                                index: Some(index),
+                                path: None,
+                                message: &e.to_string(),
+                            })?;
+                            return Ok(());
+                        }
+                    },
+                    (Some(Ok(streams)), None) => {
+                        if streams.len() > 1 {
+                            summary.warn(Warning::MultipleStreams);
+
+                            if !config.suppress.contains(&Warning::MultipleStreams) {
+                                warn!(
+                                    o,
+                                    "Source has {} audio streams, converting the first (see --stream)",
+                                    streams.len()
+                                );
+                            }
+
+                            Some(0)
+                        } else {
+                            None
+                        }
+                    }
+                    (Some(Err(e)), Some(..)) => {
+                        error!(o, "{e}");
+                        events.emit(&Event::Error {
+                            index: Some(index),
+                            path: None,
+                            message: &e.to_string(),
+                        })?;
+                        return Ok(());
+                    }
+                    (Some(Err(e)), None) => {
+                        summary.warn(Warning::ProbeFailed);
+
+                        if !config.suppress.contains(&Warning::ProbeFailed) {
+                            warn!(o, "{e}");
+                        }
+
+                        None
+                    }
+                };
+
+                let mut command = Command::new(&config.ffmpeg);
+                command.args(["-hide_banner", "-loglevel", "error"]);
+                command.args(config.input_args(from));
+
+                if let Some(split) = split {
+                    split.input_args(&mut command);
+                }
+
+                command.args([OsStr::new("-i"), argument]);
+
+                // Streams are only mapped explicitly when there is a choice, since
+                // it disables the implicit selection of artwork.
+                if let Some(selected) = selected {
+                    command.args(["-map", &format!("0:a:{selected}")]);
+
+                    if to.has_artwork() {
+                        command.args(["-map", "0:v?"]);
+                    }
+                }
+
+                if !config.meta_internal {
+                    command.args(["-map_metadata", "0"]);
+                }
+
+                // Chapters of the whole source don't apply to a single track.
+                if let Some(split) = split {
+                    split.metadata_args(&mut command);
+                    command.args(["-map_chapters", "-1"]);
+                } else {
+                    command.args(["-map_chapters", "0"]);
+                }
+
+                let source_stream = probed
+                    .as_ref()
+                    .and_then(|probed| probed.as_ref().ok())
+                    .and_then(|streams| streams.get(selected.unwrap_or_default()));
+
+                let mut filters = Vec::new();
+
+                if let (Some(normalize), Some(gain)) = (&config.normalize, &c.gain) {
+                    debug!(%gain, "normalizing");
+                    filters.push(normalize.filter(gain));
+                }
+
+                // Samples are reduced in the resampler so that they can be
+                // dithered, after which ffmpeg converts them into whatever
+                // sample format the encoder takes.
+                if let Some(max) = config.max_bit_depth
+                    && source_stream
+                        .and_then(|s| s.bit_depth)
+                        .is_some_and(|bits| bits > max.bits())
+                {
+                    match max {
+                        BitDepth::Sixteen => filters.push(String::from(
+                            "aresample=osf=s16:dither_method=triangular_hp",
+                        )),
+                        BitDepth::TwentyFour => {
+                            filters.push(String::from("aresample=osf=s32"));
+                            command.args(["-bits_per_raw_sample", &max.to_string()]);
+                        }
+                    }
+                }
+
+                if !filters.is_empty() {
+                    command.args(["-af", &filters.join(",")]);
+                }
+
+                if let Some(max) = config.max_sample_rate
+                    && source_stream
+                        .and_then(|s| s.sample_rate)
+                        .is_some_and(|rate| rate > max)
+                {
+                    command.args(["-ar", &max.to_string()]);
+                }
+
+                if let Some(encoder) = config.encoder(to) {
+                    command.args(["-c:a", encoder]);
+                }
+
+                to.bitrate(config, bitrate, &mut command);
+                config.art.ffmpeg_args(&mut command);
+                command.args(config.output_args(to));
+                command.args(["-f", to.ffmpeg_format()]);
+                command.arg(part_path);
+
+                // Chapters are written as a second output, so that the source
+                // only has to be read once.
+                let chapters_part = config.chapters_part(&c.to_path).filter(|_| split.is_none());
+
+                if let Some(chapters_part) = &chapters_part {
+                    command.args(["-f", "ffmetadata"]);
+                    command.arg(chapters_part);
+                }
+
+                debug!(command = %FormatCommand::new(&command), "converting");
+
+                let mut f = FormatCommand::new(&command);
+
+                if !config.verbosity.is_detailed() {
+                    f.replace(config.ffmpeg.as_os_str(), "<ffmpeg>");
+
+                    if archive.is_none() {
+                        f.replace(argument, "<from>");
+                    }
+
+                    f.replace(part_path.as_os_str(), format!("<to>.{}", config.part_ext));
+
+                    if let Some(chapters_part) = &chapters_part {
+                        f.replace(
+                            chapters_part.as_os_str(),
+                            format!("<chapters>.{}", config.part_ext),
+                        );
+                    }
+                }
+
+                if !config.make_dir(&mut o, "partial", part_path)? {
+                    return Ok(());
+                }
+
+                {
+                    events.emit(&Event::TaskProgress {
+                        index,
+                        step: "convert",
+                    })?;
+
+                    blank!(o, "{f}");
+                    let mut o = o.indent(1);
+
+                    if !config.dry_run {
+                        let mut outputs = vec![part_path.as_ref()];
+                        outputs.extend(chapters_part.as_deref());
+
+                        let Some(status) = run_with_retries(
+                            &mut o,
+                            config,
+                            events,
+                            index,
+                            &outputs,
+                            |o, events| {
+                                run_command(o, config, db, events, index, &mut command, archive)
+                            },
+                        )?
+                        else {
+                            return Ok(());
+                        };
+
+                        *converted = status.success();
+
+                        if !*converted {
+                            let e = anyhow!("{} exited with {status}", config.ffmpeg.display());
+                            error!(o, "{e}");
+                            events.emit(&Event::Error {
+                                index: Some(index),
+                                path: None,
+                                message: &e.to_string(),
+                            })?;
+                        }
+
+                        if *converted
+                            && let Some(chapters_part) = &chapters_part
+                            && let Err(e) = write_chapters(config, to, chapters_part, &c.to_path)
+                        {
+                            error!(o, "{e}");
+                            events.emit(&Event::Error {
+                                index: Some(index),
+                                path: None,
+                                message: &e.to_string(),
+                            })?;
+                        }
+                    } else {
+                        *converted = true;
+                    }
+
+                    // Tracks split from a source are tagged from the cue sheet
+                    // by ffmpeg, since the tags of the source are of the
+                    // whole album.
+                    if !config.meta_internal || split.is_some() {
+                        *tagged = true;
+                    }
+                }
+
+                if !*tagged {
+                    if let Some(meta) = db.meta(&c.source) {
+                        blank!(o, "tag <to>.{} ({} tags)", config.part_ext, meta.len());
+                        events.emit(&Event::TaskProgress { index, step: "tag" })?;
+                        debug!(tags = meta.len(), "tagging");
+
+                        if !config.dry_run {
+                            if let Err(e) = meta.tag_file(config, to, part_path) {
+                                error!(o, "{e}");
+                                events.emit(&Event::Error {
+                                    index: Some(index),
+                                    path: None,
+                                    message: &e.to_string(),
+                                })?;
+                            } else {
+                                *tagged = true;
+                            }
+                        } else {
+                            *tagged = true;
+                        }
+                    } else {
+                        blank!(o, "tag <to>.{} (no tags)", config.part_ext);
+                        *tagged = true;
+                    }
+                }
+
+                if *converted
+                    && *tagged
+                    && !c.moved
+                    && !config.dry_run
+                    && let Some(values) = &c.replaygain
+                {
+                    match meta::tag_replaygain(config, to, part_path, values) {
+                        Ok(true) => blank!(o, "replaygain <to>.{} ({values})", config.part_ext),
+                        Ok(false) => {}
+                        Err(e) => warn!(o, "Failed to write ReplayGain tags: {e}"),
+                    }
+                }
+
+                if *converted && *tagged && !c.moved && !config.dry_run {
+                    let mut pictures = db.meta(&c.source).map(Meta::pictures).unwrap_or_default();
+                    let mut origin = "source";
+
+                    if pictures.is_empty()
+                        && config.cover_from_folder
+                        && let Source::File { .. } = &c.source
+                        && let Some(dir) = db.to_path(&c.source)?.parent()
+                    {
+                        match art::folder_cover(dir) {
+                            Ok(Some(picture)) => {
+                                pictures.push(picture);
+                                origin = "folder";
+                            }
+                            Ok(None) => {}
+                            Err(e) => warn!(o, "{e}"),
+                        }
+                    }
+
+                    if !pictures.is_empty() {
+                        match meta::embed_art(config, to, part_path, pictures) {
+                            Ok(true) => blank!(o, "art <to>.{} (from {origin})", config.part_ext),
+                            Ok(false) => {}
+                            Err(e) => warn!(o, "Failed to embed artwork: {e}"),
+                        }
+                    }
+                }
+
+                if *converted && *tagged && !c.moved && config.verify && !config.dry_run {
+                    let expected =
+                        source_stream
+                            .and_then(|s| s.duration)
+                            .map(|total| match split {
+                                Some(split) => split.duration(total),
+                                None => total,
+                            });
+
+                    *converted = verify_part(&mut o, config, events, index, part_path, expected)?;
+                }
+
+                if *converted && *tagged && !c.moved {
+                    c.moved = rename_part(&mut o, config, events, index, part_path, &c.to_path)?;
+                }
+            }
+        }
+        TaskKind::External {
+            ref part_path,
+            ref external,
+            ref mut converted,
+        } => {
+            if !*converted {
+                let (argument, archive) = match &c.source {
+                    Source::File { file } => {
+                        let file = db.file(*file)?;
+                        (file.as_os_str(), None)
+                    }
+                    Source::Archive { archive, path } => (OsStr::new("-"), Some((*archive, path))),
+                };
+
+                let mut command = external.plugin.command(argument, part_path)?;
+
+                debug!(command = %FormatCommand::new(&command), "converting with plugin");
+
+                let mut f = FormatCommand::new(&command);
+
+                if !config.verbosity.is_detailed() {
+                    if archive.is_none() {
+                        f.replace(argument, "<from>");
+                    }
+
+                    f.replace(part_path.as_os_str(), format!("<to>.{}", config.part_ext));
+                }
+
+                if !config.make_dir(&mut o, "partial", part_path)? {
+                    return Ok(());
+                }
+
+                events.emit(&Event::TaskProgress {
+                    index,
+                    step: "convert",
+                })?;
+
+                blank!(o, "{f}");
+                let mut o = o.indent(1);
+
+                if !config.dry_run {
+                    let Some(status) = run_with_retries(
+                        &mut o,
+                        config,
+                        events,
+                        index,
+                        &[part_path],
+                        |o, events| {
+                            run_command(o, config, db, events, index, &mut command, archive)
+                        },
+                    )?
+                    else {
+                        return Ok(());
+                    };
+
+                    let result = if status.success() {
+                        plugin::verify(part_path)
+                    } else {
+                        Err(anyhow!(
+                            "{} exited with {status}",
+                            external.plugin.program()
+                        ))
+                    };
+
+                    if let Err(e) = result {
+                        error!(o, "{e}");
+                        events.emit(&Event::Error {
+                            index: Some(index),
+                            path: None,
+                            message: &e.to_string(),
+                        })?;
+                    } else {
+                        *converted = true;
+                    }
+                } else {
+                    *converted = true;
+                }
+            }
+
+            if *converted && !c.moved {
+                c.moved = rename_part(&mut o, config, events, index, part_path, &c.to_path)?;
+            }
+        }
+        TaskKind::Transfer { kind } => {
+            if !c.moved {
+                if !config.make_dir(&mut o, kind, &c.to_path)? {
+                    return Ok(());
+                }
+
+                if config.verbosity.is_detailed() {
+                    db.dump(&mut o, &c.source)?;
+                    o.link("to", &c.to_path)?;
+                } else {
+                    blank!(o, "{} <from> <to>", kind.symbolic_command());
+                }
+
+                debug!(kind = kind.symbolic_command(), "transferring");
+
+                events.emit(&Event::TaskProgress {
+                    index,
+                    step: "transfer",
+                })?;
+
+                if !config.dry_run {
+                    let result = db.move_to(&config.effects, &c.source, &c.to_path, kind);
+
+                    if let Err(e) = result {
+                        error!(o, "{e}");
+                        events.emit(&Event::Error {
+                            index: Some(index),
+                            path: None,
+                            message: &e.to_string(),
+                        })?;
+                    } else {
+                        c.moved = true;
+                    }
+                } else {
+                    c.moved = true;
+                }
+            }
         }
     }
 
     Ok(())
 }
 
-fn is_empty_dir(path: &PathBuf) -> bool {
-    let Ok(mut entries) = fs::read_dir(path) else {
-        return false;
+/// Run a conversion command, writing the source to its stdin if it is stored
+/// in an archive.
+///
+/// Returns `None` if the command couldn't be run, in which case the error has
+/// already been reported.
+fn run_command(
+    o: &mut Out<'_>,
+    config: &Config,
+    db: &Db,
+    events: &mut Events,
+    index: usize,
+    command: &mut Command,
+    archive: Option<(ArchiveId, &RelativePathBuf)>,
+) -> Result<Option<ExitStatus>> {
+    // Run the command in its own process group so that Ctrl-C only reaches
+    // us, which lets the current task finish when the run is cancelled.
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(command, 0);
+
+    let result = match archive {
+        Some((archive, path)) => {
+            command.stdin(Stdio::piped());
+            write_source_to_stdin(command, config, db, archive, path)
+        }
+        None => match config.effects.spawn(command) {
+            Ok(mut child) => wait(&mut child, config),
+            Err(e) => Err(anyhow::Error::from(e)),
+        },
     };
 
-    entries.next().is_none()
+    match result {
+        Ok(Some(status)) => {
+            debug!(%status, "conversion finished");
+            Ok(Some(status))
+        }
+        Ok(None) => {
+            warn!(o, "Aborted");
+            Ok(None)
+        }
+        Err(e) => {
+            error!(o, "{e}");
+            events.emit(&Event::Error {
+                index: Some(index),
+                path: None,
+                message: &e.to_string(),
+            })?;
+            Ok(None)
+        }
+    }
 }
 
+/// Run a command, retrying it with exponential backoff up to `--retries`
+/// times if it fails, since flaky network mounts can make a conversion fail
+/// midway.
+///
+/// Outputs left behind by a failed attempt are removed before retrying, and
+/// the status of the last attempt is returned.
+fn run_with_retries(
+    o: &mut Out<'_>,
+    config: &Config,
+    events: &mut Events,
+    index: usize,
+    outputs: &[&Path],
+    mut run: impl FnMut(&mut Out<'_>, &mut Events) -> Result<Option<ExitStatus>>,
+) -> Result<Option<ExitStatus>> {
+    let mut attempt = 0;
+
+    loop {
+        let Some(status) = run(o, events)? else {
+            // Partial files of aborted runs are removed once the task has
+            // finished, but the ones of commands which failed to run to
+            // completion, like ones which timed out, are removed here.
+            if !config.cancel.is_aborted() {
+                remove_outputs(o, config, outputs)?;
+            }
+
+            return Ok(None);
+        };
+
+        if status.success() || attempt >= config.retries || config.cancel.is_cancelled() {
+            return Ok(Some(status));
+        }
+
+        attempt += 1;
+        let delay = RETRY_DELAY.saturating_mul(1 << (attempt - 1).min(RETRY_MAX_DOUBLINGS));
+
+        warn!(
+            o,
+            "Attempt {attempt}/{} failed ({status}), retrying in {}",
+            config.retries + 1,
+            Elapsed(delay)
+        );
+
+        events.emit(&Event::TaskProgress {
+            index,
+            step: "retry",
+        })?;
+
+        remove_outputs(o, config, outputs)?;
+
+        let until = Instant::now() + delay;
+
+        while Instant::now() < until {
+            if config.cancel.is_cancelled() {
+                return Ok(Some(status));
+            }
+
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+}
+
+/// Remove the outputs of a command which failed.
+fn remove_outputs(o: &mut Out<'_>, config: &Config, outputs: &[&Path]) -> Result<()> {
+    for output in outputs {
+        if let Err(e) = config.effects.remove_file(output)
+            && e.kind() != io::ErrorKind::NotFound
+        {
+            error!(o, "{e}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Rename a partially converted file to its destination, returning `true` if
+/// it was moved.
+fn rename_part(
+    o: &mut Out<'_>,
+    config: &Config,
+    events: &mut Events,
+    index: usize,
+    part_path: &MaybeLink,
+    to_path: &MaybeLink,
+) -> Result<bool> {
+    if !config.make_dir(o, "rename", to_path)? {
+        return Ok(false);
+    }
+
+    blank!(o, "mv <to>.{} <to>", config.part_ext);
+    events.emit(&Event::TaskProgress {
+        index,
+        step: "rename",
+    })?;
+    let mut o = o.indent(1);
+
+    if config.verbosity.is_detailed() {
+        o.link("from", part_path)?;
+        o.link("to", to_path)?;
+    }
+
+    debug!(from = %shell::path(part_path), "renaming partial file");
+
+    if config.dry_run {
+        return Ok(true);
+    }
+
+    if let Err(e) = config.effects.rename(part_path, to_path) {
+        error!(o, "{e}");
+        events.emit(&Event::Error {
+            index: Some(index),
+            path: None,
+            message: &e.to_string(),
+        })?;
+        return Ok(false);
+    }
+
+    Ok(true)
+}
+
+/// Verify a partially converted file with `--verify`, removing it if it's
+/// broken so that it's converted again by the next run.
+///
+/// Returns `false` if it failed verification.
+fn verify_part(
+    o: &mut Out<'_>,
+    config: &Config,
+    events: &mut Events,
+    index: usize,
+    part_path: &MaybeLink,
+    expected: Option<Duration>,
+) -> Result<bool> {
+    blank!(o, "verify <to>.{}", config.part_ext);
+    events.emit(&Event::TaskProgress {
+        index,
+        step: "verify",
+    })?;
+    let mut o = o.indent(1);
+
+    let Err(e) = verify::check(config, part_path, expected) else {
+        return Ok(true);
+    };
+
+    error!(o, "{e}");
+    events.emit(&Event::Error {
+        index: Some(index),
+        path: None,
+        message: &e.to_string(),
+    })?;
+
+    if let Err(e) = config.effects.remove_file(part_path) {
+        error!(o, "{e}");
+    }
+
+    Ok(false)
+}
+
+/// Run a command which reads a source inside of an archive from its stdin.
+///
+/// The source is written from a thread of its own while waiting for the
+/// command, so that it can be timed out or aborted even if it stops reading.
 fn write_source_to_stdin(
     command: &mut Command,
+    config: &Config,
     archives: &Db,
     archive: ArchiveId,
     path: &RelativePath,
-) -> Result<ExitStatus> {
-    let mut child = command.spawn().context("spawning process")?;
+) -> Result<Option<ExitStatus>> {
     let contents = archives
         .archive_contents(archive, path)
         .context("reading source contents")?;
+    let mut child = config.effects.spawn(command).context("spawning process")?;
     let mut stdin = child.stdin.take().context("missing stdin")?;
-    stdin.write_all(&contents).context("writing to stdin")?;
-    stdin.flush().context("flushing stdin")?;
-    drop(stdin);
-    child.wait().context("waiting for process")
+
+    // A command which exits without reading all of its input, like when it
+    // only converts part of the source, closes the pipe, which isn't an error.
+    let writer = thread::spawn(move || {
+        _ = stdin.write_all(&contents);
+        _ = stdin.flush();
+    });
+
+    let status = wait(&mut child, config);
+    _ = writer.join();
+    status
+}
+
+/// Wait for a child process to exit, killing it and returning `None` if the
+/// run is aborted.
+///
+/// The process is also killed if it runs for longer than `--task-timeout`,
+/// which is reported as an error.
+pub(crate) fn wait(child: &mut Child, config: &Config) -> Result<Option<ExitStatus>> {
+    let deadline = config
+        .task_timeout
+        .map(|timeout| (timeout, Instant::now() + timeout.duration()));
+
+    loop {
+        if let Some(status) = child.try_wait().context("waiting for process")? {
+            return Ok(Some(status));
+        }
+
+        if config.cancel.is_aborted() {
+            child.kill().context("killing process")?;
+            child.wait().context("waiting for process")?;
+            return Ok(None);
+        }
+
+        if let Some((timeout, deadline)) = deadline
+            && Instant::now() >= deadline
+        {
+            child.kill().context("killing process")?;
+            child.wait().context("waiting for process")?;
+            bail!("Killed after running for longer than {timeout} (see --task-timeout)");
+        }
+
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Convert chapters written by ffmpeg into a sidecar file next to the
+/// destination, if there are any.
+fn write_chapters(config: &Config, to: Format, chapters_part: &Path, to_path: &Path) -> Result<()> {
+    let Some(format) = config.chapters_sidecar else {
+        return Ok(());
+    };
+
+    let metadata = fs::read_to_string(chapters_part)
+        .with_context(|| anyhow!("Failed to read {}", shell::path(chapters_part)))?;
+
+    config
+        .effects
+        .remove_file(chapters_part)
+        .with_context(|| anyhow!("Failed to remove {}", shell::path(chapters_part)))?;
+
+    let chapters = chapters::parse(&metadata);
+
+    if chapters.is_empty() {
+        return Ok(());
+    }
+
+    let name = to_path
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default();
+
+    let sidecar = format.sidecar_path(to_path);
+
+    config
+        .effects
+        .write(&sidecar, format.render(&chapters, &name, to))
+        .with_context(|| anyhow!("Failed to write {}", shell::path(&sidecar)))?;
+
+    Ok(())
+}
+
+/// Remove the partial file of a task which was aborted.
+fn remove_part(o: &mut Out<'_>, config: &Config, c: &Task) -> Result<()> {
+    let part_path = match &c.kind {
+        TaskKind::Convert { part_path, .. } | TaskKind::External { part_path, .. } => part_path,
+        TaskKind::Transfer { .. } => return Ok(()),
+    };
+
+    if config.dry_run || !part_path.exists() {
+        return Ok(());
+    }
+
+    warn!(o, "Removing partial file {}", shell::path(part_path));
+    config
+        .effects
+        .remove_file(part_path)
+        .with_context(|| anyhow!("Failed to remove {}", shell::path(part_path)))?;
+
+    if let TaskKind::Convert { .. } = c.kind
+        && let Some(chapters_part) = config.chapters_part(&c.to_path)
+        && chapters_part.exists()
+    {
+        config
+            .effects
+            .remove_file(&chapters_part)
+            .with_context(|| anyhow!("Failed to remove {}", shell::path(&chapters_part)))?;
+    }
+
+    Ok(())
 }
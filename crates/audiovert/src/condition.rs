@@ -8,6 +8,9 @@ use crate::format::{Format, FormatErr};
 #[derive(Debug)]
 pub(crate) enum ConditionErr {
     Format(FormatErr),
+    InputOnly(Format),
+    Bitrate(String),
+    NoBitrate(ToCondition),
 }
 
 impl fmt::Display for ConditionErr {
@@ -15,6 +18,11 @@ impl fmt::Display for ConditionErr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ConditionErr::Format(err) => err.fmt(f),
+            ConditionErr::InputOnly(format) => {
+                write!(f, "{format} files can only be converted from")
+            }
+            ConditionErr::Bitrate(bitrate) => write!(f, "Invalid bitrate '{bitrate}'"),
+            ConditionErr::NoBitrate(to) => write!(f, "Cannot set custom bitrate for: {to}"),
         }
     }
 }
@@ -94,6 +102,16 @@ impl ToCondition {
     }
 }
 
+impl fmt::Display for ToCondition {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToCondition::Exact(format) => format.fmt(f),
+            ToCondition::Same => write!(f, "same"),
+        }
+    }
+}
+
 impl FromStr for ToCondition {
     type Err = ConditionErr;
 
@@ -101,7 +119,15 @@ impl FromStr for ToCondition {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "same" => Ok(ToCondition::Same),
-            _ => Ok(ToCondition::Exact(s.parse()?)),
+            _ => {
+                let format = s.parse::<Format>()?;
+
+                if format.is_input_only() {
+                    return Err(ConditionErr::InputOnly(format));
+                }
+
+                Ok(ToCondition::Exact(format))
+            }
         }
     }
 }
@@ -112,21 +138,25 @@ pub(crate) enum Condition {
     FromTo {
         from: FromCondition,
         to: ToCondition,
+        bitrate: Option<u32>,
     },
     To {
         to: ToCondition,
+        bitrate: Option<u32>,
     },
 }
 
 impl Condition {
+    /// Get the format to convert the given format to, along with the bitrate
+    /// set for the conversion if any.
     #[inline]
-    pub(crate) fn to_format(self, format: Format) -> Option<Format> {
+    pub(crate) fn to_format(self, format: Format) -> Option<(Format, Option<u32>)> {
         match self {
-            Condition::Same => Some(format),
-            Condition::To { to } => Some(to.to_format(format)),
-            Condition::FromTo { from, to } => {
+            Condition::Same => Some((format, None)),
+            Condition::To { to, bitrate } => Some((to.to_format(format), bitrate)),
+            Condition::FromTo { from, to, bitrate } => {
                 if from.matches(format) {
-                    Some(to.to_format(format))
+                    Some((to.to_format(format), bitrate))
                 } else {
                     None
                 }
@@ -135,6 +165,28 @@ impl Condition {
     }
 }
 
+/// Parse the bitrate of a conversion to the given target, like the `128` in
+/// `flac=mp3@128`. A bitrate of 0 uses the default bitrate of the format.
+fn parse_bitrate(to: ToCondition, s: &str) -> Result<u32, ConditionErr> {
+    let Ok(bitrate) = s.parse::<u32>() else {
+        return Err(ConditionErr::Bitrate(s.to_string()));
+    };
+
+    let ToCondition::Exact(format) = to else {
+        return Err(ConditionErr::NoBitrate(to));
+    };
+
+    let Some(default_bitrate) = format.default_bitrate() else {
+        return Err(ConditionErr::NoBitrate(to));
+    };
+
+    Ok(if bitrate == 0 {
+        default_bitrate
+    } else {
+        bitrate
+    })
+}
+
 impl FromStr for Condition {
     type Err = ConditionErr;
 
@@ -143,14 +195,25 @@ impl FromStr for Condition {
         match s {
             "same" => Ok(Condition::Same),
             _ => {
-                let Some((from, to)) = s.split_once('=') else {
-                    return Ok(Condition::To { to: s.parse()? });
+                let (s, bitrate) = match s.split_once('@') {
+                    Some((s, bitrate)) => (s, Some(bitrate)),
+                    None => (s, None),
                 };
 
-                Ok(Condition::FromTo {
-                    from: from.parse()?,
-                    to: to.parse()?,
-                })
+                let (from, to) = match s.split_once('=') {
+                    Some((from, to)) => (Some(from.parse()?), to.parse()?),
+                    None => (None, s.parse()?),
+                };
+
+                let bitrate = match bitrate {
+                    Some(bitrate) => Some(parse_bitrate(to, bitrate)?),
+                    None => None,
+                };
+
+                match from {
+                    Some(from) => Ok(Condition::FromTo { from, to, bitrate }),
+                    None => Ok(Condition::To { to, bitrate }),
+                }
             }
         }
     }
@@ -1,53 +1,236 @@
 use core::fmt;
+use core::mem;
+use core::num::NonZeroUsize;
 
-use std::collections::{BTreeSet, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::SystemTime;
 
 use anyhow::{Context, Result, anyhow, bail};
+use mediavert_core::archive::Archive;
+use mediavert_core::cancel::Cancel;
+use mediavert_core::collate::Collation;
+use mediavert_core::effects::Effects;
+use mediavert_core::events::OutputFormat;
+use mediavert_core::link::{Link, Linkable, MaybeLink};
+use mediavert_core::log::Verbosity;
+use mediavert_core::out::{Out, blank, error, info};
+use mediavert_core::plugin::Plugin;
+use mediavert_core::report::ReportTarget;
+use mediavert_core::shell;
 use relative_path::{Component, RelativePath, RelativePathBuf};
+use tracing::{debug, trace};
 
-use crate::archive::Archive;
+use crate::art::Art;
+use crate::bit_depth::BitDepth;
 use crate::bitrates::Bitrates;
+use crate::chapters::ChaptersFormat;
 use crate::condition::Condition;
+use crate::cue::{self, Split};
+use crate::disc::DiscTemplate;
 use crate::format::Format;
-use crate::link::{Link, Linkable, MaybeLink};
-use crate::meta;
-use crate::out::{Out, blank, error, info};
-use crate::shell;
+use crate::meta::{self, Meta};
+use crate::meta_pattern::MetaPattern;
+use crate::meta_template::MetaTemplate;
+use crate::musicbrainz::MusicBrainz;
+use crate::normalize::Normalize;
+use crate::pool;
+use crate::prune::PruneEmpty;
+use crate::replaygain::ReplayGain;
+use crate::sidecar::Sidecar;
+use crate::space::LowSpace;
+use crate::stream::Stream;
+use crate::sync::SyncTarget;
 use crate::tasks::{
-    Exists, MatchingConversion, PathError, Task, TaskKind, Tasks, TransferKind, Unsupported,
+    Collision, Exists, MatchingConversion, PathError, Priority, Task, TaskKind, Tasks,
+    TransferKind, Unsupported,
 };
+use crate::timeout::Timeout;
+use crate::warning::Warning;
 
 /// Configuration for conversions.
 pub(crate) struct Config {
+    pub(crate) art: Art,
     pub(crate) bitrates: Bitrates,
+    pub(crate) cancel: Cancel,
+    pub(crate) chapters_sidecar: Option<ChaptersFormat>,
+    pub(crate) checkpoint: Option<PathBuf>,
+    pub(crate) collision: Collision,
+    pub(crate) confirm_above: usize,
     pub(crate) conversion: Vec<Condition>,
+    pub(crate) copy_sidecars: Vec<Sidecar>,
+    pub(crate) cover_from_folder: bool,
+    pub(crate) dedup_outputs: bool,
+    pub(crate) disc_template: DiscTemplate,
     pub(crate) dry_run: bool,
+    pub(crate) effects: Effects,
+    pub(crate) encoders: HashMap<Format, String>,
+    pub(crate) export_plan: Option<PathBuf>,
+    pub(crate) ext_overrides: HashMap<Format, String>,
+    pub(crate) externals: HashMap<String, Arc<External>>,
     pub(crate) ffmpeg: PathBuf,
     pub(crate) force: bool,
     pub(crate) forced_bitrates: HashSet<Format>,
+    pub(crate) input_args: HashMap<Format, Vec<String>>,
+    pub(crate) interactive: bool,
+    pub(crate) jobs: NonZeroUsize,
     pub(crate) keep_going: bool,
+    pub(crate) max_bit_depth: Option<BitDepth>,
+    pub(crate) max_removals: Option<usize>,
+    pub(crate) max_sample_rate: Option<u32>,
     pub(crate) meta_dump_error: bool,
     pub(crate) meta_dump: bool,
     pub(crate) meta_internal: bool,
     pub(crate) meta: bool,
+    pub(crate) meta_from_path: Option<MetaPattern>,
+    pub(crate) meta_template: Option<MetaTemplate>,
+    pub(crate) min_discs: u32,
+    pub(crate) min_free_space: Option<u64>,
+    pub(crate) musicbrainz: Option<MusicBrainz>,
+    pub(crate) normalize: Option<Normalize>,
+    pub(crate) normalize_album: bool,
+    pub(crate) on_low_space: LowSpace,
+    pub(crate) order: Priority,
+    pub(crate) output: Option<PathBuf>,
+    pub(crate) output_args: HashMap<Format, Vec<String>>,
+    pub(crate) output_format: OutputFormat,
     pub(crate) part_ext: String,
     pub(crate) paths: Vec<PathBuf>,
+    pub(crate) prune: bool,
+    pub(crate) prune_empty: Option<PruneEmpty>,
     pub(crate) r#move: bool,
+    pub(crate) replaygain: Option<ReplayGain>,
+    pub(crate) report: Option<ReportTarget>,
+    pub(crate) resume: bool,
+    pub(crate) retries: u32,
+    pub(crate) sort_order: Collation,
+    pub(crate) split_cue: bool,
+    pub(crate) stream: Option<Stream>,
+    pub(crate) suppress: HashSet<Warning>,
+    pub(crate) sync: bool,
+    pub(crate) sync_to: Option<SyncTarget>,
+    pub(crate) task_timeout: Option<Timeout>,
     pub(crate) to_dir: Option<PathBuf>,
     pub(crate) trash_source: bool,
     pub(crate) trash: PathBuf,
-    pub(crate) verbose: bool,
+    pub(crate) verbosity: Verbosity,
+    pub(crate) verify: bool,
+    pub(crate) workers: NonZeroUsize,
+    pub(crate) yes: bool,
 }
 
 impl Config {
+    /// The extension of files produced in the given format.
+    pub(crate) fn output_ext(&self, format: Format) -> &str {
+        match self.ext_overrides.get(&format) {
+            Some(ext) => ext,
+            None => format.ext(),
+        }
+    }
+
+    /// The format of files produced with the given extension, taking
+    /// extension overrides into account.
+    pub(crate) fn output_format(&self, ext: &str) -> Option<Format> {
+        if let Some((&format, _)) = self.ext_overrides.iter().find(|(_, e)| *e == ext) {
+            return Some(format);
+        }
+
+        Format::from_ext(ext)
+    }
+
+    /// Arguments passed to ffmpeg before the `-i` of sources in the given
+    /// format.
+    pub(crate) fn input_args(&self, format: Format) -> &[String] {
+        match self.input_args.get(&format) {
+            Some(args) => args,
+            None => &[],
+        }
+    }
+
+    /// The ffmpeg encoder used to produce files in the given format, if it
+    /// isn't the default one of its container.
+    pub(crate) fn encoder(&self, format: Format) -> Option<&str> {
+        match self.encoders.get(&format) {
+            Some(encoder) => Some(encoder),
+            None => format.ffmpeg_codec(),
+        }
+    }
+
+    /// Arguments passed to ffmpeg before the output when producing files in
+    /// the given format.
+    pub(crate) fn output_args(&self, format: Format) -> &[String] {
+        match self.output_args.get(&format) {
+            Some(args) => args,
+            None => &[],
+        }
+    }
+
+    /// The path that chapters of a conversion to the given destination are
+    /// written to before they're converted into a sidecar file.
+    pub(crate) fn chapters_part(&self, to_path: &Path) -> Option<MaybeLink> {
+        let format = self.chapters_sidecar?;
+        let path = format.sidecar_path(to_path);
+        Some(MaybeLink::new(path.with_added_extension(&self.part_ext)))
+    }
+
+    /// Test if an existing destination is out of date with `--sync`, because
+    /// its source has been modified after it was written, or because it's a
+    /// transfer which differs in size from its source.
+    fn is_outdated(
+        &self,
+        db: &Db,
+        source: &Source,
+        to_path: &Path,
+        transfer: bool,
+    ) -> Result<bool> {
+        if !self.sync {
+            return Ok(false);
+        }
+
+        let metadata = fs::metadata(to_path)
+            .with_context(|| anyhow!("Failed to get metadata of {}", to_path.display()))?;
+
+        if let Some(modified) = db.modified(source)?
+            && metadata.modified().is_ok_and(|to| modified > to)
+        {
+            return Ok(true);
+        }
+
+        if transfer
+            && let Some(size) = db.size(source)?
+            && size != metadata.len()
+        {
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
     /// Populate tasks based on configuration.
+    ///
+    /// Archives are enumerated and sources are probed for tags on a pool of
+    /// workers, after which tasks are planned in the order sources were walked.
     pub(crate) fn populate(&self, tasks: &mut Tasks) -> Result<()> {
+        // Errors collected for the current source, which have to be drained or
+        // cleared before moving on so that they aren't reported for the next.
         let mut meta_errors = Vec::new();
-        let mut to_formats = BTreeSet::new();
+        let mut to_formats = BTreeMap::new();
         let mut sources = Vec::new();
         let mut pre_remove = Vec::new();
+        let mut walked_paths = Vec::new();
+        let mut sidecars = Vec::new();
+        let mut used_cues = HashSet::new();
+
+        // With `--output` the format is taken from its extension if possible,
+        // instead of from the conversion rules.
+        let output_format = self
+            .output
+            .as_deref()
+            .and_then(|p| p.extension()?.to_str())
+            .and_then(|ext| self.output_format(ext));
 
         for walk_path in &self.paths {
             let dir = if walk_path.is_file() {
@@ -63,6 +246,10 @@ impl Config {
             };
 
             for f in ignore::Walk::new(walk_path) {
+                if self.cancel.is_cancelled() {
+                    bail!("Cancelled while planning");
+                }
+
                 let entry = f?;
 
                 let walked = entry.path();
@@ -75,207 +262,619 @@ impl Config {
                     continue;
                 };
 
-                if let Some(kind) = Archive::from_ext(ext) {
-                    let archive_id = tasks.db.push_archive(SourceArchive {
-                        kind,
-                        path: Link::new(walked)?,
-                    });
+                trace!(path = %shell::path(walked), "walked");
 
-                    let mut archive_path = walked.parent().unwrap_or(Path::new("")).to_path_buf();
+                walked_paths.push((dir, walked.to_path_buf(), ext.to_owned()));
+            }
+        }
 
-                    if let Some(file_name) = walked.file_stem() {
-                        archive_path.push(file_name);
-                    }
+        let enumerated = pool::map(self.jobs, &walked_paths, |(_, walked, ext)| {
+            if self.cancel.is_cancelled() {
+                return Ok(None);
+            }
 
-                    kind.enumerate(walked, &mut |path| {
-                        let path = RelativePath::new(path);
-                        let mut buf = archive_path.clone();
-
-                        let ok = 'ok: {
-                            for c in path.components() {
-                                match c {
-                                    Component::CurDir => {}
-                                    Component::ParentDir => {
-                                        break 'ok false;
-                                    }
-                                    Component::Normal(s) => {
-                                        buf.push(s);
-                                    }
-                                }
-                            }
-
-                            true
-                        };
+            enumerate(walked, ext)
+        });
 
-                        if ok {
-                            sources.push(Source::Archive {
-                                archive: archive_id,
-                                path: path.to_owned(),
-                            });
-                        }
+        if self.cancel.is_cancelled() {
+            bail!("Cancelled while planning");
+        }
 
-                        Ok(())
-                    })?;
-                } else {
-                    let file = tasks.db.push_file(Link::new(walked)?);
-                    let source = Source::File { file };
-                    sources.push(source);
+        let mut discovered = Vec::new();
+
+        for ((dir, walked, ext), entries) in walked_paths.iter().zip(enumerated) {
+            tasks.db.discover_entries(walked, entries?, &mut sources)?;
+
+            for source in sources.drain(..) {
+                discovered.push((*dir, ext.as_str(), source));
+            }
+        }
+
+        let probed = pool::map(self.jobs, &discovered, |(_, _, source)| {
+            if self.cancel.is_cancelled() {
+                return Ok(Probe::default());
+            }
+
+            Probe::new(source, &tasks.db, self)
+        });
+
+        if self.cancel.is_cancelled() {
+            bail!("Cancelled while planning");
+        }
+
+        for ((dir, ext, source), probe) in discovered.into_iter().zip(probed) {
+            let source_ext = tasks.db.ext(&source)?;
+
+            let Some(from) = source_ext.and_then(Format::from_ext) else {
+                let external = source_ext.and_then(|ext| self.externals.get(ext));
+
+                if let Some(external) = external.cloned() {
+                    self.populate_external(tasks, source, dir, external)?;
+                    continue;
                 }
 
-                for source in sources.drain(..) {
-                    let Some(from) = tasks.db.ext(&source)?.and_then(Format::from_ext) else {
-                        tasks.unsupported.push(Unsupported {
-                            source,
-                            ext: ext.to_string(),
-                        });
+                if self.is_sidecar(&tasks.db, &source)? {
+                    sidecars.push((source, ext.to_string()));
+                    continue;
+                }
 
-                        continue;
-                    };
+                debug!(ext, "unsupported extension");
 
-                    to_formats.clear();
+                tasks.unsupported.push(Unsupported {
+                    source,
+                    ext: ext.to_string(),
+                });
 
-                    for conversion in &self.conversion {
-                        to_formats.extend(conversion.to_format(from));
-                    }
+                continue;
+            };
 
-                    if !to_formats.is_empty() && self.verbose {
-                        tasks.matching_conversions.push(MatchingConversion {
-                            source: source.clone(),
-                            from,
-                            to_formats: to_formats.iter().cloned().collect(),
-                        });
-                    }
+            let Probe {
+                format,
+                id_parts,
+                meta,
+                errors,
+            } = probe?;
 
-                    let mut meta = None;
+            let from = format.unwrap_or(from);
 
-                    let id_parts =
-                        meta::Parts::from_path(&source, &tasks.db, &mut meta_errors, &mut meta)?;
+            to_formats.clear();
 
-                    if let Some(meta) = meta {
-                        tasks.meta.insert(source.clone(), meta);
-                    }
+            for conversion in &self.conversion {
+                to_formats.extend(conversion.to_format(from));
+            }
+
+            if let Some(to) = output_format {
+                let bitrate = to_formats.get(&to).copied().flatten();
+                to_formats.clear();
+                to_formats.insert(to, bitrate);
+            }
+
+            if !to_formats.is_empty() && self.verbosity.is_verbose() {
+                tasks.matching_conversions.push(MatchingConversion {
+                    source: source.clone(),
+                    from,
+                    to_formats: to_formats.keys().cloned().collect(),
+                });
+            }
+
+            meta_errors.extend(errors);
+
+            if let Some(meta) = meta {
+                tasks.db.insert_meta(source.clone(), meta);
+            }
+
+            let splits = match self.split_cue(&tasks.db, &source, from) {
+                Ok(splits) => splits,
+                Err(e) => {
+                    meta_errors.push(format!("{e:#}"));
+
+                    tasks.errors.push(PathError {
+                        source: source.clone(),
+                        messages: meta_errors.drain(..).collect(),
+                    });
+
+                    continue;
+                }
+            };
+
+            // Each file is converted into the given targets, which is either
+            // the whole file or each track split from it.
+            let targets = if let Some((cue, splits)) = splits {
+                used_cues.insert(cue);
+
+                // Tags are taken from the cue sheet instead.
+                meta_errors.clear();
 
-                    let meta_parts = if self.meta {
-                        let Some(id_parts) = id_parts else {
-                            meta_errors.push(
-                                "could not extract required tags (see --meta-dump-error)"
-                                    .to_string(),
-                            );
+                let mut targets = Vec::with_capacity(splits.len());
+
+                for split in splits {
+                    let parts = if self.meta {
+                        let Some(parts) =
+                            meta::Parts::from_split(&split, id_parts.as_ref(), &mut meta_errors)
+                        else {
                             continue;
                         };
 
-                        if !meta_errors.is_empty() {
-                            tasks.errors.push(PathError {
-                                source: source.clone(),
-                                messages: meta_errors.drain(..).collect(),
-                            });
-                        }
-
-                        Some(id_parts)
+                        Some(parts)
                     } else {
                         None
                     };
 
-                    for &to in &to_formats {
-                        debug_assert!(pre_remove.is_empty());
-
-                        let to_path = if let Some(to_dir) = &self.to_dir {
-                            match &meta_parts {
-                                Some(meta_parts) => {
-                                    let mut to_path = to_dir.to_path_buf();
-                                    meta_parts.append_to(&mut to_path);
-                                    to_path.add_extension(to.ext());
-                                    to_path
-                                }
-                                None => {
-                                    let mut to_path = to_dir.clone();
-                                    tasks.db.to_dir_path(&source, dir, &mut to_path)?;
-                                    to_path.set_extension(to.ext());
-                                    to_path
-                                }
-                            }
-                        } else {
-                            match &meta_parts {
-                                Some(meta_parts) => {
-                                    let mut to_path = dir.to_path_buf();
-                                    meta_parts.append_to(&mut to_path);
-                                    to_path.add_extension(to.ext());
-                                    to_path
-                                }
-                                None => {
-                                    let mut to_path = tasks.db.to_path(&source)?;
-                                    to_path.set_extension(to.ext());
-                                    to_path
-                                }
-                            }
-                        };
+                    targets.push((Some(Arc::new(split)), parts));
+                }
 
-                        if tasks.db.as_file(&source)?.is_some_and(|p| p == to_path) {
-                            continue;
-                        }
+                if !meta_errors.is_empty() {
+                    tasks.errors.push(PathError {
+                        source: source.clone(),
+                        messages: meta_errors.drain(..).collect(),
+                    });
 
-                        let to_path = MaybeLink::new(to_path);
-                        let exists;
-
-                        if to_path.exists() {
-                            if !self.force {
-                                tasks.already_exists.push(Exists {
-                                    source: source.clone(),
-                                    path: Link::new(&to_path)?,
-                                });
-                                exists = true;
-                            } else {
-                                pre_remove.push(("destination path (--force)", to_path.clone()));
-                                exists = false;
-                            }
-                        } else {
-                            exists = false;
-                        };
+                    continue;
+                }
 
-                        let kind = if from == to && !self.forced_bitrates.contains(&from) {
-                            TaskKind::Transfer {
-                                kind: match source {
-                                    Source::File { .. } => {
-                                        if self.r#move {
-                                            TransferKind::Move
-                                        } else {
-                                            TransferKind::Link
-                                        }
-                                    }
-                                    Source::Archive { .. } => TransferKind::Copy,
-                                },
-                            }
-                        } else {
-                            let part_path =
-                                MaybeLink::new(to_path.with_added_extension(&self.part_ext));
-
-                            if part_path.exists() {
-                                pre_remove.push(("partial conversion file", part_path.clone()));
-                            }
-
-                            TaskKind::Convert {
-                                part_path,
-                                from,
-                                to,
-                                converted: exists,
-                                tagged: false,
-                            }
-                        };
+                targets
+            } else if self.meta {
+                let Some(id_parts) = id_parts else {
+                    meta_errors.push(
+                        "could not extract required tags (see --meta-dump-error)".to_string(),
+                    );
+
+                    tasks.errors.push(PathError {
+                        source: source.clone(),
+                        messages: meta_errors.drain(..).collect(),
+                    });
+
+                    continue;
+                };
+
+                // A source which has been reported as failed isn't also
+                // converted with --keep-going.
+                if !meta_errors.is_empty() {
+                    tasks.errors.push(PathError {
+                        source: source.clone(),
+                        messages: meta_errors.drain(..).collect(),
+                    });
+
+                    continue;
+                }
 
-                        let index = tasks.tasks.len();
+                vec![(None, Some(id_parts))]
+            } else {
+                meta_errors.clear();
+                vec![(None, None)]
+            };
 
-                        tasks.tasks.push(Task {
-                            index,
-                            kind,
+            let planned = to_formats.iter().flat_map(|(&to, &bitrate)| {
+                targets
+                    .iter()
+                    .map(move |(split, meta_parts)| (to, bitrate, split, meta_parts))
+            });
+
+            for (to, bitrate, split, meta_parts) in planned {
+                debug_assert!(pre_remove.is_empty());
+
+                let to_path = if let Some(output) = &self.output {
+                    output.clone()
+                } else if let Some(to_dir) = &self.to_dir {
+                    match meta_parts {
+                        Some(meta_parts) => {
+                            let mut to_path = to_dir.to_path_buf();
+                            meta_parts.append_to(self, &mut to_path);
+                            to_path.add_extension(self.output_ext(to));
+                            to_path
+                        }
+                        None => {
+                            let mut to_path = to_dir.clone();
+                            tasks.db.to_dir_path(&source, dir, &mut to_path)?;
+                            self.set_file_name(&mut to_path, split.as_deref(), to);
+                            to_path
+                        }
+                    }
+                } else {
+                    match meta_parts {
+                        Some(meta_parts) => {
+                            let mut to_path = dir.to_path_buf();
+                            meta_parts.append_to(self, &mut to_path);
+                            to_path.add_extension(self.output_ext(to));
+                            to_path
+                        }
+                        None => {
+                            let mut to_path = tasks.db.to_path(&source)?;
+                            self.set_file_name(&mut to_path, split.as_deref(), to);
+                            to_path
+                        }
+                    }
+                };
+
+                if tasks.db.as_file(&source)?.is_some_and(|p| p == to_path) {
+                    continue;
+                }
+
+                let Some(to_path) = tasks.claim(self.collision, &source, to_path)? else {
+                    continue;
+                };
+
+                let to_path = MaybeLink::new(to_path);
+                let transfer =
+                    split.is_none() && from == to && !self.forced_bitrates.contains(&from);
+                let exists;
+
+                if to_path.exists() {
+                    if self.force {
+                        pre_remove.push(("destination path (--force)", to_path.clone()));
+                        exists = false;
+                    } else if self.is_outdated(&tasks.db, &source, &to_path, transfer)? {
+                        pre_remove.push(("outdated destination (--sync)", to_path.clone()));
+                        exists = false;
+                    } else {
+                        tasks.already_exists.push(Exists {
                             source: source.clone(),
-                            to_path,
-                            moved: exists,
-                            pre_remove: pre_remove.drain(..).collect(),
+                            path: Link::new(&to_path)?,
                         });
+                        exists = true;
+                    }
+                } else {
+                    exists = false;
+                };
+
+                let kind = if transfer {
+                    TaskKind::Transfer {
+                        kind: self.transfer_kind(&source),
+                    }
+                } else {
+                    let part_path = MaybeLink::new(to_path.with_added_extension(&self.part_ext));
+
+                    if part_path.exists() {
+                        pre_remove.push(("partial conversion file", part_path.clone()));
                     }
+
+                    if let Some(chapters_part) = self.chapters_part(&to_path)
+                        && chapters_part.exists()
+                    {
+                        pre_remove.push(("partial chapters file", chapters_part));
+                    }
+
+                    TaskKind::Convert {
+                        part_path,
+                        from,
+                        to,
+                        bitrate,
+                        converted: exists,
+                        tagged: false,
+                        split: split.clone(),
+                    }
+                };
+
+                let index = tasks.tasks.len();
+
+                tasks.tasks.push(Task {
+                    index,
+                    kind,
+                    source: source.clone(),
+                    to_path,
+                    moved: exists,
+                    pre_remove: pre_remove.drain(..).collect(),
+                    after: Vec::new(),
+                    gain: None,
+                    replaygain: None,
+                });
+            }
+        }
+
+        // Cue sheets which files were split by aren't unsupported.
+        if !used_cues.is_empty() {
+            let mut unsupported = Vec::new();
+
+            for u in mem::take(&mut tasks.unsupported) {
+                if !used_cues.contains(&tasks.db.to_path(&u.source)?) {
+                    unsupported.push(u);
+                }
+            }
+
+            tasks.unsupported = unsupported;
+        }
+
+        self.populate_sidecars(tasks, sidecars)?;
+        tasks.link();
+
+        // Lookups are saved once planning is done instead of after each one,
+        // since the cache is rewritten in full.
+        if let Some(musicbrainz) = &self.musicbrainz {
+            musicbrainz.save(&self.effects)?;
+        }
+
+        Ok(())
+    }
+
+    /// Find the tracks to split a source into with `--split-cue`.
+    fn split_cue(
+        &self,
+        db: &Db,
+        source: &Source,
+        from: Format,
+    ) -> Result<Option<(PathBuf, Vec<Split>)>> {
+        if !self.split_cue || !from.is_lossless() {
+            return Ok(None);
+        }
+
+        let Some(path) = db.as_file(source)? else {
+            return Ok(None);
+        };
+
+        cue::find(path)
+    }
+
+    /// Set the file name of a destination which isn't organized by tags.
+    ///
+    /// Tracks split from a source are named by their number and title instead.
+    fn set_file_name(&self, to_path: &mut PathBuf, split: Option<&Split>, to: Format) {
+        match split {
+            Some(split) => {
+                to_path.pop();
+                meta::push_sanitized(to_path, &split.file_name());
+                to_path.add_extension(self.output_ext(to));
+            }
+            None => {
+                to_path.set_extension(self.output_ext(to));
+            }
+        }
+    }
+
+    /// How a source is transferred as-is to its destination.
+    fn transfer_kind(&self, source: &Source) -> TransferKind {
+        match source {
+            Source::File { .. } => {
+                if self.r#move {
+                    TransferKind::Move
+                } else {
+                    TransferKind::Link
                 }
             }
+            Source::Archive { .. } => TransferKind::Copy,
         }
+    }
+
+    /// Test if a source is a sidecar which is carried along with
+    /// `--copy-sidecar`.
+    fn is_sidecar(&self, db: &Db, source: &Source) -> Result<bool> {
+        if self.to_dir.is_none() || self.copy_sidecars.is_empty() {
+            return Ok(false);
+        }
+
+        let path = db.to_path(source)?;
+
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            return Ok(false);
+        };
+
+        Ok(self.copy_sidecars.iter().any(|s| s.matches(name)))
+    }
+
+    /// Populate tasks transferring sidecars to the destination directory of
+    /// the files next to them.
+    ///
+    /// Sidecars in directories without any other files to convert or transfer
+    /// are reported as unsupported.
+    fn populate_sidecars(&self, tasks: &mut Tasks, sidecars: Vec<(Source, String)>) -> Result<()> {
+        let mut dirs = HashMap::new();
+
+        for c in &tasks.tasks {
+            let from = tasks.db.to_path(&c.source)?;
+
+            if let (Some(from), Some(to)) = (from.parent(), c.to_path.parent()) {
+                dirs.entry(from.to_path_buf())
+                    .or_insert_with(|| to.to_path_buf());
+            }
+        }
+
+        for (source, ext) in sidecars {
+            let path = tasks.db.to_path(&source)?;
+
+            let (Some(name), Some(to_dir)) =
+                (path.file_name(), path.parent().and_then(|p| dirs.get(p)))
+            else {
+                debug!(ext, "sidecar without destination");
+                tasks.unsupported.push(Unsupported { source, ext });
+                continue;
+            };
+
+            let Some(to_path) = tasks.claim(self.collision, &source, to_dir.join(name))? else {
+                continue;
+            };
+
+            let to_path = MaybeLink::new(to_path);
+            let mut pre_remove = Vec::new();
+            let mut exists = false;
+
+            if to_path.exists() {
+                if self.force {
+                    pre_remove.push(("destination path (--force)", to_path.clone()));
+                } else if self.is_outdated(&tasks.db, &source, &to_path, true)? {
+                    pre_remove.push(("outdated destination (--sync)", to_path.clone()));
+                } else {
+                    tasks.already_exists.push(Exists {
+                        source: source.clone(),
+                        path: Link::new(&to_path)?,
+                    });
+                    exists = true;
+                }
+            }
+
+            let index = tasks.tasks.len();
+
+            tasks.tasks.push(Task {
+                index,
+                kind: TaskKind::Transfer {
+                    kind: self.transfer_kind(&source),
+                },
+                source,
+                to_path,
+                moved: exists,
+                pre_remove,
+                after: Vec::new(),
+                gain: None,
+                replaygain: None,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Change the format a planned task produces, as picked through
+    /// `--interactive`.
+    ///
+    /// Transfers become conversions, and a task which is changed to the format
+    /// of its source re-encodes it.
+    ///
+    /// The new destination is claimed like a planned one. Returns `false` if
+    /// it collides with the destination of another task and the task should
+    /// be dropped according to `--collision`.
+    pub(crate) fn retarget(&self, tasks: &mut Tasks, index: usize, to: Format) -> Result<bool> {
+        let Some(c) = tasks.tasks.get(index) else {
+            return Ok(true);
+        };
+
+        let from = match &c.kind {
+            TaskKind::Convert {
+                from, to: current, ..
+            } if *current != to => *from,
+            TaskKind::Transfer { .. } => {
+                match tasks.db.ext(&c.source)?.and_then(Format::from_ext) {
+                    Some(from) if from != to => from,
+                    _ => return Ok(true),
+                }
+            }
+            _ => return Ok(true),
+        };
+
+        let split = match &c.kind {
+            TaskKind::Convert { split, .. } => split.clone(),
+            _ => None,
+        };
+
+        let source = c.source.clone();
+        let current = c.to_path.to_path_buf();
+
+        tasks.release(&current);
+
+        let to_path = current.with_extension(self.output_ext(to));
+
+        let Some(to_path) = tasks.claim(self.collision, &source, to_path)? else {
+            return Ok(false);
+        };
+
+        let to_path = MaybeLink::new(to_path);
+        let mut pre_remove = Vec::new();
+        let mut exists = false;
+
+        if to_path.exists() {
+            if self.force {
+                pre_remove.push(("destination path (--force)", to_path.clone()));
+            } else if self.is_outdated(&tasks.db, &source, &to_path, false)? {
+                pre_remove.push(("outdated destination (--sync)", to_path.clone()));
+            } else {
+                exists = true;
+            }
+        }
+
+        let part_path = MaybeLink::new(to_path.with_added_extension(&self.part_ext));
+
+        if part_path.exists() {
+            pre_remove.push(("partial conversion file", part_path.clone()));
+        }
+
+        if let Some(chapters_part) = self.chapters_part(&to_path)
+            && chapters_part.exists()
+        {
+            pre_remove.push(("partial chapters file", chapters_part));
+        }
+
+        let c = &mut tasks.tasks[index];
+
+        c.kind = TaskKind::Convert {
+            part_path,
+            from,
+            to,
+            bitrate: None,
+            converted: exists,
+            tagged: false,
+            split,
+        };
+
+        c.to_path = to_path;
+        c.moved = exists;
+        c.pre_remove = pre_remove;
+        Ok(true)
+    }
+
+    /// Populate a task converting a source with an external plugin.
+    fn populate_external(
+        &self,
+        tasks: &mut Tasks,
+        source: Source,
+        dir: &Path,
+        external: Arc<External>,
+    ) -> Result<()> {
+        let to_path = match (&self.output, &self.to_dir) {
+            (Some(output), _) => output.clone(),
+            (None, Some(to_dir)) => {
+                let mut to_path = to_dir.clone();
+                tasks.db.to_dir_path(&source, dir, &mut to_path)?;
+                to_path.set_extension(self.output_ext(external.to));
+                to_path
+            }
+            (None, None) => {
+                let mut to_path = tasks.db.to_path(&source)?;
+                to_path.set_extension(self.output_ext(external.to));
+                to_path
+            }
+        };
+
+        let Some(to_path) = tasks.claim(self.collision, &source, to_path)? else {
+            return Ok(());
+        };
+
+        let to_path = MaybeLink::new(to_path);
+        let mut pre_remove = Vec::new();
+        let mut exists = false;
+
+        if to_path.exists() {
+            if self.force {
+                pre_remove.push(("destination path (--force)", to_path.clone()));
+            } else if self.is_outdated(&tasks.db, &source, &to_path, false)? {
+                pre_remove.push(("outdated destination (--sync)", to_path.clone()));
+            } else {
+                tasks.already_exists.push(Exists {
+                    source: source.clone(),
+                    path: Link::new(&to_path)?,
+                });
+                exists = true;
+            }
+        }
+
+        let part_path = MaybeLink::new(to_path.with_added_extension(&self.part_ext));
+
+        if part_path.exists() {
+            pre_remove.push(("partial conversion file", part_path.clone()));
+        }
+
+        let index = tasks.tasks.len();
+
+        tasks.tasks.push(Task {
+            index,
+            kind: TaskKind::External {
+                part_path,
+                external,
+                converted: exists,
+            },
+            source,
+            to_path,
+            moved: exists,
+            pre_remove,
+            after: Vec::new(),
+            gain: None,
+            replaygain: None,
+        });
 
         Ok(())
     }
@@ -303,7 +902,7 @@ impl Config {
             return Ok(true);
         }
 
-        if let Err(e) = fs::create_dir_all(parent) {
+        if let Err(e) = self.effects.create_dir_all(parent) {
             error!(o, "{e}");
             Ok(false)
         } else {
@@ -312,6 +911,60 @@ impl Config {
     }
 }
 
+/// The tags probed from a source while planning.
+#[derive(Default)]
+struct Probe {
+    /// The format of the source, if it could only be told by its contents.
+    format: Option<Format>,
+    id_parts: Option<meta::Parts>,
+    meta: Option<Meta>,
+    errors: Vec<String>,
+}
+
+impl Probe {
+    /// Probe the tags of a source, if it's in a format which carries them.
+    fn new(source: &Source, db: &Db, config: &Config) -> Result<Self> {
+        let mut probe = Probe::default();
+
+        let Some(from) = db.ext(source)?.and_then(Format::from_ext) else {
+            return Ok(probe);
+        };
+
+        if from == Format::M4a {
+            match meta::is_alac(source, db) {
+                Ok(true) => probe.format = Some(Format::Alac),
+                Ok(false) => {}
+                Err(e) => probe.errors.push(format!("failed to detect codec: {e}")),
+            }
+        }
+
+        if from.has_tags() || config.meta_from_path.is_some() || config.musicbrainz.is_some() {
+            probe.id_parts = meta::Parts::from_path(
+                source,
+                db,
+                from,
+                config,
+                &mut probe.errors,
+                &mut probe.meta,
+            )?;
+        } else {
+            probe.errors.push(format!("{from} files don't carry tags"));
+        }
+
+        Ok(probe)
+    }
+}
+
+/// A plugin which converts files with an unsupported extension.
+pub(crate) struct External {
+    /// The extension handled by the plugin.
+    pub(crate) ext: String,
+    /// The format produced by the plugin.
+    pub(crate) to: Format,
+    /// The plugin to run.
+    pub(crate) plugin: Plugin,
+}
+
 /// The location and characteristics of a source archive.
 ///
 /// This is referenced by an [`ArchiveId`].
@@ -359,10 +1012,73 @@ impl fmt::Display for ArchiveId {
     }
 }
 
+/// The entries of an archive, along with their sizes.
+pub(crate) struct Enumerated {
+    kind: Archive,
+    entries: Vec<(RelativePathBuf, u64)>,
+}
+
+/// Enumerate the entries of the walked path if it's an archive, which is done
+/// separately from [`Db::discover_entries`] so that archives can be enumerated
+/// concurrently.
+pub(crate) fn enumerate(walked: &Path, ext: &str) -> Result<Option<Enumerated>> {
+    let Some(kind) = Archive::from_ext(ext) else {
+        return Ok(None);
+    };
+
+    let mut entries = Vec::new();
+
+    kind.enumerate(walked, &mut |path, size| {
+        entries.push((path.to_owned(), size));
+        Ok(())
+    })?;
+
+    Ok(Some(Enumerated { kind, entries }))
+}
+
+/// Extract every entry of an archive into `dir`, removing it again if
+/// extraction fails.
+///
+/// Entries which would end up outside of `dir` are skipped and read from the
+/// archive when needed instead.
+fn extract(effects: &Effects, archive: &SourceArchive, dir: &Path) -> Result<()> {
+    let result = archive.kind.extract(&archive.path, &mut |path, reader| {
+        if path.components().any(|c| matches!(c, Component::ParentDir)) {
+            return Ok(());
+        }
+
+        let target = path.to_path(dir);
+
+        if let Some(parent) = target.parent() {
+            effects.create_dir_all(parent)?;
+        }
+
+        let mut file = effects.create(&target)?;
+        io::copy(reader, &mut file)?;
+        Ok(())
+    });
+
+    if let Err(e) = &result {
+        debug!(archive = %shell::path(&archive.path), error = %e, "extracting archive failed");
+        _ = effects.remove_dir_all(dir);
+    }
+
+    result
+}
+
 /// Data associated with tasks.
 pub(crate) struct Db {
     archives: Vec<SourceArchive>,
     files: Vec<Link>,
+    archive_sizes: HashMap<Source, u64>,
+    /// Tags read from sources while planning.
+    metas: HashMap<Source, Meta>,
+    /// Directory that archives are extracted into the first time their
+    /// contents are read, if enabled, and the layer they're extracted through.
+    extract_dir: Option<(PathBuf, Effects)>,
+    /// Where each archive was extracted, indexed by [`ArchiveId`]. `None` if
+    /// extraction failed, in which case entries are read from the archive.
+    extracted: Vec<OnceLock<Option<PathBuf>>>,
 }
 
 impl Db {
@@ -371,9 +1087,20 @@ impl Db {
         Db {
             archives: Vec::new(),
             files: Vec::new(),
+            archive_sizes: HashMap::new(),
+            metas: HashMap::new(),
+            extract_dir: None,
+            extracted: Vec::new(),
         }
     }
 
+    /// Extract each archive into the given directory the first time its
+    /// contents are read, so that an archive is decoded at most once no
+    /// matter how many of its entries are converted.
+    pub(crate) fn set_extract_dir(&mut self, dir: PathBuf, effects: Effects) {
+        self.extract_dir = Some((dir, effects));
+    }
+
     /// Get a file by its identifier.
     #[inline]
     pub(crate) fn file(&self, id: FileId) -> Result<&Link> {
@@ -394,6 +1121,18 @@ impl Db {
         Ok(archive)
     }
 
+    /// Get the tags read from a source while planning, if any.
+    #[inline]
+    pub(crate) fn meta(&self, source: &Source) -> Option<&Meta> {
+        self.metas.get(source)
+    }
+
+    /// Store the tags read from a source.
+    #[inline]
+    pub(crate) fn insert_meta(&mut self, source: Source, meta: Meta) {
+        self.metas.insert(source, meta);
+    }
+
     /// Push a file to the collection.
     #[inline]
     pub(crate) fn push_file(&mut self, file: Link) -> FileId {
@@ -407,20 +1146,89 @@ impl Db {
     pub(crate) fn push_archive(&mut self, archive: SourceArchive) -> ArchiveId {
         let id = ArchiveId(self.archives.len());
         self.archives.push(archive);
+        self.extracted.push(OnceLock::new());
         id
     }
 
+    /// Discover the sources in a walked file, which is either the file itself
+    /// or the entries of an archive.
+    pub(crate) fn discover(
+        &mut self,
+        walked: &Path,
+        ext: &str,
+        sources: &mut Vec<Source>,
+    ) -> Result<()> {
+        let enumerated = enumerate(walked, ext)?;
+        self.discover_entries(walked, enumerated, sources)
+    }
+
+    /// Discover sources in the walked path, where `enumerated` are the entries
+    /// of the walked path if it's an archive as returned by [`enumerate`].
+    pub(crate) fn discover_entries(
+        &mut self,
+        walked: &Path,
+        enumerated: Option<Enumerated>,
+        sources: &mut Vec<Source>,
+    ) -> Result<()> {
+        if let Some(Enumerated { kind, entries }) = enumerated {
+            let archive_id = self.push_archive(SourceArchive {
+                kind,
+                path: Link::new(walked)?,
+            });
+
+            for (path, size) in entries {
+                let ok = !path.components().any(|c| matches!(c, Component::ParentDir));
+
+                trace!(archive = %shell::path(walked), %path, ok, "archive entry");
+
+                if ok {
+                    let source = Source::Archive {
+                        archive: archive_id,
+                        path,
+                    };
+
+                    self.archive_sizes.insert(source.clone(), size);
+                    sources.push(source);
+                }
+            }
+        } else {
+            let file = self.push_file(Link::new(walked)?);
+            let source = Source::File { file };
+            sources.push(source);
+        }
+
+        Ok(())
+    }
+
     /// Get the contents of the source file.
     pub(crate) fn archive_contents(
         &self,
         archive: ArchiveId,
         path: &RelativePath,
     ) -> Result<Vec<u8>> {
-        let Some(archive) = self.archives.get(archive.0) else {
+        let (Some(source), Some(extracted)) =
+            (self.archives.get(archive.0), self.extracted.get(archive.0))
+        else {
             anyhow::bail!("invalid archive id: {archive}");
         };
 
-        archive.contents(path)
+        if let Some((extract_dir, effects)) = &self.extract_dir {
+            let dir = extracted.get_or_init(|| {
+                let dir = extract_dir.join(archive.to_string());
+                extract(effects, source, &dir).ok().map(|()| dir)
+            });
+
+            if let Some(dir) = dir {
+                let extracted = path.to_path(dir);
+
+                if extracted.is_file() {
+                    return fs::read(&extracted)
+                        .with_context(|| anyhow!("reading {}", extracted.display()));
+                }
+            }
+        }
+
+        source.contents(path)
     }
 
     /// Append the relative source path to the given path.
@@ -507,14 +1315,20 @@ impl Db {
         }
     }
 
-    pub(crate) fn move_to(&self, source: &Source, to: &Path, kind: TransferKind) -> Result<()> {
+    pub(crate) fn move_to(
+        &self,
+        effects: &Effects,
+        source: &Source,
+        to: &Path,
+        kind: TransferKind,
+    ) -> Result<()> {
         match source {
             Source::Archive { archive, path } => match kind {
                 TransferKind::Link => bail!("cannot link from archive"),
                 TransferKind::Move => bail!("cannot move from archive"),
                 TransferKind::Copy => {
                     let contents = self.archive_contents(*archive, path)?;
-                    fs::write(to, contents).context("writing file")?;
+                    effects.write(to, contents).context("writing file")?;
                 }
             },
             Source::File { file } => {
@@ -522,13 +1336,13 @@ impl Db {
 
                 match kind {
                     TransferKind::Link => {
-                        fs::hard_link(file, to).context("creating hard link")?;
+                        effects.hard_link(file, to).context("creating hard link")?;
                     }
                     TransferKind::Move => {
-                        fs::rename(file, to).context("moving file")?;
+                        effects.rename(file, to).context("moving file")?;
                     }
                     TransferKind::Copy => {
-                        fs::copy(file, to).context("copying file")?;
+                        effects.copy(file, to).context("copying file")?;
                     }
                 }
             }
@@ -563,6 +1377,42 @@ impl Db {
         Ok(())
     }
 
+    /// Describe a source as a path, where sources in archives are described
+    /// as a path inside of the archive.
+    pub(crate) fn describe(&self, source: &Source) -> Result<String> {
+        match source {
+            Source::File { file } => Ok(shell::path(self.file(*file)?).to_string()),
+            Source::Archive { archive, path } => {
+                let archive = self.archive(*archive)?;
+                Ok(format!("{}/{path}", shell::path(&archive.path)))
+            }
+        }
+    }
+
+    /// Get the size of the source in bytes.
+    pub(crate) fn size(&self, source: &Source) -> Result<Option<u64>> {
+        match source {
+            Source::File { file } => {
+                let file = self.file(*file)?;
+                let metadata = fs::metadata(file)
+                    .with_context(|| anyhow!("Failed to get metadata of {}", file.display()))?;
+                Ok(Some(metadata.len()))
+            }
+            Source::Archive { .. } => Ok(self.archive_sizes.get(source).copied()),
+        }
+    }
+
+    /// Get when a source was last modified, which for sources in archives is
+    /// when the archive was.
+    pub(crate) fn modified(&self, source: &Source) -> Result<Option<SystemTime>> {
+        let path = match source {
+            Source::File { file } => self.file(*file)?.as_ref(),
+            Source::Archive { archive, .. } => self.archive(*archive)?.path.as_ref(),
+        };
+
+        Ok(fs::metadata(path).and_then(|m| m.modified()).ok())
+    }
+
     /// Get the file path if the source is a regular file.
     pub(crate) fn as_file<'a>(&'a self, source: &'a Source) -> Result<Option<&'a Path>> {
         match source {
@@ -0,0 +1,317 @@
+use core::time::Duration;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+
+/// The number of frames per second in cue sheet timestamps.
+const FRAMES: u64 = 75;
+
+/// A track of a cue sheet, which is converted into a file of its own with
+/// `--split-cue`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct Split {
+    /// Where the track starts in the source.
+    pub(crate) start: Duration,
+    /// Where the track ends in the source, or `None` if it runs to the end.
+    pub(crate) end: Option<Duration>,
+    /// The number of the track.
+    pub(crate) track: u32,
+    /// The number of tracks in the source.
+    pub(crate) total: u32,
+    pub(crate) title: Option<String>,
+    /// The performer of the track, if it differs from the album.
+    pub(crate) performer: Option<String>,
+    pub(crate) album_artist: Option<String>,
+    pub(crate) album: Option<String>,
+    pub(crate) year: Option<i16>,
+}
+
+impl Split {
+    /// The name of the file the track is converted into when it isn't
+    /// organized by its tags, like `01 - Title`.
+    pub(crate) fn file_name(&self) -> String {
+        match &self.title {
+            Some(title) => format!("{:02} - {title}", self.track),
+            None => format!("{:02}", self.track),
+        }
+    }
+
+    /// The duration of the track, given the duration of the source.
+    pub(crate) fn duration(&self, total: Duration) -> Duration {
+        self.end.unwrap_or(total).saturating_sub(self.start)
+    }
+
+    /// Add arguments before the input of an ffmpeg conversion which select
+    /// the track.
+    pub(crate) fn input_args(&self, command: &mut Command) {
+        command.args(["-ss", &seconds(self.start)]);
+
+        if let Some(end) = self.end {
+            command.args(["-to", &seconds(end)]);
+        }
+    }
+
+    /// Add arguments to an ffmpeg conversion which tag the output with the
+    /// track.
+    pub(crate) fn metadata_args(&self, command: &mut Command) {
+        let mut metadata = |key: &str, value: &str| {
+            command.arg("-metadata");
+            command.arg(format!("{key}={value}"));
+        };
+
+        metadata("track", &format!("{}/{}", self.track, self.total));
+
+        if let Some(title) = &self.title {
+            metadata("title", title);
+        }
+
+        if let Some(artist) = self.performer.as_ref().or(self.album_artist.as_ref()) {
+            metadata("artist", artist);
+        }
+
+        if let Some(album_artist) = &self.album_artist {
+            metadata("album_artist", album_artist);
+        }
+
+        if let Some(album) = &self.album {
+            metadata("album", album);
+        }
+
+        if let Some(year) = self.year {
+            metadata("date", &year.to_string());
+        }
+    }
+}
+
+fn seconds(d: Duration) -> String {
+    format!("{:.6}", d.as_secs_f64())
+}
+
+/// Find the cue sheet next to a source and read the tracks it has for it.
+///
+/// A cue sheet is named like the source with its extension replaced or with
+/// `.cue` added, like `album.cue` or `album.flac.cue`.
+pub(crate) fn find(path: &Path) -> Result<Option<(PathBuf, Vec<Split>)>> {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return Ok(None);
+    };
+
+    for cue in [path.with_extension("cue"), path.with_added_extension("cue")] {
+        if !cue.is_file() {
+            continue;
+        }
+
+        let contents =
+            fs::read(&cue).with_context(|| anyhow!("Failed to read {}", cue.display()))?;
+
+        let splits = parse(&decode(&contents), name)
+            .with_context(|| anyhow!("Failed to parse {}", cue.display()))?;
+
+        if !splits.is_empty() {
+            return Ok(Some((cue, splits)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Decode a cue sheet, which are commonly written by rippers in a legacy
+/// encoding if they aren't UTF-8.
+fn decode(contents: &[u8]) -> String {
+    let contents = contents.strip_prefix(b"\xef\xbb\xbf").unwrap_or(contents);
+
+    match core::str::from_utf8(contents) {
+        Ok(s) => s.to_owned(),
+        Err(..) => contents.iter().map(|&b| char::from(b)).collect(),
+    }
+}
+
+#[derive(Default)]
+struct Track {
+    number: u32,
+    title: Option<String>,
+    performer: Option<String>,
+    start: Option<Duration>,
+}
+
+/// Parse the tracks of a cue sheet which belong to the file with the given
+/// name.
+///
+/// A cue sheet which only references a single file is assumed to belong to
+/// the source, since files are often renamed after ripping.
+fn parse(contents: &str, name: &str) -> Result<Vec<Split>> {
+    let mut album_artist = None;
+    let mut album = None;
+    let mut year = None;
+    let mut files = Vec::<(String, Vec<Track>)>::new();
+
+    for (n, line) in contents.lines().enumerate() {
+        let mut args = Args::new(line);
+
+        let Some(command) = args.next() else {
+            continue;
+        };
+
+        match command.to_ascii_uppercase().as_str() {
+            "REM"
+                if args
+                    .next()
+                    .is_some_and(|key| key.eq_ignore_ascii_case("DATE")) =>
+            {
+                year = args.next().and_then(|date| date.get(..4)?.parse().ok());
+            }
+            "FILE" => {
+                let Some(file) = args.next() else {
+                    bail!("Line {}: Missing file name", n + 1);
+                };
+
+                files.push((file, Vec::new()));
+            }
+            "TRACK" => {
+                let Some((_, tracks)) = files.last_mut() else {
+                    bail!("Line {}: Track before any file", n + 1);
+                };
+
+                let Some(number) = args.next().and_then(|n| n.parse().ok()) else {
+                    bail!("Line {}: Invalid track number", n + 1);
+                };
+
+                tracks.push(Track {
+                    number,
+                    ..Track::default()
+                });
+            }
+            "TITLE" => match current(&mut files) {
+                Some(track) => track.title = args.next(),
+                None => album = args.next(),
+            },
+            "PERFORMER" => match current(&mut files) {
+                Some(track) => track.performer = args.next(),
+                None => album_artist = args.next(),
+            },
+            "INDEX" => {
+                let Some(track) = current(&mut files) else {
+                    continue;
+                };
+
+                if args.next().as_deref() != Some("01") {
+                    continue;
+                }
+
+                let Some(start) = args.next().and_then(|t| timestamp(&t)) else {
+                    bail!("Line {}: Invalid index", n + 1);
+                };
+
+                track.start = Some(start);
+            }
+            _ => {}
+        }
+    }
+
+    let tracks = match &files[..] {
+        [(_, tracks)] => tracks,
+        files => {
+            let file = files.iter().find(|(file, _)| {
+                Path::new(file.as_str())
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.eq_ignore_ascii_case(name))
+            });
+
+            let Some((_, tracks)) = file else {
+                return Ok(Vec::new());
+            };
+
+            tracks
+        }
+    };
+
+    let total = u32::try_from(tracks.len()).unwrap_or(u32::MAX);
+    let mut splits = Vec::with_capacity(tracks.len());
+
+    for (n, track) in tracks.iter().enumerate() {
+        let Some(start) = track.start else {
+            bail!("Track {} has no start", track.number);
+        };
+
+        let end = tracks.get(n + 1).and_then(|next| next.start);
+
+        splits.push(Split {
+            start,
+            end,
+            track: track.number,
+            total,
+            title: track.title.clone(),
+            performer: track
+                .performer
+                .clone()
+                .filter(|p| album_artist.as_ref() != Some(p)),
+            album_artist: album_artist.clone(),
+            album: album.clone(),
+            year,
+        });
+    }
+
+    Ok(splits)
+}
+
+/// The track currently being parsed, which commands apply to instead of the
+/// album.
+fn current(files: &mut [(String, Vec<Track>)]) -> Option<&mut Track> {
+    let (_, tracks) = files.last_mut()?;
+    tracks.last_mut()
+}
+
+/// Parse a timestamp like `mm:ss:ff`, where `ff` are frames.
+fn timestamp(s: &str) -> Option<Duration> {
+    let mut parts = s.split(':');
+    let minutes = parts.next()?.parse::<u64>().ok()?;
+    let seconds = parts.next()?.parse::<u64>().ok()?;
+    let frames = parts.next()?.parse::<u64>().ok()?;
+
+    if parts.next().is_some() || seconds >= 60 || frames >= FRAMES {
+        return None;
+    }
+
+    let frames = (minutes * 60 + seconds) * FRAMES + frames;
+    Some(Duration::from_nanos(frames * 1_000_000_000 / FRAMES))
+}
+
+/// The arguments of a line in a cue sheet, where quoted arguments can contain
+/// spaces.
+struct Args<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Args<'a> {
+    fn new(line: &'a str) -> Self {
+        Self { rest: line }
+    }
+}
+
+impl Iterator for Args<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.rest.trim_start();
+
+        if rest.is_empty() {
+            return None;
+        }
+
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let (arg, rest) = quoted.split_once('"').unwrap_or((quoted, ""));
+            self.rest = rest;
+            return Some(arg.to_owned());
+        }
+
+        let (arg, rest) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        self.rest = rest;
+        Some(arg.to_owned())
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{Context, Result, anyhow};
+use mediavert_core::out::{Out, blank, info, warn};
+use mediavert_core::shell;
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+
+/// An output which has been seen during the run.
+struct Seen {
+    path: PathBuf,
+    modified: SystemTime,
+}
+
+/// Finds outputs which are identical to an earlier output of the run, and
+/// replaces them with hard links to it.
+#[derive(Default)]
+pub(crate) struct Dedup {
+    /// The first output with a given size and digest.
+    seen: HashMap<(u64, [u8; 32]), Seen>,
+}
+
+impl Dedup {
+    /// Check a completed output, replacing it with a hard link to an identical
+    /// earlier output if there is one.
+    ///
+    /// Returns `true` if the output was replaced.
+    pub(crate) fn check(&mut self, o: &mut Out<'_>, config: &Config, path: &Path) -> Result<bool> {
+        let metadata =
+            fs::metadata(path).with_context(|| anyhow!("Failed to stat {}", path.display()))?;
+        let key = (metadata.len(), digest(path)?);
+
+        let first = match self.seen.entry(key) {
+            Entry::Vacant(e) => {
+                e.insert(Seen {
+                    path: path.to_owned(),
+                    modified: metadata.modified()?,
+                });
+
+                return Ok(false);
+            }
+            Entry::Occupied(e) => e.into_mut(),
+        };
+
+        // The first output might have been replaced since it was seen, in
+        // which case this output takes its place.
+        let unchanged = fs::metadata(&first.path)
+            .and_then(|m| m.modified())
+            .is_ok_and(|modified| modified == first.modified);
+
+        if !unchanged {
+            first.path = path.to_owned();
+            first.modified = metadata.modified()?;
+            return Ok(false);
+        }
+
+        if first.path == path || same_file(&first.path, &metadata)? {
+            return Ok(false);
+        }
+
+        info!(o, "linking identical output");
+        let mut o = o.indent(1);
+        blank!(
+            o,
+            "ln -f {} {}",
+            shell::path(&first.path),
+            shell::path(path)
+        );
+
+        let part = path.with_added_extension(&config.part_ext);
+
+        let result = config
+            .effects
+            .hard_link(&first.path, &part)
+            .and_then(|()| config.effects.rename(&part, path));
+
+        if let Err(e) = result {
+            _ = config.effects.remove_file(&part);
+            warn!(o, "{e}");
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+}
+
+/// Compute the digest of a file without reading all of it into memory.
+fn digest(path: &Path) -> Result<[u8; 32]> {
+    let mut file =
+        File::open(path).with_context(|| anyhow!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)
+        .with_context(|| anyhow!("Failed to read {}", path.display()))?;
+    Ok(hasher.finalize().into())
+}
+
+/// Test if `path` is the same file as the one `metadata` was read from.
+#[cfg(unix)]
+fn same_file(path: &Path, metadata: &fs::Metadata) -> Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    let other = fs::metadata(path).with_context(|| anyhow!("Failed to stat {}", path.display()))?;
+    Ok(other.dev() == metadata.dev() && other.ino() == metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn same_file(_: &Path, _: &fs::Metadata) -> Result<bool> {
+    Ok(false)
+}
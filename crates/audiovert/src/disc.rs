@@ -0,0 +1,158 @@
+use core::error::Error;
+use core::fmt::{self, Write};
+use core::str::FromStr;
+
+#[derive(Debug)]
+pub(crate) enum DiscTemplateErr {
+    UnclosedPlaceholder,
+    UnknownPlaceholder(String),
+    MissingDisc,
+    InvalidCharacter(char),
+}
+
+impl fmt::Display for DiscTemplateErr {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnclosedPlaceholder => write!(f, "unclosed placeholder"),
+            Self::UnknownPlaceholder(name) => write!(f, "unknown placeholder '{{{name}}}'"),
+            Self::MissingDisc => write!(f, "missing '{{disc}}' placeholder"),
+            Self::InvalidCharacter(c) => write!(f, "invalid character '{c}'"),
+        }
+    }
+}
+
+impl Error for DiscTemplateErr {}
+
+#[derive(Clone)]
+enum Segment {
+    Text(String),
+    Disc,
+    Total,
+    MediaType,
+    Track,
+}
+
+/// A template for the folder which holds a disc of a multi-disc album, or for
+/// the track number in the file name if it contains `{track}`.
+#[derive(Clone)]
+pub(crate) struct DiscTemplate {
+    segments: Vec<Segment>,
+}
+
+impl DiscTemplate {
+    /// Test if the template flattens discs into the file name instead of
+    /// separating them into folders.
+    pub(crate) fn is_flat(&self) -> bool {
+        self.segments.iter().any(|s| matches!(s, Segment::Track))
+    }
+
+    /// Render the template into the given buffer.
+    pub(crate) fn render(
+        &self,
+        out: &mut String,
+        disc: u32,
+        total: u32,
+        media_type: Option<&str>,
+        track: u32,
+    ) {
+        let start = out.len();
+
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => out.push_str(text),
+                Segment::Disc => _ = write!(out, "{disc:02}"),
+                Segment::Total => _ = write!(out, "{total:02}"),
+                Segment::MediaType => out.push_str(media_type.unwrap_or_default()),
+                Segment::Track => _ = write!(out, "{track:02}"),
+            }
+        }
+
+        // Placeholders which are missing, like the media type, shouldn't leave
+        // stray whitespace behind.
+        let trimmed = out[start..].trim();
+
+        if trimmed.len() != out.len() - start {
+            let trimmed = trimmed.to_owned();
+            out.truncate(start);
+            out.push_str(&trimmed);
+        }
+    }
+}
+
+impl Default for DiscTemplate {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            segments: vec![
+                Segment::MediaType,
+                Segment::Text(String::from(" ")),
+                Segment::Disc,
+            ],
+        }
+    }
+}
+
+impl FromStr for DiscTemplate {
+    type Err = DiscTemplateErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut rest = s;
+
+        while !rest.is_empty() {
+            let Some((text, tail)) = rest.split_once('{') else {
+                segments.push(Segment::Text(rest.to_owned()));
+                break;
+            };
+
+            if !text.is_empty() {
+                segments.push(Segment::Text(text.to_owned()));
+            }
+
+            let (name, tail) = tail
+                .split_once('}')
+                .ok_or(DiscTemplateErr::UnclosedPlaceholder)?;
+
+            segments.push(match name {
+                "disc" => Segment::Disc,
+                "total" => Segment::Total,
+                "media_type" => Segment::MediaType,
+                "track" => Segment::Track,
+                _ => return Err(DiscTemplateErr::UnknownPlaceholder(name.to_owned())),
+            });
+
+            rest = tail;
+        }
+
+        for segment in &segments {
+            if let Segment::Text(text) = segment
+                && let Some(c) = text.chars().find(|c| matches!(c, '/' | '\\' | '}'))
+            {
+                return Err(DiscTemplateErr::InvalidCharacter(c));
+            }
+        }
+
+        if !segments.iter().any(|s| matches!(s, Segment::Disc)) {
+            return Err(DiscTemplateErr::MissingDisc);
+        }
+
+        Ok(Self { segments })
+    }
+}
+
+impl fmt::Display for DiscTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => f.write_str(text)?,
+                Segment::Disc => f.write_str("{disc}")?,
+                Segment::Total => f.write_str("{total}")?,
+                Segment::MediaType => f.write_str("{media_type}")?,
+                Segment::Track => f.write_str("{track}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::format::{Format, FormatErr};
+
+#[derive(Debug)]
+pub(crate) enum EncoderErr {
+    MissingSeparator,
+    InvalidFormat(FormatErr),
+    MissingEncoder,
+}
+
+impl fmt::Display for EncoderErr {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingSeparator => write!(f, "missing '=' separator"),
+            Self::InvalidFormat(e) => write!(f, "invalid format: {e}"),
+            Self::MissingEncoder => write!(f, "missing encoder"),
+        }
+    }
+}
+
+impl Error for EncoderErr {}
+
+impl From<FormatErr> for EncoderErr {
+    #[inline]
+    fn from(e: FormatErr) -> Self {
+        EncoderErr::InvalidFormat(e)
+    }
+}
+
+/// The ffmpeg encoder used to produce files in a given format, like
+/// `aac=libfdk_aac`.
+#[derive(Clone)]
+pub(crate) struct Encoder {
+    pub(crate) format: Format,
+    pub(crate) encoder: String,
+}
+
+impl FromStr for Encoder {
+    type Err = EncoderErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format, encoder) = s.split_once('=').ok_or(EncoderErr::MissingSeparator)?;
+        let encoder = encoder.trim();
+
+        if encoder.is_empty() {
+            return Err(EncoderErr::MissingEncoder);
+        }
+
+        Ok(Encoder {
+            format: format.parse()?,
+            encoder: encoder.to_owned(),
+        })
+    }
+}
+
+impl fmt::Display for Encoder {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.format, self.encoder)
+    }
+}
@@ -0,0 +1,64 @@
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::format::{Format, FormatErr};
+
+#[derive(Debug)]
+pub(crate) enum ExtOverrideErr {
+    MissingSeparator,
+    InvalidFormat(FormatErr),
+    InvalidExtension,
+}
+
+impl fmt::Display for ExtOverrideErr {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingSeparator => write!(f, "missing '=' separator"),
+            Self::InvalidFormat(e) => write!(f, "invalid format: {e}"),
+            Self::InvalidExtension => write!(f, "invalid extension"),
+        }
+    }
+}
+
+impl Error for ExtOverrideErr {}
+
+impl From<FormatErr> for ExtOverrideErr {
+    #[inline]
+    fn from(e: FormatErr) -> Self {
+        ExtOverrideErr::InvalidFormat(e)
+    }
+}
+
+/// An override of the extension of files produced in a given format.
+#[derive(Clone)]
+pub(crate) struct ExtOverride {
+    pub(crate) format: Format,
+    pub(crate) ext: String,
+}
+
+impl FromStr for ExtOverride {
+    type Err = ExtOverrideErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format, ext) = s.split_once('=').ok_or(ExtOverrideErr::MissingSeparator)?;
+        let ext = ext.strip_prefix('.').unwrap_or(ext);
+
+        if ext.is_empty() || ext.contains(['.', '/', '\\']) {
+            return Err(ExtOverrideErr::InvalidExtension);
+        }
+
+        Ok(ExtOverride {
+            format: format.parse()?,
+            ext: ext.to_owned(),
+        })
+    }
+}
+
+impl fmt::Display for ExtOverride {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.format, self.ext)
+    }
+}
@@ -21,7 +21,14 @@ impl Error for FormatErr {}
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub(crate) enum Format {
     Aac,
+    Ac3,
+    /// Lossless ALAC audio in an MP4 container.
+    Alac,
+    Dts,
     Flac,
+    /// Lossy AAC audio in an MP4 container.
+    M4a,
+    Mp2,
     Mp3,
     Ogg,
     Wav,
@@ -32,9 +39,23 @@ impl Format {
     pub(crate) const DEFAULT_BITRATE_MP3: u32 = 320;
     pub(crate) const DEFAULT_BITRATE_OGG: u32 = 192;
 
+    /// Every supported format, in the order they're listed in.
+    pub(crate) const ALL: [Format; 10] = [
+        Format::Aac,
+        Format::Ac3,
+        Format::Alac,
+        Format::Dts,
+        Format::Flac,
+        Format::M4a,
+        Format::Mp2,
+        Format::Mp3,
+        Format::Ogg,
+        Format::Wav,
+    ];
+
     pub(crate) fn default_bitrate(&self) -> Option<u32> {
         match self {
-            Format::Aac => Some(Format::DEFAULT_BITRATE_AAC),
+            Format::Aac | Format::M4a => Some(Format::DEFAULT_BITRATE_AAC),
             Format::Mp3 => Some(Format::DEFAULT_BITRATE_MP3),
             Format::Ogg => Some(Format::DEFAULT_BITRATE_OGG),
             _ => None,
@@ -42,11 +63,34 @@ impl Format {
     }
 
     pub(crate) fn is_lossless(&self) -> bool {
-        matches!(self, Format::Flac | Format::Wav)
+        matches!(self, Format::Alac | Format::Flac | Format::Wav)
+    }
+
+    /// Test if files can be converted from the format, but not to it.
+    pub(crate) fn is_input_only(&self) -> bool {
+        matches!(self, Format::Ac3 | Format::Dts | Format::Mp2)
     }
 
-    pub(crate) fn bitrate(&self, config: &Config, command: &mut Command) {
-        if let Some(bitrate) = config.bitrates.get(self)
+    /// Test if ffmpeg can store artwork in files of the format.
+    pub(crate) fn has_artwork(&self) -> bool {
+        matches!(self, Format::Flac | Format::Mp3)
+    }
+
+    /// Test if tags can be read from files in the format.
+    pub(crate) fn has_tags(&self) -> bool {
+        !matches!(self, Format::Ac3 | Format::Dts)
+    }
+
+    pub(crate) fn bitrate(&self, config: &Config, bitrate: Option<u32>, command: &mut Command) {
+        if bitrate.is_none()
+            && let Some(quality) = config.bitrates.quality(self)
+        {
+            command.arg("-q:a");
+            command.arg(quality.to_string());
+            return;
+        }
+
+        if let Some(bitrate) = bitrate.or_else(|| config.bitrates.get(self))
             && bitrate > 0
         {
             command.arg("-ab");
@@ -54,10 +98,38 @@ impl Format {
         }
     }
 
+    /// Parse a quality level for variable bitrate encoding in the format, like
+    /// `V0` for mp3 or `6` for ogg.
+    pub(crate) fn parse_quality(&self, s: &str) -> Option<f32> {
+        let (s, range) = match self {
+            Format::Mp3 => (s.strip_prefix(['V', 'v']).unwrap_or(s), 0.0..=9.0),
+            Format::Ogg => (s, -1.0..=10.0),
+            Format::Aac | Format::M4a => (s, 0.1..=2.0),
+            _ => return None,
+        };
+
+        let quality = s.parse::<f32>().ok()?;
+        range.contains(&quality).then_some(quality)
+    }
+
+    /// The name of the format, which is the same as its extension unless
+    /// several formats share one.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Format::Alac => "alac",
+            _ => self.ext(),
+        }
+    }
+
     pub(crate) fn ext(&self) -> &'static str {
         match self {
             Format::Aac => "aac",
+            Format::Ac3 => "ac3",
+            Format::Alac => "m4a",
+            Format::Dts => "dts",
             Format::Flac => "flac",
+            Format::M4a => "m4a",
+            Format::Mp2 => "mp2",
             Format::Mp3 => "mp3",
             Format::Ogg => "ogg",
             Format::Wav => "wav",
@@ -67,17 +139,39 @@ impl Format {
     pub(crate) fn ffmpeg_format(&self) -> &'static str {
         match self {
             Format::Aac => "adts",
+            Format::Ac3 => "ac3",
+            Format::Alac => "ipod",
+            Format::Dts => "dts",
             Format::Flac => "flac",
+            Format::M4a => "ipod",
+            Format::Mp2 => "mp2",
             Format::Mp3 => "mp3",
             Format::Ogg => "ogg",
             Format::Wav => "wav",
         }
     }
 
+    /// The codec ffmpeg encodes the format with, if it's not the default one
+    /// of its container.
+    pub(crate) fn ffmpeg_codec(&self) -> Option<&'static str> {
+        match self {
+            Format::Alac => Some("alac"),
+            _ => None,
+        }
+    }
+
+    /// Get the format of files with the given extension.
+    ///
+    /// Since ALAC and AAC share the `m4a` extension, such files have to be
+    /// probed to tell if they are [`Format::Alac`].
     pub(crate) fn from_ext(ext: &str) -> Option<Format> {
         match ext {
             "aac" => Some(Format::Aac),
+            "ac3" => Some(Format::Ac3),
+            "dts" => Some(Format::Dts),
             "flac" => Some(Format::Flac),
+            "m4a" => Some(Format::M4a),
+            "mp2" => Some(Format::Mp2),
             "mp3" => Some(Format::Mp3),
             "ogg" => Some(Format::Ogg),
             "wav" => Some(Format::Wav),
@@ -89,7 +183,7 @@ impl Format {
 impl fmt::Display for Format {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.ext().fmt(f)
+        self.name().fmt(f)
     }
 }
 
@@ -98,6 +192,9 @@ impl FromStr for Format {
 
     #[inline]
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Self::from_ext(s).ok_or(FormatErr)
+        match s {
+            "alac" => Ok(Format::Alac),
+            _ => Self::from_ext(s).ok_or(FormatErr),
+        }
     }
 }
@@ -0,0 +1,69 @@
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::format::{Format, FormatErr};
+
+#[derive(Debug)]
+pub(crate) enum InputArgsErr {
+    MissingSeparator,
+    InvalidFormat(FormatErr),
+    MissingArgs,
+}
+
+impl fmt::Display for InputArgsErr {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingSeparator => write!(f, "missing '=' separator"),
+            Self::InvalidFormat(e) => write!(f, "invalid format: {e}"),
+            Self::MissingArgs => write!(f, "missing arguments"),
+        }
+    }
+}
+
+impl Error for InputArgsErr {}
+
+impl From<FormatErr> for InputArgsErr {
+    #[inline]
+    fn from(e: FormatErr) -> Self {
+        InputArgsErr::InvalidFormat(e)
+    }
+}
+
+/// Arguments passed to ffmpeg before the `-i` of sources in a given format.
+///
+/// Arguments are separated by whitespace.
+#[derive(Clone)]
+pub(crate) struct InputArgs {
+    pub(crate) format: Format,
+    pub(crate) args: Vec<String>,
+}
+
+impl FromStr for InputArgs {
+    type Err = InputArgsErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format, args) = s.split_once('=').ok_or(InputArgsErr::MissingSeparator)?;
+        let args = args
+            .split_whitespace()
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+
+        if args.is_empty() {
+            return Err(InputArgsErr::MissingArgs);
+        }
+
+        Ok(InputArgs {
+            format: format.parse()?,
+            args,
+        })
+    }
+}
+
+impl fmt::Display for InputArgs {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.format, self.args.join(" "))
+    }
+}
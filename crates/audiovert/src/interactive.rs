@@ -0,0 +1,703 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{
+    Block, Clear, List, ListItem, ListState, Paragraph, Scrollbar, ScrollbarOrientation,
+    ScrollbarState,
+};
+
+use crate::config::Config;
+use crate::format::Format;
+use crate::keymap::{self, Action, Keymap};
+use crate::tasks::{TaskKind, Tasks};
+
+/// The state of an interactive review of planned tasks.
+pub(crate) struct State {
+    /// Planned tasks grouped by the directory of their destination.
+    groups: Vec<Group>,
+}
+
+/// Tasks which share a destination directory, typically an album.
+struct Group {
+    /// The destination directory, relative to `--to` if specified.
+    name: String,
+    /// The format picked for every task in the group, if it was changed.
+    format: Option<Format>,
+    entries: Vec<Entry>,
+}
+
+impl Group {
+    fn enabled(&self) -> usize {
+        self.entries.iter().filter(|e| e.enabled).count()
+    }
+}
+
+/// A single planned task.
+struct Entry {
+    /// The index of the task.
+    index: usize,
+    /// The file name of the destination.
+    name: String,
+    /// What the task does, like `converting flac to mp3`.
+    kind: String,
+    /// Whether the destination already exists, in which case the task is
+    /// skipped.
+    exists: bool,
+    /// Whether the format of the task can be changed.
+    retarget: bool,
+    enabled: bool,
+}
+
+impl State {
+    /// Group the planned tasks for review.
+    pub(crate) fn new(config: &Config, tasks: &Tasks) -> Self {
+        let mut groups = Vec::<Group>::new();
+        let mut by_dir = HashMap::<PathBuf, usize>::new();
+
+        for c in &tasks.tasks {
+            let dir = c.to_path.parent().unwrap_or(Path::new(""));
+
+            let n = *by_dir.entry(dir.to_path_buf()).or_insert_with(|| {
+                let name = match &config.to_dir {
+                    Some(to_dir) => dir.strip_prefix(to_dir).unwrap_or(dir),
+                    None => dir,
+                };
+
+                let name = if name.as_os_str().is_empty() {
+                    String::from(".")
+                } else {
+                    name.display().to_string()
+                };
+
+                groups.push(Group {
+                    name,
+                    format: None,
+                    entries: Vec::new(),
+                });
+
+                groups.len() - 1
+            });
+
+            groups[n].entries.push(Entry {
+                index: c.index,
+                name: c
+                    .to_path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+                kind: c.kind.to_string(),
+                exists: c.is_completed(),
+                retarget: !matches!(c.kind, TaskKind::External { .. }),
+                enabled: true,
+            });
+        }
+
+        Self { groups }
+    }
+
+    /// Apply the review to the planned tasks, changing the format of groups
+    /// and dropping deselected tasks.
+    pub(crate) fn apply(self, config: &Config, tasks: &mut Tasks) -> Result<()> {
+        let mut enabled = vec![true; tasks.tasks.len()];
+
+        for group in &self.groups {
+            for e in &group.entries {
+                enabled[e.index] = e.enabled;
+
+                if let Some(format) = group.format
+                    && e.retarget
+                    && e.enabled
+                    && !config.retarget(tasks, e.index, format)?
+                {
+                    enabled[e.index] = false;
+                }
+            }
+        }
+
+        tasks.retain(&enabled);
+        Ok(())
+    }
+
+    fn enabled(&self) -> usize {
+        self.groups.iter().map(Group::enabled).sum()
+    }
+
+    fn total(&self) -> usize {
+        self.groups.iter().map(|g| g.entries.len()).sum()
+    }
+}
+
+enum ViewEvent {
+    PushView(View),
+    PopView,
+    Finish,
+    None,
+}
+
+struct GroupsView {
+    index: usize,
+    list_state: ListState,
+}
+
+impl Default for GroupsView {
+    fn default() -> Self {
+        Self {
+            index: 1,
+            list_state: ListState::default(),
+        }
+    }
+}
+
+impl GroupsView {
+    fn update(&mut self, key: KeyEvent, state: &mut State) -> ViewEvent {
+        match keymap::GROUPS.action(key.code) {
+            Some(Action::Up) => {
+                self.index = self.index.saturating_sub(1);
+            }
+            Some(Action::Down) => {
+                self.index = self.index.saturating_add(1).min(state.groups.len());
+            }
+            Some(Action::Open) => {
+                if self.index == 0 {
+                    return ViewEvent::Finish;
+                }
+
+                return ViewEvent::PushView(View::Tasks(TasksView::new(self.index - 1)));
+            }
+            Some(Action::Toggle) => {
+                if let Some(group) = self
+                    .index
+                    .checked_sub(1)
+                    .and_then(|n| state.groups.get_mut(n))
+                {
+                    // Enable every task unless all of them already are.
+                    let enable = group.enabled() < group.entries.len();
+
+                    for e in &mut group.entries {
+                        e.enabled = enable;
+                    }
+                }
+            }
+            Some(Action::Format) => {
+                if let Some(n) = self.index.checked_sub(1)
+                    && let Some(group) = state.groups.get(n)
+                {
+                    return ViewEvent::PushView(View::Formats(FormatsView::new(n, group.format)));
+                }
+            }
+            Some(Action::Finish) => {
+                return ViewEvent::Finish;
+            }
+            Some(Action::Back) => {
+                return ViewEvent::PopView;
+            }
+            _ => {}
+        }
+
+        ViewEvent::None
+    }
+
+    fn draw(&mut self, state: &State, styles: &Styles, frame: &mut Frame) {
+        let sub_header = {
+            let is_selected = self.index == 0;
+            let marker = styles.selected(is_selected);
+            let style = styles.item_style(is_selected, true);
+
+            Line::from(Span::styled(
+                format!(
+                    "{marker} Run audiovert with {}/{} tasks selected",
+                    state.enabled(),
+                    state.total()
+                ),
+                style,
+            ))
+        };
+
+        let mut items = Vec::new();
+        let mut selected = None;
+
+        for (i, group) in state.groups.iter().enumerate() {
+            let is_selected = i.saturating_add(1) == self.index;
+            let enabled = group.enabled();
+
+            if is_selected {
+                selected = Some(items.len());
+            }
+
+            let marker = styles.selected(is_selected);
+            let style = styles.item_style(is_selected, enabled > 0);
+
+            let mut line = Line::from(Span::styled(format!("{marker} {}", group.name), style));
+
+            if let Some(format) = group.format {
+                line.push_span(Span::styled(
+                    format!(" → {format}"),
+                    styles.header_hint_style(),
+                ));
+            }
+
+            line.push_span(Span::styled(
+                format!(" ({enabled}/{} tasks)", group.entries.len()),
+                styles.dim_style(),
+            ));
+
+            items.push(ListItem::new(line));
+        }
+
+        self.list_state.select(selected);
+
+        let mut scrollbar_state = ScrollbarState::new(items.len())
+            .position(self.list_state.selected().unwrap_or_default());
+
+        let header = Line::from(vec![
+            Span::styled("Albums", styles.header_style()),
+            Span::styled(
+                keymap::GROUPS.hint(&keymap::GLOBAL),
+                styles.header_hint_style(),
+            ),
+        ]);
+
+        let separator = Line::from(Span::styled(
+            "─".repeat(frame.area().width as usize),
+            styles.dim_style(),
+        ));
+
+        let layout = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Min(1),
+        ])
+        .split(frame.area());
+
+        let list = List::new(items);
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight);
+
+        frame.render_widget(header, layout[0]);
+        frame.render_widget(sub_header, layout[1]);
+        frame.render_widget(separator, layout[2]);
+        frame.render_stateful_widget(list, layout[3], &mut self.list_state);
+        frame.render_stateful_widget(scrollbar, layout[3], &mut scrollbar_state);
+    }
+}
+
+struct TasksView {
+    group: usize,
+    index: usize,
+    list_state: ListState,
+}
+
+impl TasksView {
+    fn new(group: usize) -> Self {
+        Self {
+            group,
+            index: 0,
+            list_state: ListState::default(),
+        }
+    }
+
+    fn update(&mut self, key: KeyEvent, state: &mut State) -> ViewEvent {
+        let Some(group) = state.groups.get_mut(self.group) else {
+            return ViewEvent::PopView;
+        };
+
+        match keymap::TASKS.action(key.code) {
+            Some(Action::Up) => {
+                self.index = self.index.saturating_sub(1);
+            }
+            Some(Action::Down) => {
+                self.index = self
+                    .index
+                    .saturating_add(1)
+                    .min(group.entries.len().saturating_sub(1));
+            }
+            Some(Action::Toggle) => {
+                if let Some(e) = group.entries.get_mut(self.index) {
+                    e.enabled = !e.enabled;
+                }
+            }
+            Some(Action::Format) => {
+                return ViewEvent::PushView(View::Formats(FormatsView::new(
+                    self.group,
+                    group.format,
+                )));
+            }
+            Some(Action::Back) => {
+                return ViewEvent::PopView;
+            }
+            _ => {}
+        }
+
+        ViewEvent::None
+    }
+
+    fn draw(&mut self, state: &State, styles: &Styles, frame: &mut Frame) {
+        let Some(group) = state.groups.get(self.group) else {
+            return;
+        };
+
+        let mut items = Vec::new();
+        let mut selected = None;
+
+        for (i, e) in group.entries.iter().enumerate() {
+            let is_selected = i == self.index;
+
+            if is_selected {
+                selected = Some(items.len());
+            }
+
+            let marker = styles.selected(is_selected);
+            let check = if e.enabled { "[x]" } else { "[ ]" };
+            let style = styles.item_style(is_selected, e.enabled);
+
+            let mut line = Line::from(Span::styled(format!("{marker} {check} {}", e.name), style));
+
+            if e.exists {
+                line.push_span(Span::styled(" (exists)", styles.header_hint_style()));
+            }
+
+            items.push(ListItem::new(line));
+
+            let kind = match group.format {
+                Some(format) if e.retarget => format!("    {} (to {format})", e.kind),
+                _ => format!("    {}", e.kind),
+            };
+
+            items.push(ListItem::new(Span::styled(kind, styles.dim_style())));
+        }
+
+        self.list_state.select(selected);
+
+        let mut scrollbar_state = ScrollbarState::new(items.len())
+            .position(self.list_state.selected().unwrap_or_default());
+
+        let header = Line::from(vec![
+            Span::styled(
+                format!("{} - Select tasks", group.name),
+                styles.header_style(),
+            ),
+            Span::styled(
+                keymap::TASKS.hint(&keymap::GLOBAL),
+                styles.header_hint_style(),
+            ),
+        ]);
+
+        let list = List::new(items);
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight);
+
+        let layout =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(1)]).split(frame.area());
+
+        frame.render_widget(header, layout[0]);
+        frame.render_stateful_widget(list, layout[1], &mut self.list_state);
+        frame.render_stateful_widget(scrollbar, layout[1], &mut scrollbar_state);
+    }
+}
+
+struct FormatsView {
+    group: usize,
+    /// The highlighted format, where `0` keeps the planned formats.
+    index: usize,
+    list_state: ListState,
+}
+
+impl FormatsView {
+    fn new(group: usize, format: Option<Format>) -> Self {
+        Self {
+            group,
+            index: format_index(format),
+            list_state: ListState::default(),
+        }
+    }
+
+    fn update(&mut self, key: KeyEvent, state: &mut State) -> ViewEvent {
+        match keymap::FORMATS.action(key.code) {
+            Some(Action::Up) => {
+                self.index = self.index.saturating_sub(1);
+            }
+            Some(Action::Down) => {
+                self.index = self.index.saturating_add(1).min(Format::ALL.len());
+            }
+            Some(Action::Open) => {
+                if let Some(group) = state.groups.get_mut(self.group) {
+                    group.format = self
+                        .index
+                        .checked_sub(1)
+                        .and_then(|n| Format::ALL.get(n).copied());
+                }
+
+                return ViewEvent::PopView;
+            }
+            Some(Action::Back) => {
+                return ViewEvent::PopView;
+            }
+            _ => {}
+        }
+
+        ViewEvent::None
+    }
+
+    fn draw(&mut self, state: &State, styles: &Styles, frame: &mut Frame) {
+        let Some(group) = state.groups.get(self.group) else {
+            return;
+        };
+
+        let names = [String::from("(as planned)")]
+            .into_iter()
+            .chain(Format::ALL.iter().map(|f| f.to_string()));
+
+        let mut items = Vec::new();
+
+        for (i, name) in names.enumerate() {
+            let is_selected = i == self.index;
+            let is_picked = format_index(group.format) == i;
+
+            let marker = styles.selected(is_selected);
+            let style = styles.normal_item_style(is_selected, is_picked);
+            items.push(ListItem::new(Span::styled(
+                format!("{marker} {name}"),
+                style,
+            )));
+        }
+
+        self.list_state.select(Some(self.index));
+
+        let header = Line::from(vec![
+            Span::styled(
+                format!("{} - Change format", group.name),
+                styles.header_style(),
+            ),
+            Span::styled(
+                keymap::FORMATS.hint(&keymap::GLOBAL),
+                styles.header_hint_style(),
+            ),
+        ]);
+
+        let layout =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(1)]).split(frame.area());
+
+        frame.render_widget(header, layout[0]);
+        frame.render_stateful_widget(List::new(items), layout[1], &mut self.list_state);
+    }
+}
+
+enum View {
+    Groups(GroupsView),
+    Tasks(TasksView),
+    Formats(FormatsView),
+}
+
+impl View {
+    /// The keymap which is active in the view.
+    fn keymap(&self) -> &'static Keymap {
+        match self {
+            View::Groups(..) => &keymap::GROUPS,
+            View::Tasks(..) => &keymap::TASKS,
+            View::Formats(..) => &keymap::FORMATS,
+        }
+    }
+}
+
+/// Styling of the interactive review.
+pub(crate) struct Styles {
+    color: bool,
+}
+
+impl Styles {
+    /// Construct styles, which are uncolored if `color` is `false`.
+    pub(crate) fn new(color: bool) -> Self {
+        Self { color }
+    }
+
+    fn fg(&self, color: Color) -> Style {
+        if self.color {
+            Style::default().fg(color)
+        } else {
+            Style::default()
+        }
+    }
+
+    fn selected(&self, selected: bool) -> &'static str {
+        if selected { "*" } else { " " }
+    }
+
+    fn item_style(&self, selected: bool, done: bool) -> Style {
+        let s = self.fg(if done { Color::Green } else { Color::Red });
+
+        if selected {
+            s.add_modifier(Modifier::BOLD)
+        } else {
+            s
+        }
+    }
+
+    fn normal_item_style(&self, selected: bool, done: bool) -> Style {
+        let s = self.fg(if done { Color::Green } else { Color::Reset });
+
+        if selected {
+            s.add_modifier(Modifier::BOLD)
+        } else {
+            s
+        }
+    }
+
+    fn header_style(&self) -> Style {
+        self.fg(Color::Cyan).bold()
+    }
+
+    fn header_hint_style(&self) -> Style {
+        self.fg(Color::Cyan)
+    }
+
+    fn dim_style(&self) -> Style {
+        self.fg(Color::DarkGray)
+    }
+}
+
+/// The interactive review of audiovert.
+pub(crate) struct App {
+    views: Vec<View>,
+    help: bool,
+    styles: Styles,
+}
+
+impl App {
+    /// Construct a new interactive review using the given styles.
+    pub(crate) fn new(styles: Styles) -> Self {
+        Self {
+            views: Vec::new(),
+            help: false,
+            styles,
+        }
+    }
+
+    /// Run the interactive review, returning `false` if it was cancelled.
+    pub(crate) fn run(&mut self, state: &mut State) -> Result<bool> {
+        self.views.clear();
+        self.views.push(View::Groups(GroupsView::default()));
+        self.help = false;
+
+        let mut terminal = ratatui::init();
+
+        let outcome = loop {
+            let Some(view) = self.views.last_mut() else {
+                break false;
+            };
+
+            let help = self.help;
+
+            terminal.draw(|frame| {
+                match view {
+                    View::Groups(v) => v.draw(state, &self.styles, frame),
+                    View::Tasks(v) => v.draw(state, &self.styles, frame),
+                    View::Formats(v) => v.draw(state, &self.styles, frame),
+                }
+
+                if help {
+                    draw_help(view.keymap(), &self.styles, frame);
+                }
+            })?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            // Any key closes the help overlay.
+            if self.help {
+                self.help = false;
+                continue;
+            }
+
+            if keymap::GLOBAL.action(key.code) == Some(Action::Help) {
+                self.help = true;
+                continue;
+            }
+
+            let ev = match view {
+                View::Groups(v) => v.update(key, state),
+                View::Tasks(v) => v.update(key, state),
+                View::Formats(v) => v.update(key, state),
+            };
+
+            match ev {
+                ViewEvent::PushView(view) => {
+                    self.views.push(view);
+                }
+                ViewEvent::PopView => {
+                    self.views.pop();
+                }
+                ViewEvent::Finish => {
+                    break true;
+                }
+                ViewEvent::None => {}
+            }
+        };
+
+        ratatui::restore();
+        Ok(outcome)
+    }
+}
+
+/// The index of a format in the list of formats, where `0` keeps the planned
+/// formats.
+fn format_index(format: Option<Format>) -> usize {
+    format
+        .and_then(|f| Format::ALL.iter().position(|a| *a == f))
+        .map_or(0, |n| n + 1)
+}
+
+/// Draw a help overlay listing the key bindings of the given keymap.
+fn draw_help(keymap: &Keymap, styles: &Styles, frame: &mut Frame) {
+    let mut lines = Vec::new();
+
+    for keymap in [keymap, &keymap::GLOBAL] {
+        if !lines.is_empty() {
+            lines.push(Line::default());
+        }
+
+        lines.push(Line::from(Span::styled(
+            keymap.title,
+            styles.header_style(),
+        )));
+
+        for (keys, description) in keymap.entries() {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {keys:<20}"), styles.header_hint_style()),
+                Span::raw(description),
+            ]));
+        }
+    }
+
+    lines.push(Line::default());
+    lines.push(Line::from(Span::styled(
+        "Press any key to close",
+        styles.dim_style(),
+    )));
+
+    let area = frame.area();
+    let width = area.width.min(60);
+    let height = area.height.min(lines.len() as u16 + 2);
+
+    let area = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    let help = Paragraph::new(lines).block(Block::bordered().title(" Help "));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(help, area);
+}
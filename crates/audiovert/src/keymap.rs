@@ -0,0 +1,146 @@
+use mediavert_core::keymap;
+use ratatui::crossterm::event::KeyCode::{Char, Down, Enter, Esc, Left, Right, Up};
+
+/// Actions which can be bound to keys in the interactive review.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Action {
+    Up,
+    Down,
+    Open,
+    Back,
+    Toggle,
+    Format,
+    Finish,
+    Help,
+}
+
+/// A single key binding.
+pub(crate) type Binding = keymap::Binding<Action>;
+
+/// The key bindings of a single view.
+pub(crate) type Keymap = keymap::Keymap<Action>;
+
+/// Bindings which are available in every view.
+pub(crate) const GLOBAL: Keymap = Keymap {
+    title: "Global",
+    bindings: &[Binding {
+        codes: &[Char('?')],
+        action: Action::Help,
+        description: "help",
+        hint: true,
+    }],
+};
+
+pub(crate) const GROUPS: Keymap = Keymap {
+    title: "Albums",
+    bindings: &[
+        Binding {
+            codes: &[Up, Char('k')],
+            action: Action::Up,
+            description: "move up",
+            hint: false,
+        },
+        Binding {
+            codes: &[Down, Char('j')],
+            action: Action::Down,
+            description: "move down",
+            hint: false,
+        },
+        Binding {
+            codes: &[Enter, Char('o'), Right, Char('l')],
+            action: Action::Open,
+            description: "select",
+            hint: true,
+        },
+        Binding {
+            codes: &[Char(' ')],
+            action: Action::Toggle,
+            description: "toggle",
+            hint: true,
+        },
+        Binding {
+            codes: &[Char('f')],
+            action: Action::Format,
+            description: "change format",
+            hint: true,
+        },
+        Binding {
+            codes: &[Char('x')],
+            action: Action::Finish,
+            description: "run the selected tasks",
+            hint: false,
+        },
+        Binding {
+            codes: &[Esc, Char('q')],
+            action: Action::Back,
+            description: "quit",
+            hint: true,
+        },
+    ],
+};
+
+pub(crate) const TASKS: Keymap = Keymap {
+    title: "Tasks",
+    bindings: &[
+        Binding {
+            codes: &[Up, Char('k')],
+            action: Action::Up,
+            description: "move up",
+            hint: false,
+        },
+        Binding {
+            codes: &[Down, Char('j')],
+            action: Action::Down,
+            description: "move down",
+            hint: false,
+        },
+        Binding {
+            codes: &[Enter, Char(' ')],
+            action: Action::Toggle,
+            description: "toggle",
+            hint: true,
+        },
+        Binding {
+            codes: &[Char('f')],
+            action: Action::Format,
+            description: "change format of the album",
+            hint: true,
+        },
+        Binding {
+            codes: &[Esc, Char('q'), Left, Char('h')],
+            action: Action::Back,
+            description: "go back",
+            hint: true,
+        },
+    ],
+};
+
+pub(crate) const FORMATS: Keymap = Keymap {
+    title: "Change format",
+    bindings: &[
+        Binding {
+            codes: &[Up, Char('k')],
+            action: Action::Up,
+            description: "move up",
+            hint: false,
+        },
+        Binding {
+            codes: &[Down, Char('j')],
+            action: Action::Down,
+            description: "move down",
+            hint: false,
+        },
+        Binding {
+            codes: &[Enter, Char('o'), Char(' ')],
+            action: Action::Open,
+            description: "pick",
+            hint: true,
+        },
+        Binding {
+            codes: &[Esc, Char('q'), Left, Char('h')],
+            action: Action::Back,
+            description: "go back",
+            hint: true,
+        },
+    ],
+};
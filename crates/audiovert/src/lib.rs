@@ -11,13 +11,43 @@
 //! lossy files will be hard linked to the target directory, but the exact
 //! behavior can be configured using commandline arguments.
 //!
+//! Audio from TV rips and old DAB recordings in ac3, dts, or mp2 is treated as
+//! lossy. It can be converted to other formats, but not produced.
+//!
+//! Files with the `m4a` extension hold either lossy AAC or lossless ALAC audio,
+//! which is told apart by probing the codec of each file. Conditions use `m4a`
+//! for AAC and `alac` for ALAC, so `lossless` matches ALAC files but not AAC
+//! ones, and `-c alac=flac` converts only the lossless ones.
+//!
 //! Unless `--to <dir>` is specified, conversions are performed in-placed, the
 //! source file will not be moved unless `--trash-source` or `--remove-source`
 //! is specified.
 //!
+//! Files which already exist at the destination are skipped unless `--force` is
+//! used. With `--sync` they are only produced again if their source has been
+//! modified since, which keeps the destination a one-way mirror of the sources
+//! across repeated runs:
+//!
+//! ```sh
+//! toolkit --sync music --to phone
+//! ```
+//!
+//! To make the destination an exact mirror, `--prune` additionally trashes files in
+//! the `--to` directory which don't correspond to any source once the run has
+//! finished, such as conversions of sources which have since been removed or
+//! renamed, and removes directories left empty by it. Hidden files and partial
+//! conversion files are left alone, and nothing is pruned if any source couldn't be
+//! planned. It can't be combined with `--trash-source` or `--move`, since the outputs
+//! of sources which have been trashed or moved away would be pruned by the next run:
+//!
+//! ```sh
+//! toolkit --sync --prune music --to phone
+//! ```
+//!
 //! If any archives are encountered (zip, rar, 7z), they will be extracted
-//! in-memory and treated as-if they are files inside of a folder named the same
-//! as the archive.
+//! and treated as-if they are files inside of a folder named the same as the
+//! archive. Each archive is decoded once per run into the temporary directory of
+//! the run, regardless of how many of its files are converted.
 //!
 //! So if you have an archive like `music.zip` containing `song1.flac` and
 //! `song2.flac` it will be treated as if it was a directory like:
@@ -41,23 +71,564 @@
 //! toolkit --dry-run unsorted --to sorted
 //! ```
 //!
+//! To make sure that a dry run doesn't change anything, use `--read-only`
+//! instead. Every operation which modifies the filesystem then goes through a
+//! single guarded layer which refuses it with an error, so a missed check fails
+//! loudly instead of touching your files. Temporary files, reports, events and
+//! exported plans are still written.
+//!
 //! Once this looks good, you can run the command without `--dry-run`.
 //!
 //! ```sh
 //! toolkit --to sorted
 //! ```
+//!
+//! Before a run starts, the number of files it would overwrite with `--force`,
+//! trash with `--trash-source`, or move away with `--move` is counted. When
+//! running in a terminal and more than 100 files would be affected you're asked to
+//! confirm first, which can be adjusted with `--confirm-above` or skipped with
+//! `--yes`. To put a hard limit on it which also applies to unattended runs, use
+//! `--max-removals`:
+//!
+//! ```sh
+//! toolkit --to sorted --force --trash-source --max-removals 50
+//! ```
+//!
+//! Directories left empty once sources have been trashed or moved are removed with
+//! `--prune-empty`, walking upwards from each source but never past the path it was
+//! found in. It takes the number of directory levels to remove at most, or `all`:
+//!
+//! ```sh
+//! toolkit --trash-source --prune-empty all unsorted --to sorted
+//! ```
+//!
+//! Albums ripped into a single lossless file with a cue sheet next to it, like
+//! `album.flac` and `album.cue`, can be split into one file per track with
+//! `--split-cue`. Each track is tagged from the cue sheet and named like `01 - Title`,
+//! or organized by the tags from the cue sheet with `--meta`:
+//!
+//! ```sh
+//! toolkit --split-cue unsorted --to sorted
+//! ```
+//!
+//! Converted files can be brought to a consistent loudness with `--normalize`,
+//! which measures each source with ffmpeg's `loudnorm` filter before converting it.
+//! It takes `ebur128` to target -23 LUFS, or a target of its own like `ebur128:-16`.
+//! With `--normalize-album` the files converted into the same directory get the same
+//! gain instead, so that quiet tracks of an album stay quiet:
+//!
+//! ```sh
+//! toolkit --normalize ebur128:-16 --normalize-album unsorted --to sorted
+//! ```
+//!
+//! To leave the audio alone and let players adjust the volume instead, use
+//! `--replaygain track` to write ReplayGain tags with the gain of each file, or
+//! `--replaygain album` to also write the gain of the album it belongs to:
+//!
+//! ```sh
+//! toolkit --replaygain album unsorted --to sorted
+//! ```
+//!
+//! Loudness isn't measured with `--dry-run`, since it requires decoding every source.
+//!
+//! Hi-res sources can be brought down to what a device or format can handle with
+//! `--max-sample-rate` and `--max-bit-depth`, which only affect sources that exceed
+//! them. Reducing to 16 bits is dithered:
+//!
+//! ```sh
+//! toolkit -c flac=mp3 --max-sample-rate 48000 --max-bit-depth 16 unsorted --to sorted
+//! ```
+//!
+//! Converted files can be checked before they are renamed into place with
+//! `--verify`, which decodes them in full and compares their duration with the
+//! source. Files which fail are removed and their tasks are reported as failed, so
+//! that they are converted again by the next run.
+//!
+//! Conversions which fail can be retried with `--retries`, which waits a second
+//! before the first retry and twice as long before each one after it. This helps
+//! with network mounts which occasionally make ffmpeg fail midway:
+//!
+//! ```sh
+//! toolkit --retries 3 /mnt/nas/unsorted --to sorted
+//! ```
+//!
+//! A broken source can make ffmpeg hang. With `--task-timeout`, conversions which
+//! run for longer than the given duration, like `10m` or `1h30m`, are killed and
+//! their tasks are reported as failed, so that the rest of the run can proceed.
+//!
+//! Files which aren't audio, like cue sheets, rip logs, or cover images, are
+//! reported as unsupported and left behind. They can be carried along to the
+//! directory the files next to them end up in with `--copy-sidecar`, which takes a
+//! pattern matching their names and can be specified multiple times:
+//!
+//! ```sh
+//! toolkit --copy-sidecar '*.cue' --copy-sidecar '*.log' --copy-sidecar 'cover.jpg' unsorted --to sorted
+//! ```
+//!
+//! To get an overview of a library before deciding how to convert it, use
+//! `--stats`. This only scans the sources and reports the number and size of files
+//! in each format, bitrate range, archive type, and top-level directory, along with
+//! how many files are missing tags:
+//!
+//! ```sh
+//! toolkit --stats unsorted
+//! ```
+//!
+//! To review a large migration in a spreadsheet, the planned tasks can be
+//! exported as CSV with one row per task using `--export-plan`. Each row has the
+//! source, destination, kind of task, formats, and the size of the source along
+//! with the estimated size of the destination where it can be determined:
+//!
+//! ```sh
+//! toolkit --dry-run unsorted --to sorted --export-plan plan.csv
+//! ```
+//!
+//! A single file can be converted to a path of your choosing with `--output`. The
+//! format is taken from the extension of the path, while bitrates, tags and artwork
+//! are handled like in any other run:
+//!
+//! ```sh
+//! toolkit song.flac --output ~/phone/song.mp3
+//! ```
+//!
+//! Multiple sources might produce the same file, like two files with identical tags
+//! or a file which is both inside of an archive and extracted next to it. This is
+//! reported as an error before anything is run, unless `--on-collision skip` is used
+//! to only produce it from the first source, or `--on-collision rename` to add a
+//! number to the name of later files, like `Song (2).mp3`.
+//!
+//! Tasks run in the order they were planned in by default. If a run might be
+//! interrupted, use `--order smallest-first` to get as many files done as possible
+//! early, or `--order largest-first` or `--order lossless-first` to start the
+//! longest conversions first.
+//!
+//! Formats are encoded at a constant bitrate set with `--bitrates`. Use `--quality`
+//! to encode them with a variable bitrate instead, like `V0` for mp3 or `6` for ogg:
+//!
+//! ```sh
+//! toolkit -c lossless=mp3 --quality mp3=V0 music --to sorted
+//! ```
+//!
+//! A conversion can carry its own bitrate after an `@`, which takes precedence over
+//! `--bitrates`. This way a single run can produce both a high quality and a low
+//! bandwidth copy of the same sources:
+//!
+//! ```sh
+//! toolkit -c flac=ogg@128 -c flac=mp3@320 music --to sorted
+//! ```
+//!
+//! Some players expect a different extension than the one a format is normally
+//! saved with, like `.oga` for ogg or `.m4b` for audiobooks in aac. The extension
+//! of produced files can be changed with `--ext-override` without changing the
+//! format they're written in:
+//!
+//! ```sh
+//! toolkit -c lossless=ogg --ext-override ogg=oga unsorted --to sorted
+//! ```
+//!
+//! Sources which ffmpeg struggles to read, like wav files with broken headers, can be
+//! given extra input options with `--input-args`. These are passed before the `-i` of
+//! every source in the given format:
+//!
+//! ```sh
+//! toolkit --input-args wav="-ignore_length 1" unsorted --to sorted
+//! ```
+//!
+//! The encoder used to produce a format can be changed with `--encoder`, and extra
+//! output options are passed with `--ffmpeg-arg`, one argument at a time and in the
+//! order they're given:
+//!
+//! ```sh
+//! toolkit -c lossless=aac --encoder aac=libfdk_aac --ffmpeg-arg aac=-vbr --ffmpeg-arg aac=5 unsorted --to sorted
+//! ```
+//!
+//! Chapters of long-form audio like audiobooks and live sets are copied into
+//! converted files which can store them. Since many players ignore chapters embedded
+//! in mp3 and ogg files, they can also be written to a sidecar next to each
+//! converted file with `--chapters-sidecar`, either as a cue sheet or as a text file
+//! listing the start time of each chapter:
+//!
+//! ```sh
+//! toolkit -c lossless=mp3 --chapters-sidecar cue audiobooks --to sorted
+//! ```
+//!
+//! Sources with more than one audio stream, like TV rips with alternate languages
+//! or commentary, have the first audio stream converted with a warning. Another one
+//! can be selected by its index or language with `--stream`:
+//!
+//! ```sh
+//! toolkit -c mp2=mp3 --stream jpn recordings --to sorted
+//! ```
+//!
+//! When organizing files by their tags with `--meta`, each disc of a multi-disc
+//! album is put in a folder of its own named like `CD 01`. The name can be changed
+//! with `--disc-template`, and a template containing `{track}` puts the disc number
+//! in the file name instead of a folder. With `--min-discs 3` double albums are
+//! kept in a single folder:
+//!
+//! ```sh
+//! toolkit --meta --disc-template 'CD{disc}' --min-discs 3 unsorted --to sorted
+//! toolkit --meta --disc-template '{disc}-{track}' unsorted --to sorted
+//! ```
+//!
+//! The whole layout can be replaced with `--meta-template`, where `/` separates
+//! directories and the extension is added to the last component. The placeholders
+//! `{artist}`, `{album}`, `{year}`, `{track}`, `{title}`, `{disc}`, `{total}`, and
+//! `{media_type}` are available, numbers can be padded like `{track:02}`, and
+//! segments in brackets are only included if all of their placeholders have a value.
+//! Since `{disc}` only has a value for albums with at least `--min-discs` discs, this
+//! puts discs in folders of their own only for multi-disc albums:
+//!
+//! ```sh
+//! toolkit --meta --meta-template '{artist}/{album} ({year})/[CD{disc}/]{track:02} - {title}' unsorted --to sorted
+//! ```
+//!
+//! Files which are missing tags, or are in a format which doesn't carry any, can
+//! have them filled in from their path with `--meta-from-path`. The pattern takes
+//! the same placeholders and is matched against the last components of the path
+//! with the extension removed. Tags which are present always take precedence:
+//!
+//! ```sh
+//! toolkit --meta --meta-from-path '{artist}/{album} ({year})/{track} - {title}' unsorted --to sorted
+//! ```
+//!
+//! What is still missing after that can be looked up on MusicBrainz with
+//! `--musicbrainz`. Files are searched for by their title along with their artist or
+//! album, or otherwise by their AcoustID fingerprint computed with `fpcalc` if an API
+//! key is specified with `--acoustid-key` or `acoustid-key` in the `[audiovert]`
+//! section of the configuration file. Requests are limited to what the services
+//! allow, and their results are cached in `mediavert/musicbrainz.json` in the user's
+//! cache directory so that files are only looked up once:
+//!
+//! ```sh
+//! toolkit --meta --musicbrainz --acoustid-key KEY unsorted --to sorted
+//! ```
+//!
+//! Dry runs only use lookups which are already cached, and report the files which
+//! would have to be looked up.
+//!
+//! Some players, like many car stereos, refuse to display artwork which is too
+//! large or stored as a progressive jpeg. Artwork embedded in converted files can
+//! be downscaled with `--art-max-size` and stored as baseline jpeg with
+//! `--art-format jpeg`, which are also available as `--max-cover-size` and
+//! `--cover-format`:
+//!
+//! ```sh
+//! toolkit --art-max-size 1000 --art-format jpeg unsorted --to sorted
+//! ```
+//!
+//! Artwork which is lost when converting, like when converting to ogg, is embedded
+//! again from the source once the conversion has finished. Sources without artwork
+//! of their own can have an image next to them, like `cover.jpg` or `folder.png`,
+//! embedded with `--cover-from-folder`:
+//!
+//! ```sh
+//! toolkit --cover-from-folder unsorted --to sorted
+//! ```
+//!
+//! When running unattended, like on a headless server, a summary of the run can
+//! be sent to a webhook once it finishes with `--notify-url`. Use
+//! `--notify-format` to send a message understood by ntfy, Discord, or Matrix
+//! instead of the summary as JSON:
+//!
+//! ```sh
+//! toolkit --to sorted --notify-url https://ntfy.sh/my-topic --notify-format ntfy
+//! ```
+//!
+//! A command can also be run with the summary as JSON on its stdin using
+//! `--notify-cmd`.
+//!
+//! With `--watch` audiovert keeps running after converting the sources, and converts
+//! files as they are added to the source paths. New files are only picked up once
+//! they have stopped growing for `--watch-settle` seconds, so rips can be dropped
+//! into an inbox while they are still being copied:
+//!
+//! ```sh
+//! toolkit --watch --meta inbox --to sorted
+//! ```
+//!
+//! Albums can be synchronized to a phone or a cloud drive once all of their files
+//! have been converted into the `--to` directory with `--sync-to`. The target is
+//! passed to rsync, or to rclone if it is prefixed with `rclone:`:
+//!
+//! ```sh
+//! toolkit --to staging --sync-to phone:/sdcard/Music
+//! toolkit --to staging --sync-to rclone:drive:Music
+//! ```
+//!
+//! The amount of output is increased by stacking `-v` flags. With `-v` matching
+//! conversions and existing files are shown, `-vv` adds full command lines and how
+//! paths were resolved, and `-vvv` adds traces of tag probing and archive
+//! enumeration.
+//!
+//! Long runs can be correlated with other logs using `--timestamps`, which prefixes
+//! every line of output with the wall-clock time and reports how long each task took
+//! once it completes.
+//!
+//! Diagnostics can be enabled through the `RUST_LOG` environment variable, which
+//! is useful to debug individual files without the noise of `--verbose` for an
+//! entire run. Each task is recorded in a `task` span with the destination path
+//! as its `to` field:
+//!
+//! ```sh
+//! RUST_LOG='[task{to=.*Song.*}]=debug' toolkit --to sorted
+//! ```
+//!
+//! <br>
+//!
+//! ## Interactive review
+//!
+//! With `--interactive` (or `-i`) the planned tasks are reviewed in an interactive
+//! interface before anything is run. Tasks are grouped into albums by the directory
+//! they're written to, where individual tasks or whole albums can be deselected with
+//! `Space`, and the format an album is converted to can be changed with `f`. The
+//! selected tasks are run once `Run audiovert` is picked or `x` is pressed, and
+//! pressing `?` lists every key binding:
+//!
+//! ```sh
+//! toolkit -i unsorted --to sorted
+//! ```
+//!
+//! <br>
+//!
+//! ## Remote sources
+//!
+//! Sources can be specified as URLs, which are downloaded to a temporary spool
+//! directory before they are converted and removed once the run has finished.
+//! Since the spool is temporary, `--to` has to be specified. `http://` and
+//! `https://` URLs ending with a `/` are treated as directory listings, which are
+//! downloaded recursively:
+//!
+//! ```sh
+//! toolkit --to sorted https://nas.local/music/unsorted/
+//! ```
+//!
+//! Other schemes are fetched with a helper command configured in the `[remote]`
+//! section, where `{from}` is replaced with the URL and `{to}` with the directory
+//! to download into. The spool is stored in the temporary directory of the run:
+//!
+//! ```toml
+//! [remote.scp]
+//! command = ["scp", "-r", "{from}", "{to}"]
+//! ```
+//!
+//! Every run stores its temporary files in a directory of its own inside of
+//! `--tmp-dir`, the `tmp-dir` setting, or the system temporary directory, which is
+//! removed once the run finishes. Directories left behind by runs which crashed
+//! are removed with `--clean-temp`. This is only supported on Linux, since
+//! elsewhere it can't be told whether the run a directory belongs to is still
+//! going.
+//!
+//! <br>
+//!
+//! ## Configuration
+//!
+//! audiovert reads an optional configuration file from `mediavert/config.toml` in
+//! the user's configuration directory, such as `~/.config/mediavert/config.toml`
+//! on Linux. A different file can be specified with `--config`.
+//!
+//! Settings shared by all tools can be specified at the top level, and be
+//! overridden for audiovert in the `[audiovert]` section. Options specified on the
+//! command line take precedence over both:
+//!
+//! ```toml
+//! trash = "/mnt/storage/trash"
+//! color = "never"
+//!
+//! [audiovert]
+//! color = "always"
+//! ```
+//!
+//! Archives are enumerated and files are probed for tags on `jobs` threads while
+//! planning, which defaults to the number of available CPUs and can be overridden with
+//! `--jobs`. Tasks are planned in the same order regardless of how many jobs are used.
+//! Conversions are run one at a time unless `jobs` or `--jobs` is set, in which case as
+//! many conversions as there are jobs are run in parallel, with the output of each
+//! conversion written once it has finished so that it isn't interleaved with the output
+//! of others.
+//!
+//! Files with an extension audiovert doesn't support can be converted by an
+//! external command configured as a plugin in the `[audiovert.plugins]` section.
+//! Each plugin declares the format it produces with `to`, and in its `command`
+//! `{from}` is replaced with the source file and `{to}` with the partial file to
+//! write. For files inside of archives `{from}` is `-` and the file is written to
+//! the command's stdin. The output is verified to not be empty before it is
+//! renamed to its destination, just like built-in conversions:
+//!
+//! ```toml
+//! [audiovert.plugins.shn]
+//! to = "wav"
+//! command = ["shorten", "-x", "{from}", "{to}"]
+//! ```
+//!
+//! <br>
+//!
+//! ## Reports
+//!
+//! A self-contained HTML report of a run can be written with
+//! `--report html:<path>`, or as plain text with `--report text:<path>`. It contains a
+//! table per album of what was planned or done for each file along with its size
+//! before and after, where the size after is estimated during a dry run, and any
+//! warnings and errors encountered. Each file is marked with its outcome, like
+//! `✓ converted`, `✓ linked`, `= exists`, or `✗ failed`, where failed files are
+//! listed first and the heading of each album counts the outcomes in it.
+//!
+//! <br>
+//!
+//! ## Warnings
+//!
+//! Each warning belongs to a category, and the number of warnings in each category
+//! is listed at the end of a run. Categories which are expected, like images in
+//! album folders being reported as unsupported, can be silenced with `--suppress`:
+//! * `unsupported` - a file has an extension which isn't supported.
+//! * `already-exists` - the destination of a file already exists.
+//! * `multiple-streams` - a source has multiple audio streams and none was selected
+//!   with `--stream`. Suppressing it also skips probing the streams of sources
+//!   unless `--stream`, `--max-sample-rate`, `--max-bit-depth`, or `--verify`
+//!   needs them.
+//! * `probe-failed` - the audio streams of a source couldn't be probed.
+//!
+//! <br>
+//!
+//! ## Events
+//!
+//! Frontends can follow the progress of a run through a stream of JSON events
+//! written with `--events <path>`, one event per line. The path can be a named
+//! pipe or a file descriptor like `/dev/fd/3`.
+//!
+//! With `--output-format json` the same events are written to stdout instead of the
+//! colored output, so that the outcome of a run can be fed into scripts and
+//! dashboards. Since there is nowhere to ask, this never asks for confirmation:
+//!
+//! ```sh
+//! toolkit --output-format json unsorted --to sorted | jq 'select(.type == "error")'
+//! ```
+//!
+//! Every event has a `version` field with the version of the protocol, which is
+//! currently `1`, and a `type` field which is one of:
+//! * `plan` - the number of `tasks` which will be performed.
+//! * `task-planned` - a task with the given `index` has been planned, emitted for
+//!   every task after `plan` with the same fields as `task-start`.
+//! * `task-start` - a task with the given `index` has started, converting or
+//!   transferring `from` a source `to` a destination as described by `kind`.
+//! * `task-progress` - a task has reached a new `step`, which is one of `remove`,
+//!   `convert`, `tag`, `rename`, `transfer`, or `sync`.
+//! * `task-done` - a task has finished, where `ok` indicates if it succeeded.
+//! * `trashed` - a file described by `what` has been moved `from` where it was `to`
+//!   the trash.
+//! * `warning` - a warning `message` about an optional `path`, with the `code` of
+//!   its category.
+//! * `error` - an error `message` about an optional task `index` or `path`.
+//! * `summary` - the `summary` of the run, which is the last event emitted.
+//!
+//! ```json
+//! {"version":1,"type":"plan","tasks":1}
+//! {"version":1,"type":"task-start","index":0,"kind":"converting flac to mp3","from":"unsorted/song.flac","to":"sorted/song.mp3"}
+//! {"version":1,"type":"task-progress","index":0,"step":"convert"}
+//! {"version":1,"type":"task-done","index":0,"ok":true}
+//! ```
+//!
+//! <br>
+//!
+//! ## Control
+//!
+//! Pressing Ctrl-C stops a run once the current task has finished, and pressing it
+//! again aborts the current task immediately and removes its partial file. A run
+//! which is terminated, like when the system shuts down, is aborted the same way.
+//! An interrupted run reports how many tasks completed and how many remain, and
+//! lists the remaining ones with `-v`. Either way, running the same command
+//! again resumes where the run stopped since finished
+//! files are skipped. Library users can stop a run the same way through the
+//! `Cancel` token passed to `cli::entry_with`.
+//!
+//! For long runs the plan can be saved with `--checkpoint <path>`, which also
+//! records every task as it completes in a file next to it. An interrupted run is
+//! then continued with `--resume`, without walking the sources again or checking
+//! destinations that are already known to be finished:
+//!
+//! ```sh
+//! toolkit --to sorted unsorted --checkpoint run.json
+//! toolkit --to sorted --checkpoint run.json --resume
+//! ```
+//!
+//! Completed tasks are recorded along with when their source was last modified, so
+//! tasks whose source has been modified since the checkpoint was saved are done
+//! again when resuming. If their destination exists it's only replaced with
+//! `--force` or `--sync`.
+//!
+//! To avoid failing every remaining task once the target fills up, use
+//! `--min-free-space <MB>` to check the free space on the target before each task.
+//! When it drops below the threshold the run is stopped cleanly so that it can be
+//! resumed, or with `--on-low-space pause` it waits until space has been freed:
+//!
+//! ```sh
+//! toolkit --to sorted unsorted --checkpoint run.json --min-free-space 2048
+//! ```
+//!
+//! When duplicate sources or multiple conversions produce byte-identical files in
+//! different places, `--dedup-outputs` replaces the later copies with hard links to
+//! the first one to save space. Outputs are compared by their contents once they're
+//! complete.
+//!
+//! Files which are complete are skipped by later runs, so when storage might lose
+//! power use `--durable` to sync every converted or transferred file along with
+//! the directory it's in to disk once it has been moved into place. Otherwise a
+//! power loss right after a long conversion could leave an empty file behind
+//! which looks finished.
+//!
+//! A running batch can be controlled through a unix-domain socket with
+//! `--control <path>`. Clients send one command per line, which is one of
+//! `status`, `pause`, `resume`, or `stop`, and every command is answered with
+//! the status of the run as a line of JSON. A paused or stopped run finishes the
+//! task it is currently working on first. With `--watch` a stop only applies to
+//! the current run, and the next one starts once new files are found.
+//!
+//! ```sh
+//! echo pause | socat - UNIX-CONNECT:audiovert.sock
+//! {"state":"paused","completed":12,"total":40,"current":"converting flac to mp3 to sorted/song.mp3"}
+//! ```
 
 #![allow(clippy::drain_collect)]
 
-mod archive;
+mod art;
+mod bit_depth;
 mod bitrates;
+mod chapters;
+mod checkpoint;
 pub mod cli;
 mod condition;
 mod config;
+mod cue;
+mod dedup;
+mod disc;
+mod encoder;
+mod ext_override;
 mod format;
-mod link;
+mod input_args;
+mod interactive;
+mod keymap;
 mod meta;
-mod out;
+mod meta_pattern;
+mod meta_template;
+mod musicbrainz;
+mod normalize;
+mod output_arg;
+mod plan;
+mod pool;
+mod prune;
+mod replaygain;
+mod report;
 mod set_bit_rate;
-mod shell;
+mod set_quality;
+mod settings;
+mod sidecar;
+mod space;
+mod stats;
+mod stream;
+mod summary;
+mod sync;
 mod tasks;
+mod timeout;
+mod verify;
+mod warning;
+mod watch;
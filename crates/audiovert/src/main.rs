@@ -22,5 +22,6 @@ pub struct Opts {
 
 fn main() -> Result<()> {
     let opts = Opts::parse();
+    mediavert_core::log::init(opts.inner.verbosity());
     audiovert::cli::entry(&opts.inner)
 }
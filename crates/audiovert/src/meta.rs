@@ -1,19 +1,28 @@
 use core::str::FromStr;
+use core::time::Duration;
 
 use std::borrow::Cow;
-use std::io::Cursor;
+use std::fs::File;
+use std::io::{BufReader, Cursor};
 use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 use jiff::civil::Date;
-use lofty::config::WriteOptions;
+use lofty::config::{ParseOptions, WriteOptions};
 use lofty::file::{AudioFile, FileType, TaggedFile, TaggedFileExt};
+use lofty::mp4::{Mp4Codec, Mp4File};
+use lofty::picture::Picture;
 use lofty::probe::Probe;
 use lofty::tag::{ItemKey, ItemValue, Tag, TagItem, TagType};
+use mediavert_core::out::{Out, blank, info};
+use tracing::trace;
 
-use crate::config::{Db, Source};
+use crate::config::{Config, Db, Source};
+use crate::cue::Split;
 use crate::format::Format;
-use crate::out::{Out, blank, info};
+use crate::meta_template::{Field, Values};
+use crate::musicbrainz::Query;
+use crate::replaygain;
 
 pub(crate) struct Parts {
     year: i16,
@@ -26,35 +35,40 @@ pub(crate) struct Parts {
 }
 
 impl Parts {
+    /// Extract parts from the tags of a source.
+    ///
+    /// Tags which are missing are filled in by matching the path of the source
+    /// against `--meta-from-path`, and then by looking the source up on
+    /// MusicBrainz with `--musicbrainz`.
     pub(crate) fn from_path(
         source: &Source,
         db: &Db,
+        from: Format,
+        config: &Config,
         errors: &mut Vec<String>,
         tagged: &mut Option<Meta>,
     ) -> Result<Option<Self>> {
-        let file: TaggedFile = match source {
-            Source::File { file } => {
-                let path = db.file(*file)?;
-                lofty::read_from_path(path)?
-            }
-            Source::Archive { archive, path } => {
-                let contents = db.archive_contents(*archive, path)?;
-                let mut probe = Probe::new(Cursor::new(contents));
+        /// Tags always take precedence over values taken from the path.
+        const PATH_PRIORITY: u32 = 100;
+        /// Values found on MusicBrainz only fill in what's still missing.
+        const MUSICBRAINZ_PRIORITY: u32 = 200;
 
-                if let Some(file_type) = db.ext(source)?.and_then(FileType::from_ext) {
-                    probe = probe.set_file_type(file_type);
-                }
+        let pattern = config.meta_from_path.as_ref();
 
-                probe.read()?
-            }
+        let tag = if from.has_tags() {
+            let meta = tagged.get_or_insert(Meta::read(source, db)?);
+            meta.file.primary_tag()
+        } else {
+            None
         };
 
-        let meta = tagged.get_or_insert(Meta { file });
-
-        let Some(tag) = meta.file.primary_tag() else {
+        if tag.is_none() && pattern.is_none() && config.musicbrainz.is_none() {
             errors.push("missing primary tag".to_string());
             return Ok(None);
-        };
+        }
+
+        let path;
+        let found;
 
         /// A priority container.
         struct Prio<T> {
@@ -92,7 +106,7 @@ impl Parts {
             ) => {
                 $(let mut $name = Prio::new();)*
 
-                for item in tag.items() {
+                for item in tag.into_iter().flat_map(|tag| tag.items()) {
                     let value = item.value();
 
                     match item.key() {
@@ -164,6 +178,75 @@ impl Parts {
             T::from_str(s).ok()
         }
 
+        macro_rules! is_missing {
+            () => {
+                year.value.is_none()
+                    || album.value.is_none()
+                    || artist.value.is_none()
+                    || title.value.is_none()
+                    || track.value.is_none()
+            };
+        }
+
+        if let Some(pattern) = pattern
+            && is_missing!()
+        {
+            path = db.to_path(source)?;
+
+            match pattern.captures(&path) {
+                Some(captures) => {
+                    for (field, value) in captures {
+                        match field {
+                            Field::Artist => artist.update(Some(value), PATH_PRIORITY),
+                            Field::Album => album.update(Some(value), PATH_PRIORITY),
+                            Field::Year => year.update(value.parse().ok(), PATH_PRIORITY),
+                            Field::Track => track.update(value.parse().ok(), PATH_PRIORITY),
+                            Field::Title => title.update(Some(value), PATH_PRIORITY),
+                            Field::Disc => disc_number.update(value.parse().ok(), PATH_PRIORITY),
+                            Field::Total => disc_total.update(value.parse().ok(), PATH_PRIORITY),
+                            Field::MediaType => media_type.update(Some(value), PATH_PRIORITY),
+                        }
+                    }
+                }
+                None => {
+                    errors.push(format!("path doesn't match --meta-from-path {pattern}"));
+                }
+            }
+        }
+
+        if let Some(musicbrainz) = &config.musicbrainz
+            && is_missing!()
+        {
+            let query = Query {
+                artist: artist.value,
+                album: album.value,
+                title: title.value,
+            };
+
+            // Only files on disk can be fingerprinted.
+            let file = match source {
+                Source::File { .. } => Some(db.to_path(source)?),
+                Source::Archive { .. } => None,
+            };
+
+            match musicbrainz.lookup(&query, file.as_deref()) {
+                Ok(Some(f)) => {
+                    found = f;
+                    artist.update(found.artist.as_deref(), MUSICBRAINZ_PRIORITY);
+                    album.update(found.album.as_deref(), MUSICBRAINZ_PRIORITY);
+                    year.update(found.year, MUSICBRAINZ_PRIORITY);
+                    track.update(found.track, MUSICBRAINZ_PRIORITY);
+                    title.update(found.title.as_deref(), MUSICBRAINZ_PRIORITY);
+                }
+                Ok(None) => {
+                    errors.push("no match found on MusicBrainz".to_string());
+                }
+                Err(e) => {
+                    errors.push(format!("MusicBrainz lookup failed: {e:#}"));
+                }
+            }
+        }
+
         let mut value = || {
             if year.value.is_none() {
                 errors.push("missing year".to_string());
@@ -204,10 +287,77 @@ impl Parts {
         Ok(value())
     }
 
+    /// Construct parts for a track split from a source with `--split-cue`.
+    ///
+    /// Tags which are missing from the cue sheet are taken from the source.
+    pub(crate) fn from_split(
+        split: &Split,
+        source: Option<&Parts>,
+        errors: &mut Vec<String>,
+    ) -> Option<Self> {
+        let year = split.year.or(source.map(|p| p.year));
+
+        let artist = split
+            .album_artist
+            .clone()
+            .or_else(|| source.map(|p| p.artist.clone()));
+
+        let album = split
+            .album
+            .clone()
+            .or_else(|| source.map(|p| p.album.clone()));
+
+        if year.is_none() {
+            errors.push(format!("missing year for track {}", split.track));
+        }
+
+        if artist.is_none() {
+            errors.push(format!("missing artist for track {}", split.track));
+        }
+
+        if album.is_none() {
+            errors.push(format!("missing album for track {}", split.track));
+        }
+
+        if split.title.is_none() {
+            errors.push(format!("missing title for track {}", split.track));
+        }
+
+        Some(Self {
+            year: year?,
+            artist: artist?,
+            album: album?,
+            track: split.track,
+            title: split.title.clone()?,
+            media_type: source.and_then(|p| p.media_type.clone()),
+            set: source.and_then(|p| p.set),
+        })
+    }
+
     /// Append parts to a buffer.
-    pub(crate) fn append_to(&self, path: &mut PathBuf) {
+    pub(crate) fn append_to(&self, config: &Config, path: &mut PathBuf) {
         use core::fmt::Write;
 
+        if let Some(template) = &config.meta_template {
+            let values = Values {
+                artist: &self.artist,
+                album: &self.album,
+                year: self.year,
+                track: self.track,
+                title: &self.title,
+                media_type: self.media_type.as_deref(),
+                set: self
+                    .set
+                    .filter(|&(_, total)| total > 1 && total >= config.min_discs),
+            };
+
+            for component in template.render(&values) {
+                push_sanitized(path, &component);
+            }
+
+            return;
+        }
+
         let mut s = String::new();
 
         macro_rules! s {
@@ -221,34 +371,44 @@ impl Parts {
         push_sanitized(path, s!("{}", self.artist));
         push_sanitized(path, s!("{} ({})", &self.album, self.year));
 
-        if let Some((n, total)) = self.set
-            && total > 1
-        {
-            s.clear();
+        let media_type = self.media_type.as_deref();
+        let mut track = String::new();
 
-            if let Some(media_type) = &self.media_type {
-                s.push_str(media_type);
-                s.push(' ');
+        match self.set {
+            Some((n, total)) if total > 1 && total < config.min_discs => {
+                _ = write!(track, "{n:02}-{:02}", self.track);
+            }
+            Some((n, total)) if total > 1 && config.disc_template.is_flat() => {
+                config
+                    .disc_template
+                    .render(&mut track, n, total, media_type, self.track);
+            }
+            Some((n, total)) if total > 1 => {
+                s.clear();
+                config
+                    .disc_template
+                    .render(&mut s, n, total, media_type, self.track);
+                push_sanitized(path, &s);
+                _ = write!(track, "{:02}", self.track);
+            }
+            _ => {
+                _ = write!(track, "{:02}", self.track);
             }
-
-            _ = write!(s, "{n:02}");
-            push_sanitized(path, &s);
         }
 
         push_sanitized(
             path,
             s!(
-                "{} - {} - {:02} - {}",
+                "{} - {} - {track} - {}",
                 self.artist,
                 self.album,
-                self.track,
                 &self.title
             ),
         );
     }
 }
 
-fn push_sanitized(path: &mut PathBuf, s: &str) {
+pub(crate) fn push_sanitized(path: &mut PathBuf, s: &str) {
     path.push(sanitize(s).as_ref());
 }
 
@@ -325,11 +485,60 @@ pub(super) struct Meta {
 }
 
 impl Meta {
+    /// Probe the tags of a source.
+    pub(crate) fn read(source: &Source, db: &Db) -> Result<Self> {
+        let file: TaggedFile = match source {
+            Source::File { file } => {
+                let path = db.file(*file)?;
+                lofty::read_from_path(path)?
+            }
+            Source::Archive { archive, path } => {
+                let contents = db.archive_contents(*archive, path)?;
+                let mut probe = Probe::new(Cursor::new(contents));
+
+                if let Some(file_type) = db.ext(source)?.and_then(FileType::from_ext) {
+                    probe = probe.set_file_type(file_type);
+                }
+
+                probe.read()?
+            }
+        };
+
+        trace!(
+            file_type = ?file.file_type(),
+            tags = file.tags().len(),
+            "probed tags"
+        );
+
+        Ok(Meta { file })
+    }
+
+    /// Get the duration of the audio.
+    pub(crate) fn duration(&self) -> Duration {
+        self.file.properties().duration()
+    }
+
+    /// Get the bitrate of the audio in kbps, if known.
+    pub(crate) fn bitrate(&self) -> Option<u32> {
+        self.file.properties().audio_bitrate()
+    }
+
     /// Get the total number of tags.
     pub(crate) fn len(&self) -> u32 {
         self.file.tags().iter().map(|tag| tag.item_count()).sum()
     }
 
+    /// Get the artwork embedded in the tags.
+    pub(crate) fn pictures(&self) -> Vec<Picture> {
+        let mut pictures = Vec::new();
+
+        for tag in self.file.tags() {
+            pictures.extend(tag.pictures().iter().cloned());
+        }
+
+        pictures
+    }
+
     /// Dump tags to output.
     pub(crate) fn dump(&self, o: &mut Out<'_>) -> Result<()> {
         for tag in self.file.tags() {
@@ -344,14 +553,20 @@ impl Meta {
         Ok(())
     }
 
-    pub(crate) fn tag_file(&self, to: Format, path: &Path) -> Result<()> {
+    pub(crate) fn tag_file(&self, config: &Config, to: Format, path: &Path) -> Result<()> {
+        config.effects.check("tag", path)?;
+
         // First try to copy tags immediately.
         let Some(source_tag) = self.file.primary_tag() else {
             return Ok(());
         };
 
+        let Some(file_type) = format_file_type(to) else {
+            bail!("Cannot tag {to} files");
+        };
+
         let mut probe = Probe::open(path)?;
-        probe = probe.set_file_type(format_file_type(to));
+        probe = probe.set_file_type(file_type);
 
         let mut existing = probe.read()?;
 
@@ -363,7 +578,9 @@ impl Meta {
             // Primary method: try to insert the primary tag directly if it is
             // identical to the source tag type.
             if source_tag.tag_type() == tag_type {
-                existing.insert_tag(source_tag.clone());
+                let mut tag = source_tag.clone();
+                config.art.apply(&mut tag)?;
+                existing.insert_tag(tag);
                 break 'done;
             }
 
@@ -385,6 +602,107 @@ impl Meta {
     }
 }
 
+/// Embed artwork in a converted file unless it already has some, since
+/// conversions sometimes drop it.
+///
+/// Returns `false` if the file has artwork or its format can't store any.
+pub(crate) fn embed_art(
+    config: &Config,
+    to: Format,
+    path: &Path,
+    pictures: Vec<Picture>,
+) -> Result<bool> {
+    config.effects.check("tag", path)?;
+
+    let Some(file_type) = format_file_type(to) else {
+        return Ok(false);
+    };
+
+    let mut probe = Probe::open(path)?;
+    probe = probe.set_file_type(file_type);
+
+    let mut existing = probe.read()?;
+
+    if existing.tags().iter().any(|tag| !tag.pictures().is_empty()) {
+        return Ok(false);
+    }
+
+    let tag_type = existing.primary_tag_type();
+
+    if !matches!(
+        tag_type,
+        TagType::Id3v2 | TagType::VorbisComments | TagType::Mp4Ilst
+    ) {
+        return Ok(false);
+    }
+
+    if existing.primary_tag().is_none() {
+        existing.insert_tag(Tag::new(tag_type));
+    }
+
+    let Some(tag) = existing.primary_tag_mut() else {
+        return Ok(false);
+    };
+
+    for picture in pictures {
+        tag.push_picture(picture);
+    }
+
+    config.art.apply(tag)?;
+
+    let mut options = WriteOptions::default();
+    options.use_id3v23(true);
+    existing.save_to_path(path, options)?;
+    Ok(true)
+}
+
+/// Write ReplayGain tags to a converted file.
+///
+/// Returns `false` if its format can't store them.
+pub(crate) fn tag_replaygain(
+    config: &Config,
+    to: Format,
+    path: &Path,
+    values: &replaygain::Values,
+) -> Result<bool> {
+    config.effects.check("tag", path)?;
+
+    let Some(file_type) = format_file_type(to) else {
+        return Ok(false);
+    };
+
+    let mut probe = Probe::open(path)?;
+    probe = probe.set_file_type(file_type);
+
+    let mut existing = probe.read()?;
+
+    let tag_type = existing.primary_tag_type();
+
+    if !matches!(
+        tag_type,
+        TagType::Id3v2 | TagType::VorbisComments | TagType::Mp4Ilst | TagType::Ape
+    ) {
+        return Ok(false);
+    }
+
+    if existing.primary_tag().is_none() {
+        existing.insert_tag(Tag::new(tag_type));
+    }
+
+    let Some(tag) = existing.primary_tag_mut() else {
+        return Ok(false);
+    };
+
+    for (key, value) in values.items() {
+        tag.insert_text(key, value);
+    }
+
+    let mut options = WriteOptions::default();
+    options.use_id3v23(true);
+    existing.save_to_path(path, options)?;
+    Ok(true)
+}
+
 fn dump_tag_item(o: &mut Out<'_>, item: &TagItem) -> Result<()> {
     info!(o, "{:?}:", item.key());
     let mut o = o.indent(1);
@@ -404,13 +722,33 @@ fn dump_tag_item(o: &mut Out<'_>, item: &TagItem) -> Result<()> {
     Ok(())
 }
 
-fn format_file_type(format: Format) -> FileType {
+/// Test if an MP4 source holds lossless ALAC audio rather than AAC.
+pub(crate) fn is_alac(source: &Source, db: &Db) -> Result<bool> {
+    let options = ParseOptions::new().read_tags(false);
+
+    let file = match source {
+        Source::File { file } => {
+            let path = db.file(*file)?;
+            Mp4File::read_from(&mut BufReader::new(File::open(path)?), options)?
+        }
+        Source::Archive { archive, path } => {
+            let contents = db.archive_contents(*archive, path)?;
+            Mp4File::read_from(&mut Cursor::new(contents), options)?
+        }
+    };
+
+    Ok(*file.properties().codec() == Mp4Codec::ALAC)
+}
+
+fn format_file_type(format: Format) -> Option<FileType> {
     match format {
-        Format::Aac => FileType::Aac,
-        Format::Flac => FileType::Flac,
-        Format::Mp3 => FileType::Mpeg,
-        Format::Ogg => FileType::Vorbis,
-        Format::Wav => FileType::Wav,
+        Format::Aac => Some(FileType::Aac),
+        Format::Flac => Some(FileType::Flac),
+        Format::Alac | Format::M4a => Some(FileType::Mp4),
+        Format::Mp2 | Format::Mp3 => Some(FileType::Mpeg),
+        Format::Ogg => Some(FileType::Vorbis),
+        Format::Wav => Some(FileType::Wav),
+        Format::Ac3 | Format::Dts => None,
     }
 }
 
@@ -0,0 +1,193 @@
+use core::error::Error;
+use core::fmt::{self, Write};
+use core::str::FromStr;
+
+use std::path::{Component, Path};
+
+use crate::meta_template::Field;
+
+#[derive(Debug)]
+pub(crate) enum MetaPatternErr {
+    UnclosedPlaceholder,
+    UnknownPlaceholder(String),
+    UnexpectedCharacter(char),
+    AdjacentPlaceholders,
+    EmptyComponent,
+}
+
+impl fmt::Display for MetaPatternErr {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnclosedPlaceholder => write!(f, "unclosed placeholder"),
+            Self::UnknownPlaceholder(name) => write!(f, "unknown placeholder '{{{name}}}'"),
+            Self::UnexpectedCharacter(c) => write!(f, "unexpected character '{c}'"),
+            Self::AdjacentPlaceholders => {
+                write!(f, "placeholders have to be separated by some text")
+            }
+            Self::EmptyComponent => write!(f, "empty path component"),
+        }
+    }
+}
+
+impl Error for MetaPatternErr {}
+
+#[derive(Clone)]
+enum Segment {
+    Text(String),
+    Field(Field),
+}
+
+/// A pattern which extracts tags from the path of a source with
+/// `--meta-from-path`, like `{artist}/{album} ({year})/{track} - {title}`.
+///
+/// Each `/` separates a directory, and the pattern is matched against the
+/// trailing components of the path, where the last one is the file name
+/// without extension.
+#[derive(Clone)]
+pub(crate) struct MetaPattern {
+    components: Vec<Vec<Segment>>,
+}
+
+impl MetaPattern {
+    /// Match the pattern against a path, returning the value of every
+    /// placeholder in it.
+    ///
+    /// Placeholders match as little as possible, and values are trimmed.
+    pub(crate) fn captures<'a>(&self, path: &'a Path) -> Option<Vec<(Field, &'a str)>> {
+        let mut parts = Vec::new();
+
+        for c in path.components() {
+            if let Component::Normal(part) = c {
+                parts.push(part.to_str()?);
+            }
+        }
+
+        let last = parts.last_mut()?;
+        *last = Path::new(*last).file_stem()?.to_str()?;
+
+        let n = parts.len().checked_sub(self.components.len())?;
+
+        let mut captures = Vec::new();
+
+        for (segments, part) in self.components.iter().zip(&parts[n..]) {
+            if !matches(segments, part, &mut captures) {
+                return None;
+            }
+        }
+
+        Some(captures)
+    }
+}
+
+fn matches<'a>(segments: &[Segment], s: &'a str, captures: &mut Vec<(Field, &'a str)>) -> bool {
+    let Some((first, rest)) = segments.split_first() else {
+        return s.is_empty();
+    };
+
+    match first {
+        Segment::Text(text) => s
+            .strip_prefix(text.as_str())
+            .is_some_and(|s| matches(rest, s, captures)),
+        Segment::Field(field) => {
+            let ends = s.char_indices().skip(1).map(|(n, _)| n).chain([s.len()]);
+
+            for n in ends {
+                let value = s[..n].trim();
+
+                if value.is_empty()
+                    || field.is_numeric() && !value.bytes().all(|b| b.is_ascii_digit())
+                {
+                    continue;
+                }
+
+                let len = captures.len();
+                captures.push((*field, value));
+
+                if matches(rest, &s[n..], captures) {
+                    return true;
+                }
+
+                captures.truncate(len);
+            }
+
+            false
+        }
+    }
+}
+
+impl FromStr for MetaPattern {
+    type Err = MetaPatternErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut components = Vec::new();
+
+        for component in s.split('/') {
+            let mut segments = Vec::new();
+            let mut text = String::new();
+            let mut chars = component.chars();
+
+            while let Some(c) = chars.next() {
+                match c {
+                    '{' => {
+                        if !text.is_empty() {
+                            segments.push(Segment::Text(core::mem::take(&mut text)));
+                        } else if matches!(segments.last(), Some(Segment::Field(..))) {
+                            // There would be no telling where one ends and the
+                            // other begins.
+                            return Err(MetaPatternErr::AdjacentPlaceholders);
+                        }
+
+                        let (name, tail) = chars
+                            .as_str()
+                            .split_once('}')
+                            .ok_or(MetaPatternErr::UnclosedPlaceholder)?;
+
+                        let field = Field::from_name(name)
+                            .ok_or_else(|| MetaPatternErr::UnknownPlaceholder(name.to_owned()))?;
+
+                        segments.push(Segment::Field(field));
+                        chars = tail.chars();
+                    }
+                    '}' | '\\' => {
+                        return Err(MetaPatternErr::UnexpectedCharacter(c));
+                    }
+                    c => {
+                        text.push(c);
+                    }
+                }
+            }
+
+            if !text.is_empty() {
+                segments.push(Segment::Text(text));
+            }
+
+            if segments.is_empty() {
+                return Err(MetaPatternErr::EmptyComponent);
+            }
+
+            components.push(segments);
+        }
+
+        Ok(Self { components })
+    }
+}
+
+impl fmt::Display for MetaPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (n, segments) in self.components.iter().enumerate() {
+            if n > 0 {
+                f.write_char('/')?;
+            }
+
+            for segment in segments {
+                match segment {
+                    Segment::Text(text) => f.write_str(text)?,
+                    Segment::Field(field) => write!(f, "{{{}}}", field.name())?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,327 @@
+use core::error::Error;
+use core::fmt::{self, Write};
+use core::str::FromStr;
+
+#[derive(Debug)]
+pub(crate) enum MetaTemplateErr {
+    UnclosedPlaceholder,
+    UnknownPlaceholder(String),
+    InvalidWidth(String),
+    UnclosedOptional,
+    UnexpectedCharacter(char),
+    MissingFileName,
+}
+
+impl fmt::Display for MetaTemplateErr {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnclosedPlaceholder => write!(f, "unclosed placeholder"),
+            Self::UnknownPlaceholder(name) => write!(f, "unknown placeholder '{{{name}}}'"),
+            Self::InvalidWidth(name) => write!(f, "invalid width in '{{{name}}}'"),
+            Self::UnclosedOptional => write!(f, "unclosed optional segment"),
+            Self::UnexpectedCharacter(c) => write!(f, "unexpected character '{c}'"),
+            Self::MissingFileName => {
+                write!(f, "file name has to contain '{{title}}' or '{{track}}'")
+            }
+        }
+    }
+}
+
+impl Error for MetaTemplateErr {}
+
+/// A placeholder which can be used in a [`MetaTemplate`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Field {
+    Artist,
+    Album,
+    Year,
+    Track,
+    Title,
+    Disc,
+    Total,
+    MediaType,
+}
+
+impl Field {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "artist" => Some(Field::Artist),
+            "album" => Some(Field::Album),
+            "year" => Some(Field::Year),
+            "track" => Some(Field::Track),
+            "title" => Some(Field::Title),
+            "disc" => Some(Field::Disc),
+            "total" => Some(Field::Total),
+            "media_type" => Some(Field::MediaType),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Field::Artist => "artist",
+            Field::Album => "album",
+            Field::Year => "year",
+            Field::Track => "track",
+            Field::Title => "title",
+            Field::Disc => "disc",
+            Field::Total => "total",
+            Field::MediaType => "media_type",
+        }
+    }
+
+    pub(crate) fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            Field::Year | Field::Track | Field::Disc | Field::Total
+        )
+    }
+}
+
+#[derive(Clone)]
+enum Segment {
+    Text(String),
+    Field { field: Field, width: usize },
+    Separator,
+    Optional(Vec<Segment>),
+}
+
+/// The tags of a file which a [`MetaTemplate`] is rendered with.
+pub(crate) struct Values<'a> {
+    pub(crate) artist: &'a str,
+    pub(crate) album: &'a str,
+    pub(crate) year: i16,
+    pub(crate) track: u32,
+    pub(crate) title: &'a str,
+    pub(crate) media_type: Option<&'a str>,
+    /// The disc number and total number of discs, if the file is part of a
+    /// disc set which is large enough to be separated.
+    pub(crate) set: Option<(u32, u32)>,
+}
+
+impl Values<'_> {
+    /// Write the value of a field, which is left out if it has none.
+    fn write(&self, out: &mut String, field: Field, width: usize) {
+        match field {
+            Field::Artist => out.push_str(self.artist),
+            Field::Album => out.push_str(self.album),
+            Field::Year => _ = write!(out, "{:0width$}", self.year),
+            Field::Track => _ = write!(out, "{:0width$}", self.track),
+            Field::Title => out.push_str(self.title),
+            Field::Disc => {
+                if let Some((disc, _)) = self.set {
+                    _ = write!(out, "{disc:0width$}");
+                }
+            }
+            Field::Total => {
+                if let Some((_, total)) = self.set {
+                    _ = write!(out, "{total:0width$}");
+                }
+            }
+            Field::MediaType => {
+                if let Some(media_type) = self.media_type {
+                    out.push_str(media_type);
+                }
+            }
+        }
+    }
+
+    fn has(&self, field: Field) -> bool {
+        match field {
+            Field::Disc | Field::Total => self.set.is_some(),
+            Field::MediaType => self.media_type.is_some(),
+            _ => true,
+        }
+    }
+}
+
+/// A template for the path of a file organized by its tags with `--meta`,
+/// like `{artist}/{album} ({year})/[CD{disc}/]{track:02} - {title}`.
+///
+/// Each `/` separates a directory, and the last component is the file name
+/// without extension. Segments in brackets are left out unless every
+/// placeholder in them has a value.
+#[derive(Clone)]
+pub(crate) struct MetaTemplate {
+    segments: Vec<Segment>,
+}
+
+impl MetaTemplate {
+    /// Render the template into the components of a path.
+    ///
+    /// Components are trimmed, and empty ones are left out.
+    pub(crate) fn render(&self, values: &Values<'_>) -> Vec<String> {
+        let mut components = Vec::new();
+        let mut current = String::new();
+        render(&self.segments, values, &mut components, &mut current);
+        components.push(current);
+
+        components
+            .into_iter()
+            .map(|c| c.trim().to_owned())
+            .filter(|c| !c.is_empty())
+            .collect()
+    }
+}
+
+fn render(
+    segments: &[Segment],
+    values: &Values<'_>,
+    components: &mut Vec<String>,
+    current: &mut String,
+) {
+    for segment in segments {
+        match segment {
+            Segment::Text(text) => current.push_str(text),
+            Segment::Field { field, width } => values.write(current, *field, *width),
+            Segment::Separator => components.push(core::mem::take(current)),
+            Segment::Optional(inner) => {
+                if is_present(inner, values) {
+                    render(inner, values, components, current);
+                }
+            }
+        }
+    }
+}
+
+/// Test if every placeholder in segments has a value, where nested optional
+/// segments are decided on their own.
+fn is_present(segments: &[Segment], values: &Values<'_>) -> bool {
+    segments.iter().all(|s| match s {
+        Segment::Field { field, .. } => values.has(*field),
+        _ => true,
+    })
+}
+
+impl FromStr for MetaTemplate {
+    type Err = MetaTemplateErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut stack = vec![Vec::new()];
+        let mut text = String::new();
+        let mut chars = s.chars();
+
+        macro_rules! flush {
+            () => {
+                if !text.is_empty() {
+                    let segments = stack.last_mut().expect("stack is never empty");
+                    segments.push(Segment::Text(core::mem::take(&mut text)));
+                }
+            };
+        }
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' => {
+                    flush!();
+
+                    let rest = chars.as_str();
+
+                    let (placeholder, tail) = rest
+                        .split_once('}')
+                        .ok_or(MetaTemplateErr::UnclosedPlaceholder)?;
+
+                    let (name, width) = match placeholder.split_once(':') {
+                        Some((name, width)) => {
+                            let width = width.parse::<usize>().map_err(|_| {
+                                MetaTemplateErr::InvalidWidth(placeholder.to_owned())
+                            })?;
+                            (name, width)
+                        }
+                        None => (placeholder, 0),
+                    };
+
+                    let field = Field::from_name(name)
+                        .ok_or_else(|| MetaTemplateErr::UnknownPlaceholder(name.to_owned()))?;
+
+                    if width > 0 && !field.is_numeric() {
+                        return Err(MetaTemplateErr::InvalidWidth(placeholder.to_owned()));
+                    }
+
+                    let segments = stack.last_mut().expect("stack is never empty");
+                    segments.push(Segment::Field { field, width });
+                    chars = tail.chars();
+                }
+                '[' => {
+                    flush!();
+                    stack.push(Vec::new());
+                }
+                ']' => {
+                    flush!();
+
+                    if stack.len() == 1 {
+                        return Err(MetaTemplateErr::UnexpectedCharacter(c));
+                    }
+
+                    let inner = stack.pop().expect("stack is never empty");
+                    let segments = stack.last_mut().expect("stack is never empty");
+                    segments.push(Segment::Optional(inner));
+                }
+                '/' => {
+                    flush!();
+                    let segments = stack.last_mut().expect("stack is never empty");
+                    segments.push(Segment::Separator);
+                }
+                '}' | '\\' => {
+                    return Err(MetaTemplateErr::UnexpectedCharacter(c));
+                }
+                c => {
+                    text.push(c);
+                }
+            }
+        }
+
+        flush!();
+
+        let [segments] =
+            <[_; 1]>::try_from(stack).map_err(|_| MetaTemplateErr::UnclosedOptional)?;
+
+        // Files of an album would otherwise overwrite each other.
+        let file_name = match segments
+            .iter()
+            .rposition(|s| matches!(s, Segment::Separator))
+        {
+            Some(n) => &segments[n + 1..],
+            None => &segments[..],
+        };
+
+        if !has_field(file_name, Field::Title) && !has_field(file_name, Field::Track) {
+            return Err(MetaTemplateErr::MissingFileName);
+        }
+
+        Ok(Self { segments })
+    }
+}
+
+fn has_field(segments: &[Segment], field: Field) -> bool {
+    segments.iter().any(|s| match s {
+        Segment::Field { field: f, .. } => *f == field,
+        Segment::Optional(inner) => has_field(inner, field),
+        _ => false,
+    })
+}
+
+impl fmt::Display for MetaTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display(&self.segments, f)
+    }
+}
+
+fn display(segments: &[Segment], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for segment in segments {
+        match segment {
+            Segment::Text(text) => f.write_str(text)?,
+            Segment::Field { field, width: 0 } => write!(f, "{{{}}}", field.name())?,
+            Segment::Field { field, width } => write!(f, "{{{}:0{width}}}", field.name())?,
+            Segment::Separator => f.write_char('/')?,
+            Segment::Optional(inner) => {
+                f.write_char('[')?;
+                display(inner, f)?;
+                f.write_char(']')?;
+            }
+        }
+    }
+
+    Ok(())
+}
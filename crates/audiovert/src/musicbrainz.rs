@@ -0,0 +1,500 @@
+use core::fmt::Write as _;
+use core::time::Duration;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::Instant;
+
+use anyhow::{Context, Result, anyhow, bail};
+use mediavert_core::cancel::Cancel;
+use mediavert_core::config;
+use mediavert_core::effects::Effects;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+const USER_AGENT: &str = concat!(
+    "audiovert/",
+    env!("CARGO_PKG_VERSION"),
+    " ( https://github.com/udoprog/mediavert )"
+);
+
+/// MusicBrainz allows one request per second.
+const MUSICBRAINZ_INTERVAL: Duration = Duration::from_secs(1);
+/// AcoustID allows three requests per second.
+const ACOUSTID_INTERVAL: Duration = Duration::from_millis(334);
+/// How often a request waiting for its turn checks whether the run was
+/// cancelled.
+const POLL: Duration = Duration::from_millis(50);
+
+/// Tags of a recording found on MusicBrainz.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct Found {
+    pub(crate) artist: Option<String>,
+    pub(crate) album: Option<String>,
+    pub(crate) year: Option<i16>,
+    pub(crate) track: Option<u32>,
+    pub(crate) title: Option<String>,
+}
+
+/// The tags already known about a file, which are used to search for it.
+pub(crate) struct Query<'a> {
+    pub(crate) artist: Option<&'a str>,
+    pub(crate) album: Option<&'a str>,
+    pub(crate) title: Option<&'a str>,
+}
+
+/// Looks up the tags of files on MusicBrainz, either by the tags they already
+/// have or by their AcoustID fingerprint.
+pub(crate) struct MusicBrainz {
+    fpcalc: PathBuf,
+    acoustid_key: Option<String>,
+    cache: Mutex<Cache>,
+    musicbrainz: RateLimit,
+    acoustid: RateLimit,
+    cancel: Cancel,
+    offline: bool,
+}
+
+impl MusicBrainz {
+    /// Construct a new client, loading previous lookups from the cache.
+    ///
+    /// Fingerprints are only computed with `fpcalc` if an AcoustID API key is
+    /// specified.
+    ///
+    /// If `offline` is set, like in dry runs, only cached lookups are used.
+    pub(crate) fn new(
+        fpcalc: PathBuf,
+        acoustid_key: Option<String>,
+        cancel: Cancel,
+        offline: bool,
+    ) -> Result<Self> {
+        let cache = Cache::load(config::cache_dir().map(|dir| dir.join("musicbrainz.json")))?;
+
+        Ok(Self {
+            fpcalc,
+            acoustid_key,
+            cache: Mutex::new(cache),
+            musicbrainz: RateLimit::new(MUSICBRAINZ_INTERVAL),
+            acoustid: RateLimit::new(ACOUSTID_INTERVAL),
+            cancel,
+            offline,
+        })
+    }
+
+    /// Save lookups which have been performed since the cache was loaded.
+    pub(crate) fn save(&self, effects: &Effects) -> Result<()> {
+        self.lock().save(effects)
+    }
+
+    /// Look up a file.
+    ///
+    /// Files are searched for by their title if it's known, otherwise by
+    /// fingerprint if the file is given and an AcoustID API key is set.
+    pub(crate) fn lookup(&self, query: &Query<'_>, path: Option<&Path>) -> Result<Option<Found>> {
+        if let Some(title) = query.title
+            && (query.artist.is_some() || query.album.is_some())
+        {
+            let mut search = format!("recording:{}", quote(title));
+
+            if let Some(artist) = query.artist {
+                _ = write!(search, " AND artist:{}", quote(artist));
+            }
+
+            if let Some(album) = query.album {
+                _ = write!(search, " AND release:{}", quote(album));
+            }
+
+            return self.cached(&format!("search:{search}"), || {
+                let response = self.get::<Search>(
+                    "https://musicbrainz.org/ws/2/recording/",
+                    &[("query", &search), ("limit", "1")],
+                )?;
+
+                Ok(response
+                    .recordings
+                    .into_iter()
+                    .next()
+                    .map(|r| r.found(query.album)))
+            });
+        }
+
+        let (Some(key), Some(path)) = (&self.acoustid_key, path) else {
+            return Ok(None);
+        };
+
+        let fingerprint = self.fingerprint(path)?;
+
+        let digest = Sha256::digest(fingerprint.fingerprint.as_bytes());
+        let mut id = format!("acoustid:{}:", fingerprint.duration.round());
+
+        for b in digest {
+            _ = write!(id, "{b:02x}");
+        }
+
+        self.cached(&id, || {
+            let duration = fingerprint.duration.round().to_string();
+
+            self.acoustid.wait(&self.cancel)?;
+
+            let response = ureq::get("https://api.acoustid.org/v2/lookup")
+                .header("User-Agent", USER_AGENT)
+                .query("client", key)
+                .query("meta", "recordingids")
+                .query("duration", &duration)
+                .query("fingerprint", &fingerprint.fingerprint)
+                .call()
+                .and_then(|mut r| r.body_mut().read_json::<AcoustId>())
+                .context("AcoustID lookup failed")?;
+
+            if response.status != "ok" {
+                bail!("AcoustID lookup failed with status {}", response.status);
+            }
+
+            let best = response
+                .results
+                .into_iter()
+                .filter(|r| !r.recordings.is_empty())
+                .max_by(|a, b| a.score.total_cmp(&b.score));
+
+            let Some(recording) = best.and_then(|r| r.recordings.into_iter().next()) else {
+                return Ok(None);
+            };
+
+            let recording = self.get::<Recording>(
+                &format!("https://musicbrainz.org/ws/2/recording/{}", recording.id),
+                &[("inc", "artist-credits releases media")],
+            )?;
+
+            Ok(Some(recording.found(query.album)))
+        })
+    }
+
+    /// Perform a lookup unless its result is already cached.
+    fn cached(&self, id: &str, f: impl FnOnce() -> Result<Option<Found>>) -> Result<Option<Found>> {
+        if let Some(found) = self.lock().entries.get(id) {
+            debug!(id, "cached lookup");
+            return Ok(found.clone());
+        }
+
+        if self.offline {
+            bail!("Not looked up since it isn't cached and this is a dry run");
+        }
+
+        if self.cancel.is_cancelled() {
+            bail!("Cancelled");
+        }
+
+        let found = f()?;
+
+        let mut cache = self.lock();
+        cache.entries.insert(id.to_owned(), found.clone());
+        cache.dirty = true;
+        Ok(found)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Cache> {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Perform a rate limited request to the MusicBrainz API.
+    fn get<T>(&self, url: &str, query: &[(&str, &str)]) -> Result<T>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        self.musicbrainz.wait(&self.cancel)?;
+
+        let mut request = ureq::get(url)
+            .header("User-Agent", USER_AGENT)
+            .query("fmt", "json");
+
+        for (key, value) in query {
+            request = request.query(*key, *value);
+        }
+
+        request
+            .call()
+            .and_then(|mut r| r.body_mut().read_json::<T>())
+            .with_context(|| anyhow!("MusicBrainz request to {url} failed"))
+    }
+
+    /// Compute the fingerprint of a file with `fpcalc`.
+    fn fingerprint(&self, path: &Path) -> Result<Fingerprint> {
+        let output = Command::new(&self.fpcalc)
+            .arg("-json")
+            .arg(path)
+            .output()
+            .with_context(|| anyhow!("Failed to run {}", self.fpcalc.display()))?;
+
+        if !output.status.success() {
+            bail!("{} exited with {}", self.fpcalc.display(), output.status);
+        }
+
+        serde_json::from_slice(&output.stdout)
+            .with_context(|| anyhow!("Failed to parse output of {}", self.fpcalc.display()))
+    }
+}
+
+/// Lookups which have already been performed, including the ones which found
+/// nothing.
+struct Cache {
+    path: Option<PathBuf>,
+    entries: BTreeMap<String, Option<Found>>,
+    dirty: bool,
+}
+
+impl Cache {
+    fn load(path: Option<PathBuf>) -> Result<Self> {
+        let entries = match &path {
+            Some(path) if path.is_file() => {
+                let contents =
+                    fs::read(path).with_context(|| anyhow!("Failed to read {}", path.display()))?;
+
+                match serde_json::from_slice(&contents) {
+                    Ok(entries) => entries,
+                    Err(e) => {
+                        // A broken cache is only a reason to look things up
+                        // again.
+                        debug!(
+                            path = path.display().to_string(),
+                            ?e,
+                            "ignoring broken cache"
+                        );
+                        BTreeMap::new()
+                    }
+                }
+            }
+            _ => BTreeMap::new(),
+        };
+
+        Ok(Self {
+            path,
+            entries,
+            dirty: false,
+        })
+    }
+
+    fn save(&mut self, effects: &Effects) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        if !self.dirty {
+            return Ok(());
+        }
+
+        if let Some(parent) = path.parent() {
+            effects
+                .create_dir_all(parent)
+                .with_context(|| anyhow!("Failed to create {}", parent.display()))?;
+        }
+
+        let part = path.with_added_extension("part");
+        let contents = serde_json::to_vec(&self.entries)?;
+
+        effects
+            .write(&part, contents)
+            .with_context(|| anyhow!("Failed to write {}", part.display()))?;
+        effects
+            .rename(&part, path)
+            .with_context(|| anyhow!("Failed to write {}", path.display()))?;
+
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+/// Spaces out requests to a service which are made from any thread.
+struct RateLimit {
+    interval: Duration,
+    next: Mutex<Option<Instant>>,
+}
+
+impl RateLimit {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next: Mutex::new(None),
+        }
+    }
+
+    /// Wait until the next request can be made, failing if the run is
+    /// cancelled while waiting.
+    fn wait(&self, cancel: &Cancel) -> Result<()> {
+        // The lock is held while sleeping so that waiting requests are made
+        // one at a time.
+        let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(next) = *next {
+            loop {
+                if cancel.is_cancelled() {
+                    bail!("Cancelled");
+                }
+
+                let now = Instant::now();
+
+                if next <= now {
+                    break;
+                }
+
+                thread::sleep((next - now).min(POLL));
+            }
+        }
+
+        *next = Some(Instant::now() + self.interval);
+        Ok(())
+    }
+}
+
+/// Quote a term in a MusicBrainz search query.
+fn quote(term: &str) -> String {
+    let mut out = String::with_capacity(term.len() + 2);
+    out.push('"');
+
+    for c in term.chars() {
+        if matches!(c, '"' | '\\') {
+            out.push('\\');
+        }
+
+        out.push(c);
+    }
+
+    out.push('"');
+    out
+}
+
+#[derive(Deserialize)]
+struct Fingerprint {
+    duration: f64,
+    fingerprint: String,
+}
+
+#[derive(Deserialize)]
+struct AcoustId {
+    status: String,
+    #[serde(default)]
+    results: Vec<AcoustIdResult>,
+}
+
+#[derive(Deserialize)]
+struct AcoustIdResult {
+    score: f64,
+    #[serde(default)]
+    recordings: Vec<AcoustIdRecording>,
+}
+
+#[derive(Deserialize)]
+struct AcoustIdRecording {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct Search {
+    #[serde(default)]
+    recordings: Vec<Recording>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Recording {
+    title: String,
+    #[serde(default)]
+    artist_credit: Vec<ArtistCredit>,
+    #[serde(default)]
+    releases: Vec<Release>,
+}
+
+impl Recording {
+    /// Convert into tags, preferring the release with the given album title.
+    fn found(self, album: Option<&str>) -> Found {
+        let index = album
+            .and_then(|album| {
+                self.releases
+                    .iter()
+                    .position(|r| r.title.eq_ignore_ascii_case(album))
+            })
+            .unwrap_or_default();
+
+        let release = self.releases.into_iter().nth(index);
+
+        let mut found = Found {
+            artist: artist(&self.artist_credit),
+            album: None,
+            year: None,
+            track: None,
+            title: Some(self.title),
+        };
+
+        if let Some(release) = release {
+            if let Some(artist) = artist(&release.artist_credit) {
+                found.artist = Some(artist);
+            }
+
+            found.year = release
+                .date
+                .as_deref()
+                .and_then(|date| date.get(..4))
+                .and_then(|year| year.parse().ok());
+
+            found.track = release
+                .media
+                .iter()
+                .flat_map(|m| &m.track)
+                .find_map(|t| t.number.parse().ok());
+
+            found.album = Some(release.title);
+        }
+
+        found
+    }
+}
+
+/// Join the names of the artists of a credit.
+fn artist(credit: &[ArtistCredit]) -> Option<String> {
+    if credit.is_empty() {
+        return None;
+    }
+
+    let mut out = String::new();
+
+    for c in credit {
+        out.push_str(&c.name);
+        out.push_str(&c.joinphrase);
+    }
+
+    Some(out)
+}
+
+#[derive(Deserialize)]
+struct ArtistCredit {
+    name: String,
+    #[serde(default)]
+    joinphrase: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Release {
+    title: String,
+    date: Option<String>,
+    #[serde(default)]
+    artist_credit: Vec<ArtistCredit>,
+    #[serde(default)]
+    media: Vec<Medium>,
+}
+
+#[derive(Deserialize)]
+struct Medium {
+    #[serde(default)]
+    track: Vec<Track>,
+}
+
+#[derive(Deserialize)]
+struct Track {
+    number: String,
+}
@@ -0,0 +1,341 @@
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+use core::time::Duration;
+
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Deserialize;
+
+use crate::cli;
+use crate::config::{Config, Db, Source};
+use crate::cue::Split;
+use crate::format::Format;
+use crate::stream;
+
+/// The integrated loudness recommended by EBU R128, in LUFS.
+const DEFAULT_TARGET: f64 = -23.0;
+/// The maximum true peak of normalized files, in dBTP.
+const TRUE_PEAK: f64 = -1.0;
+/// The loudness range targeted by `loudnorm`, which is set as wide as it goes
+/// so that it normalizes linearly instead of compressing.
+const RANGE: f64 = 20.0;
+
+#[derive(Debug)]
+pub(crate) enum NormalizeErr {
+    UnknownMethod(String),
+    InvalidTarget(String),
+}
+
+impl fmt::Display for NormalizeErr {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownMethod(method) => {
+                write!(f, "unknown method '{method}', expected 'ebur128'")
+            }
+            Self::InvalidTarget(target) => {
+                write!(f, "invalid target '{target}', expected LUFS like -16")
+            }
+        }
+    }
+}
+
+impl Error for NormalizeErr {}
+
+/// How converted files are normalized with `--normalize`, like `ebur128` or
+/// `ebur128:-16` to target an integrated loudness of -16 LUFS.
+#[derive(Clone, Copy)]
+pub(crate) struct Normalize {
+    target: f64,
+}
+
+impl Default for Normalize {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            target: DEFAULT_TARGET,
+        }
+    }
+}
+
+impl Normalize {
+    /// Measure the loudness of a source with the first pass of `loudnorm`.
+    pub(crate) fn analyze(
+        &self,
+        config: &Config,
+        db: &Db,
+        source: &Source,
+        from: Format,
+        split: Option<&Split>,
+    ) -> Result<Loudness> {
+        let mut command = Command::new(&config.ffmpeg);
+        command.args(["-hide_banner", "-nostats"]);
+        command.args(config.input_args(from));
+
+        if let Some(split) = split {
+            split.input_args(&mut command);
+        }
+
+        let archive = match source {
+            Source::File { file } => {
+                command.arg("-i").arg(db.file(*file)?.as_os_str());
+                command.stdin(Stdio::null());
+                None
+            }
+            Source::Archive { archive, path } => {
+                command.args(["-i", "pipe:"]);
+                command.stdin(Stdio::piped());
+                Some(
+                    db.archive_contents(*archive, path)
+                        .context("reading source contents")?,
+                )
+            }
+        };
+
+        let selected = match &config.stream {
+            Some(stream) => stream.select(&stream::probe(config, db, source)?)?,
+            None => 0,
+        };
+
+        command.args(["-map", &format!("0:a:{selected}")]);
+        command.args(["-af", &format!("{}:print_format=json", self.loudnorm())]);
+        command.args(["-f", "null", "-"]);
+        command.stdout(Stdio::null());
+        command.stderr(Stdio::piped());
+
+        let mut child = command.spawn().with_context(|| {
+            anyhow!(
+                "Failed to analyze loudness with {}",
+                config.ffmpeg.display()
+            )
+        })?;
+
+        let writer = archive.map(|contents| {
+            let mut stdin = child.stdin.take();
+
+            thread::spawn(move || {
+                if let Some(stdin) = &mut stdin {
+                    _ = stdin.write_all(&contents);
+                }
+            })
+        });
+
+        // The output is read on a thread of its own so that ffmpeg doesn't
+        // block on a full pipe while we wait for it.
+        let reader = child.stderr.take().map(|mut stderr| {
+            thread::spawn(move || {
+                let mut output = Vec::new();
+                _ = stderr.read_to_end(&mut output);
+                output
+            })
+        });
+
+        let status = cli::wait(&mut child, config)?;
+
+        if let Some(writer) = writer {
+            _ = writer.join();
+        }
+
+        let output = reader
+            .and_then(|reader| reader.join().ok())
+            .unwrap_or_default();
+
+        let Some(status) = status else {
+            bail!("Aborted");
+        };
+
+        if !status.success() {
+            bail!("{} exited with {status}", config.ffmpeg.display());
+        }
+
+        let stderr = String::from_utf8_lossy(&output);
+
+        // The measurement is printed as the last thing, after the details of
+        // the input.
+        let measured = stderr
+            .rfind('{')
+            .and_then(|start| Some(&stderr[start..=start + stderr[start..].find('}')?]))
+            .context("Missing loudness measurement in output of ffmpeg")?;
+
+        let measured = serde_json::from_str::<Measured>(measured)
+            .context("Failed to parse loudness measurement")?;
+
+        let parse = |value: &str| {
+            value
+                .trim()
+                .parse::<f64>()
+                .map_err(|_| anyhow!("Invalid loudness measurement '{value}'"))
+        };
+
+        let loudness = Loudness {
+            integrated: parse(&measured.input_i)?,
+            true_peak: parse(&measured.input_tp)?,
+            range: parse(&measured.input_lra)?,
+            threshold: parse(&measured.input_thresh)?,
+            offset: parse(&measured.target_offset)?,
+            sample_rate: sample_rate(&stderr),
+            duration: stream::duration(&stderr).map(|total| match split {
+                Some(split) => split.duration(total),
+                None => total,
+            }),
+        };
+
+        if !loudness.integrated.is_finite() {
+            bail!("Source is silent");
+        }
+
+        Ok(loudness)
+    }
+
+    /// The gain of an album with the given integrated loudness and true
+    /// peak, which is applied to each of its tracks so that they keep their
+    /// loudness relative to each other.
+    ///
+    /// The gain is reduced if it would make the loudest peak exceed the true
+    /// peak limit.
+    pub(crate) fn album_gain(&self, (integrated, true_peak): (f64, f64)) -> f64 {
+        (self.target - integrated).min(TRUE_PEAK - true_peak)
+    }
+
+    /// The audio filter which applies the gain of a file.
+    pub(crate) fn filter(&self, gain: &Gain) -> String {
+        match gain {
+            Gain::Track(loudness) => {
+                let mut filter = format!(
+                    "{}:measured_I={}:measured_TP={}:measured_LRA={}:measured_thresh={}:offset={}:linear=true",
+                    self.loudnorm(),
+                    loudness.integrated,
+                    loudness.true_peak,
+                    loudness.range,
+                    loudness.threshold,
+                    loudness.offset,
+                );
+
+                // The filter upsamples to 192 kHz, which is almost never
+                // wanted in the output.
+                if let Some(sample_rate) = loudness.sample_rate {
+                    filter.push_str(&format!(",aresample={sample_rate}"));
+                }
+
+                filter
+            }
+            Gain::Album(gain) => format!("volume={gain:.2}dB"),
+        }
+    }
+
+    fn loudnorm(&self) -> String {
+        format!("loudnorm=I={}:TP={TRUE_PEAK}:LRA={RANGE}", self.target)
+    }
+}
+
+impl FromStr for Normalize {
+    type Err = NormalizeErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (method, target) = match s.split_once(':') {
+            Some((method, target)) => (method, Some(target)),
+            None => (s, None),
+        };
+
+        if !method.eq_ignore_ascii_case("ebur128") {
+            return Err(NormalizeErr::UnknownMethod(method.to_owned()));
+        }
+
+        let target = match target {
+            Some(target) => target
+                .parse::<f64>()
+                .ok()
+                .filter(|t| (-70.0..=-5.0).contains(t))
+                .ok_or_else(|| NormalizeErr::InvalidTarget(target.to_owned()))?,
+            None => DEFAULT_TARGET,
+        };
+
+        Ok(Self { target })
+    }
+}
+
+impl fmt::Display for Normalize {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ebur128:{}", self.target)
+    }
+}
+
+/// The gain applied to a file when it's converted with `--normalize`.
+#[derive(Clone)]
+pub(crate) enum Gain {
+    /// Normalize the file on its own from its measured loudness.
+    Track(Loudness),
+    /// Apply the gain of the album the file belongs to, in dB.
+    Album(f64),
+}
+
+impl fmt::Display for Gain {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Track(loudness) => write!(f, "{:.1} LUFS", loudness.integrated),
+            Self::Album(gain) => write!(f, "{gain:+.1} dB album gain"),
+        }
+    }
+}
+
+/// Compute the integrated loudness and true peak of an album from the
+/// loudness of its tracks.
+///
+/// The loudness of the album is the mean power of its tracks weighted by their
+/// duration, and its peak is the loudest peak of any track.
+pub(crate) fn album<'a>(tracks: impl IntoIterator<Item = &'a Loudness>) -> Option<(f64, f64)> {
+    let mut power = 0.0;
+    let mut total = 0.0;
+    let mut peak = f64::NEG_INFINITY;
+
+    for track in tracks {
+        let weight = track.duration.map_or(1.0, |d| d.as_secs_f64());
+        power += weight * 10f64.powf(track.integrated / 10.0);
+        total += weight;
+        peak = peak.max(track.true_peak);
+    }
+
+    if power <= 0.0 || total <= 0.0 {
+        return None;
+    }
+
+    Some((10.0 * (power / total).log10(), peak))
+}
+
+/// The loudness of a file measured by the first pass of `loudnorm`.
+#[derive(Clone)]
+pub(crate) struct Loudness {
+    /// The integrated loudness in LUFS.
+    pub(crate) integrated: f64,
+    /// The true peak in dBTP.
+    pub(crate) true_peak: f64,
+    range: f64,
+    threshold: f64,
+    offset: f64,
+    sample_rate: Option<u32>,
+    duration: Option<Duration>,
+}
+
+#[derive(Deserialize)]
+struct Measured {
+    input_i: String,
+    input_tp: String,
+    input_lra: String,
+    input_thresh: String,
+    target_offset: String,
+}
+
+/// Find the sample rate of the first audio stream in the details ffmpeg
+/// prints about its input, like `Audio: flac, 44100 Hz, stereo`.
+fn sample_rate(stderr: &str) -> Option<u32> {
+    let (_, audio) = stderr.split_once(": Audio: ")?;
+    let (rate, _) = audio.split_once(" Hz")?;
+    let (_, rate) = rate.rsplit_once(", ")?;
+    rate.parse().ok()
+}
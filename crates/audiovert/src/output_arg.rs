@@ -0,0 +1,63 @@
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::format::{Format, FormatErr};
+
+#[derive(Debug)]
+pub(crate) enum OutputArgErr {
+    MissingSeparator,
+    InvalidFormat(FormatErr),
+}
+
+impl fmt::Display for OutputArgErr {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingSeparator => write!(f, "missing '=' separator"),
+            Self::InvalidFormat(e) => write!(f, "invalid format: {e}"),
+        }
+    }
+}
+
+impl Error for OutputArgErr {}
+
+impl From<FormatErr> for OutputArgErr {
+    #[inline]
+    fn from(e: FormatErr) -> Self {
+        OutputArgErr::InvalidFormat(e)
+    }
+}
+
+/// A single argument passed to ffmpeg before the output when producing files
+/// in a given format.
+///
+/// Unlike [`InputArgs`], the argument is passed as-is, so it may contain
+/// whitespace.
+///
+/// [`InputArgs`]: crate::input_args::InputArgs
+#[derive(Clone)]
+pub(crate) struct OutputArg {
+    pub(crate) format: Format,
+    pub(crate) arg: String,
+}
+
+impl FromStr for OutputArg {
+    type Err = OutputArgErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format, arg) = s.split_once('=').ok_or(OutputArgErr::MissingSeparator)?;
+
+        Ok(OutputArg {
+            format: format.parse()?,
+            arg: arg.to_owned(),
+        })
+    }
+}
+
+impl fmt::Display for OutputArg {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.format, self.arg)
+    }
+}
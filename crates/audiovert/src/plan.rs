@@ -0,0 +1,121 @@
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+
+use crate::config::{Config, Source};
+use crate::cue::Split;
+use crate::format::Format;
+use crate::meta::Meta;
+use crate::tasks::{TaskKind, Tasks, TransferKind};
+
+/// Export the planned tasks as CSV to the given path.
+pub(crate) fn export_csv(path: &Path, config: &Config, tasks: &Tasks) -> Result<()> {
+    let file =
+        File::create(path).with_context(|| anyhow!("Failed to create {}", path.display()))?;
+    let mut w = BufWriter::new(file);
+
+    writeln!(
+        w,
+        "index,source,destination,kind,from,to,completed,source-size,estimated-size"
+    )?;
+
+    for c in &tasks.tasks {
+        let source = match &c.source {
+            Source::File { file } => tasks.db.file(*file)?.display().to_string(),
+            Source::Archive { archive, path } => {
+                let archive = tasks.db.archive(*archive)?;
+                format!("{}/{path}", archive.path.display())
+            }
+        };
+
+        let source_ext = tasks.db.ext(&c.source)?.unwrap_or_default();
+
+        let (kind, from, to, estimate) = match &c.kind {
+            TaskKind::Convert {
+                from,
+                to,
+                bitrate,
+                split,
+                ..
+            } => (
+                "convert",
+                from.name(),
+                to.name(),
+                estimate(
+                    config,
+                    tasks.db.meta(&c.source),
+                    split.as_deref(),
+                    *to,
+                    *bitrate,
+                ),
+            ),
+            TaskKind::External { external, .. } => {
+                ("external", external.ext.as_str(), external.to.name(), None)
+            }
+            TaskKind::Transfer { kind } => {
+                let kind = match kind {
+                    TransferKind::Copy => "copy",
+                    TransferKind::Link => "link",
+                    TransferKind::Move => "move",
+                };
+
+                (kind, source_ext, source_ext, tasks.db.size(&c.source)?)
+            }
+        };
+
+        let source_size = tasks.db.size(&c.source)?;
+
+        writeln!(
+            w,
+            "{},{},{},{kind},{from},{to},{},{},{}",
+            c.index,
+            escape(&source),
+            escape(&c.to_path.display().to_string()),
+            c.is_completed(),
+            source_size.map(|n| n.to_string()).unwrap_or_default(),
+            estimate.map(|n| n.to_string()).unwrap_or_default(),
+        )?;
+    }
+
+    w.flush()?;
+    Ok(())
+}
+
+/// Estimate the size of a conversion based on the duration of the source, or
+/// of the track split from it, and the bitrate of the conversion, or the one configured for the target format.
+pub(crate) fn estimate(
+    config: &Config,
+    meta: Option<&Meta>,
+    split: Option<&Split>,
+    to: Format,
+    bitrate: Option<u32>,
+) -> Option<u64> {
+    // The size of variable bitrate encodings can't be estimated.
+    if bitrate.is_none() && config.bitrates.quality(&to).is_some() {
+        return None;
+    }
+
+    let bitrate = bitrate
+        .or_else(|| config.bitrates.get(&to))
+        .filter(|&b| b > 0)?;
+    let duration = meta?.duration();
+
+    let seconds = match split {
+        Some(split) => split.duration(duration).as_secs_f64(),
+        None => duration.as_secs_f64(),
+    };
+
+    Some((f64::from(bitrate) * 1000.0 / 8.0 * seconds) as u64)
+}
+
+/// Escape a CSV field.
+fn escape(field: &str) -> Cow<'_, str> {
+    if !field.contains([',', '"', '\n', '\r']) {
+        return Cow::Borrowed(field);
+    }
+
+    Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+}
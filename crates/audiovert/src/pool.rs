@@ -0,0 +1,51 @@
+use core::num::NonZeroUsize;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use std::sync::Mutex;
+use std::thread;
+
+/// Apply `f` to every item on a pool of `jobs` worker threads.
+///
+/// Results are returned in the same order as the items, regardless of the
+/// order in which they were completed.
+pub(crate) fn map<T, U>(jobs: NonZeroUsize, items: &[T], f: impl Fn(&T) -> U + Sync) -> Vec<U>
+where
+    T: Sync,
+    U: Send,
+{
+    let jobs = jobs.get().min(items.len());
+
+    if jobs <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(items.len()));
+
+    thread::scope(|s| {
+        for _ in 0..jobs {
+            s.spawn(|| {
+                let mut local = Vec::new();
+
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+
+                    let Some(item) = items.get(index) else {
+                        break;
+                    };
+
+                    local.push((index, f(item)));
+                }
+
+                results
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .extend(local);
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap_or_else(|e| e.into_inner());
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
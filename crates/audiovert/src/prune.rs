@@ -0,0 +1,283 @@
+use core::fmt;
+use core::str::FromStr;
+
+use std::collections::{BTreeSet, HashSet};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Error, Result, anyhow, bail};
+use mediavert_core::link::Link;
+use mediavert_core::out::{Out, blank, error, info};
+use mediavert_core::shell;
+use mediavert_core::trash;
+
+use crate::config::Config;
+use crate::tasks::{Tasks, Trash, TrashWhat};
+
+/// How far up empty directories left behind by trashed or moved sources are
+/// removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PruneEmpty {
+    /// Remove at most this many levels of directories.
+    Depth(usize),
+    /// Remove every empty directory up to the source path it was found in.
+    All,
+}
+
+impl PruneEmpty {
+    fn depth(&self) -> usize {
+        match self {
+            PruneEmpty::Depth(depth) => *depth,
+            PruneEmpty::All => usize::MAX,
+        }
+    }
+}
+
+impl FromStr for PruneEmpty {
+    type Err = Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        if s == "all" {
+            return Ok(PruneEmpty::All);
+        }
+
+        match s.parse() {
+            Ok(depth) => Ok(PruneEmpty::Depth(depth)),
+            Err(..) => Err(anyhow!("Invalid prune depth '{s}'")),
+        }
+    }
+}
+
+impl fmt::Display for PruneEmpty {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PruneEmpty::Depth(depth) => depth.fmt(f),
+            PruneEmpty::All => write!(f, "all"),
+        }
+    }
+}
+
+/// Remove directories which were left empty by trashing or moving the given
+/// sources, walking upwards from each of them but never past or including the
+/// source path it was found in.
+///
+/// Returns the number of directories removed.
+pub(crate) fn prune(o: &mut Out<'_>, config: &Config, sources: &[PathBuf]) -> Result<usize> {
+    let Some(prune) = config.prune_empty else {
+        return Ok(0);
+    };
+
+    let roots = config
+        .paths
+        .iter()
+        .map(|path| {
+            if path.is_dir() {
+                path.as_path()
+            } else {
+                path.parent().unwrap_or(Path::new(""))
+            }
+        })
+        .collect::<Vec<_>>();
+
+    remove_empty(o, config, &roots, sources, prune.depth())
+}
+
+/// Remove directories which were left empty by trashing orphaned destinations
+/// with `--prune`, walking upwards from each of them but never past or
+/// including the destination directory.
+///
+/// Returns the number of directories removed.
+pub(crate) fn prune_orphans(
+    o: &mut Out<'_>,
+    config: &Config,
+    orphans: &[PathBuf],
+) -> Result<usize> {
+    let Some(to_dir) = &config.to_dir else {
+        return Ok(0);
+    };
+
+    if !config.prune {
+        return Ok(0);
+    }
+
+    remove_empty(o, config, &[to_dir.as_path()], orphans, usize::MAX)
+}
+
+/// Collect files in the destination directory which don't correspond to the
+/// destination of any planned task, so that they can be trashed with
+/// `--prune`.
+///
+/// Hidden files, partial conversion files and files in the trash directory are
+/// left alone.
+pub(crate) fn orphans(config: &Config, tasks: &Tasks) -> Result<Vec<Trash>> {
+    let Some(to_dir) = &config.to_dir else {
+        return Ok(Vec::new());
+    };
+
+    if !config.prune || !to_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let canonical_to = fs::canonicalize(to_dir)?;
+
+    for path in &config.paths {
+        let Ok(path) = fs::canonicalize(path) else {
+            continue;
+        };
+
+        if path.starts_with(&canonical_to) || canonical_to.starts_with(&path) {
+            bail!(
+                "--prune requires --to to be outside of the source path {}",
+                shell::path(&path)
+            );
+        }
+    }
+
+    let mut expected = HashSet::new();
+
+    for c in &tasks.tasks {
+        expected.insert(c.to_path.to_path_buf());
+
+        if let Some(format) = config.chapters_sidecar {
+            expected.insert(format.sidecar_path(&c.to_path));
+        }
+    }
+
+    let mut paths = Vec::new();
+
+    for entry in ignore::Walk::new(to_dir) {
+        let entry = entry?;
+
+        if !entry.file_type().is_some_and(|ty| ty.is_file()) {
+            continue;
+        }
+
+        let path = entry.into_path();
+
+        if expected.contains(&path) || path.starts_with(&config.trash) {
+            continue;
+        }
+
+        if path
+            .extension()
+            .is_some_and(|ext| ext.to_str() == Some(config.part_ext.as_str()))
+        {
+            continue;
+        }
+
+        paths.push(path);
+    }
+
+    paths.sort();
+
+    let mut orphans = Vec::with_capacity(paths.len());
+    let mut names = HashSet::new();
+
+    for path in paths {
+        orphans.push(Trash {
+            what: TrashWhat::Orphan,
+            name: trash_name(config, &mut names, &path),
+            path: Link::new(&path)?,
+        });
+    }
+
+    Ok(orphans)
+}
+
+/// Pick a name in the trash directory for an orphan which doesn't collide with
+/// other orphans or files already in the trash, since destinations in
+/// different directories commonly share names.
+fn trash_name(config: &Config, names: &mut HashSet<OsString>, path: &Path) -> OsString {
+    let stem = path.file_stem().unwrap_or_default();
+    let ext = path.extension();
+
+    let mut n = 1;
+
+    loop {
+        let mut name = stem.to_owned();
+
+        if n > 1 {
+            name.push(format!(" ({n})"));
+        }
+
+        if let Some(ext) = ext {
+            name.push(".");
+            name.push(ext);
+        }
+
+        if !names.contains(&name) && !config.trash.join(&name).exists() {
+            names.insert(name.clone());
+            return name;
+        }
+
+        n += 1;
+    }
+}
+
+/// Remove empty directories walking upwards from each of the given paths, at
+/// most `depth` levels and never past or including the root it's in.
+fn remove_empty(
+    o: &mut Out<'_>,
+    config: &Config,
+    roots: &[&Path],
+    paths: &[PathBuf],
+    depth: usize,
+) -> Result<usize> {
+    // Deeper directories are visited first, so that their parents are empty
+    // by the time they are checked.
+    let mut dirs = BTreeSet::new();
+
+    for source in paths {
+        let root = roots
+            .iter()
+            .filter(|r| source.starts_with(r))
+            .max_by_key(|r| r.components().count());
+
+        let Some(root) = root else {
+            continue;
+        };
+
+        let mut dir = source.as_path();
+
+        for _ in 0..depth {
+            let Some(parent) = dir.parent() else {
+                break;
+            };
+
+            if parent == *root || !parent.starts_with(root) {
+                break;
+            }
+
+            dirs.insert(parent.to_path_buf());
+            dir = parent;
+        }
+    }
+
+    let mut removed = 0;
+
+    for dir in dirs.iter().rev() {
+        if !trash::is_empty_dir(dir) {
+            continue;
+        }
+
+        info!(o, "Removing empty directory");
+        let mut o = o.indent(1);
+        blank!(o, "path: {}", shell::path(dir));
+
+        if config.dry_run {
+            continue;
+        }
+
+        if let Err(e) = config.effects.remove_dir(dir) {
+            error!(o, "{e}");
+            continue;
+        }
+
+        removed += 1;
+    }
+
+    Ok(removed)
+}
@@ -0,0 +1,97 @@
+use core::fmt;
+use core::str::FromStr;
+
+use anyhow::{Error, Result, anyhow};
+use lofty::tag::ItemKey;
+
+use crate::normalize::Loudness;
+
+/// The loudness ReplayGain 2.0 adjusts files to, in LUFS.
+const REFERENCE: f64 = -18.0;
+
+/// Which ReplayGain tags are written to converted files with `--replaygain`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ReplayGain {
+    /// Only the gain of each track.
+    Track,
+    /// The gain of each track and of the album it belongs to.
+    Album,
+}
+
+impl FromStr for ReplayGain {
+    type Err = Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "track" => Ok(ReplayGain::Track),
+            "album" => Ok(ReplayGain::Album),
+            _ => Err(anyhow!("Invalid ReplayGain mode '{s}'")),
+        }
+    }
+}
+
+impl fmt::Display for ReplayGain {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayGain::Track => write!(f, "track"),
+            ReplayGain::Album => write!(f, "album"),
+        }
+    }
+}
+
+/// The ReplayGain values of a file.
+#[derive(Clone)]
+pub(crate) struct Values {
+    track: (f64, f64),
+    album: Option<(f64, f64)>,
+}
+
+impl Values {
+    /// Compute values from the loudness of a track and optionally the
+    /// integrated loudness and true peak of its album.
+    pub(crate) fn new(track: &Loudness, album: Option<(f64, f64)>) -> Self {
+        Self {
+            track: gain(track.integrated, track.true_peak),
+            album: album.map(|(integrated, true_peak)| gain(integrated, true_peak)),
+        }
+    }
+
+    /// The tags to write.
+    pub(crate) fn items(&self) -> Vec<(ItemKey, String)> {
+        let (gain, peak) = self.track;
+
+        let mut items = vec![
+            (ItemKey::ReplayGainTrackGain, format!("{gain:.2} dB")),
+            (ItemKey::ReplayGainTrackPeak, format!("{peak:.6}")),
+        ];
+
+        if let Some((gain, peak)) = self.album {
+            items.push((ItemKey::ReplayGainAlbumGain, format!("{gain:.2} dB")));
+            items.push((ItemKey::ReplayGainAlbumPeak, format!("{peak:.6}")));
+        }
+
+        items
+    }
+}
+
+impl fmt::Display for Values {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (gain, _) = self.track;
+        write!(f, "track {gain:+.2} dB")?;
+
+        if let Some((gain, _)) = self.album {
+            write!(f, ", album {gain:+.2} dB")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Convert an integrated loudness and true peak into a gain in dB and a
+/// linear peak.
+fn gain(integrated: f64, true_peak: f64) -> (f64, f64) {
+    (REFERENCE - integrated, 10f64.powf(true_peak / 20.0))
+}
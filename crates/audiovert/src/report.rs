@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use anyhow::Result;
+use mediavert_core::report::{self, Report};
+
+use crate::config::{Config, Source};
+use crate::plan;
+use crate::summary::Summary;
+use crate::tasks::{TaskKind, Tasks, TransferKind};
+
+/// The outcome of a task as shown in a report.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    /// The destination already existed.
+    Exists,
+    /// The task would be performed, but this is a dry run.
+    Planned,
+    /// The task was performed.
+    Done,
+    /// The task failed.
+    Failed,
+    /// The task wasn't performed since the run was stopped.
+    Stopped,
+}
+
+impl Outcome {
+    /// An icon which tells outcomes apart at a glance.
+    fn icon(&self) -> &'static str {
+        match self {
+            Outcome::Exists => "=",
+            Outcome::Planned => "·",
+            Outcome::Done => "✓",
+            Outcome::Failed => "✗",
+            Outcome::Stopped => "‖",
+        }
+    }
+
+    /// Describe the outcome of a task of the given kind, like `converted` or
+    /// `linked` for tasks which were done.
+    fn describe(&self, kind: &TaskKind) -> &'static str {
+        match (self, kind) {
+            (Outcome::Exists, _) => "exists",
+            (Outcome::Planned, _) => "planned",
+            (Outcome::Done, TaskKind::Convert { .. } | TaskKind::External { .. }) => "converted",
+            (Outcome::Done, TaskKind::Transfer { kind }) => match kind {
+                TransferKind::Copy => "copied",
+                TransferKind::Link => "linked",
+                TransferKind::Move => "moved",
+            },
+            (Outcome::Failed, _) => "failed",
+            (Outcome::Stopped, _) => "stopped",
+        }
+    }
+
+    /// The order outcomes are listed in within an album, where outcomes which
+    /// need attention come first.
+    fn rank(&self) -> u8 {
+        match self {
+            Outcome::Failed => 0,
+            Outcome::Stopped => 1,
+            Outcome::Done => 2,
+            Outcome::Planned => 3,
+            Outcome::Exists => 4,
+        }
+    }
+}
+
+/// Add the summary of a run to a report.
+pub(crate) fn summary(report: &mut Report, summary: &Summary) {
+    report.summary("Dry run", if summary.dry_run { "yes" } else { "no" });
+    report.summary("Converted", summary.converted);
+    report.summary("Transferred", summary.transferred);
+    report.summary("Failed", summary.failed);
+    report.summary("Already exists", summary.already_exists);
+    report.summary("Unsupported", summary.unsupported);
+    report.summary("Trashed", summary.trashed);
+
+    if summary.deduplicated > 0 {
+        report.summary("Deduplicated", summary.deduplicated);
+    }
+
+    for (warning, count) in &summary.warnings {
+        report.summary(format_args!("Warnings ({warning})"), count);
+    }
+}
+
+/// Add a table per album with the outcome of its tasks to a report.
+pub(crate) fn tasks(
+    report: &mut Report,
+    config: &Config,
+    tasks: &Tasks,
+    outcomes: &[Outcome],
+) -> Result<()> {
+    let mut albums = BTreeMap::<_, Vec<_>>::new();
+
+    for (c, &outcome) in tasks.tasks.iter().zip(outcomes) {
+        let album = match c.to_path.parent() {
+            Some(parent) => parent.display().to_string(),
+            None => String::new(),
+        };
+
+        albums.entry(album).or_default().push((c, outcome));
+    }
+
+    for (album, mut entries) in albums {
+        entries.sort_by_key(|(_, outcome)| outcome.rank());
+
+        let mut counts = BTreeMap::<_, usize>::new();
+
+        for (c, outcome) in &entries {
+            *counts
+                .entry((outcome.rank(), outcome.describe(&c.kind)))
+                .or_default() += 1;
+        }
+
+        let counts = counts
+            .into_iter()
+            .map(|((_, describe), count)| format!("{count} {describe}"))
+            .collect::<Vec<_>>();
+
+        let section = report.section(
+            format_args!("{album} ({})", counts.join(", ")),
+            &["Status", "Source", "Destination", "Task", "Before", "After"],
+        );
+
+        for (c, outcome) in entries {
+            let source = match &c.source {
+                Source::File { file } => tasks.db.file(*file)?.display().to_string(),
+                Source::Archive { archive, path } => {
+                    let archive = tasks.db.archive(*archive)?;
+                    format!("{}/{path}", archive.path.display())
+                }
+            };
+
+            let before = tasks.db.size(&c.source)?;
+
+            let after = match outcome {
+                Outcome::Exists | Outcome::Done if !config.dry_run => {
+                    fs::metadata(&c.to_path).ok().map(|m| m.len())
+                }
+                Outcome::Exists | Outcome::Done | Outcome::Planned => match &c.kind {
+                    TaskKind::Convert {
+                        to, bitrate, split, ..
+                    } => plan::estimate(
+                        config,
+                        tasks.db.meta(&c.source),
+                        split.as_deref(),
+                        *to,
+                        *bitrate,
+                    ),
+                    TaskKind::External { .. } => None,
+                    TaskKind::Transfer { .. } => before,
+                },
+                Outcome::Failed | Outcome::Stopped => None,
+            };
+
+            let destination = match c.to_path.file_name() {
+                Some(name) => name.to_string_lossy().into_owned(),
+                None => c.to_path.display().to_string(),
+            };
+
+            let status = format!("{} {}", outcome.icon(), outcome.describe(&c.kind));
+
+            section.row(vec![
+                status.into(),
+                source.into(),
+                destination.into(),
+                c.kind.to_string().into(),
+                before.map(report::size).unwrap_or_default().into(),
+                after.map(report::size).unwrap_or_default().into(),
+            ]);
+        }
+    }
+
+    Ok(())
+}
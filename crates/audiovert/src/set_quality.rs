@@ -0,0 +1,60 @@
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::condition::{ConditionErr, FromCondition};
+
+#[derive(Debug)]
+pub(crate) enum SetQualityErr {
+    MissingSeparator,
+    InvalidFromCondition(ConditionErr),
+}
+
+impl fmt::Display for SetQualityErr {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingSeparator => write!(f, "missing '=' separator"),
+            Self::InvalidFromCondition(e) => write!(f, "invalid from condition: {e}"),
+        }
+    }
+}
+
+impl Error for SetQualityErr {}
+
+impl From<ConditionErr> for SetQualityErr {
+    #[inline]
+    fn from(e: ConditionErr) -> Self {
+        SetQualityErr::InvalidFromCondition(e)
+    }
+}
+
+/// A quality level for variable bitrate encoding, like `mp3=V0`.
+///
+/// The quality is validated once it's known which formats it applies to, since
+/// each format has its own scale.
+#[derive(Clone)]
+pub(crate) struct SetQuality {
+    pub(crate) from: FromCondition,
+    pub(crate) quality: String,
+}
+
+impl FromStr for SetQuality {
+    type Err = SetQualityErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (from, quality) = s.split_once('=').ok_or(SetQualityErr::MissingSeparator)?;
+
+        Ok(SetQuality {
+            from: from.parse()?,
+            quality: quality.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for SetQuality {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.from, self.quality)
+    }
+}
@@ -0,0 +1,47 @@
+use std::collections::BTreeMap;
+
+use mediavert_core::config::Shared;
+use mediavert_core::plugin::Plugin;
+use serde::Deserialize;
+
+/// The configuration file shared by mediavert tools.
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub(crate) struct Settings {
+    /// Settings shared by all tools.
+    #[serde(flatten)]
+    pub(crate) shared: Shared,
+    /// Configuration specific to audiovert.
+    pub(crate) audiovert: AudiovertSettings,
+}
+
+impl Settings {
+    /// The shared settings, where settings in the `[audiovert]` section take
+    /// precedence.
+    pub(crate) fn shared(&self) -> Shared {
+        self.audiovert.shared.clone().or(self.shared.clone())
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub(crate) struct AudiovertSettings {
+    /// Shared settings which only apply to audiovert.
+    #[serde(flatten)]
+    pub(crate) shared: Shared,
+    /// The AcoustID API key used with `--musicbrainz`.
+    pub(crate) acoustid_key: Option<String>,
+    /// Plugins used to convert files by extension.
+    pub(crate) plugins: BTreeMap<String, PluginSettings>,
+}
+
+/// A plugin which converts files with a given extension into a supported
+/// format.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct PluginSettings {
+    /// The format produced by the plugin.
+    pub(crate) to: String,
+    #[serde(flatten)]
+    pub(crate) plugin: Plugin,
+}
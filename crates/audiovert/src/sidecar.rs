@@ -0,0 +1,69 @@
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+
+#[derive(Debug)]
+pub(crate) enum SidecarErr {
+    Empty,
+    Separator,
+}
+
+impl fmt::Display for SidecarErr {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty pattern"),
+            Self::Separator => write!(f, "pattern can only match file names"),
+        }
+    }
+}
+
+impl Error for SidecarErr {}
+
+/// A pattern matching the names of files which are carried along to the
+/// destination with `--copy-sidecar`, like `*.cue` or `cover.jpg`.
+///
+/// A `*` matches any number of characters and a `?` matches exactly one.
+/// Names are matched without regard to case.
+#[derive(Clone)]
+pub(crate) struct Sidecar {
+    pattern: Vec<char>,
+}
+
+impl Sidecar {
+    /// Test if the pattern matches a file name.
+    pub(crate) fn matches(&self, name: &str) -> bool {
+        let name = name
+            .chars()
+            .flat_map(char::to_lowercase)
+            .collect::<Vec<_>>();
+        matches(&self.pattern, &name)
+    }
+}
+
+fn matches(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|n| matches(rest, &name[n..])),
+        Some(('?', rest)) => !name.is_empty() && matches(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && matches(rest, &name[1..]),
+    }
+}
+
+impl FromStr for Sidecar {
+    type Err = SidecarErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(SidecarErr::Empty);
+        }
+
+        if s.contains(['/', '\\']) {
+            return Err(SidecarErr::Separator);
+        }
+
+        Ok(Self {
+            pattern: s.chars().flat_map(char::to_lowercase).collect(),
+        })
+    }
+}
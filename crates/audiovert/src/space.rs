@@ -0,0 +1,103 @@
+use core::fmt;
+use core::str::FromStr;
+use core::time::Duration;
+
+use std::path::Path;
+use std::thread;
+
+use anyhow::{Error, Result, anyhow};
+use mediavert_core::out::{Out, info, warn};
+use mediavert_core::report;
+use mediavert_core::space;
+
+use crate::config::Config;
+
+/// How often free space is checked again while paused.
+const POLL: Duration = Duration::from_secs(1);
+
+/// What to do when free space on the target runs low.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LowSpace {
+    /// Stop the run, so that it can be resumed once space has been freed.
+    #[default]
+    Stop,
+    /// Pause the run until space has been freed.
+    Pause,
+}
+
+impl FromStr for LowSpace {
+    type Err = Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "stop" => Ok(LowSpace::Stop),
+            "pause" => Ok(LowSpace::Pause),
+            _ => Err(anyhow!("Invalid low space policy '{s}'")),
+        }
+    }
+}
+
+impl fmt::Display for LowSpace {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LowSpace::Stop => write!(f, "stop"),
+            LowSpace::Pause => write!(f, "pause"),
+        }
+    }
+}
+
+/// Check that there's enough free space on the filesystem `to_path` is written
+/// to before running a task, waiting for it to be freed if configured to.
+///
+/// Returns `false` if the run should stop.
+pub(crate) fn check(o: &mut Out<'_>, config: &Config, to_path: &Path) -> Result<bool> {
+    let Some(min) = config.min_free_space else {
+        return Ok(true);
+    };
+
+    let mut paused = false;
+
+    loop {
+        let Some(available) = space::available(to_path)? else {
+            return Ok(true);
+        };
+
+        if available >= min {
+            if paused {
+                info!(o, "Resuming with {} free", report::size(available));
+            }
+
+            return Ok(true);
+        }
+
+        match config.on_low_space {
+            LowSpace::Stop => {
+                warn!(
+                    o,
+                    "Stopping since only {} is free on the target, run again to resume",
+                    report::size(available)
+                );
+                return Ok(false);
+            }
+            LowSpace::Pause => {
+                if !paused {
+                    warn!(
+                        o,
+                        "Pausing since only {} is free on the target, waiting for {} to be free",
+                        report::size(available),
+                        report::size(min)
+                    );
+                    paused = true;
+                }
+
+                if config.cancel.is_cancelled() {
+                    return Ok(false);
+                }
+
+                thread::sleep(POLL);
+            }
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use core::fmt;
+
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+
+use anyhow::{Result, bail};
+use mediavert_core::archive::Archive;
+use mediavert_core::out::{Out, blank, info};
+use mediavert_core::report;
+use mediavert_core::shell;
+
+use crate::config::{Config, Db};
+use crate::format::Format;
+use crate::meta::Meta;
+
+/// The number and total size of files in a group.
+#[derive(Default)]
+struct Count {
+    files: u64,
+    bytes: u64,
+}
+
+impl Count {
+    #[inline]
+    fn add(&mut self, bytes: u64) {
+        self.files += 1;
+        self.bytes += bytes;
+    }
+}
+
+impl fmt::Display for Count {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} files, {}", self.files, report::size(self.bytes))
+    }
+}
+
+/// A range of bitrates that audio files are grouped by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Bucket {
+    Lossless,
+    Above320,
+    Below320,
+    Below256,
+    Below192,
+    Below128,
+    Unknown,
+}
+
+impl Bucket {
+    fn new(format: Format, bitrate: Option<u32>) -> Self {
+        if format.is_lossless() {
+            return Bucket::Lossless;
+        }
+
+        match bitrate {
+            Some(320..) => Bucket::Above320,
+            Some(256..) => Bucket::Below320,
+            Some(192..) => Bucket::Below256,
+            Some(128..) => Bucket::Below192,
+            Some(_) => Bucket::Below128,
+            None => Bucket::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for Bucket {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bucket::Lossless => write!(f, "lossless"),
+            Bucket::Above320 => write!(f, "320 kbps and above"),
+            Bucket::Below320 => write!(f, "256-319 kbps"),
+            Bucket::Below256 => write!(f, "192-255 kbps"),
+            Bucket::Below192 => write!(f, "128-191 kbps"),
+            Bucket::Below128 => write!(f, "below 128 kbps"),
+            Bucket::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// The composition of the sources in the library.
+#[derive(Default)]
+struct Stats {
+    total: Count,
+    formats: BTreeMap<String, Count>,
+    bitrates: BTreeMap<Bucket, Count>,
+    archives: BTreeMap<String, Count>,
+    dirs: BTreeMap<String, Count>,
+    untagged: u64,
+}
+
+/// Scan the configured paths and report the composition of the library
+/// without planning or running any tasks.
+pub(crate) fn run(o: &mut Out<'_>, config: &Config) -> Result<()> {
+    let mut db = Db::new();
+    let mut stats = Stats::default();
+    let mut sources = Vec::new();
+
+    for walk_path in &config.paths {
+        for f in ignore::Walk::new(walk_path) {
+            if config.cancel.is_cancelled() {
+                bail!("Cancelled while scanning");
+            }
+
+            let entry = f?;
+            let walked = entry.path();
+
+            if !walked.is_file() {
+                continue;
+            }
+
+            let Some(ext) = walked.extension().and_then(|s| s.to_str()) else {
+                continue;
+            };
+
+            let dir = top_level(walk_path, walked);
+
+            if let Some(kind) = Archive::from_ext(ext) {
+                let bytes = walked.metadata().map(|m| m.len()).unwrap_or_default();
+                stats
+                    .archives
+                    .entry(kind.to_string())
+                    .or_default()
+                    .add(bytes);
+            }
+
+            db.discover(walked, ext, &mut sources)?;
+
+            for source in sources.drain(..) {
+                let bytes = db.size(&source)?.unwrap_or_default();
+                let ext = db.ext(&source)?.unwrap_or_default().to_owned();
+
+                stats.total.add(bytes);
+                stats.dirs.entry(dir.clone()).or_default().add(bytes);
+                stats.formats.entry(ext.clone()).or_default().add(bytes);
+
+                let Some(format) = Format::from_ext(&ext) else {
+                    continue;
+                };
+
+                let meta = Meta::read(&source, &db).ok();
+
+                if meta.as_ref().is_none_or(|m| m.len() == 0) {
+                    stats.untagged += 1;
+                }
+
+                let bitrate = meta.as_ref().and_then(Meta::bitrate);
+                let bucket = Bucket::new(format, bitrate);
+                stats.bitrates.entry(bucket).or_default().add(bytes);
+            }
+        }
+    }
+
+    info!(o, "Sources: {}", stats.total);
+
+    {
+        info!(o, "Formats:");
+        let mut o = o.indent(1);
+
+        for (ext, count) in &stats.formats {
+            if Format::from_ext(ext).is_some() {
+                blank!(o, "{ext}: {count}");
+            } else if config.externals.contains_key(ext) {
+                blank!(o, "{ext} (plugin): {count}");
+            } else {
+                blank!(o, "{ext} (unsupported): {count}");
+            }
+        }
+    }
+
+    if !stats.bitrates.is_empty() {
+        info!(o, "Bitrates:");
+        let mut o = o.indent(1);
+
+        for (bucket, count) in &stats.bitrates {
+            blank!(o, "{bucket}: {count}");
+        }
+    }
+
+    if !stats.archives.is_empty() {
+        info!(o, "Archives:");
+        let mut o = o.indent(1);
+
+        for (kind, count) in &stats.archives {
+            blank!(o, "{kind}: {count}");
+        }
+    }
+
+    info!(o, "Untagged: {} files", stats.untagged);
+
+    let mut dirs = stats.dirs.into_iter().collect::<Vec<_>>();
+    dirs.sort_by(|a, b| config.sort_order.compare(&a.0, &b.0));
+
+    info!(o, "Directories:");
+    let mut o = o.indent(1);
+
+    for (dir, count) in &dirs {
+        blank!(o, "{dir}: {count}");
+    }
+
+    Ok(())
+}
+
+/// The top-level directory inside of the walked path that a file belongs to.
+fn top_level(walk_path: &Path, walked: &Path) -> String {
+    let relative = walked.strip_prefix(walk_path).unwrap_or(walked);
+    let mut components = relative.components();
+
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(first)), Some(_)) => {
+            shell::path(&walk_path.join(first)).to_string()
+        }
+        _ => shell::path(walk_path).to_string(),
+    }
+}
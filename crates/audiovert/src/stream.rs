@@ -0,0 +1,219 @@
+use core::fmt;
+use core::str::FromStr;
+use core::time::Duration;
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use anyhow::{Context, Error, Result, anyhow, bail};
+
+use crate::config::{Config, Db, Source};
+use crate::format::Format;
+
+/// Selects which audio stream of a source to convert.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Stream {
+    /// The audio stream at the given index, counting from zero.
+    Index(usize),
+    /// The first audio stream in the given language, like `eng`.
+    Language(String),
+}
+
+impl Stream {
+    /// Select an audio stream, returning its index.
+    pub(crate) fn select(&self, streams: &[AudioStream]) -> Result<usize> {
+        match self {
+            Stream::Index(index) => {
+                if *index >= streams.len() {
+                    bail!(
+                        "Audio stream {index} selected, but there are only {}",
+                        streams.len()
+                    );
+                }
+
+                Ok(*index)
+            }
+            Stream::Language(language) => streams
+                .iter()
+                .position(|s| s.language.as_deref() == Some(language.as_str()))
+                .ok_or_else(|| anyhow!("No audio stream in language '{language}'")),
+        }
+    }
+}
+
+impl FromStr for Stream {
+    type Err = Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        if let Ok(index) = s.parse() {
+            return Ok(Stream::Index(index));
+        }
+
+        if !s.is_empty() && s.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Ok(Stream::Language(s.to_ascii_lowercase()));
+        }
+
+        Err(anyhow!("Invalid stream '{s}'"))
+    }
+}
+
+impl fmt::Display for Stream {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stream::Index(index) => index.fmt(f),
+            Stream::Language(language) => language.fmt(f),
+        }
+    }
+}
+
+/// An audio stream in a source.
+pub(crate) struct AudioStream {
+    language: Option<String>,
+    /// The sample rate in Hz.
+    pub(crate) sample_rate: Option<u32>,
+    /// The number of bits per sample, which is only known for integer
+    /// samples.
+    pub(crate) bit_depth: Option<u32>,
+    /// The duration of the source the stream is in.
+    pub(crate) duration: Option<Duration>,
+}
+
+/// Probe the audio streams of a source using ffmpeg.
+pub(crate) fn probe(config: &Config, db: &Db, source: &Source) -> Result<Vec<AudioStream>> {
+    let mut command = Command::new(&config.ffmpeg);
+    command.arg("-hide_banner");
+
+    if let Some(from) = db.ext(source)?.and_then(Format::from_ext) {
+        command.args(config.input_args(from));
+    }
+
+    command.stdout(Stdio::null());
+    command.stderr(Stdio::piped());
+
+    let output = match source {
+        Source::File { file } => {
+            command.arg("-i").arg(db.file(*file)?.as_os_str());
+            command.stdin(Stdio::null());
+            command.output().with_context(|| {
+                anyhow!("Failed to probe streams with {}", config.ffmpeg.display())
+            })?
+        }
+        Source::Archive { archive, path } => {
+            let contents = db
+                .archive_contents(*archive, path)
+                .context("reading source contents")?;
+
+            command.args(["-i", "pipe:"]);
+            command.stdin(Stdio::piped());
+
+            let mut child = command.spawn().with_context(|| {
+                anyhow!("Failed to probe streams with {}", config.ffmpeg.display())
+            })?;
+            let mut stdin = child.stdin.take().context("missing stdin")?;
+
+            // ffmpeg stops reading once it has found the streams, so errors
+            // from writing the rest of the source are expected.
+            let writer = thread::spawn(move || {
+                _ = stdin.write_all(&contents);
+            });
+
+            let output = child.wait_with_output().with_context(|| {
+                anyhow!("Failed to probe streams with {}", config.ffmpeg.display())
+            })?;
+            _ = writer.join();
+            output
+        }
+    };
+
+    // Without an output ffmpeg exits with an error after listing the streams
+    // of the input, so the status isn't checked.
+    Ok(parse(&String::from_utf8_lossy(&output.stderr)))
+}
+
+/// Parse the audio streams listed by ffmpeg, which look like this:
+///
+/// ```text
+/// Stream #0:0(eng): Audio: flac, 44100 Hz, stereo, s16
+/// Stream #0:1[0x1c0](jpn): Audio: mp2, 48000 Hz, stereo, fltp, 192 kb/s
+/// Stream #0:0: Audio: flac, 96000 Hz, stereo, s32 (24 bit)
+/// ```
+fn parse(stderr: &str) -> Vec<AudioStream> {
+    let duration = duration(stderr);
+    let mut streams = Vec::new();
+
+    for line in stderr.lines() {
+        let Some(rest) = line.trim_start().strip_prefix("Stream #0:") else {
+            continue;
+        };
+
+        let Some((head, kind)) = rest.split_once(": ") else {
+            continue;
+        };
+
+        if !kind.starts_with("Audio:") {
+            continue;
+        }
+
+        let language = head
+            .split_once('(')
+            .and_then(|(_, rest)| rest.split_once(')'))
+            .map(|(language, _)| language.to_ascii_lowercase())
+            .filter(|language| language != "und");
+
+        let mut sample_rate = None;
+        let mut bit_depth = None;
+
+        for field in kind.split(", ") {
+            if let Some(rate) = field.strip_suffix(" Hz") {
+                sample_rate = rate.parse().ok();
+                continue;
+            }
+
+            // The number of bits actually used is noted when it's fewer than
+            // the sample format has room for.
+            if let Some((_, bits)) = field.split_once(" (")
+                && let Some(bits) = bits.strip_suffix(" bit)")
+            {
+                bit_depth = bits.parse().ok();
+                continue;
+            }
+
+            bit_depth = match field.trim_end_matches('p') {
+                "u8" => Some(8),
+                "s16" => Some(16),
+                "s32" => Some(32),
+                "s64" => Some(64),
+                _ => continue,
+            };
+        }
+
+        streams.push(AudioStream {
+            language,
+            sample_rate,
+            bit_depth,
+            duration,
+        });
+    }
+
+    streams
+}
+
+/// Find the duration of the input in the details ffmpeg prints about it, like
+/// `Duration: 00:03:12.34`.
+pub(crate) fn duration(stderr: &str) -> Option<Duration> {
+    let (_, rest) = stderr.split_once("Duration: ")?;
+    let (time, _) = rest.split_once(',')?;
+
+    let mut parts = time.split(':');
+    let hours = parts.next()?.parse::<u64>().ok()?;
+    let minutes = parts.next()?.parse::<u64>().ok()?;
+    let seconds = parts.next()?.parse::<f64>().ok()?;
+
+    Some(
+        Duration::from_secs(hours * 3600 + minutes * 60)
+            + Duration::try_from_secs_f64(seconds).ok()?,
+    )
+}
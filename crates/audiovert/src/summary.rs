@@ -0,0 +1,97 @@
+use core::fmt;
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::warning::Warning;
+
+/// A summary of the outcome of a run.
+#[derive(Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct Summary {
+    /// Whether this was a dry run.
+    pub(crate) dry_run: bool,
+    /// Number of files converted.
+    pub(crate) converted: usize,
+    /// Number of files linked, copied, or moved.
+    pub(crate) transferred: usize,
+    /// Number of files which failed to process.
+    pub(crate) failed: usize,
+    /// Number of files not processed since the run was stopped.
+    pub(crate) stopped: usize,
+    /// Number of files skipped since their destination already exists.
+    pub(crate) already_exists: usize,
+    /// Number of files with an unsupported extension.
+    pub(crate) unsupported: usize,
+    /// Number of outputs replaced with hard links to identical outputs.
+    pub(crate) deduplicated: usize,
+    /// Number of files moved to the trash.
+    pub(crate) trashed: usize,
+    /// Number of albums synchronized with `--sync-to`.
+    pub(crate) synced: usize,
+    /// Number of albums which failed to synchronize.
+    pub(crate) sync_failed: usize,
+    /// Number of warnings by category, including suppressed ones.
+    pub(crate) warnings: BTreeMap<Warning, usize>,
+    /// The error which aborted the run, if any.
+    pub(crate) error: Option<String>,
+}
+
+impl Summary {
+    /// Count a warning of the given category.
+    pub(crate) fn warn(&mut self, warning: Warning) {
+        *self.warnings.entry(warning).or_default() += 1;
+    }
+
+    /// Add the warnings counted in another summary, like the one of a task run
+    /// on a worker.
+    pub(crate) fn merge_warnings(&mut self, other: &Summary) {
+        for (&warning, count) in &other.warnings {
+            *self.warnings.entry(warning).or_default() += count;
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.dry_run {
+            writeln!(f, "Dry run")?;
+        }
+
+        writeln!(f, "Converted: {}", self.converted)?;
+        writeln!(f, "Transferred: {}", self.transferred)?;
+        writeln!(f, "Failed: {}", self.failed)?;
+
+        if self.stopped > 0 {
+            writeln!(f, "Stopped: {}", self.stopped)?;
+        }
+
+        writeln!(f, "Already exists: {}", self.already_exists)?;
+        writeln!(f, "Unsupported: {}", self.unsupported)?;
+        write!(f, "Trashed: {}", self.trashed)?;
+
+        if self.deduplicated > 0 {
+            write!(f, "\nDeduplicated: {}", self.deduplicated)?;
+        }
+
+        if self.synced > 0 || self.sync_failed > 0 {
+            write!(f, "\nSynced: {}", self.synced)?;
+            write!(f, "\nSync failed: {}", self.sync_failed)?;
+        }
+
+        if !self.warnings.is_empty() {
+            write!(f, "\nWarnings:")?;
+
+            for (warning, count) in &self.warnings {
+                write!(f, " {warning}={count}")?;
+            }
+        }
+
+        if let Some(error) = &self.error {
+            write!(f, "\nError: {error}")?;
+        }
+
+        Ok(())
+    }
+}
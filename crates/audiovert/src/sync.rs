@@ -0,0 +1,109 @@
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+
+use std::ffi::OsString;
+use std::path::{Component, Path};
+use std::process::Command;
+
+#[derive(Debug)]
+pub(crate) struct SyncTargetErr;
+
+impl fmt::Display for SyncTargetErr {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "missing sync target")
+    }
+}
+
+impl Error for SyncTargetErr {}
+
+/// The tool used to synchronize albums.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum SyncTool {
+    Rsync,
+    Rclone,
+}
+
+/// A target albums are synchronized to once converted, like
+/// `rsync:phone:/sdcard/Music` or `rclone:drive:Music`.
+#[derive(Debug, Clone)]
+pub(crate) struct SyncTarget {
+    pub(crate) tool: SyncTool,
+    pub(crate) target: String,
+}
+
+impl SyncTarget {
+    /// Construct the command which synchronizes the album in `dir` to the
+    /// relative path `album` in the target.
+    pub(crate) fn command(&self, dir: &Path, album: &Path) -> Command {
+        let mut to = self.target.clone();
+
+        for c in album.components() {
+            if let Component::Normal(s) = c {
+                if !(to.is_empty() || to.ends_with(['/', ':'])) {
+                    to.push('/');
+                }
+
+                to.push_str(&s.to_string_lossy());
+            }
+        }
+
+        match self.tool {
+            SyncTool::Rsync => {
+                let mut from = OsString::from(dir);
+                from.push("/");
+
+                if !(to.is_empty() || to.ends_with(['/', ':'])) {
+                    to.push('/');
+                }
+
+                let mut command = Command::new("rsync");
+                command.args(["-a", "--mkpath"]);
+                command.arg(from);
+                command.arg(to);
+                command
+            }
+            SyncTool::Rclone => {
+                let mut command = Command::new("rclone");
+                command.arg("copy");
+                command.arg(dir);
+                command.arg(to);
+                command
+            }
+        }
+    }
+}
+
+impl FromStr for SyncTarget {
+    type Err = SyncTargetErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (tool, target) = if let Some(target) = s.strip_prefix("rclone:") {
+            (SyncTool::Rclone, target)
+        } else if let Some(target) = s.strip_prefix("rsync:") {
+            (SyncTool::Rsync, target)
+        } else {
+            (SyncTool::Rsync, s)
+        };
+
+        if target.is_empty() {
+            return Err(SyncTargetErr);
+        }
+
+        Ok(SyncTarget {
+            tool,
+            target: target.to_owned(),
+        })
+    }
+}
+
+impl fmt::Display for SyncTarget {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.tool {
+            SyncTool::Rsync => write!(f, "rsync:{}", self.target),
+            SyncTool::Rclone => write!(f, "rclone:{}", self.target),
+        }
+    }
+}
@@ -1,12 +1,21 @@
+use core::cmp::Reverse;
 use core::fmt;
+use core::str::FromStr;
 
 use std::collections::HashMap;
 use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use crate::config::{Db, Source};
+use anyhow::{Error, Result, anyhow};
+use mediavert_core::link::{Link, MaybeLink};
+use mediavert_core::shell;
+
+use crate::config::{Db, External, Source};
+use crate::cue::Split;
 use crate::format::Format;
-use crate::link::{Link, MaybeLink};
-use crate::meta::Meta;
+use crate::normalize::Gain;
+use crate::replaygain;
 
 pub(crate) struct Tasks {
     pub(crate) errors: Vec<PathError>,
@@ -16,7 +25,8 @@ pub(crate) struct Tasks {
     pub(crate) already_exists: Vec<Exists>,
     pub(crate) unsupported: Vec<Unsupported>,
     pub(crate) db: Db,
-    pub(crate) meta: HashMap<Source, Meta>,
+    /// The source each planned destination is produced from.
+    pub(crate) destinations: HashMap<PathBuf, Source>,
 }
 
 impl Tasks {
@@ -29,11 +39,257 @@ impl Tasks {
             already_exists: Vec::new(),
             unsupported: Vec::new(),
             db: Db::new(),
-            meta: HashMap::new(),
+            destinations: HashMap::new(),
+        }
+    }
+
+    /// Claim a destination for a source, resolving collisions with the
+    /// destinations of previously planned tasks according to `collision`.
+    ///
+    /// Returns `None` if no task should be planned for the source.
+    pub(crate) fn claim(
+        &mut self,
+        collision: Collision,
+        source: &Source,
+        to_path: PathBuf,
+    ) -> Result<Option<PathBuf>> {
+        let Some(other) = self.destinations.get(&to_path) else {
+            self.destinations.insert(to_path.clone(), source.clone());
+            return Ok(Some(to_path));
+        };
+
+        match collision {
+            Collision::Error => {
+                let message = format!(
+                    "Destination {} is also produced from {}",
+                    shell::path(&to_path),
+                    self.db.describe(other)?
+                );
+
+                self.errors.push(PathError {
+                    source: source.clone(),
+                    messages: vec![message],
+                });
+
+                Ok(None)
+            }
+            Collision::Skip => Ok(None),
+            Collision::Rename => {
+                let stem = to_path.file_stem().unwrap_or_default().to_owned();
+                let ext = to_path.extension().map(|e| e.to_owned());
+
+                let mut n = 2;
+
+                loop {
+                    let mut name = stem.clone();
+                    name.push(format!(" ({n})"));
+
+                    let mut renamed = to_path.with_file_name(name);
+
+                    if let Some(ext) = &ext {
+                        renamed.add_extension(ext);
+                    }
+
+                    if !self.destinations.contains_key(&renamed) {
+                        self.destinations.insert(renamed.clone(), source.clone());
+                        return Ok(Some(renamed));
+                    }
+
+                    n += 1;
+                }
+            }
+        }
+    }
+
+    /// Release the claim on a destination, so that it can be claimed by
+    /// another task.
+    pub(crate) fn release(&mut self, to_path: &Path) {
+        self.destinations.remove(to_path);
+    }
+
+    /// Drop tasks which aren't enabled, renumbering the remaining ones.
+    pub(crate) fn retain(&mut self, enabled: &[bool]) {
+        let mut n = 0;
+
+        self.tasks.retain(|_| {
+            let keep = enabled.get(n).copied().unwrap_or(true);
+            n += 1;
+            keep
+        });
+
+        for (index, c) in self.tasks.iter_mut().enumerate() {
+            c.index = index;
+        }
+
+        self.link();
+    }
+
+    /// Make every task depend on the tasks in earlier stages which share its
+    /// source.
+    pub(crate) fn link(&mut self) {
+        let mut by_source = HashMap::<_, Vec<usize>>::new();
+
+        for (n, c) in self.tasks.iter().enumerate() {
+            by_source.entry(c.source.clone()).or_default().push(n);
+        }
+
+        for group in by_source.into_values() {
+            for &n in &group {
+                let stage = self.tasks[n].kind.stage();
+
+                let after = group
+                    .iter()
+                    .copied()
+                    .filter(|&m| self.tasks[m].kind.stage() < stage)
+                    .collect();
+
+                self.tasks[n].after = after;
+            }
+        }
+    }
+
+    /// The order to run tasks in, where every task comes after the tasks it
+    /// depends on and otherwise is placed according to `priority`.
+    pub(crate) fn schedule(&self, priority: Priority) -> Result<Vec<usize>> {
+        let mut roots = (0..self.tasks.len()).collect::<Vec<_>>();
+
+        match priority {
+            Priority::Planned => {}
+            Priority::SmallestFirst | Priority::LargestFirst => {
+                let mut sizes = Vec::with_capacity(self.tasks.len());
+
+                for task in &self.tasks {
+                    sizes.push(self.db.size(&task.source)?.unwrap_or_default());
+                }
+
+                if priority == Priority::SmallestFirst {
+                    roots.sort_by_key(|&n| sizes[n]);
+                } else {
+                    roots.sort_by_key(|&n| Reverse(sizes[n]));
+                }
+            }
+            Priority::LosslessFirst => {
+                roots.sort_by_key(|&n| match &self.tasks[n].kind {
+                    TaskKind::Convert { from, .. } => !from.is_lossless(),
+                    _ => true,
+                });
+            }
+        }
+
+        let mut order = Vec::with_capacity(self.tasks.len());
+        let mut visited = vec![false; self.tasks.len()];
+
+        for n in roots {
+            self.visit(n, &mut visited, &mut order);
+        }
+
+        Ok(order)
+    }
+
+    fn visit(&self, n: usize, visited: &mut [bool], order: &mut Vec<usize>) {
+        if visited[n] {
+            return;
+        }
+
+        visited[n] = true;
+
+        for &m in &self.tasks[n].after {
+            self.visit(m, visited, order);
+        }
+
+        order.push(n);
+    }
+}
+
+/// What to do when multiple sources would produce the same destination.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Collision {
+    /// Report every source after the first as an error.
+    #[default]
+    Error,
+    /// Only plan a task for the first source.
+    Skip,
+    /// Add a number to the name of the destination, like `Song (2).mp3`.
+    Rename,
+}
+
+impl FromStr for Collision {
+    type Err = Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "error" => Ok(Collision::Error),
+            "skip" => Ok(Collision::Skip),
+            "rename" => Ok(Collision::Rename),
+            _ => Err(anyhow!("Invalid collision policy '{s}'")),
+        }
+    }
+}
+
+impl fmt::Display for Collision {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Collision::Error => write!(f, "error"),
+            Collision::Skip => write!(f, "skip"),
+            Collision::Rename => write!(f, "rename"),
+        }
+    }
+}
+
+/// The order in which tasks are run.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Priority {
+    /// The order in which tasks were planned.
+    #[default]
+    Planned,
+    /// Tasks with the smallest sources first.
+    SmallestFirst,
+    /// Tasks with the largest sources first.
+    LargestFirst,
+    /// Conversions from lossless formats first.
+    LosslessFirst,
+}
+
+impl FromStr for Priority {
+    type Err = Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "planned" => Ok(Priority::Planned),
+            "smallest-first" => Ok(Priority::SmallestFirst),
+            "largest-first" => Ok(Priority::LargestFirst),
+            "lossless-first" => Ok(Priority::LosslessFirst),
+            _ => Err(anyhow!("Invalid order '{s}'")),
         }
     }
 }
 
+impl fmt::Display for Priority {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Priority::Planned => write!(f, "planned"),
+            Priority::SmallestFirst => write!(f, "smallest-first"),
+            Priority::LargestFirst => write!(f, "largest-first"),
+            Priority::LosslessFirst => write!(f, "lossless-first"),
+        }
+    }
+}
+
+/// The stage of a pipeline that a task belongs to.
+///
+/// Tasks run after every task in an earlier stage which shares their source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Stage {
+    /// The task reads its source.
+    Read,
+    /// The task consumes its source, like by moving it.
+    Consume,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub(crate) enum TransferKind {
     Copy,
@@ -64,6 +320,7 @@ impl fmt::Display for TransferKind {
 }
 
 /// The kind of a task.
+#[derive(Clone)]
 pub(crate) enum TaskKind {
     /// Convert from one format to another.
     Convert {
@@ -73,10 +330,24 @@ pub(crate) enum TaskKind {
         from: Format,
         /// Format to convert to.
         to: Format,
+        /// Bitrate of the conversion rule which produced the task, which takes
+        /// precedence over the bitrate configured for the format.
+        bitrate: Option<u32>,
         /// Whether conversion has been done.
         converted: bool,
         /// Whether metadata tagging has been done.
         tagged: bool,
+        /// The track of a cue sheet to convert, instead of all of the source.
+        split: Option<Arc<Split>>,
+    },
+    /// Convert using an external plugin.
+    External {
+        /// Path of a partially converted file.
+        part_path: MaybeLink,
+        /// The plugin to convert with.
+        external: Arc<External>,
+        /// Whether conversion has been done.
+        converted: bool,
     },
     /// Transfer from source to destination.
     Transfer {
@@ -86,10 +357,22 @@ pub(crate) enum TaskKind {
 }
 
 impl TaskKind {
+    /// The stage the task belongs to.
+    #[inline]
+    pub(crate) fn stage(&self) -> Stage {
+        match self {
+            TaskKind::Transfer {
+                kind: TransferKind::Move,
+            } => Stage::Consume,
+            _ => Stage::Read,
+        }
+    }
+
     #[inline]
     pub(crate) fn is_completed(&self) -> bool {
         match self {
             TaskKind::Convert { converted, .. } => *converted,
+            TaskKind::External { converted, .. } => *converted,
             TaskKind::Transfer { .. } => true,
         }
     }
@@ -100,6 +383,13 @@ impl fmt::Display for TaskKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             TaskKind::Convert { from, to, .. } => write!(f, "converting {} to {}", from, to),
+            TaskKind::External { external, .. } => write!(
+                f,
+                "converting {} to {} with {}",
+                external.ext,
+                external.to,
+                external.plugin.program()
+            ),
             TaskKind::Transfer { kind } => kind.fmt(f),
         }
     }
@@ -112,6 +402,7 @@ pub(crate) struct PathError {
 }
 
 /// A prepared task for conversion or transfer.
+#[derive(Clone)]
 pub(crate) struct Task {
     pub(crate) index: usize,
     pub(crate) kind: TaskKind,
@@ -119,6 +410,14 @@ pub(crate) struct Task {
     pub(crate) to_path: MaybeLink,
     pub(crate) moved: bool,
     pub(crate) pre_remove: Vec<(&'static str, MaybeLink)>,
+    /// Tasks which have to complete before this one can run.
+    pub(crate) after: Vec<usize>,
+    /// The gain found by analyzing the loudness of the source with
+    /// `--normalize`.
+    pub(crate) gain: Option<Gain>,
+    /// The ReplayGain tags found by analyzing the loudness of the source with
+    /// `--replaygain`.
+    pub(crate) replaygain: Option<replaygain::Values>,
 }
 
 impl Task {
@@ -135,6 +434,7 @@ pub(crate) struct MatchingConversion {
 
 pub(crate) enum TrashWhat {
     SourceFile,
+    Orphan,
 }
 
 impl fmt::Display for TrashWhat {
@@ -142,6 +442,7 @@ impl fmt::Display for TrashWhat {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::SourceFile => write!(f, "source file"),
+            Self::Orphan => write!(f, "orphaned destination"),
         }
     }
 }
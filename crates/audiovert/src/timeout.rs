@@ -0,0 +1,105 @@
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+use core::time::Duration;
+
+#[derive(Debug)]
+pub(crate) enum TimeoutErr {
+    Empty,
+    MissingNumber,
+    UnknownUnit(String),
+    Zero,
+}
+
+impl fmt::Display for TimeoutErr {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty timeout"),
+            Self::MissingNumber => write!(f, "expected a number like 10m or 90s"),
+            Self::UnknownUnit(unit) => {
+                write!(f, "unknown unit '{unit}', expected one of s, m, or h")
+            }
+            Self::Zero => write!(f, "timeout has to be longer than zero"),
+        }
+    }
+}
+
+impl Error for TimeoutErr {}
+
+/// How long a task is allowed to run with `--task-timeout`, like `90s`, `10m`
+/// or `1h30m`. A number without a unit is in seconds.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Timeout {
+    duration: Duration,
+}
+
+impl Timeout {
+    pub(crate) fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+impl FromStr for Timeout {
+    type Err = TimeoutErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(TimeoutErr::Empty);
+        }
+
+        let mut secs = 0u64;
+        let mut rest = s;
+
+        while !rest.is_empty() {
+            let n = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+
+            let (number, tail) = rest.split_at(n);
+
+            let number = number
+                .parse::<u64>()
+                .map_err(|_| TimeoutErr::MissingNumber)?;
+
+            let n = tail
+                .find(|c: char| c.is_ascii_digit())
+                .unwrap_or(tail.len());
+
+            let (unit, tail) = tail.split_at(n);
+
+            let scale = match unit {
+                "" | "s" => 1,
+                "m" => 60,
+                "h" => 3600,
+                unit => return Err(TimeoutErr::UnknownUnit(unit.to_owned())),
+            };
+
+            secs = secs.saturating_add(number.saturating_mul(scale));
+            rest = tail;
+        }
+
+        if secs == 0 {
+            return Err(TimeoutErr::Zero);
+        }
+
+        Ok(Self {
+            duration: Duration::from_secs(secs),
+        })
+    }
+}
+
+impl fmt::Display for Timeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.duration.as_secs();
+
+        match (secs / 3600, secs / 60 % 60, secs % 60) {
+            (0, 0, s) => write!(f, "{s}s"),
+            (0, m, 0) => write!(f, "{m}m"),
+            (0, m, s) => write!(f, "{m}m{s}s"),
+            (h, 0, 0) => write!(f, "{h}h"),
+            (h, m, 0) => write!(f, "{h}h{m}m"),
+            (h, m, s) => write!(f, "{h}h{m}m{s}s"),
+        }
+    }
+}
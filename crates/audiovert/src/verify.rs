@@ -0,0 +1,57 @@
+use core::time::Duration;
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result, anyhow, bail};
+
+use crate::config::Config;
+use crate::stream;
+
+/// The smallest difference in duration which is tolerated, since encoders add
+/// padding and some formats only store an estimate.
+const TOLERANCE: Duration = Duration::from_secs(1);
+
+/// Verify a converted file with `--verify` by decoding it in full, checking
+/// that it decodes without errors and that its duration is within tolerance
+/// of the expected one.
+pub(crate) fn check(config: &Config, path: &Path, expected: Option<Duration>) -> Result<()> {
+    let output = Command::new(&config.ffmpeg)
+        .args(["-hide_banner", "-nostats", "-xerror", "-i"])
+        .arg(path)
+        .args(["-map", "0:a:0", "-f", "null", "-"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .with_context(|| anyhow!("Failed to verify with {}", config.ffmpeg.display()))?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    if !output.status.success() {
+        match stderr.lines().rfind(|line| !line.trim().is_empty()) {
+            Some(line) => bail!("Output doesn't decode: {}", line.trim()),
+            None => bail!("Output doesn't decode: {}", output.status),
+        }
+    }
+
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+
+    let Some(actual) = stream::duration(&stderr) else {
+        bail!("Output has no duration");
+    };
+
+    let tolerance = TOLERANCE.max(expected / 100);
+
+    if actual.abs_diff(expected) > tolerance {
+        bail!(
+            "Output is {:.1}s long, but the source is {:.1}s",
+            actual.as_secs_f64(),
+            expected.as_secs_f64()
+        );
+    }
+
+    Ok(())
+}
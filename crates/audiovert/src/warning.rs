@@ -0,0 +1,65 @@
+use core::fmt;
+use core::str::FromStr;
+
+use anyhow::{Error, Result, anyhow};
+use serde::Serialize;
+
+/// The category of a warning, which can be silenced with `--suppress`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Warning {
+    /// A file has an extension which isn't supported.
+    Unsupported,
+    /// The destination of a file already exists.
+    AlreadyExists,
+    /// A source has multiple audio streams and none was selected.
+    MultipleStreams,
+    /// The audio streams of a source couldn't be probed.
+    ProbeFailed,
+}
+
+impl Warning {
+    /// All warning categories.
+    const ALL: [Warning; 4] = [
+        Warning::Unsupported,
+        Warning::AlreadyExists,
+        Warning::MultipleStreams,
+        Warning::ProbeFailed,
+    ];
+
+    /// The code of the warning, as accepted by `--suppress`.
+    pub(crate) fn code(&self) -> &'static str {
+        match self {
+            Warning::Unsupported => "unsupported",
+            Warning::AlreadyExists => "already-exists",
+            Warning::MultipleStreams => "multiple-streams",
+            Warning::ProbeFailed => "probe-failed",
+        }
+    }
+}
+
+impl FromStr for Warning {
+    type Err = Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        for warning in Warning::ALL {
+            if warning.code() == s {
+                return Ok(warning);
+            }
+        }
+
+        let codes = Warning::ALL.map(|w| w.code());
+        Err(anyhow!(
+            "Invalid warning '{s}', expected one of: {}",
+            codes.join(", ")
+        ))
+    }
+}
+
+impl fmt::Display for Warning {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.code().fmt(f)
+    }
+}
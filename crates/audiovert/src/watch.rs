@@ -0,0 +1,152 @@
+use core::time::Duration;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Instant;
+
+use anyhow::{Context, Result, anyhow};
+use mediavert_core::cancel::Cancel;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::config::Config;
+
+/// How often pending files are checked for whether they've stopped growing.
+const POLL: Duration = Duration::from_millis(250);
+
+/// A file which has been created or changed since the last run.
+struct Pending {
+    size: Option<u64>,
+    changed: Instant,
+}
+
+/// Watches source paths for new or changed files.
+pub(crate) struct Watch {
+    _watcher: RecommendedWatcher,
+    rx: Receiver<notify::Result<Event>>,
+    ignored: Vec<PathBuf>,
+    part_ext: String,
+    settle: Duration,
+}
+
+impl Watch {
+    /// Start watching the source paths of the given configuration.
+    ///
+    /// Changes to the destination directory and to partial conversion files are
+    /// ignored, so that a run doesn't trigger itself.
+    pub(crate) fn new(config: &Config, settle: Duration) -> Result<Self> {
+        let (tx, rx) = mpsc::channel();
+
+        let mut watcher = notify::recommended_watcher(tx).context("Failed to start watcher")?;
+
+        for path in &config.paths {
+            watcher
+                .watch(path, RecursiveMode::Recursive)
+                .with_context(|| anyhow!("Failed to watch {}", path.display()))?;
+        }
+
+        Ok(Self {
+            _watcher: watcher,
+            rx,
+            ignored: config.to_dir.iter().cloned().collect(),
+            part_ext: config.part_ext.clone(),
+            settle,
+        })
+    }
+
+    /// Wait until files have been created or changed, and have stopped growing
+    /// for the settle duration.
+    ///
+    /// Returns `false` if cancelled before that happens.
+    pub(crate) fn wait(&self, cancel: &Cancel) -> Result<bool> {
+        let mut pending = HashMap::<PathBuf, Pending>::new();
+
+        loop {
+            if cancel.is_cancelled() {
+                return Ok(false);
+            }
+
+            match self.rx.recv_timeout(POLL) {
+                Ok(event) => {
+                    let event = event.context("Failed to watch for changes")?;
+
+                    if !matches!(
+                        event.kind,
+                        EventKind::Any | EventKind::Create(..) | EventKind::Modify(..)
+                    ) {
+                        continue;
+                    }
+
+                    for path in event.paths {
+                        if self.is_ignored(&path) {
+                            continue;
+                        }
+
+                        pending.insert(
+                            path,
+                            Pending {
+                                size: None,
+                                changed: Instant::now(),
+                            },
+                        );
+                    }
+
+                    continue;
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(anyhow!("Watcher stopped unexpectedly"));
+                }
+            }
+
+            if pending.is_empty() {
+                continue;
+            }
+
+            let now = Instant::now();
+
+            pending.retain(|path, p| {
+                // Files which were removed again don't need to be waited for.
+                let Ok(metadata) = fs::metadata(path) else {
+                    return false;
+                };
+
+                let size = metadata.is_file().then_some(metadata.len());
+
+                if p.size != size {
+                    p.size = size;
+                    p.changed = now;
+                }
+
+                true
+            });
+
+            if !pending.is_empty()
+                && pending
+                    .values()
+                    .all(|p| now.duration_since(p.changed) >= self.settle)
+            {
+                return Ok(true);
+            }
+        }
+    }
+
+    fn is_ignored(&self, path: &Path) -> bool {
+        // Paths are compared canonically, since the destination might only
+        // have been created by the first run.
+        let canonical = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let path = canonical(path);
+
+        if self
+            .ignored
+            .iter()
+            .any(|dir| path.starts_with(canonical(dir)))
+        {
+            return true;
+        }
+
+        path.extension()
+            .is_some_and(|ext| ext.to_str() == Some(self.part_ext.as_str()))
+    }
+}
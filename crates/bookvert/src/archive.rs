@@ -0,0 +1,70 @@
+use core::cell::RefCell;
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, anyhow};
+use mediavert_core::archive::OpenArchive;
+use relative_path::RelativePath;
+
+use crate::magic;
+
+pub(crate) use mediavert_core::archive::Archive;
+
+/// The location and characteristics of a source archive.
+pub(crate) struct SourceArchive {
+    /// Kind of the archive.
+    pub(crate) kind: Archive,
+    /// Path to the archive.
+    pub(crate) path: PathBuf,
+    /// The archive opened for reading, which is opened on the first read and
+    /// kept open so that reading each page doesn't scan the archive again.
+    open: RefCell<Option<OpenArchive>>,
+}
+
+impl SourceArchive {
+    /// Construct a new source archive.
+    pub(crate) fn new(kind: Archive, path: PathBuf) -> Self {
+        Self {
+            kind,
+            path,
+            open: RefCell::new(None),
+        }
+    }
+
+    /// Get the contents of a file inside the archive.
+    pub(crate) fn contents(&self, path: &RelativePath) -> Result<Vec<u8>> {
+        self.read(path, |open| open.contents(path))
+    }
+
+    /// Get the header of a file inside the archive used to detect its format,
+    /// without decompressing all of it.
+    pub(crate) fn header(&self, path: &RelativePath) -> Result<Vec<u8>> {
+        self.read(path, |open| open.header(path, magic::HEADER_LEN))
+    }
+
+    fn read(
+        &self,
+        path: &RelativePath,
+        read: impl FnOnce(&mut OpenArchive) -> Result<Option<Vec<u8>>>,
+    ) -> Result<Vec<u8>> {
+        let mut open = self.open.borrow_mut();
+
+        let open = match &mut *open {
+            Some(open) => open,
+            None => open.insert(
+                self.kind
+                    .open(&self.path)
+                    .with_context(|| anyhow!("Failed to open {}", self.path.display()))?,
+            ),
+        };
+
+        if let Some(contents) = read(open)? {
+            return Ok(contents);
+        }
+
+        Err(anyhow!(
+            "Not found in archive: {}: {path}",
+            self.path.display()
+        ))
+    }
+}
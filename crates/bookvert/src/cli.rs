@@ -1,27 +1,58 @@
+mod check;
+mod checksums;
+mod extract;
+mod rename_only;
+mod single;
+mod stamp;
+mod stats;
+mod update_metadata;
+
 use core::fmt::{self, Write as _};
 use core::iter;
 use core::str::FromStr;
 
 use std::borrow::Cow;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
 use std::fs;
-use std::io::{Cursor, Write as _};
+use std::io::{self, Cursor, Read as _, Write as _};
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::time::SystemTime;
 
 use anyhow::{Context, Result, anyhow};
-use clap::Parser;
-use ignore::Walk;
+use clap::{ArgAction, CommandFactory, Parser};
+use ignore::{Walk, WalkBuilder};
 use language_tags::LanguageTag;
+use mediavert_core::cancel::Cancel;
+use mediavert_core::collate::Collation;
+use mediavert_core::config::{self, ColorMode};
+use mediavert_core::effects::Effects;
+use mediavert_core::log::Verbosity;
+use mediavert_core::report::{self, Cell, Report, ReportTarget, Section};
+use mediavert_core::temp::{self, RunDir};
 use regex::Regex;
+use relative_path::{Component, RelativePathBuf};
 use termcolor::{ColorSpec, StandardStream, WriteColor};
+use tracing::{debug, info_span, trace};
 use zip::write::SimpleFileOptions;
-use zip::{CompressionMethod, ZipWriter};
-
-use crate::{App, Book, Catalog, Page, State};
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use self::checksums::Checksum;
+use self::stamp::Stamp;
+use crate::archive::{Archive, SourceArchive};
+use crate::config::Config;
+use crate::epub::{self, BookFormat};
+use crate::language::{self, LanguageMap};
+use crate::profile::Profile;
+use crate::state::PageType;
+use crate::styles::{Styles, Theme};
+use crate::{App, Book, Catalog, OutputPage, Page, Source, State};
+use crate::{magic, numerals, pipeline, plugin, preview, strips};
 
 /// A tool to perform batch conversion of books.
 #[derive(Parser)]
+#[command(after_long_help = AFTER_LONG_HELP)]
 pub struct Bookvert {
     /// Output directory to write to.
     #[arg(long, default_value = ".")]
@@ -30,6 +61,14 @@ pub struct Bookvert {
     /// a series. Otherwise the directory name will be used.
     #[arg(long)]
     name: Option<String>,
+    /// Override the name of an individual book, like `12="Special Edition"`.
+    /// The override is used both for the output file name and the title in
+    /// ComicInfo.xml.
+    ///
+    /// The name of a book can also be overridden by putting a file named
+    /// `.bookvert-name` containing the name in the directory of the book.
+    #[arg(long)]
+    name_override: Vec<NameOverride>,
     /// When there are more than one book, specify a predicate for how to pick.
     ///
     /// Format: `[from=]to` where `from` is an book number or range to match.
@@ -45,24 +84,203 @@ pub struct Bookvert {
     /// - `-p fix' will match *any* book that contains the string `fix`.
     #[arg(long, short = 'p', verbatim_doc_comment)]
     pick: Vec<String>,
-    /// Overwrite existing files.
+    /// Overwrite existing files. This is the same as `--on-conflict
+    /// overwrite`.
     #[arg(long, short = 'f')]
     force: bool,
+    /// What to do when an output file already exists.
+    ///
+    /// Can be `skip` (default), `overwrite`, `rename` to write to a new file
+    /// like `name012 (1).cbz`, `skip-same` to only skip the file if it has
+    /// the same contents as what would be written, or `refresh` to only skip
+    /// the file if it's stamped with the same source pages. `refresh` implies
+    /// `--stamp`.
+    #[arg(long, default_value_t = OnConflict::Skip)]
+    on_conflict: OnConflict,
     /// Non-interactive mode: errors out if a choice is required.
     #[arg(long, short = 'n')]
     noninteractive: bool,
-    /// Verbose output.
-    #[arg(long, short = 'v')]
-    verbose: bool,
+    /// Increase the verbosity of the output, can be specified multiple times.
+    ///
+    /// With `-v` sources and sizes are shown, `-vv` adds generated metadata and
+    /// every extracted page, and `-vvv` adds traces of archive enumeration and
+    /// page reads.
+    #[arg(long, short = 'v', action = ArgAction::Count)]
+    verbose: u8,
+    /// The order names and books are listed in, either `natural` which ignores
+    /// case and accents and compares numbers by value, or `bytewise`.
+    #[arg(long, value_name = "ORDER", default_value_t)]
+    sort_order: Collation,
+    /// Path to the configuration file to use instead of
+    /// `mediavert/config.toml` in the user's configuration directory.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Directory to store temporary files in, such as pages extracted by
+    /// plugins.
+    ///
+    /// Each run uses its own directory inside of it which is removed when the
+    /// run finishes. This overrides the `tmp-dir` setting in the configuration
+    /// file.
+    #[arg(long, value_name = "DIR")]
+    tmp_dir: Option<PathBuf>,
+    /// Remove temporary directories left behind by earlier runs which are no
+    /// longer running, and exit.
+    #[arg(long)]
+    clean_temp: bool,
+    /// When to use colored output, either `auto`, `always`, or `never`.
+    ///
+    /// This overrides the `color` setting in the configuration file.
+    #[arg(long, value_name = "WHEN")]
+    color: Option<ColorMode>,
+    /// The color theme of the interactive interface, either `default`,
+    /// `high-contrast`, or `no-color`.
+    ///
+    /// This overrides the theme in the configuration file. If the `NO_COLOR`
+    /// environment variable is set, `no-color` is used by default.
+    #[arg(long)]
+    theme: Option<Theme>,
     /// Perform a trial run with no changes made.
     #[arg(long)]
     dry_run: bool,
+    /// Perform a trial run where any attempt to modify the filesystem is
+    /// refused with an error. This implies `--dry-run`.
+    #[arg(long)]
+    read_only: bool,
+    /// Sync written books along with the directory they're in to disk, so
+    /// that a power loss right after can't leave an empty book behind which
+    /// later runs would skip.
+    #[arg(long)]
+    durable: bool,
+    /// Write a self-contained report of the planned or performed run, like
+    /// `html:report.html` or `text:report.txt`, with the books written and a
+    /// thumbnail of each cover in the HTML report.
+    #[arg(long, value_name = "FORMAT:PATH")]
+    report: Option<ReportTarget>,
+    /// Don't respect ignore files such as `.gitignore` and `.ignore` when
+    /// walking directories.
+    #[arg(long)]
+    no_ignore: bool,
+    /// Include hidden files and directories when walking directories.
+    #[arg(long)]
+    hidden: bool,
+    /// Follow symbolic links when walking directories.
+    #[arg(long)]
+    follow_symlinks: bool,
     /// Specify a regular expression for a name to skip.
     #[arg(long)]
     skip: Vec<String>,
     /// Only include series numbers matching these predicates.
     #[arg(long)]
     include: Vec<From>,
+    /// Specify a regular expression for the name of books to include, like
+    /// `omake` to only include directories containing `omake`. Catalogs
+    /// without any matching books are left out. Can be specified multiple
+    /// times to include books matching any of them.
+    #[arg(long, value_name = "REGEX")]
+    include_name: Vec<String>,
+    /// Group pages into books using a number in their file names instead of
+    /// their parent directory. This is useful for flat layouts where all pages
+    /// are stored in a single directory.
+    ///
+    /// The pattern is a regular expression matched against the file name of
+    /// each page. The number is taken from a capture group named `number`, or
+    /// the first capture group if there is no such group.
+    ///
+    /// Example: `--number-pattern 'c(\d+)'` groups `Series_c012_p001.jpg` into
+    /// book number 12.
+    #[arg(long)]
+    number_pattern: Option<String>,
+    /// Also recognize book numbers written as Roman numerals or spelled-out
+    /// English words, like `Volume IV` or `Book Two`.
+    ///
+    /// Such numbers are only recognized when they follow a word like `Volume`
+    /// or `Book`, or when they are the last word in the name.
+    #[arg(long)]
+    word_numbers: bool,
+    /// Which of the numbers in a directory name becomes the number of the
+    /// book. By default every number is used, so a name like `2nd Season 03`
+    /// shows up as both book 2 and book 3.
+    ///
+    /// Either `all`, `first`, `last`, `largest`, or a regular expression which
+    /// captures the number in a group named `number` or its first group. Names
+    /// which don't match the regular expression use all of their numbers.
+    ///
+    /// Numbers written as words with `--word-numbers` are only used when a
+    /// name contains no digits, unless `all` is used.
+    #[arg(long, value_name = "POLICY", default_value_t)]
+    number_select: NumberSelect,
+    /// Keep the original file names of pages inside of the produced books
+    /// instead of renaming them to `p000.<ext>`, `p001.<ext>`, and so forth.
+    ///
+    /// Names are sanitized and made unique within each book.
+    #[arg(long)]
+    keep_page_names: bool,
+    /// Split pages which are taller than the specified height in pixels into
+    /// multiple pages. This is useful for webtoons which are often stored as
+    /// very tall strips.
+    ///
+    /// Cuts are made at blank rows where possible to avoid slicing panels.
+    #[arg(long, value_name = "PIXELS", value_parser = clap::value_parser!(u32).range(1..))]
+    split_strips: Option<u32>,
+    /// Stitch consecutive pages together vertically into strips which are at
+    /// most the specified height in pixels. This is useful for readers which
+    /// scroll continuously.
+    #[arg(long, conflicts_with = "split_strips")]
+    stitch: Option<u32>,
+    /// Instead of creating new books, rewrite or insert ComicInfo.xml in
+    /// existing .cbz files found in the specified paths without touching their
+    /// pages.
+    ///
+    /// The number of each book is taken from the last number in its file name.
+    #[arg(long)]
+    update_metadata: bool,
+    /// Instead of creating new books, rename existing .cbz files found in the
+    /// specified paths to the configured name without repacking them.
+    ///
+    /// The number of each book is taken from the last number in its file name,
+    /// and books are renamed in the directory they are in.
+    #[arg(long, conflicts_with = "update_metadata")]
+    rename_only: bool,
+    /// Instead of creating new books, inspect existing .cbz files found in the
+    /// specified paths and report issues like missing ComicInfo.xml, gaps in
+    /// numbering, books without pages, corrupt archives and inconsistent names.
+    #[arg(long, conflicts_with_all = ["update_metadata", "rename_only"])]
+    check: bool,
+    /// Instead of creating new books, extract existing .cbz files found in the
+    /// specified paths into directories of pages in the output directory.
+    ///
+    /// Use `--include` to only extract books with matching numbers.
+    #[arg(long, conflicts_with_all = ["update_metadata", "rename_only", "check"])]
+    extract: bool,
+    /// Instead of creating new books, report the shape of the collection found
+    /// in the specified paths: the number of series names, catalogs and books,
+    /// how many candidates each catalog has, total pages and bytes, the
+    /// breakdown of image formats and the largest books.
+    ///
+    /// Nothing is written.
+    #[arg(
+        long,
+        conflicts_with_all = ["update_metadata", "rename_only", "check", "extract"]
+    )]
+    stats: bool,
+    /// Package exactly this directory of pages into the book at `--output`,
+    /// without detecting series names or numbers.
+    ///
+    /// Every image in the directory and its subdirectories becomes a page, in
+    /// the order of their paths.
+    #[arg(
+        long,
+        value_name = "DIR",
+        requires = "output",
+        conflicts_with_all = ["update_metadata", "rename_only", "check", "extract", "stats"]
+    )]
+    single: Option<PathBuf>,
+    /// The book to write with `--single`.
+    #[arg(long, value_name = "FILE", requires = "single")]
+    output: Option<PathBuf>,
+    /// Output the report of `--check` as JSON.
+    #[arg(long, requires = "check")]
+    json: bool,
     /// Series for ComicInfo.xml metadata.
     #[arg(long)]
     series: Option<String>,
@@ -81,16 +299,163 @@ pub struct Bookvert {
     /// Language ISO code for ComicInfo.xml metadata (e.g., "en", "ja").
     #[arg(long)]
     language: Option<LanguageTag>,
+    /// When `--language` isn't specified, detect the language of each book
+    /// from a `.bookvert-language` file in its directory, or from tags like
+    /// `[EN]` or `(Japanese)` in the names of its directories.
+    #[arg(long)]
+    detect_language: bool,
+    /// A file mapping book directories to languages, which is consulted
+    /// before other ways of detecting the language and implies
+    /// `--detect-language`.
+    ///
+    /// Each line is in the form `<pattern>=<language>`, where the pattern is
+    /// a regular expression matched against the directory of the book, like
+    /// `/Scanlations/=en`. Later lines take precedence.
+    #[arg(long, value_name = "PATH")]
+    language_map: Option<PathBuf>,
     /// Manga reading direction: "Yes", "No", or "YesAndRightToLeft".
     #[arg(long)]
     manga: Option<Manga>,
+    /// The reading direction of the produced books, either `ltr` or `rtl`.
+    ///
+    /// Unless `--manga` is specified, this sets the Manga value in
+    /// ComicInfo.xml to "YesAndRightToLeft" for `rtl` and "No" for `ltr`.
+    #[arg(long)]
+    reading_direction: Option<ReadingDirection>,
+    /// Reverse the order of pages in each book, and swap the halves of any
+    /// double-page spreads. This is useful for sources which were scanned in
+    /// the opposite reading direction.
+    #[arg(long)]
+    reverse_pages: bool,
+    /// Recompress the pages of each book which exceeds the given size in
+    /// megabytes, using the highest JPEG quality which makes it fit.
+    #[arg(long, value_name = "MB", value_parser = parse_megabytes)]
+    target_size: Option<f64>,
+    /// Prepare pages for a reader or device, which sets the size pages are
+    /// downscaled to, whether they're converted to grayscale, the format and
+    /// quality they're recompressed with, and how double-page spreads are
+    /// handled.
+    ///
+    /// The built-in profiles are `kobo-libra2`, `kindle-pw5`, and
+    /// `tablet-10in`. More can be defined in the `[bookvert.profiles]` section
+    /// of the configuration file.
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+    /// The format books are written in, either `cbz` (default), `epub` for a
+    /// fixed-layout EPUB, or `kepub` for a fixed-layout EPUB with the markup
+    /// Kobo readers use for page turns and reading statistics.
+    ///
+    /// Books in the `kepub` format are written with the `.kepub.epub`
+    /// extension, which Kobo readers need to recognize them.
+    #[arg(long, value_name = "FORMAT", default_value_t)]
+    format: BookFormat,
+    /// Insert an image into every produced book at the given zero-based
+    /// position, like `credits.png:0` or `note.png:end`. If no position is
+    /// specified, the image is inserted first.
+    ///
+    /// Positions refer to the pages of the book before anything is inserted,
+    /// and images inserted at the same position keep the order they are
+    /// specified in.
+    ///
+    /// Inserted pages are marked with the type `Other` in ComicInfo.xml. Can
+    /// be specified multiple times.
+    #[arg(long, value_name = "PATH[:POSITION]")]
+    insert_page: Vec<InsertPage>,
+    /// Mark pages with a type in ComicInfo.xml, like `front-cover=first` or
+    /// `advertisement=^ad_`, which readers such as CDisplayEx and Komga use
+    /// to display and filter pages.
+    ///
+    /// The type is one of `front-cover`, `inner-cover`, `advertisement`, or
+    /// `deleted`. The pattern is `first`, `last`, or a regular expression
+    /// matched against the file name of each page. Pages can also be marked in
+    /// the page view of the interactive interface. Can be specified multiple
+    /// times, where later patterns take precedence.
+    #[arg(long, value_name = "TYPE=PATTERN")]
+    page_type: Vec<PageTypePattern>,
+    /// Write a manifest with checksums of produced books into the output
+    /// directory, which can be used to verify them later.
+    ///
+    /// Currently only `sha256` is supported, which writes a `SHA256SUMS` file
+    /// that can be verified with `sha256sum -c`.
+    #[arg(long, value_name = "ALGORITHM")]
+    checksums: Option<Checksum>,
     /// Summary/description for ComicInfo.xml metadata.
     #[arg(long)]
     summary: Option<String>,
+    /// Stamp the Notes field of ComicInfo.xml with the version of bookvert, a
+    /// hash of the source pages and the date the book was packaged.
+    ///
+    /// Stamps are kept by `--update-metadata`, used by `--on-conflict
+    /// refresh` to tell if a book is up to date, and reported as missing or
+    /// outdated by `--check`.
+    #[arg(long)]
+    stamp: bool,
+    /// Write a man page for bookvert to stdout and exit.
+    #[arg(long, hide = true)]
+    generate_man: bool,
     /// Directories to convert.
     path: Vec<PathBuf>,
 }
 
+impl Bookvert {
+    /// The verbosity requested through the `-v` flags.
+    #[inline]
+    pub fn verbosity(&self) -> Verbosity {
+        Verbosity::new(self.verbose)
+    }
+
+    /// Test if a trial run has been requested through `--dry-run` or
+    /// `--read-only`.
+    #[inline]
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run || self.read_only
+    }
+
+    /// The Manga value of produced books, which is derived from
+    /// `--reading-direction` unless `--manga` is specified.
+    fn manga(&self) -> Option<Manga> {
+        self.manga.or_else(|| Some(self.reading_direction?.manga()))
+    }
+
+    /// Test if produced books are read from right to left.
+    fn is_rtl(&self) -> bool {
+        matches!(self.manga(), Some(Manga::YesAndRightToLeft))
+    }
+
+    /// The layer through which the filesystem is modified.
+    #[inline]
+    pub(crate) fn effects(&self) -> Effects {
+        Effects::new(self.read_only).with_durable(self.durable)
+    }
+}
+
+/// Extended documentation of the pick grammar included in `--help` and the
+/// man page.
+const AFTER_LONG_HELP: &str = "\
+Pick predicates:
+  A predicate passed to --pick has the form [<from>=]<to>, and multiple
+  predicates can be separated by commas. Predicates with a <from> are tried
+  before the ones without one.
+
+  <from> selects the book numbers the predicate applies to:
+    n       The single book number n.
+    n..m    Book numbers from n up to but not including m.
+    n..=m   Book numbers from n up to and including m.
+    n..     Book numbers from n and up.
+    ..m     Book numbers below m, or ..=m to include m.
+    ..      All book numbers.
+
+  The same syntax is used by --include.
+
+  <to> selects which of the matching books to pick:
+    first       The first match.
+    last        The last match.
+    most-pages  The match with the most pages.
+    largest     The match with the largest total size.
+    smallest    The match with the smallest total size.
+    <index>     The match at the given zero-based index.
+    <regex>     The first match whose name matches the regular expression.";
+
 #[derive(Debug, Clone, Copy)]
 enum Manga {
     Yes,
@@ -123,7 +488,232 @@ impl fmt::Display for Manga {
     }
 }
 
-enum To {
+#[derive(Debug, Clone, Copy)]
+enum ReadingDirection {
+    Ltr,
+    Rtl,
+}
+
+impl ReadingDirection {
+    /// Get the Manga value corresponding to the reading direction.
+    fn manga(&self) -> Manga {
+        match self {
+            ReadingDirection::Ltr => Manga::No,
+            ReadingDirection::Rtl => Manga::YesAndRightToLeft,
+        }
+    }
+}
+
+impl FromStr for ReadingDirection {
+    type Err = anyhow::Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ltr" => Ok(ReadingDirection::Ltr),
+            "rtl" => Ok(ReadingDirection::Rtl),
+            _ => Err(anyhow!("Invalid reading direction '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for ReadingDirection {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadingDirection::Ltr => write!(f, "ltr"),
+            ReadingDirection::Rtl => write!(f, "rtl"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Position {
+    Index(usize),
+    End,
+}
+
+#[derive(Debug, Clone)]
+struct InsertPage {
+    path: PathBuf,
+    position: Position,
+}
+
+impl FromStr for InsertPage {
+    type Err = anyhow::Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        // Only treat the suffix as a position if it parses as one, so that
+        // paths containing `:` can still be used.
+        let position = s.rsplit_once(':').and_then(|(path, position)| {
+            let position = match position {
+                "end" => Position::End,
+                n => Position::Index(n.parse().ok()?),
+            };
+
+            Some((path, position))
+        });
+
+        let (path, position) = position.unwrap_or((s, Position::Index(0)));
+
+        if path.is_empty() {
+            return Err(anyhow!("Inserted page '{s}' is missing a path"));
+        }
+
+        Ok(InsertPage {
+            path: PathBuf::from(path),
+            position,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+enum PagePattern {
+    First,
+    Last,
+    Regex(Regex),
+}
+
+#[derive(Debug, Clone)]
+struct PageTypePattern {
+    page_type: PageType,
+    pattern: PagePattern,
+}
+
+impl PageTypePattern {
+    /// Test if the pattern matches the page at `index` out of `len` pages.
+    fn matches(&self, index: usize, len: usize, page: &Page) -> bool {
+        match &self.pattern {
+            PagePattern::First => index == 0,
+            PagePattern::Last => index + 1 == len,
+            PagePattern::Regex(re) => page
+                .path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| re.is_match(n)),
+        }
+    }
+}
+
+impl FromStr for PageTypePattern {
+    type Err = anyhow::Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((page_type, pattern)) = s.split_once('=') else {
+            return Err(anyhow!(
+                "Page type '{s}' must be in the form <type>=<pattern>"
+            ));
+        };
+
+        let page_type = page_type.trim().parse()?;
+
+        let pattern = match pattern {
+            "first" => PagePattern::First,
+            "last" => PagePattern::Last,
+            pattern => PagePattern::Regex(
+                Regex::new(pattern)
+                    .with_context(|| anyhow!("Invalid pattern in page type '{s}'"))?,
+            ),
+        };
+
+        Ok(PageTypePattern { page_type, pattern })
+    }
+}
+
+/// Parse a size in megabytes, which has to be larger than zero.
+fn parse_megabytes(s: &str) -> Result<f64> {
+    let size = s
+        .parse::<f64>()
+        .with_context(|| anyhow!("Invalid size '{s}'"))?;
+
+    if !size.is_finite() || size <= 0.0 {
+        return Err(anyhow!("Size '{s}' has to be a number larger than zero"));
+    }
+
+    Ok(size)
+}
+
+/// The name of the sidecar file used to override the name of a book.
+const NAME_SIDECAR: &str = ".bookvert-name";
+
+#[derive(Debug, Clone)]
+struct NameOverride {
+    number: u32,
+    name: String,
+}
+
+impl FromStr for NameOverride {
+    type Err = anyhow::Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((number, name)) = s.split_once('=') else {
+            return Err(anyhow!(
+                "Name override '{s}' must be in the form <number>=<name>"
+            ));
+        };
+
+        let number = number
+            .trim()
+            .parse()
+            .with_context(|| anyhow!("Invalid number in name override '{s}'"))?;
+
+        let name = name.trim().trim_matches('"');
+
+        if name.is_empty() {
+            return Err(anyhow!("Name override '{s}' has an empty name"));
+        }
+
+        check_book_name(name).with_context(|| anyhow!("Invalid name override '{s}'"))?;
+
+        Ok(NameOverride {
+            number,
+            name: name.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OnConflict {
+    Skip,
+    Overwrite,
+    Rename,
+    SkipSame,
+    Refresh,
+}
+
+impl FromStr for OnConflict {
+    type Err = anyhow::Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "skip" => Ok(OnConflict::Skip),
+            "overwrite" => Ok(OnConflict::Overwrite),
+            "rename" => Ok(OnConflict::Rename),
+            "skip-same" => Ok(OnConflict::SkipSame),
+            "refresh" => Ok(OnConflict::Refresh),
+            _ => Err(anyhow!("Invalid conflict policy '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for OnConflict {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OnConflict::Skip => write!(f, "skip"),
+            OnConflict::Overwrite => write!(f, "overwrite"),
+            OnConflict::Rename => write!(f, "rename"),
+            OnConflict::SkipSame => write!(f, "skip-same"),
+            OnConflict::Refresh => write!(f, "refresh"),
+        }
+    }
+}
+
+pub(crate) enum To {
     First,
     Last,
     MostPages,
@@ -135,7 +725,7 @@ enum To {
 
 impl To {
     /// Picks a book from the list according to the strategy.
-    fn pick(&self, books: &[Rc<Book>]) -> Option<usize> {
+    pub(crate) fn pick(&self, books: &[Rc<Book>]) -> Option<usize> {
         match *self {
             To::First if !books.is_empty() => Some(0),
             To::Last => books.len().checked_sub(1),
@@ -203,6 +793,85 @@ impl fmt::Display for To {
     }
 }
 
+/// Selects which of the numbers in a directory name is used as the number
+/// of the book.
+#[derive(Default, Clone)]
+enum NumberSelect {
+    #[default]
+    All,
+    First,
+    Last,
+    Largest,
+    Regex(Regex),
+}
+
+impl NumberSelect {
+    /// Select the numbers of a book from its name.
+    fn select(&self, name: &str, word_numbers: bool) -> BTreeSet<u32> {
+        let mut candidates = numbers(name).collect::<Vec<_>>();
+
+        if word_numbers && (candidates.is_empty() || matches!(self, NumberSelect::All)) {
+            candidates.extend(numerals::numerals(name));
+        }
+
+        let selected = match self {
+            NumberSelect::All => None,
+            NumberSelect::First => candidates.first().copied(),
+            NumberSelect::Last => candidates.last().copied(),
+            NumberSelect::Largest => candidates.iter().max().copied(),
+            NumberSelect::Regex(re) => re.captures(name).and_then(|captures| {
+                let number = match captures.name("number") {
+                    Some(number) => number,
+                    None => captures.get(1)?,
+                };
+
+                number.as_str().parse().ok()
+            }),
+        };
+
+        match selected {
+            Some(number) => BTreeSet::from([number]),
+            None => candidates.into_iter().collect(),
+        }
+    }
+}
+
+impl FromStr for NumberSelect {
+    type Err = anyhow::Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "all" => Ok(NumberSelect::All),
+            "first" => Ok(NumberSelect::First),
+            "last" => Ok(NumberSelect::Last),
+            "largest" => Ok(NumberSelect::Largest),
+            s => {
+                let re = Regex::new(s).with_context(|| anyhow!("Parsing regex '{s}'"))?;
+
+                if re.captures_len() < 2 {
+                    return Err(anyhow!("Number pattern '{s}' must contain a capture group"));
+                }
+
+                Ok(NumberSelect::Regex(re))
+            }
+        }
+    }
+}
+
+impl fmt::Display for NumberSelect {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NumberSelect::All => write!(f, "all"),
+            NumberSelect::First => write!(f, "first"),
+            NumberSelect::Last => write!(f, "last"),
+            NumberSelect::Largest => write!(f, "largest"),
+            NumberSelect::Regex(re) => re.fmt(f),
+        }
+    }
+}
+
 #[derive(Clone)]
 enum From {
     Full,
@@ -283,15 +952,19 @@ struct Match {
     to: To,
 }
 
+/// A collection of predicates used to pick books in catalogs with more than
+/// one candidate.
 #[derive(Default)]
-struct Picker {
+pub struct Picker {
     matches: Vec<Match>,
     catch_all: Vec<To>,
 }
 
 impl Picker {
     /// Parse a predicate to add to the picker.
-    fn parse(&mut self, input: &str) -> Result<()> {
+    ///
+    /// This uses the same format as the `--pick` option.
+    pub fn parse(&mut self, input: &str) -> Result<()> {
         for p in input.split(',') {
             let p = p.trim();
 
@@ -308,7 +981,7 @@ impl Picker {
     }
 
     /// Returns the index of the book to pick, or None if no predicate matched.
-    fn pick(&self, catalog: &Catalog) -> Option<usize> {
+    pub fn pick(&self, catalog: &Catalog) -> Option<usize> {
         for m in &self.matches {
             if m.from.matches(catalog.number)
                 && let Some(index) = m.to.pick(&catalog.books)
@@ -327,6 +1000,47 @@ impl Picker {
     }
 }
 
+/// A pattern used to extract book numbers from page file names.
+struct NumberPattern {
+    regex: Regex,
+}
+
+impl NumberPattern {
+    /// Construct a new number pattern.
+    fn new(pattern: &str) -> Result<Self> {
+        let regex = Regex::new(pattern).with_context(|| anyhow!("Parsing regex '{pattern}'"))?;
+
+        if regex.captures_len() < 2 {
+            return Err(anyhow!(
+                "Number pattern '{pattern}' must contain a capture group"
+            ));
+        }
+
+        Ok(Self { regex })
+    }
+
+    /// Match the pattern against a file name.
+    ///
+    /// Returns the number, the name of the book, and the name of the series.
+    fn capture<'a>(&self, file_name: &'a str) -> Option<(u32, &'a str, &'a str)> {
+        let captures = self.regex.captures(file_name)?;
+        let whole = captures.get(0)?;
+
+        let number = match captures.name("number") {
+            Some(number) => number,
+            None => captures.get(1)?,
+        };
+
+        let number = number.as_str().parse().ok()?;
+        let book = &file_name[..whole.end()];
+        let series = file_name[..whole.start()]
+            .trim_end_matches(|c: char| !c.is_alphanumeric())
+            .trim();
+
+        Some((number, book, series))
+    }
+}
+
 /// Accepted image file extensions.
 macro_rules! ext {
     () => {
@@ -347,111 +1061,484 @@ fn translate(input: &str) -> &str {
     input
 }
 
-pub fn entry(opts: &Bookvert) -> Result<()> {
-    let mut warn: ColorSpec = ColorSpec::new();
-    warn.set_fg(Some(termcolor::Color::Yellow));
+/// Get the normalized extension of a page, if it is an accepted image.
+fn page_ext(ext: &str) -> Option<String> {
+    let ext = translate(ext).to_lowercase();
 
-    let mut ok: ColorSpec = ColorSpec::new();
-    ok.set_fg(Some(termcolor::Color::Green));
+    if !matches!(ext.as_str(), ext!()) {
+        return None;
+    }
 
-    let mut error: ColorSpec = ColorSpec::new();
-    error.set_fg(Some(termcolor::Color::Red));
+    Some(ext)
+}
 
-    let mut skip = Vec::<Regex>::new();
-    let mut picker = Picker::default();
+/// Colors used for output.
+struct Colors {
+    ok: ColorSpec,
+    warn: ColorSpec,
+    error: ColorSpec,
+}
 
-    for pat in &opts.pick {
-        picker
-            .parse(pat)
-            .with_context(|| anyhow!("Parsing pick predicate '{}'", pat))?;
+impl Colors {
+    fn new() -> Self {
+        let mut warn = ColorSpec::new();
+        warn.set_fg(Some(termcolor::Color::Yellow));
+
+        let mut ok = ColorSpec::new();
+        ok.set_fg(Some(termcolor::Color::Green));
+
+        let mut error = ColorSpec::new();
+        error.set_fg(Some(termcolor::Color::Red));
+
+        Self { ok, warn, error }
     }
+}
 
-    for pat in &opts.skip {
-        let re = Regex::new(pat).with_context(|| anyhow!("Parsing regex '{}'", pat))?;
-        skip.push(re);
+pub fn entry(opts: &Bookvert) -> Result<()> {
+    if opts.generate_man {
+        let man = clap_mangen::Man::new(Bookvert::command());
+        man.render(&mut io::stdout())?;
+        return Ok(());
     }
 
-    let mut files = Vec::new();
+    let config = config::load::<Config>(opts.config.as_deref())?;
+    let color = opts.color.or(config.shared().color).unwrap_or_default();
 
-    for path in &opts.path {
-        for p in Walk::new(path) {
-            let entry = p?;
+    if opts.clean_temp {
+        let o = StandardStream::stdout(color.choice());
+        let mut o = o.lock();
+        let Colors { ok, .. } = Colors::new();
 
-            let Some(ty) = entry.file_type() else {
-                continue;
-            };
+        for path in temp::clean(&tmp_dir(opts, &config))? {
+            o.set_color(&ok)?;
+            write!(o, "[removed]")?;
+            o.reset()?;
+            writeln!(o, " {}", path.display())?;
+        }
 
-            if ty.is_file() {
-                let path = entry.into_path();
+        return Ok(());
+    }
 
-                let ext = path
-                    .extension()
-                    .and_then(|e| e.to_str())
-                    .map(translate)
-                    .map(|e| e.to_lowercase());
+    if let (Some(dir), Some(output)) = (&opts.single, &opts.output) {
+        return self::single::entry(opts, dir, output, color.choice());
+    }
 
-                let Some(ext) = ext else {
-                    continue;
-                };
+    if opts.check {
+        return self::check::entry(opts, color.choice());
+    }
 
-                if !matches!(ext.as_str(), ext!()) {
-                    continue;
-                }
+    if opts.extract {
+        return self::extract::entry(opts, color.choice());
+    }
+
+    if opts.update_metadata {
+        return self::update_metadata::entry(opts, color.choice());
+    }
+
+    if opts.rename_only {
+        return self::rename_only::entry(opts, color.choice());
+    }
+
+    let o = StandardStream::stdout(color.choice());
+    let mut o = o.lock();
+
+    let mut state = scan(opts, &mut o)?;
+
+    if opts.stats {
+        return self::stats::entry(&mut o, &state);
+    }
+
+    pick(opts, &mut state)?;
+
+    let Colors { warn, error, .. } = Colors::new();
+
+    if opts.noninteractive {
+        let mut is_error = false;
 
-                files.push((path, ext));
+        if state.name.is_none() {
+            o.set_color(&error)?;
+            write!(o, "[error] ")?;
+            o.reset()?;
+
+            writeln!(o, "Use `--name <name>` to set one name of the series:")?;
+
+            for name in &state.names {
+                writeln!(o, "  {}", escape(name))?;
             }
+
+            is_error = true;
+        }
+
+        for catalog in &state.catalogs {
+            if catalog.picked.is_some() {
+                continue;
+            }
+
+            o.set_color(&error)?;
+            write!(o, "[error] ")?;
+            o.reset()?;
+
+            writeln!(
+                o,
+                "{number:03}: more than one match, use something like `-p {number}=0` to pick one:",
+                number = catalog.number,
+            )?;
+
+            for (idx, book) in catalog.books.iter().enumerate() {
+                writeln!(
+                    o,
+                    "  {idx}: {} ({} pages, {} bytes)",
+                    escape(&book.name),
+                    book.pages.len(),
+                    book.bytes(),
+                )?;
+
+                if opts.verbosity().is_verbose() {
+                    o.set_color(&warn)?;
+                    write!(o, "    [source]")?;
+                    o.reset()?;
+                    writeln!(o, " {}", book.dir.display())?;
+                }
+            }
+
+            is_error = true;
+        }
+
+        if is_error {
+            return Err(anyhow!("Aborting due to non-interactive errors."));
+        }
+    } else {
+        let theme = opts.theme.or_else(|| {
+            let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+            (no_color || color == ColorMode::Never).then_some(Theme::NoColor)
+        });
+
+        let styles = Styles::from_config(theme, &config.bookvert.theme)?;
+        let mut app = App::new(styles);
+
+        if !app.run(&mut state)? {
+            return Err(anyhow!("Aborting due to user cancellation."));
         }
     }
 
-    files.sort();
+    let cancel = Cancel::ctrl_c()?;
 
-    let o = StandardStream::stdout(termcolor::ColorChoice::Auto);
-    let mut o = o.lock();
+    let Some(target) = &opts.report else {
+        return write(opts, &state, &cancel, &mut o);
+    };
+
+    let mut report = if opts.is_dry_run() {
+        Report::new("bookvert dry run")
+    } else {
+        Report::new("bookvert run")
+    };
+
+    report.summary("Name", state.name.as_deref().unwrap_or_default());
+    report.summary("Dry run", if opts.is_dry_run() { "yes" } else { "no" });
+    report.summary("Output", opts.out.display());
+
+    for catalog in &state.catalogs {
+        if catalog.selected().is_none() {
+            report.warning(format_args!("{:03}: No book picked", catalog.number));
+        }
+    }
+
+    let heading = state.name.as_deref().unwrap_or("Books").to_owned();
+
+    let section = report.section(
+        heading,
+        &[
+            "Cover",
+            "Number",
+            "Source",
+            "Destination",
+            "Status",
+            "Pages",
+            "Before",
+            "After",
+        ],
+    );
+
+    let result = write_books(opts, &state, &cancel, &mut o, Some(section));
+
+    if let Err(error) = &result {
+        report.error(format_args!("{error:#}"));
+    }
+
+    report.write(target)?;
+
+    o.set_color(&Colors::new().ok)?;
+    write!(o, "[report]")?;
+    o.reset()?;
+    writeln!(o, " {}", target.path.display())?;
+
+    result
+}
+
+/// Scan the paths specified in the options into a new [`State`].
+///
+/// Catalogs which only contain a single book have it picked automatically, the
+/// rest are left to [`pick`] or to the user to decide.
+pub fn scan(opts: &Bookvert, o: &mut dyn WriteColor) -> Result<State> {
+    let Colors { warn, .. } = Colors::new();
+
+    let mut skip = Vec::<Regex>::new();
+    for pat in &opts.skip {
+        let re = Regex::new(pat).with_context(|| anyhow!("Parsing regex '{}'", pat))?;
+        skip.push(re);
+    }
+
+    let mut include_name = Vec::<Regex>::new();
+    for pat in &opts.include_name {
+        let re = Regex::new(pat).with_context(|| anyhow!("Parsing regex '{}'", pat))?;
+        include_name.push(re);
+    }
+
+    let number_pattern = opts
+        .number_pattern
+        .as_deref()
+        .map(NumberPattern::new)
+        .transpose()?;
+
+    let config = config::load::<Config>(opts.config.as_deref())?;
+    let run_dir = Rc::new(RunDir::new(&tmp_dir(opts, &config), "bookvert"));
+
+    let mut files = Vec::new();
+
+    for path in &opts.path {
+        for p in walk(opts, path) {
+            let entry = p?;
+
+            let Some(ty) = entry.file_type() else {
+                continue;
+            };
+
+            if !ty.is_file() {
+                continue;
+            }
+
+            let path = entry.into_path();
+
+            let Some(ext) = path.extension().and_then(|e| e.to_str()) else {
+                continue;
+            };
+
+            let ext = ext.to_lowercase();
+
+            if let Some(kind) = Archive::from_ext(&ext) {
+                let archive = Rc::new(SourceArchive::new(kind, path.clone()));
+
+                let mut archive_path = path.parent().unwrap_or(Path::new("")).to_path_buf();
+
+                if let Some(stem) = path.file_stem() {
+                    archive_path.push(stem);
+                }
+
+                kind.enumerate(&path, &mut |entry, size| {
+                    trace!(archive = %path.display(), %entry, size, "archive entry");
+
+                    let Some(ext) = entry.extension().and_then(page_ext) else {
+                        return Ok(());
+                    };
+
+                    let mut page_path = archive_path.clone();
+
+                    for c in entry.components() {
+                        match c {
+                            Component::CurDir => {}
+                            Component::ParentDir => return Ok(()),
+                            Component::Normal(s) => page_path.push(s),
+                        }
+                    }
+
+                    let source = Source::Archive {
+                        archive: archive.clone(),
+                        path: entry.to_owned(),
+                    };
 
-    let mut books_by_path = BTreeMap::<&Path, _>::new();
+                    files.push((page_path, ext, source, Some(size)));
+                    Ok(())
+                })
+                .with_context(|| anyhow!("{}: Failed to read archive", path.display()))?;
+
+                continue;
+            }
+
+            if let Some(plugin) = config.bookvert.plugins.get(&ext) {
+                let dir = run_dir.allocate("extract")?;
+
+                let extracted = plugin::extract(plugin, &path, dir, run_dir.clone())
+                    .with_context(|| anyhow!("{}: Failed to extract pages", path.display()))?;
+                let extracted = Rc::new(extracted);
+
+                let mut extracted_path = path.parent().unwrap_or(Path::new("")).to_path_buf();
+
+                if let Some(stem) = path.file_stem() {
+                    extracted_path.push(stem);
+                }
+
+                for entry in WalkBuilder::new(&extracted.dir)
+                    .standard_filters(false)
+                    .build()
+                {
+                    let entry = entry?;
+
+                    if !entry.file_type().is_some_and(|ty| ty.is_file()) {
+                        continue;
+                    }
+
+                    let Ok(entry_path) = entry.path().strip_prefix(&extracted.dir) else {
+                        continue;
+                    };
+
+                    let Ok(entry_path) = RelativePathBuf::from_path(entry_path) else {
+                        continue;
+                    };
+
+                    let Some(ext) = entry_path.extension().and_then(page_ext) else {
+                        continue;
+                    };
+
+                    let page_path = entry_path.to_path(&extracted_path);
+                    let size = entry.metadata()?.len();
+
+                    let source = Source::Extracted {
+                        extracted: extracted.clone(),
+                        path: entry_path,
+                    };
+
+                    files.push((page_path, ext, source, Some(size)));
+                }
+
+                continue;
+            }
+
+            let Some(ext) = page_ext(&ext) else {
+                continue;
+            };
+
+            files.push((path, ext, Source::File, None));
+        }
+    }
+
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut books_by_path = BTreeMap::<(&Path, Option<u32>), _>::new();
     let mut by_number = BTreeMap::<_, Vec<_>>::new();
+    let mut names = BTreeSet::new();
     let mut state = State::default();
 
-    for (from, ext) in &files {
+    for (from, ext, source, size) in &files {
         let Some(dir) = from.parent() else {
             continue;
         };
 
-        let Some(name) = dir.file_name().and_then(|n| n.to_str()) else {
-            continue;
+        let dir_name = dir.file_name().and_then(|n| n.to_str());
+
+        let (key, name, series) = match &number_pattern {
+            Some(pattern) => {
+                let Some(file_name) = from.file_name().and_then(|n| n.to_str()) else {
+                    continue;
+                };
+
+                let Some((number, name, series)) = pattern.capture(file_name) else {
+                    o.set_color(&warn)?;
+                    write!(o, "[skip]")?;
+                    o.reset()?;
+                    writeln!(o, " {} (does not match --number-pattern)", from.display())?;
+                    continue;
+                };
+
+                let series = match dir_name {
+                    Some(dir_name) if series.is_empty() => dir_name,
+                    _ => series,
+                };
+
+                ((dir, Some(number)), name, series)
+            }
+            None => {
+                let Some(dir_name) = dir_name else {
+                    continue;
+                };
+
+                ((dir, None), dir_name, dir_name)
+            }
         };
 
         if skip.iter().any(|re| re.is_match(name)) {
             continue;
         }
 
-        let book = books_by_path.entry(dir).or_insert_with(|| Book {
-            dir: dir.to_path_buf(),
-            name: name.to_string(),
-            pages: Vec::new(),
-            numbers: numbers(name).collect(),
+        let ext = match magic::sniff(&source.header(from)?) {
+            Some(actual) => {
+                if actual != ext.as_str() {
+                    o.set_color(&warn)?;
+                    write!(o, "[ext]")?;
+                    o.reset()?;
+                    writeln!(o, " {}: contains a {actual} image", from.display())?;
+                }
+
+                actual
+            }
+            None => {
+                o.set_color(&warn)?;
+                write!(o, "[not image]")?;
+                o.reset()?;
+                writeln!(o, " {}: skipping", from.display())?;
+                continue;
+            }
+        };
+
+        let book = books_by_path.entry(key).or_insert_with(|| {
+            names.insert(series.to_string());
+
+            Book {
+                dir: dir.to_path_buf(),
+                name: name.to_string(),
+                pages: Vec::new(),
+                numbers: match key.1 {
+                    Some(number) => BTreeSet::from([number]),
+                    None => opts.number_select.select(name, opts.word_numbers),
+                },
+            }
         });
 
+        let size = match size {
+            Some(size) => *size,
+            None => fs::metadata(from)
+                .with_context(|| anyhow!("{}: Failed to get metadata", from.display()))?
+                .len(),
+        };
+
+        let page_name = if opts.keep_page_names {
+            page_name(book, from, ext)
+        } else {
+            format!("p{:03}.{ext}", book.pages.len())
+        };
+
         book.pages.push(Page {
             path: from.to_owned(),
-            name: format!("p{:03}.{ext}", book.pages.len()),
-            metadata: fs::metadata(from)
-                .with_context(|| anyhow!("{}: Failed to get metadata", from.display()))?,
+            name: page_name,
+            size,
+            source: source.clone(),
+            page_type: Default::default(),
         });
     }
 
     for (_, book) in books_by_path {
         let book = Rc::new(book);
 
-        state.names.insert(book.name.clone());
-
         for &n in &book.numbers {
             by_number.entry(n).or_default().push(book.clone());
         }
     }
 
     for value in by_number.values_mut() {
-        value.sort_by(|a, b| a.key().cmp(&b.key()));
+        value.sort_by(|a, b| {
+            opts.sort_order
+                .compare(&a.name, &b.name)
+                .then_with(|| a.dir.cmp(&b.dir))
+        });
     }
 
     if !opts.include.is_empty() {
@@ -462,6 +1549,16 @@ pub fn entry(opts: &Bookvert) -> Result<()> {
         });
     }
 
+    if !include_name.is_empty() {
+        by_number.retain(|_, books| {
+            books.retain(|book| include_name.iter().any(|re| re.is_match(&book.name)));
+            !books.is_empty()
+        });
+    }
+
+    state.names = names.into_iter().collect();
+    state.names.sort_by(|a, b| opts.sort_order.compare(a, b));
+
     for (number, books) in by_number {
         let mut catalog = Catalog {
             number,
@@ -471,13 +1568,51 @@ pub fn entry(opts: &Bookvert) -> Result<()> {
 
         if catalog.books.len() == 1 {
             catalog.picked = Some(0);
-        } else {
-            catalog.picked = picker.pick(&catalog);
         }
 
         state.catalogs.push(catalog);
     }
 
+    Ok(state)
+}
+
+/// Apply the pick predicates, page types, and the name specified in the
+/// options to the given [`State`].
+///
+/// Catalogs which already have a picked book are left as-is.
+pub fn pick(opts: &Bookvert, state: &mut State) -> Result<()> {
+    let mut picker = Picker::default();
+
+    for pat in &opts.pick {
+        picker
+            .parse(pat)
+            .with_context(|| anyhow!("Parsing pick predicate '{}'", pat))?;
+    }
+
+    for catalog in &mut state.catalogs {
+        if catalog.picked.is_none() {
+            catalog.picked = picker.pick(catalog);
+        }
+    }
+
+    if !opts.page_type.is_empty() {
+        for book in state.catalogs.iter().flat_map(|c| &c.books) {
+            let len = book.pages.len();
+
+            for (index, page) in book.pages.iter().enumerate() {
+                let matched = opts
+                    .page_type
+                    .iter()
+                    .rev()
+                    .find(|p| p.matches(index, len, page));
+
+                if let Some(matched) = matched {
+                    page.page_type.set(Some(matched.page_type));
+                }
+            }
+        }
+    }
+
     // Automatically determine name to use if possible.
     'name: {
         if let Some(name) = &opts.name {
@@ -495,90 +1630,307 @@ pub fn entry(opts: &Bookvert) -> Result<()> {
         }
     }
 
-    if opts.noninteractive {
-        let mut is_error = false;
+    Ok(())
+}
 
-        if state.name.is_none() {
-            o.set_color(&error)?;
-            write!(o, "[error] ")?;
-            o.reset()?;
+/// Write the books picked in the given [`State`] to the output directory.
+///
+/// If the cancellation token is cancelled the book currently being written is
+/// finished before stopping, and if it is aborted nothing more is written.
+pub fn write(
+    opts: &Bookvert,
+    state: &State,
+    cancel: &Cancel,
+    o: &mut dyn WriteColor,
+) -> Result<()> {
+    write_books(opts, state, cancel, o, None)
+}
 
-            writeln!(o, "Use `--name <name>` to set one name of the series:")?;
+/// Write the picked books, recording the outcome for each book in the given
+/// report section if one is provided.
+fn write_books(
+    opts: &Bookvert,
+    state: &State,
+    cancel: &Cancel,
+    o: &mut dyn WriteColor,
+    mut section: Option<&mut Section>,
+) -> Result<()> {
+    let Colors { ok, warn, .. } = Colors::new();
+
+    let name = state
+        .name
+        .as_deref()
+        .context("No name specified for catalog")?;
+
+    let name_overrides = name_overrides(opts);
+
+    let profile = match &opts.profile {
+        Some(name) => {
+            let config = config::load::<Config>(opts.config.as_deref())?;
+            Some(Profile::find(name, &config.bookvert.profiles)?)
+        }
+        None => None,
+    };
 
-            for name in &state.names {
-                writeln!(o, "  {}", escape(name))?;
-            }
+    let language_map = match &opts.language_map {
+        Some(path) => Some(LanguageMap::load(path)?),
+        None => None,
+    };
 
-            is_error = true;
-        }
+    let detect_language =
+        opts.language.is_none() && (opts.detect_language || language_map.is_some());
 
-        for catalog in &state.catalogs {
-            if catalog.picked.is_some() {
-                continue;
-            }
+    let mut checksums = Vec::new();
 
-            o.set_color(&error)?;
-            write!(o, "[error] ")?;
+    let mut inserts = Vec::with_capacity(opts.insert_page.len());
+
+    for insert in &opts.insert_page {
+        let content = fs::read(&insert.path)
+            .with_context(|| anyhow!("Failed to read {}", insert.path.display()))?;
+
+        let ext = magic::sniff(&content)
+            .with_context(|| anyhow!("{}: Not a supported image", insert.path.display()))?;
+
+        inserts.push((insert.position, ext, content));
+    }
+
+    for c in &state.catalogs {
+        let Some(book) = c.selected() else {
+            continue;
+        };
+
+        if cancel.is_cancelled() {
+            o.set_color(&warn)?;
+            write!(o, "[cancelled]")?;
             o.reset()?;
+            writeln!(o, " stopping before {:03}, run again to resume", c.number)?;
+            break;
+        }
 
-            writeln!(
-                o,
-                "{number:03}: more than one match, use something like `-p {number}=0` to pick one:",
-                number = catalog.number,
-            )?;
+        let span = info_span!("book", number = c.number, dir = %book.dir.display());
+        let _enter = span.enter();
 
-            for (idx, book) in catalog.books.iter().enumerate() {
-                writeln!(
-                    o,
-                    "  {idx}: {} ({} pages, {} bytes)",
-                    escape(&book.name),
-                    book.pages.len(),
-                    book.bytes(),
-                )?;
+        let name_override = match name_overrides.get(&c.number) {
+            Some(name) => Some(name.to_string()),
+            None => read_name_sidecar(&book.dir)?,
+        };
+
+        let (stem, title) = book_names(name, c.number, name_override);
+
+        let mut target = opts.out.clone();
+        target.push(&stem);
+        target.add_extension(opts.format.ext());
+
+        let color = if opts.is_dry_run() { &warn } else { &ok };
+        o.set_color(color)?;
+        write!(o, "[from]")?;
+        o.reset()?;
+
+        writeln!(o, " {:03}: {}", c.number, book.dir.display())?;
+
+        let on_conflict = if opts.force {
+            OnConflict::Overwrite
+        } else {
+            opts.on_conflict
+        };
 
-                if opts.verbose {
+        let stamp = (opts.stamp || on_conflict == OnConflict::Refresh).then(|| Stamp::new(book));
+
+        if target.exists() {
+            match on_conflict {
+                OnConflict::Skip => {
                     o.set_color(&warn)?;
-                    write!(o, "    [source]")?;
+                    write!(o, "  [exists] ")?;
                     o.reset()?;
-                    writeln!(o, " {}", book.dir.display())?;
+                    writeln!(o, "{} (--force to overwrite)", target.display())?;
+
+                    if let Some(section) = &mut section {
+                        let row = report_row(c.number, book, &target, "exists", None, None);
+                        section.row(row);
+                    }
+
+                    continue;
                 }
+                OnConflict::Rename => {
+                    target = unique_target(&opts.out, &stem, opts.format);
+                }
+                OnConflict::Refresh => {
+                    // Books which can't be read are refreshed like ones which
+                    // aren't stamped.
+                    let existing = Stamp::read(&target).ok().flatten();
+
+                    if let (Some(existing), Some(stamp)) = (&existing, &stamp)
+                        && existing.source == stamp.source
+                    {
+                        o.set_color(&ok)?;
+                        write!(o, "  [same] ")?;
+                        o.reset()?;
+                        writeln!(o, "{} (stamped {})", target.display(), existing.date)?;
+
+                        if let Some(section) = &mut section {
+                            let row = report_row(c.number, book, &target, "same", None, None);
+                            section.row(row);
+                        }
+
+                        continue;
+                    }
+                }
+                OnConflict::Overwrite | OnConflict::SkipSame => {}
             }
+        }
 
-            is_error = true;
+        // Books whose pages are written as they are can be streamed from their
+        // sources, instead of being read into memory. Dry runs and
+        // `skip-same` need the whole book to measure or compare it.
+        let streamed = opts.format == BookFormat::Cbz
+            && !opts.is_dry_run()
+            && on_conflict != OnConflict::SkipSame
+            && opts.split_strips.is_none()
+            && opts.stitch.is_none()
+            && !opts.reverse_pages
+            && inserts.is_empty()
+            && profile.is_none()
+            && opts.target_size.is_none();
+
+        let mut pages = Vec::with_capacity(book.pages.len());
+
+        if !streamed {
+            for page in &book.pages {
+                trace!(path = %page.path.display(), name = page.name, "reading page");
+                let mut output = OutputPage::new(page.name.clone(), page.read()?);
+                output.page_type = page.page_type.get();
+                pages.push(output);
+            }
         }
 
-        if is_error {
-            return Err(anyhow!("Aborting due to non-interactive errors."));
+        if let Some(max_height) = opts.split_strips {
+            pages = strips::split(pages, max_height)
+                .with_context(|| anyhow!("{}: Failed to split strips", book.dir.display()))?;
+            debug!(pages = pages.len(), "split strips");
         }
-    } else {
-        let mut app = App::default();
 
-        if !app.run(&mut state)? {
-            return Err(anyhow!("Aborting due to user cancellation."));
+        if let Some(max_height) = opts.stitch {
+            pages = strips::stitch(pages, max_height)
+                .with_context(|| anyhow!("{}: Failed to stitch pages", book.dir.display()))?;
+            debug!(pages = pages.len(), "stitched pages");
         }
-    }
 
-    let name = state.name.context("No name specified for catalog")?;
+        if opts.reverse_pages {
+            pages = pipeline::reverse(pages)
+                .with_context(|| anyhow!("{}: Failed to reverse pages", book.dir.display()))?;
+        }
 
-    for c in &state.catalogs {
-        let Some(book) = c.selected() else {
-            continue;
-        };
+        if !inserts.is_empty() {
+            let inserted = inserts.iter().map(|(position, ext, content)| {
+                let index = match *position {
+                    Position::Index(n) => Some(n),
+                    Position::End => None,
+                };
 
-        let mut target = opts.out.clone();
-        target.push(format!("{name}{:03}", c.number));
-        target.add_extension("cbz");
+                let mut page = OutputPage::new(format!("insert.{ext}"), content.clone());
+                page.page_type = Some(PageType::Other);
+                (index, page)
+            });
 
-        let color = if opts.dry_run { &warn } else { &ok };
-        o.set_color(color)?;
-        write!(o, "[from]")?;
-        o.reset()?;
+            pages = pipeline::insert(pages, inserted);
+            debug!(pages = pages.len(), "inserted pages");
+        }
 
-        writeln!(o, " {:03}: {}", c.number, book.dir.display())?;
+        // Pages are numbered once they are in their final order, so that
+        // their names aren't prefixed more than once.
+        if opts.reverse_pages || !inserts.is_empty() {
+            pipeline::renumber(&mut pages, opts.keep_page_names);
+        }
 
-        let comic_info = config_info(opts, &name, c, book).context("ComicInfo.xml generation")?;
+        if let Some(profile) = &profile {
+            pages = profile
+                .apply(pages, opts.is_rtl())
+                .with_context(|| anyhow!("{}: Failed to apply profile", book.dir.display()))?;
+            debug!(pages = pages.len(), "applied profile");
+        }
+
+        if let Some(target_size) = opts.target_size {
+            let budget = (target_size * 1024.0 * 1024.0) as u64;
+
+            let (fitted, quality) = pipeline::fit_to_size(pages, budget)
+                .with_context(|| anyhow!("{}: Failed to fit size", book.dir.display()))?;
+
+            pages = fitted;
+            debug!(?quality, "fitted to size");
 
-        if opts.verbose {
+            if let Some(quality) = quality {
+                let size = pipeline::total_size(&pages);
+
+                if size > budget {
+                    o.set_color(&warn)?;
+                    write!(o, "  [size] ")?;
+                    o.reset()?;
+                    writeln!(
+                        o,
+                        "{size} bytes at quality {quality} exceeds target of {budget} bytes"
+                    )?;
+                } else if opts.verbosity().is_verbose() {
+                    o.set_color(&ok)?;
+                    write!(o, "  [size] ")?;
+                    o.reset()?;
+                    writeln!(o, "{size} bytes at quality {quality}")?;
+                }
+            }
+        }
+
+        let language = if detect_language {
+            let detected = language::detect(book, language_map.as_ref())?;
+
+            if let Some((language, origin)) = &detected
+                && opts.verbosity().is_verbose()
+            {
+                o.set_color(&ok)?;
+                write!(o, "  [language] ")?;
+                o.reset()?;
+                writeln!(o, "{language} (from {origin})")?;
+            }
+
+            detected.map(|(language, _)| language)
+        } else {
+            opts.language.clone()
+        };
+
+        let (page_count, typed) = if streamed {
+            let typed = book
+                .pages
+                .iter()
+                .enumerate()
+                .filter_map(|(n, p)| Some((n, p.page_type.get()?, p.size as usize)))
+                .collect::<Vec<_>>();
+
+            (book.pages.len(), typed)
+        } else {
+            let typed = pages
+                .iter()
+                .enumerate()
+                .filter_map(|(n, p)| Some((n, p.page_type?, p.content.len())))
+                .collect::<Vec<_>>();
+
+            (pages.len(), typed)
+        };
+
+        let notes = stamp.as_ref().map(|stamp| stamp.to_string());
+
+        let comic_info = config_info(
+            opts,
+            ComicInfo {
+                name,
+                title: &title,
+                number: Some(c.number),
+                page_count,
+                language: language.as_ref(),
+                typed: &typed,
+                notes: notes.as_deref(),
+            },
+        )
+        .context("ComicInfo.xml generation")?;
+
+        if opts.verbosity().is_detailed() && opts.format == BookFormat::Cbz {
             o.set_color(&ok)?;
             write!(o, "  [info] ")?;
             o.reset()?;
@@ -589,34 +1941,114 @@ pub fn entry(opts: &Bookvert) -> Result<()> {
             }
         }
 
-        if target.exists() && !opts.force {
-            o.set_color(&warn)?;
-            write!(o, "  [exists] ")?;
+        if streamed {
+            if cancel.is_aborted() {
+                break;
+            }
+
+            if let Some(parent) = target.parent() {
+                opts.effects().create_dir_all(parent).with_context(|| {
+                    anyhow!("Failed to create parent directory {}", parent.display())
+                })?;
+            }
+
+            debug!(target = %target.display(), "streaming book");
+
+            let size = stream_book(opts, book, &comic_info, &target)
+                .with_context(|| anyhow!("Failed to write file {}", target.display()))?;
+
+            o.set_color(&ok)?;
+            write!(o, "  [file] ")?;
             o.reset()?;
-            writeln!(o, "{} (--force to overwrite)", target.display())?;
+            writeln!(o, "{} ({size} bytes)", target.display())?;
+
+            if let Some(section) = &mut section {
+                let row = report_row(
+                    c.number,
+                    book,
+                    &target,
+                    "written",
+                    Some(page_count),
+                    Some(size),
+                );
+                section.row(row);
+            }
+
+            if let Some(checksum) = opts.checksums {
+                let name = target.strip_prefix(&opts.out).unwrap_or(&target);
+                checksums.push((name.display().to_string(), checksum.digest_file(&target)?));
+            }
+
             continue;
         }
 
-        let mut w = ZipWriter::new(Cursor::new(Vec::new()));
+        let out = match opts.format {
+            BookFormat::Cbz => {
+                let mut w = ZipWriter::new(Cursor::new(Vec::new()));
+
+                let options = SimpleFileOptions::default()
+                    .compression_method(CompressionMethod::Stored)
+                    .unix_permissions(0o755);
 
-        let options = SimpleFileOptions::default()
-            .compression_method(CompressionMethod::Stored)
-            .unix_permissions(0o755);
+                w.start_file("ComicInfo.xml", options)?;
+                w.write_all(comic_info.as_bytes())?;
 
-        w.start_file("ComicInfo.xml", options)?;
-        w.write_all(comic_info.as_bytes())?;
+                for page in &pages {
+                    w.start_file(&page.name, options)?;
+                    w.write_all(&page.content)?;
+                }
 
-        for page in book.pages.iter() {
-            let content = fs::read(&page.path)
-                .with_context(|| anyhow!("Failed to read file {}", page.path.display()))?;
+                w.finish()?.into_inner()
+            }
+            BookFormat::Epub | BookFormat::Kepub => {
+                let modified = fs::metadata(&book.dir)
+                    .and_then(|m| m.modified())
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+
+                let meta = epub::Metadata {
+                    title: &title,
+                    series: opts.series.as_deref().unwrap_or(name),
+                    number: c.number,
+                    author: opts.author.as_deref(),
+                    publisher: opts.publisher.as_deref(),
+                    language: language.as_ref().map(|l| l.to_string()),
+                    summary: opts.summary.as_deref(),
+                    rtl: opts.is_rtl(),
+                    modified,
+                };
 
-            w.start_file(&page.name, options)?;
-            w.write_all(&content)?;
-        }
+                let kobo = opts.format == BookFormat::Kepub;
 
-        let out = w.finish()?.into_inner();
+                epub::write(&pages, &meta, kobo)
+                    .with_context(|| anyhow!("{}: Failed to write EPUB", book.dir.display()))?
+            }
+        };
+
+        if on_conflict == OnConflict::SkipSame && target.exists() {
+            let same = same_contents(&target, &out)?;
+
+            if same {
+                o.set_color(&ok)?;
+                write!(o, "  [same] ")?;
+                o.reset()?;
+                writeln!(o, "{}", target.display())?;
+            } else {
+                o.set_color(&warn)?;
+                write!(o, "  [exists] ")?;
+                o.reset()?;
+                writeln!(o, "{} (differs, --force to overwrite)", target.display())?;
+            }
+
+            if let Some(section) = &mut section {
+                let status = if same { "same" } else { "exists" };
+                let row = report_row(c.number, book, &target, status, Some(pages.len()), None);
+                section.row(row);
+            }
 
-        if opts.dry_run {
+            continue;
+        }
+
+        if opts.is_dry_run() {
             o.set_color(&warn)?;
             write!(o, "  [dry-run] ")?;
             o.reset()?;
@@ -628,23 +2060,366 @@ pub fn entry(opts: &Bookvert) -> Result<()> {
 
         writeln!(o, "{} ({} bytes)", target.display(), out.len())?;
 
-        if opts.dry_run {
+        if let Some(section) = &mut section {
+            let status = if opts.is_dry_run() {
+                "planned"
+            } else {
+                "written"
+            };
+            let after = Some(out.len() as u64);
+            let row = report_row(c.number, book, &target, status, Some(pages.len()), after);
+            section.row(row);
+        }
+
+        if opts.is_dry_run() {
             continue;
         }
 
+        if cancel.is_aborted() {
+            break;
+        }
+
         if let Some(parent) = target.parent() {
-            fs::create_dir_all(parent).with_context(|| {
+            opts.effects().create_dir_all(parent).with_context(|| {
                 anyhow!("Failed to create parent directory {}", parent.display())
             })?;
         }
 
-        fs::write(&target, out)
+        if let Some(checksum) = opts.checksums {
+            let name = target.strip_prefix(&opts.out).unwrap_or(&target);
+            checksums.push((name.display().to_string(), checksum.digest(&out)));
+        }
+
+        debug!(target = %target.display(), bytes = out.len(), "writing book");
+
+        opts.effects()
+            .write(&target, out)
             .with_context(|| anyhow!("Failed to write file {}", target.display()))?;
     }
 
+    if let Some(checksum) = opts.checksums
+        && !checksums.is_empty()
+    {
+        checksum.update_manifest(opts.effects(), &opts.out, &checksums)?;
+    }
+
+    if cancel.is_aborted() {
+        return Err(anyhow!("Aborted, run again to resume"));
+    }
+
+    Ok(())
+}
+
+/// Write a book by streaming its pages from their sources into a partial file
+/// next to `target`, which is moved into place once it's complete.
+///
+/// Returns the size of the written book.
+fn stream_book(opts: &Bookvert, book: &Book, comic_info: &str, target: &Path) -> Result<u64> {
+    let effects = opts.effects();
+    let part = target.with_added_extension("part");
+    let file = effects.create(&part)?;
+
+    let size = match stream_pages(book, comic_info, file) {
+        Ok(size) => size,
+        Err(e) => {
+            _ = effects.remove_file(&part);
+            return Err(e);
+        }
+    };
+
+    effects.rename(&part, target)?;
+    Ok(size)
+}
+
+/// Stream the pages of a book into a zip archive written to `file`.
+fn stream_pages(book: &Book, comic_info: &str, file: fs::File) -> Result<u64> {
+    let mut w = ZipWriter::new(file);
+
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Stored)
+        .unix_permissions(0o755);
+
+    w.start_file("ComicInfo.xml", options)?;
+    w.write_all(comic_info.as_bytes())?;
+
+    for page in &book.pages {
+        trace!(path = %page.path.display(), name = page.name, "streaming page");
+        w.start_file(&page.name, options)?;
+        page.copy_to(&mut w)?;
+    }
+
+    let file = w.finish()?;
+    Ok(file.metadata()?.len())
+}
+
+/// Construct a report row for a book.
+fn report_row(
+    number: u32,
+    book: &Book,
+    target: &Path,
+    status: &str,
+    pages: Option<usize>,
+    after: Option<u64>,
+) -> Vec<Cell> {
+    vec![
+        cover(book),
+        format!("{number:03}").into(),
+        book.dir.display().to_string().into(),
+        target.display().to_string().into(),
+        status.into(),
+        pages.map(|n| n.to_string()).unwrap_or_default().into(),
+        report::size(book.bytes()).into(),
+        after.map(report::size).unwrap_or_default().into(),
+    ]
+}
+
+/// Encode a thumbnail of the cover of a book as an image cell.
+///
+/// If the cover can't be read or decoded, an empty cell is returned instead.
+fn cover(book: &Book) -> Cell {
+    let Some(image) = book.pages.first().and_then(preview::preview) else {
+        return Cell::from("");
+    };
+
+    let mut data = Cursor::new(Vec::new());
+
+    if image.write_to(&mut data, image::ImageFormat::Png).is_err() {
+        return Cell::from("");
+    }
+
+    Cell::Image {
+        mime: "image/png",
+        data: data.into_inner(),
+    }
+}
+
+/// The directory temporary files are stored in.
+fn tmp_dir(opts: &Bookvert, config: &Config) -> PathBuf {
+    opts.tmp_dir
+        .clone()
+        .or(config.shared().tmp_dir)
+        .unwrap_or_else(env::temp_dir)
+}
+
+/// Collect name overrides specified on the command line by book number.
+fn name_overrides(opts: &Bookvert) -> BTreeMap<u32, &str> {
+    opts.name_override
+        .iter()
+        .map(|o| (o.number, o.name.as_str()))
+        .collect()
+}
+
+/// Split the stem of an existing book into its name and number.
+///
+/// The number is the last number in the stem, since that is where bookvert
+/// puts it when naming books.
+fn split_number(stem: &str) -> Option<(&str, u32)> {
+    let end = stem.rfind(|c: char| c.is_ascii_digit())? + 1;
+    let start = stem[..end]
+        .rfind(|c: char| !c.is_ascii_digit())
+        .map(|n| n + 1)
+        .unwrap_or(0);
+
+    let number = stem[start..end].parse().ok()?;
+    let name = stem[..start]
+        .trim_end_matches(|c: char| !c.is_alphanumeric())
+        .trim();
+
+    Some((name, number))
+}
+
+/// Construct a directory walker which respects the walking options.
+fn walk(opts: &Bookvert, path: &Path) -> Walk {
+    WalkBuilder::new(path)
+        .standard_filters(!opts.no_ignore)
+        .hidden(!opts.hidden)
+        .follow_links(opts.follow_symlinks)
+        .build()
+}
+
+/// Find existing books in the paths specified on the command line.
+fn existing_books(opts: &Bookvert) -> Result<Vec<PathBuf>> {
+    let mut books = Vec::new();
+
+    for path in &opts.path {
+        for entry in walk(opts, path) {
+            let entry = entry?;
+
+            if !entry.file_type().is_some_and(|ty| ty.is_file()) {
+                continue;
+            }
+
+            let is_cbz = entry
+                .path()
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("cbz"));
+
+            if is_cbz {
+                books.push(entry.into_path());
+            }
+        }
+    }
+
+    books.sort();
+    Ok(books)
+}
+
+/// Compute the file stem and the title of a book.
+///
+/// The stem is sanitized so that it can't name a file outside of the output
+/// directory.
+fn book_names(name: &str, number: u32, name_override: Option<String>) -> (String, String) {
+    let (stem, title) = match name_override {
+        Some(name) => (name.clone(), name),
+        None => (format!("{name}{number:03}"), format!("{name}{number}")),
+    };
+
+    (sanitize_name(&stem), title)
+}
+
+/// Check that the name of a book doesn't contain path separators, refer to
+/// another directory, or end up empty once it's been sanitized.
+fn check_book_name(name: &str) -> Result<()> {
+    if name.contains(['/', '\\']) {
+        return Err(anyhow!("Name '{name}' contains a path separator"));
+    }
+
+    if name == "." || name == ".." || Path::new(name).is_absolute() {
+        return Err(anyhow!("Name '{name}' is not a file name"));
+    }
+
+    if sanitize_name(name).is_empty() {
+        return Err(anyhow!("Name '{name}' is empty as a file name"));
+    }
+
     Ok(())
 }
 
+/// Read the name override sidecar from the directory of a book, if present.
+fn read_name_sidecar(dir: &Path) -> Result<Option<String>> {
+    let path = dir.join(NAME_SIDECAR);
+
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let contents =
+        fs::read_to_string(&path).with_context(|| anyhow!("Failed to read {}", path.display()))?;
+
+    let name = contents.lines().next().unwrap_or_default().trim();
+
+    if name.is_empty() {
+        return Ok(None);
+    }
+
+    check_book_name(name).with_context(|| anyhow!("Invalid name in {}", path.display()))?;
+    Ok(Some(name.to_string()))
+}
+
+/// Construct a path to a book which doesn't exist, like `name012 (1).cbz`.
+fn unique_target(out: &Path, stem: &str, format: BookFormat) -> PathBuf {
+    let mut n = 1usize;
+
+    loop {
+        let mut target = out.to_path_buf();
+        target.push(format!("{stem} ({n})"));
+        target.add_extension(format.ext());
+
+        if !target.exists() {
+            return target;
+        }
+
+        n += 1;
+    }
+}
+
+/// Test if the existing book at the given path has the same entries as the
+/// provided book.
+///
+/// This compares the names and contents of the entries rather than the raw
+/// bytes, since the archive might contain timestamps.
+fn same_contents(path: &Path, book: &[u8]) -> Result<bool> {
+    let existing = fs::read(path).with_context(|| anyhow!("Failed to read {}", path.display()))?;
+
+    let Ok(mut a) = ZipArchive::new(Cursor::new(existing)) else {
+        return Ok(false);
+    };
+
+    let mut b = ZipArchive::new(Cursor::new(book))?;
+
+    if a.len() != b.len() {
+        return Ok(false);
+    }
+
+    let mut a_buf = Vec::new();
+    let mut b_buf = Vec::new();
+
+    for i in 0..a.len() {
+        let mut a = a.by_index(i)?;
+        let mut b = b.by_index(i)?;
+
+        if a.name() != b.name() {
+            return Ok(false);
+        }
+
+        a_buf.clear();
+        b_buf.clear();
+        a.read_to_end(&mut a_buf)?;
+        b.read_to_end(&mut b_buf)?;
+
+        if a_buf != b_buf {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Construct a unique page name based on the original file name of the page.
+fn page_name(book: &Book, path: &Path, ext: &str) -> String {
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .map(sanitize_name)
+        .unwrap_or_default();
+
+    let stem = if stem.is_empty() {
+        format!("p{:03}", book.pages.len())
+    } else {
+        stem
+    };
+
+    let mut name = format!("{stem}.{ext}");
+    let mut n = 1usize;
+
+    while book
+        .pages
+        .iter()
+        .any(|p| p.name.eq_ignore_ascii_case(&name))
+    {
+        name = format!("{stem} ({n}).{ext}");
+        n += 1;
+    }
+
+    name
+}
+
+/// Sanitize a page or book name so that it can be safely used as a file name.
+fn sanitize_name(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+
+    for c in input.chars() {
+        if c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') {
+            out.push('_');
+        } else {
+            out.push(c);
+        }
+    }
+
+    out.trim_matches(|c: char| c.is_whitespace() || c == '.')
+        .to_string()
+}
+
 /// Extracts all numbers from the input string as an iterator.
 fn numbers(mut input: &str) -> impl Iterator<Item = u32> {
     iter::from_fn(move || {
@@ -662,8 +2437,29 @@ fn numbers(mut input: &str) -> impl Iterator<Item = u32> {
     })
 }
 
+/// Fields of ComicInfo.xml which are specific to a book.
+struct ComicInfo<'a> {
+    name: &'a str,
+    title: &'a str,
+    number: Option<u32>,
+    page_count: usize,
+    language: Option<&'a LanguageTag>,
+    typed: &'a [(usize, PageType, usize)],
+    notes: Option<&'a str>,
+}
+
 /// Generates ComicInfo.xml content if any metadata options are provided.
-fn config_info(opts: &Bookvert, name: &str, catalog: &Catalog, book: &Book) -> Result<String> {
+fn config_info(opts: &Bookvert, info: ComicInfo<'_>) -> Result<String> {
+    let ComicInfo {
+        name,
+        title,
+        number,
+        page_count,
+        language,
+        typed,
+        notes,
+    } = info;
+
     let mut o = String::new();
 
     writeln!(o, "<?xml version=\"1.0\" encoding=\"utf-8\"?>")?;
@@ -672,16 +2468,16 @@ fn config_info(opts: &Bookvert, name: &str, catalog: &Catalog, book: &Book) -> R
         "<ComicInfo xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\">"
     )?;
 
-    writeln!(
-        o,
-        "  <Title>{}</Title>",
-        xml_escape(&format!("{name}{}", catalog.number))
-    )?;
+    writeln!(o, "  <Title>{}</Title>", xml_escape(title))?;
 
     let series = opts.series.as_deref().unwrap_or(name);
     writeln!(o, "  <Series>{}</Series>", xml_escape(series))?;
-    writeln!(o, "  <Number>{}</Number>", catalog.number)?;
-    writeln!(o, "  <PageCount>{}</PageCount>", book.pages.len())?;
+
+    if let Some(number) = number {
+        writeln!(o, "  <Number>{number}</Number>")?;
+    }
+
+    writeln!(o, "  <PageCount>{page_count}</PageCount>")?;
 
     if let Some(author) = &opts.author {
         writeln!(o, "  <Writer>{}</Writer>", xml_escape(author))?;
@@ -699,11 +2495,11 @@ fn config_info(opts: &Bookvert, name: &str, catalog: &Catalog, book: &Book) -> R
         writeln!(o, "  <Genre>{}</Genre>", xml_escape(genre))?;
     }
 
-    if let Some(language) = &opts.language {
+    if let Some(language) = language {
         writeln!(o, "  <LanguageISO>{language}</LanguageISO>")?;
     }
 
-    if let Some(manga) = &opts.manga {
+    if let Some(manga) = opts.manga() {
         writeln!(o, "  <Manga>{manga}</Manga>")?;
     }
 
@@ -711,6 +2507,24 @@ fn config_info(opts: &Bookvert, name: &str, catalog: &Catalog, book: &Book) -> R
         writeln!(o, "  <Summary>{}</Summary>", xml_escape(summary))?;
     }
 
+    if let Some(notes) = notes {
+        writeln!(o, "  <Notes>{}</Notes>", xml_escape(notes))?;
+    }
+
+    if !typed.is_empty() {
+        writeln!(o, "  <Pages>")?;
+
+        for (image, page_type, size) in typed {
+            writeln!(
+                o,
+                "    <Page Image=\"{image}\" Type=\"{}\" ImageSize=\"{size}\" />",
+                page_type.comic_info()
+            )?;
+        }
+
+        writeln!(o, "  </Pages>")?;
+    }
+
     writeln!(o, "</ComicInfo>")?;
     Ok(o)
 }
@@ -750,7 +2564,7 @@ fn escape(input: &str) -> Cow<'_, str> {
 }
 
 /// Escapes special XML characters.
-fn xml_escape(input: &str) -> Cow<'_, str> {
+pub(crate) fn xml_escape(input: &str) -> Cow<'_, str> {
     let mut escaped = String::new();
 
     let n = 'escape: {
@@ -0,0 +1,321 @@
+use core::fmt::Write as _;
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read as _, Write as _};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, anyhow};
+use termcolor::{ColorChoice, StandardStream, WriteColor};
+use zip::ZipArchive;
+
+use super::stamp::{self, Stamp};
+use super::{Bookvert, Colors, existing_books, page_ext, split_number};
+
+/// The kind of an issue found in a library.
+#[derive(Debug, Clone, Copy)]
+enum IssueKind {
+    Corrupt,
+    MissingComicInfo,
+    NoPages,
+    NoNumber,
+    DuplicateNumber,
+    MissingNumbers,
+    InconsistentName,
+    MissingStamp,
+    OutdatedStamp,
+}
+
+impl IssueKind {
+    /// Get the machine-readable identifier of the issue.
+    fn id(&self) -> &'static str {
+        match self {
+            IssueKind::Corrupt => "corrupt",
+            IssueKind::MissingComicInfo => "missing-comic-info",
+            IssueKind::NoPages => "no-pages",
+            IssueKind::NoNumber => "no-number",
+            IssueKind::DuplicateNumber => "duplicate-number",
+            IssueKind::MissingNumbers => "missing-numbers",
+            IssueKind::InconsistentName => "inconsistent-name",
+            IssueKind::MissingStamp => "missing-stamp",
+            IssueKind::OutdatedStamp => "outdated-stamp",
+        }
+    }
+}
+
+/// An issue found in a library.
+struct Issue {
+    path: PathBuf,
+    kind: IssueKind,
+    message: String,
+}
+
+/// A book which has been inspected.
+struct Inspected<'a> {
+    path: &'a Path,
+    name: &'a str,
+    number: u32,
+}
+
+/// Inspect an existing library of books and report issues.
+pub(super) fn entry(opts: &Bookvert, color: ColorChoice) -> Result<()> {
+    let books = existing_books(opts)?;
+
+    let mut issues = Vec::new();
+    let mut by_dir = BTreeMap::<&Path, Vec<Inspected<'_>>>::new();
+
+    for path in &books {
+        inspect(path, opts.stamp, &mut issues);
+
+        let Some((name, number)) = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(split_number)
+        else {
+            issues.push(Issue {
+                path: path.clone(),
+                kind: IssueKind::NoNumber,
+                message: "no number in file name".to_string(),
+            });
+
+            continue;
+        };
+
+        let dir = path.parent().unwrap_or(Path::new(""));
+
+        by_dir
+            .entry(dir)
+            .or_default()
+            .push(Inspected { path, name, number });
+    }
+
+    for (dir, mut books) in by_dir {
+        books.sort_by_key(|b| b.number);
+
+        let mut names = BTreeMap::<&str, usize>::new();
+
+        for b in &books {
+            *names.entry(b.name).or_default() += 1;
+        }
+
+        let common = names
+            .iter()
+            .max_by_key(|(_, count)| **count)
+            .map(|(name, _)| *name);
+
+        for b in &books {
+            if let Some(common) = common
+                && b.name != common
+            {
+                issues.push(Issue {
+                    path: b.path.to_path_buf(),
+                    kind: IssueKind::InconsistentName,
+                    message: format!("name `{}` differs from `{common}`", b.name),
+                });
+            }
+
+            let Some(stem) = b.path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+
+            if stem != format!("{}{:03}", b.name, b.number) {
+                issues.push(Issue {
+                    path: b.path.to_path_buf(),
+                    kind: IssueKind::InconsistentName,
+                    message: format!("expected name `{}{:03}.cbz`", b.name, b.number),
+                });
+            }
+        }
+
+        for w in books.windows(2) {
+            let [a, b] = w else {
+                continue;
+            };
+
+            if a.number == b.number {
+                issues.push(Issue {
+                    path: b.path.to_path_buf(),
+                    kind: IssueKind::DuplicateNumber,
+                    message: format!("number {} also used by {}", b.number, a.path.display()),
+                });
+            }
+        }
+
+        let mut missing = Vec::new();
+
+        if let (Some(first), Some(last)) = (books.first(), books.last()) {
+            for n in first.number..last.number {
+                if books.binary_search_by_key(&n, |b| b.number).is_err() {
+                    missing.push(n.to_string());
+                }
+            }
+        }
+
+        if !missing.is_empty() {
+            issues.push(Issue {
+                path: dir.to_path_buf(),
+                kind: IssueKind::MissingNumbers,
+                message: format!("missing numbers: {}", missing.join(", ")),
+            });
+        }
+    }
+
+    let o = StandardStream::stdout(color);
+    let mut o = o.lock();
+
+    if opts.json {
+        writeln!(o, "{}", to_json(books.len(), &issues)?)?;
+    } else {
+        let Colors { ok, warn, .. } = Colors::new();
+
+        for issue in &issues {
+            o.set_color(&warn)?;
+            write!(o, "[{}]", issue.kind.id())?;
+            o.reset()?;
+            writeln!(o, " {}: {}", issue.path.display(), issue.message)?;
+        }
+
+        o.set_color(&ok)?;
+        write!(o, "[checked]")?;
+        o.reset()?;
+        writeln!(o, " {} books, {} issues", books.len(), issues.len())?;
+    }
+
+    if !issues.is_empty() {
+        return Err(anyhow!("Found {} issues in library", issues.len()));
+    }
+
+    Ok(())
+}
+
+/// Inspect the contents of a single book.
+///
+/// With `stamp`, books which weren't stamped by this version of bookvert are
+/// reported.
+fn inspect(path: &Path, stamp: bool, issues: &mut Vec<Issue>) {
+    let mut corrupt = |e: &dyn core::fmt::Display| {
+        issues.push(Issue {
+            path: path.to_path_buf(),
+            kind: IssueKind::Corrupt,
+            message: e.to_string(),
+        });
+    };
+
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) => return corrupt(&e),
+    };
+
+    let mut archive = match ZipArchive::new(file) {
+        Ok(archive) => archive,
+        Err(e) => return corrupt(&e),
+    };
+
+    let mut comic_info = None;
+    let mut pages = 0usize;
+
+    for i in 0..archive.len() {
+        let mut file = match archive.by_index(i) {
+            Ok(file) => file,
+            Err(e) => return corrupt(&e),
+        };
+
+        if file.name().eq_ignore_ascii_case("ComicInfo.xml") {
+            let mut contents = String::new();
+
+            if let Err(e) = file.read_to_string(&mut contents) {
+                return corrupt(&e);
+            }
+
+            comic_info = Some(contents);
+            continue;
+        }
+
+        let ext = Path::new(file.name()).extension().and_then(|e| e.to_str());
+
+        if !file.is_dir() && ext.and_then(page_ext).is_some() {
+            pages += 1;
+        }
+    }
+
+    if comic_info.is_none() {
+        issues.push(Issue {
+            path: path.to_path_buf(),
+            kind: IssueKind::MissingComicInfo,
+            message: "missing ComicInfo.xml".to_string(),
+        });
+    }
+
+    if stamp && let Some(comic_info) = &comic_info {
+        match stamp::notes(comic_info).and_then(|notes| Stamp::parse(&notes)) {
+            Some(stamp) if stamp.version != env!("CARGO_PKG_VERSION") => {
+                issues.push(Issue {
+                    path: path.to_path_buf(),
+                    kind: IssueKind::OutdatedStamp,
+                    message: format!("packaged by bookvert {} on {}", stamp.version, stamp.date),
+                });
+            }
+            Some(..) => {}
+            None => {
+                issues.push(Issue {
+                    path: path.to_path_buf(),
+                    kind: IssueKind::MissingStamp,
+                    message: "not stamped by bookvert".to_string(),
+                });
+            }
+        }
+    }
+
+    if pages == 0 {
+        issues.push(Issue {
+            path: path.to_path_buf(),
+            kind: IssueKind::NoPages,
+            message: "book has no pages".to_string(),
+        });
+    }
+}
+
+/// Format the report as JSON.
+fn to_json(books: usize, issues: &[Issue]) -> Result<String> {
+    let mut o = String::new();
+
+    write!(o, "{{\"books\":{books},\"issues\":[")?;
+
+    for (n, issue) in issues.iter().enumerate() {
+        if n > 0 {
+            o.push(',');
+        }
+
+        write!(
+            o,
+            "{{\"path\":\"{}\",\"kind\":\"{}\",\"message\":\"{}\"}}",
+            json_escape(&issue.path.to_string_lossy()),
+            issue.kind.id(),
+            json_escape(&issue.message),
+        )?;
+    }
+
+    o.push_str("]}");
+    Ok(o)
+}
+
+/// Escape a string for inclusion in JSON.
+fn json_escape(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+
+    for c in input.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => {
+                _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
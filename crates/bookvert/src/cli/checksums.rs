@@ -0,0 +1,128 @@
+use core::fmt::{self, Write as _};
+use core::str::FromStr;
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+use mediavert_core::effects::Effects;
+use sha2::{Digest, Sha256};
+
+/// Algorithms which can be used for checksum manifests.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Checksum {
+    Sha256,
+}
+
+impl Checksum {
+    /// The name of the manifest file in the output directory.
+    fn manifest(&self) -> &'static str {
+        match self {
+            Checksum::Sha256 => "SHA256SUMS",
+        }
+    }
+
+    /// Compute the hex-encoded checksum of the given data.
+    pub(super) fn digest(&self, data: &[u8]) -> String {
+        let hash = match self {
+            Checksum::Sha256 => Sha256::digest(data),
+        };
+
+        hex(&hash)
+    }
+
+    /// Compute the hex-encoded checksum of a file without reading all of it
+    /// into memory.
+    pub(super) fn digest_file(&self, path: &Path) -> Result<String> {
+        let mut file =
+            File::open(path).with_context(|| anyhow!("Failed to open {}", path.display()))?;
+
+        let hash = match self {
+            Checksum::Sha256 => {
+                let mut hasher = Sha256::new();
+                io::copy(&mut file, &mut hasher)
+                    .with_context(|| anyhow!("Failed to read {}", path.display()))?;
+                hasher.finalize()
+            }
+        };
+
+        Ok(hex(&hash))
+    }
+
+    /// Update the manifest in the given output directory with new entries.
+    ///
+    /// The manifest uses the same format as `sha256sum` and can be verified
+    /// with `sha256sum -c`. Existing entries for other files are preserved.
+    pub(super) fn update_manifest(
+        &self,
+        effects: Effects,
+        out: &Path,
+        entries: &[(String, String)],
+    ) -> Result<()> {
+        let path = out.join(self.manifest());
+
+        let mut manifest = BTreeMap::new();
+
+        match fs::read_to_string(&path) {
+            Ok(existing) => {
+                for line in existing.lines() {
+                    if let Some((hash, name)) = line.split_once("  ") {
+                        manifest.insert(name.to_string(), hash.to_string());
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e).with_context(|| anyhow!("Failed to read {}", path.display()));
+            }
+        }
+
+        for (name, hash) in entries {
+            manifest.insert(name.clone(), hash.clone());
+        }
+
+        let mut contents = String::new();
+
+        for (name, hash) in &manifest {
+            writeln!(contents, "{hash}  {name}")?;
+        }
+
+        effects
+            .write(&path, contents)
+            .with_context(|| anyhow!("Failed to write {}", path.display()))
+    }
+}
+
+/// Hex-encode a hash.
+pub(super) fn hex(hash: &[u8]) -> String {
+    let mut out = String::with_capacity(hash.len() * 2);
+
+    for b in hash {
+        _ = write!(out, "{b:02x}");
+    }
+
+    out
+}
+
+impl FromStr for Checksum {
+    type Err = anyhow::Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "sha256" => Ok(Checksum::Sha256),
+            _ => Err(anyhow!("Invalid checksum algorithm '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for Checksum {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Checksum::Sha256 => write!(f, "sha256"),
+        }
+    }
+}
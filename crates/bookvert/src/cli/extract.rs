@@ -0,0 +1,104 @@
+use std::fs::File;
+use std::io::{self, Write as _};
+
+use anyhow::{Context, Result, anyhow};
+use termcolor::{ColorChoice, StandardStream, WriteColor};
+use zip::ZipArchive;
+
+use super::{Bookvert, Colors, existing_books, split_number};
+
+/// Extract existing books into directories of pages.
+pub(super) fn entry(opts: &Bookvert, color: ColorChoice) -> Result<()> {
+    let Colors { ok, warn, .. } = Colors::new();
+
+    let o = StandardStream::stdout(color);
+    let mut o = o.lock();
+
+    for path in existing_books(opts)? {
+        let Some(stem) = path.file_stem() else {
+            continue;
+        };
+
+        if !opts.include.is_empty() {
+            let number = stem.to_str().and_then(split_number).map(|(_, n)| n);
+
+            let included = number.is_some_and(|number| {
+                opts.include
+                    .iter()
+                    .any(|predicate| predicate.matches(number))
+            });
+
+            if !included {
+                continue;
+            }
+        }
+
+        let target = opts.out.join(stem);
+
+        if target.exists() && !opts.force {
+            o.set_color(&warn)?;
+            write!(o, "[exists]")?;
+            o.reset()?;
+            writeln!(o, " {} (--force to overwrite)", target.display())?;
+            continue;
+        }
+
+        let file =
+            File::open(&path).with_context(|| anyhow!("Failed to open {}", path.display()))?;
+        let mut archive = ZipArchive::new(file)
+            .with_context(|| anyhow!("{}: Failed to open archive", path.display()))?;
+
+        if opts.is_dry_run() {
+            o.set_color(&warn)?;
+            write!(o, "[dry-run]")?;
+        } else {
+            o.set_color(&ok)?;
+            write!(o, "[extract]")?;
+        }
+
+        o.reset()?;
+        writeln!(o, " {} -> {}", path.display(), target.display())?;
+
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+
+            if file.is_dir() || file.name().eq_ignore_ascii_case("ComicInfo.xml") {
+                continue;
+            }
+
+            let Some(name) = file.enclosed_name() else {
+                o.set_color(&warn)?;
+                write!(o, "  [skip]")?;
+                o.reset()?;
+                writeln!(o, " {} (unsafe path)", file.name())?;
+                continue;
+            };
+
+            let page = target.join(name);
+
+            if opts.verbosity().is_detailed() {
+                writeln!(o, "  {}", page.display())?;
+            }
+
+            if opts.is_dry_run() {
+                continue;
+            }
+
+            if let Some(parent) = page.parent() {
+                opts.effects()
+                    .create_dir_all(parent)
+                    .with_context(|| anyhow!("Failed to create directory {}", parent.display()))?;
+            }
+
+            let mut out = opts
+                .effects()
+                .create(&page)
+                .with_context(|| anyhow!("Failed to create file {}", page.display()))?;
+            io::copy(&mut file, &mut out)
+                .with_context(|| anyhow!("Failed to write file {}", page.display()))?;
+            out.flush()?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use std::io::Write as _;
+
+use anyhow::{Context, Result, anyhow};
+use termcolor::{ColorChoice, StandardStream, WriteColor};
+
+use super::{Bookvert, Colors, book_names, existing_books, name_overrides, split_number};
+
+/// Rename existing books to the configured name.
+pub(super) fn entry(opts: &Bookvert, color: ColorChoice) -> Result<()> {
+    let Colors { ok, warn, .. } = Colors::new();
+
+    let o = StandardStream::stdout(color);
+    let mut o = o.lock();
+
+    let name_overrides = name_overrides(opts);
+
+    for path in existing_books(opts)? {
+        let Some((stem_name, number)) = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(split_number)
+        else {
+            o.set_color(&warn)?;
+            write!(o, "[skip]")?;
+            o.reset()?;
+            writeln!(o, " {} (no number in file name)", path.display())?;
+            continue;
+        };
+
+        let name = opts.name.as_deref().unwrap_or(stem_name);
+        let name_override = name_overrides.get(&number).map(|name| name.to_string());
+        let (stem, _) = book_names(name, number, name_override);
+
+        let mut target = path.with_file_name(&stem);
+        target.add_extension("cbz");
+
+        if target == path {
+            if opts.verbosity().is_verbose() {
+                o.set_color(&ok)?;
+                write!(o, "[same]")?;
+                o.reset()?;
+                writeln!(o, " {}", path.display())?;
+            }
+
+            continue;
+        }
+
+        if target.exists() && !opts.force {
+            o.set_color(&warn)?;
+            write!(o, "[exists]")?;
+            o.reset()?;
+            writeln!(o, " {} (--force to overwrite)", target.display())?;
+            continue;
+        }
+
+        if opts.is_dry_run() {
+            o.set_color(&warn)?;
+            write!(o, "[dry-run]")?;
+        } else {
+            o.set_color(&ok)?;
+            write!(o, "[rename]")?;
+        }
+
+        o.reset()?;
+        writeln!(o, " {} -> {}", path.display(), target.display())?;
+
+        if opts.is_dry_run() {
+            continue;
+        }
+
+        opts.effects().rename(&path, &target).with_context(|| {
+            anyhow!(
+                "Failed to rename {} to {}",
+                path.display(),
+                target.display()
+            )
+        })?;
+    }
+
+    Ok(())
+}
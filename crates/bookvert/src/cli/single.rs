@@ -0,0 +1,166 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::Write as _;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow, bail};
+use termcolor::{ColorChoice, StandardStream, WriteColor};
+
+use super::stamp::Stamp;
+use super::{Bookvert, Colors, ComicInfo, config_info, page_ext, page_name, stream_book, walk};
+use crate::{Book, Page, Source, magic};
+
+/// Package a single directory of pages into a single book, without detecting
+/// series names or numbers.
+pub(super) fn entry(opts: &Bookvert, dir: &Path, target: &Path, color: ColorChoice) -> Result<()> {
+    let Colors { ok, warn, .. } = Colors::new();
+
+    let o = StandardStream::stdout(color);
+    let mut o = o.lock();
+
+    if !opts.path.is_empty() {
+        bail!("--single does not take any other paths");
+    }
+
+    if !dir.is_dir() {
+        bail!("{}: Not a directory", dir.display());
+    }
+
+    let book = read_book(opts, &mut o, dir)?;
+
+    if book.pages.is_empty() {
+        bail!("{}: No pages found", dir.display());
+    }
+
+    if target.exists() && !opts.force {
+        o.set_color(&warn)?;
+        write!(o, "[exists]")?;
+        o.reset()?;
+        writeln!(o, " {} (--force to overwrite)", target.display())?;
+        return Ok(());
+    }
+
+    let title = target
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(&book.name);
+
+    let name = opts.name.as_deref().unwrap_or(title);
+
+    let notes = opts.stamp.then(|| Stamp::new(&book).to_string());
+
+    let comic_info = config_info(
+        opts,
+        ComicInfo {
+            name,
+            title,
+            number: None,
+            page_count: book.pages.len(),
+            language: opts.language.as_ref(),
+            typed: &[],
+            notes: notes.as_deref(),
+        },
+    )
+    .context("ComicInfo.xml generation")?;
+
+    if opts.is_dry_run() {
+        o.set_color(&warn)?;
+        write!(o, "[dry-run]")?;
+        o.reset()?;
+        writeln!(
+            o,
+            " {} ({} pages, {} bytes)",
+            target.display(),
+            book.pages.len(),
+            book.bytes()
+        )?;
+        return Ok(());
+    }
+
+    if let Some(parent) = target.parent() {
+        opts.effects()
+            .create_dir_all(parent)
+            .with_context(|| anyhow!("Failed to create parent directory {}", parent.display()))?;
+    }
+
+    let size = stream_book(opts, &book, &comic_info, target)
+        .with_context(|| anyhow!("Failed to write file {}", target.display()))?;
+
+    o.set_color(&ok)?;
+    write!(o, "[file]")?;
+    o.reset()?;
+    writeln!(o, " {} ({size} bytes)", target.display())?;
+    Ok(())
+}
+
+/// Read every page in a directory and its subdirectories, in the order of
+/// their paths.
+fn read_book(opts: &Bookvert, o: &mut impl WriteColor, dir: &Path) -> Result<Book> {
+    let Colors { warn, .. } = Colors::new();
+
+    let mut files = Vec::new();
+
+    for entry in walk(opts, dir) {
+        let entry = entry?;
+
+        if !entry.file_type().is_some_and(|ty| ty.is_file()) {
+            continue;
+        }
+
+        let path = entry.into_path();
+
+        if path
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(page_ext)
+            .is_none()
+        {
+            continue;
+        }
+
+        files.push(path);
+    }
+
+    files.sort();
+
+    let mut book = Book {
+        dir: dir.to_path_buf(),
+        name: dir
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default()
+            .to_owned(),
+        pages: Vec::new(),
+        numbers: BTreeSet::new(),
+    };
+
+    for path in files {
+        let Some(ext) = magic::sniff(&Source::File.header(&path)?) else {
+            o.set_color(&warn)?;
+            write!(o, "[not image]")?;
+            o.reset()?;
+            writeln!(o, " {}: skipping", path.display())?;
+            continue;
+        };
+
+        let size = fs::metadata(&path)
+            .with_context(|| anyhow!("{}: Failed to get metadata", path.display()))?
+            .len();
+
+        let name = if opts.keep_page_names {
+            page_name(&book, &path, ext)
+        } else {
+            format!("p{:03}.{ext}", book.pages.len())
+        };
+
+        book.pages.push(Page {
+            path,
+            name,
+            size,
+            source: Source::File,
+            page_type: Default::default(),
+        });
+    }
+
+    Ok(book)
+}
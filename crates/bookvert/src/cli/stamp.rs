@@ -0,0 +1,129 @@
+use core::fmt;
+
+use std::fs::File;
+use std::io::Read as _;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+use jiff::Zoned;
+use sha2::{Digest, Sha256};
+use zip::ZipArchive;
+
+use super::checksums::hex;
+use crate::Book;
+
+/// The prefix of a stamp in the Notes field of ComicInfo.xml.
+const PREFIX: &str = "bookvert ";
+
+/// A stamp recording how a book was packaged, which is written to the Notes
+/// field of ComicInfo.xml with `--stamp`.
+///
+/// It looks like `bookvert 0.0.8; source 3f1a9c0b2d4e6f80; packaged
+/// 2025-01-31`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Stamp {
+    /// The version of bookvert which packaged the book.
+    pub(super) version: String,
+    /// A hash of the names and sizes of the source pages.
+    pub(super) source: String,
+    /// The date the book was packaged.
+    pub(super) date: String,
+}
+
+impl Stamp {
+    /// Construct a stamp for packaging the given book today.
+    pub(super) fn new(book: &Book) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            source: source_hash(book),
+            date: Zoned::now().date().to_string(),
+        }
+    }
+
+    /// Parse a stamp out of the Notes field of ComicInfo.xml.
+    pub(super) fn parse(notes: &str) -> Option<Self> {
+        let rest = notes.trim().strip_prefix(PREFIX)?;
+
+        let mut parts = rest.split("; ");
+        let version = parts.next()?;
+        let source = parts.next()?.strip_prefix("source ")?;
+        let date = parts.next()?.strip_prefix("packaged ")?;
+
+        Some(Self {
+            version: version.to_string(),
+            source: source.to_string(),
+            date: date.to_string(),
+        })
+    }
+
+    /// Read the stamp of an existing book, if it has one.
+    pub(super) fn read(path: &Path) -> Result<Option<Self>> {
+        let Some(comic_info) = read_comic_info(path)? else {
+            return Ok(None);
+        };
+
+        Ok(notes(&comic_info).and_then(|notes| Self::parse(&notes)))
+    }
+}
+
+impl fmt::Display for Stamp {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{PREFIX}{}; source {}; packaged {}",
+            self.version, self.source, self.date
+        )
+    }
+}
+
+/// Read ComicInfo.xml out of an existing book.
+fn read_comic_info(path: &Path) -> Result<Option<String>> {
+    let file = File::open(path).with_context(|| anyhow!("Failed to open {}", path.display()))?;
+
+    let mut archive = ZipArchive::new(file)
+        .with_context(|| anyhow!("{}: Failed to open archive", path.display()))?;
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+
+        if file.name().eq_ignore_ascii_case("ComicInfo.xml") {
+            let mut contents = String::new();
+            file.read_to_string(&mut contents)?;
+            return Ok(Some(contents));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Extract the unescaped Notes field of ComicInfo.xml.
+pub(super) fn notes(comic_info: &str) -> Option<String> {
+    let (_, rest) = comic_info.split_once("<Notes>")?;
+    let (notes, _) = rest.split_once("</Notes>")?;
+
+    Some(
+        notes
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&"),
+    )
+}
+
+/// Hash the names and sizes of the pages of a book, which changes if pages
+/// are added, removed, renamed or replaced with ones of a different size.
+fn source_hash(book: &Book) -> String {
+    let mut hasher = Sha256::new();
+
+    for page in &book.pages {
+        let path = page.path.strip_prefix(&book.dir).unwrap_or(&page.path);
+        hasher.update(path.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        hasher.update(page.size.to_le_bytes());
+    }
+
+    let hash = hex(&hasher.finalize());
+    hash[..16].to_string()
+}
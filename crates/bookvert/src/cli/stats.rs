@@ -0,0 +1,106 @@
+use core::cmp::Reverse;
+
+use std::collections::{BTreeMap, HashSet};
+use std::rc::Rc;
+
+use anyhow::Result;
+use mediavert_core::report;
+use termcolor::WriteColor;
+
+use super::{Colors, escape};
+use crate::{Book, State};
+
+/// The number of largest books which are listed.
+const LARGEST: usize = 10;
+
+/// The number and total size of pages in a group.
+#[derive(Default)]
+struct Count {
+    pages: u64,
+    bytes: u64,
+}
+
+/// Report the shape of the scanned collection without writing anything.
+pub(super) fn entry(o: &mut impl WriteColor, state: &State) -> Result<()> {
+    let Colors { ok, .. } = Colors::new();
+
+    // Books can be candidates in more than one catalog, but are only counted
+    // once.
+    let mut seen = HashSet::new();
+    let mut books = Vec::<&Rc<Book>>::new();
+    let mut candidates = BTreeMap::<usize, usize>::new();
+
+    for catalog in &state.catalogs {
+        *candidates.entry(catalog.books.len()).or_default() += 1;
+
+        for book in &catalog.books {
+            if seen.insert(Rc::as_ptr(book)) {
+                books.push(book);
+            }
+        }
+    }
+
+    let mut total = Count::default();
+    let mut formats = BTreeMap::<String, Count>::new();
+
+    for page in books.iter().flat_map(|b| &b.pages) {
+        let ext = page
+            .name
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_ascii_lowercase())
+            .unwrap_or_default();
+
+        let count = formats.entry(ext).or_default();
+        count.pages += 1;
+        count.bytes += page.size;
+        total.pages += 1;
+        total.bytes += page.size;
+    }
+
+    writeln!(o, "Series names: {}", state.names.len())?;
+    writeln!(o, "Catalogs: {}", state.catalogs.len())?;
+    writeln!(o, "Books: {}", books.len())?;
+    writeln!(o, "Pages: {} ({})", total.pages, report::size(total.bytes))?;
+
+    o.set_color(&ok)?;
+    write!(o, "[candidates]")?;
+    o.reset()?;
+    writeln!(o, " books per catalog")?;
+
+    for (count, catalogs) in &candidates {
+        writeln!(o, "  {count}: {catalogs} catalogs")?;
+    }
+
+    o.set_color(&ok)?;
+    write!(o, "[formats]")?;
+    o.reset()?;
+    writeln!(o, " pages per image format")?;
+
+    for (ext, count) in &formats {
+        writeln!(
+            o,
+            "  {ext}: {} pages, {}",
+            count.pages,
+            report::size(count.bytes)
+        )?;
+    }
+
+    books.sort_by_key(|b| Reverse(b.bytes()));
+
+    o.set_color(&ok)?;
+    write!(o, "[largest]")?;
+    o.reset()?;
+    writeln!(o, " books by size")?;
+
+    for book in books.iter().take(LARGEST) {
+        writeln!(
+            o,
+            "  {}: {} ({} pages)",
+            report::size(book.bytes()),
+            escape(&book.name),
+            book.pages.len()
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,150 @@
+use std::fs::File;
+use std::io::{Cursor, Read as _, Write as _};
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+use termcolor::{ColorChoice, StandardStream, WriteColor};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use super::{
+    Bookvert, Colors, ComicInfo, book_names, config_info, existing_books, name_overrides, page_ext,
+    split_number, stamp,
+};
+
+/// The name of the metadata file inside of a book.
+const COMIC_INFO: &str = "ComicInfo.xml";
+
+/// Rewrite or insert ComicInfo.xml in existing books.
+pub(super) fn entry(opts: &Bookvert, color: ColorChoice) -> Result<()> {
+    let Colors { ok, warn, .. } = Colors::new();
+
+    let o = StandardStream::stdout(color);
+    let mut o = o.lock();
+
+    let name_overrides = name_overrides(opts);
+
+    for path in existing_books(opts)? {
+        let Some((stem_name, number)) = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(split_number)
+        else {
+            o.set_color(&warn)?;
+            write!(o, "[skip]")?;
+            o.reset()?;
+            writeln!(o, " {} (no number in file name)", path.display())?;
+            continue;
+        };
+
+        let name = opts.name.as_deref().unwrap_or(stem_name);
+
+        let name_override = name_overrides.get(&number).map(|name| name.to_string());
+        let (_, title) = book_names(name, number, name_override);
+
+        let file =
+            File::open(&path).with_context(|| anyhow!("Failed to open {}", path.display()))?;
+        let mut archive = ZipArchive::new(file)
+            .with_context(|| anyhow!("{}: Failed to open archive", path.display()))?;
+
+        let mut page_count = 0;
+        let mut existing = None;
+
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+
+            if file.name().eq_ignore_ascii_case(COMIC_INFO) {
+                let mut contents = String::new();
+                file.read_to_string(&mut contents)?;
+                existing = Some(contents);
+                continue;
+            }
+
+            let ext = Path::new(file.name()).extension().and_then(|e| e.to_str());
+
+            if !file.is_dir() && ext.and_then(page_ext).is_some() {
+                page_count += 1;
+            }
+        }
+
+        // Notes like the stamp of the book are kept, since they can't be
+        // recreated without its sources.
+        let notes = existing.as_deref().and_then(stamp::notes);
+
+        let comic_info = config_info(
+            opts,
+            ComicInfo {
+                name,
+                title: &title,
+                number: Some(number),
+                page_count,
+                language: opts.language.as_ref(),
+                typed: &[],
+                notes: notes.as_deref(),
+            },
+        )
+        .context("ComicInfo.xml generation")?;
+
+        if existing.as_deref() == Some(comic_info.as_str()) {
+            o.set_color(&ok)?;
+            write!(o, "[same]")?;
+            o.reset()?;
+            writeln!(o, " {}", path.display())?;
+            continue;
+        }
+
+        if opts.is_dry_run() {
+            o.set_color(&warn)?;
+            write!(o, "[dry-run]")?;
+        } else {
+            o.set_color(&ok)?;
+            write!(o, "[update]")?;
+        }
+
+        o.reset()?;
+        writeln!(o, " {:03}: {}", number, path.display())?;
+
+        if opts.verbosity().is_detailed() {
+            for line in comic_info.lines() {
+                writeln!(o, "    {line}")?;
+            }
+        }
+
+        if opts.is_dry_run() {
+            continue;
+        }
+
+        let mut w = ZipWriter::new(Cursor::new(Vec::new()));
+
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Stored)
+            .unix_permissions(0o755);
+
+        w.start_file(COMIC_INFO, options)?;
+        w.write_all(comic_info.as_bytes())?;
+
+        for i in 0..archive.len() {
+            let file = archive.by_index_raw(i)?;
+
+            if file.name().eq_ignore_ascii_case(COMIC_INFO) {
+                continue;
+            }
+
+            w.raw_copy_file(file)?;
+        }
+
+        let out = w.finish()?.into_inner();
+        drop(archive);
+
+        let part = path.with_added_extension("part");
+
+        opts.effects()
+            .write(&part, out)
+            .with_context(|| anyhow!("Failed to write file {}", part.display()))?;
+        opts.effects()
+            .rename(&part, &path)
+            .with_context(|| anyhow!("Failed to replace file {}", path.display()))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use std::collections::BTreeMap;
+
+use mediavert_core::config::Shared;
+use mediavert_core::plugin::Plugin;
+use serde::Deserialize;
+
+use crate::profile::Profile;
+
+/// The configuration file shared by mediavert tools.
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub(crate) struct Config {
+    /// Settings shared by all tools.
+    #[serde(flatten)]
+    pub(crate) shared: Shared,
+    /// Configuration specific to bookvert.
+    pub(crate) bookvert: BookvertConfig,
+}
+
+impl Config {
+    /// The shared settings, where settings in the `[bookvert]` section take
+    /// precedence.
+    pub(crate) fn shared(&self) -> Shared {
+        self.bookvert.shared.clone().or(self.shared.clone())
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub(crate) struct BookvertConfig {
+    /// Shared settings which only apply to bookvert.
+    #[serde(flatten)]
+    pub(crate) shared: Shared,
+    /// The color theme of the interactive interface.
+    pub(crate) theme: ThemeConfig,
+    /// Plugins used to extract pages from files by extension.
+    pub(crate) plugins: BTreeMap<String, Plugin>,
+    /// Device profiles which can be selected with `--profile`.
+    pub(crate) profiles: BTreeMap<String, Profile>,
+}
+
+/// Theme configuration, where colors are specified by name like `red` or
+/// `light-blue`, by index like `42`, or in hex like `#ff0000`.
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub(crate) struct ThemeConfig {
+    /// The built-in theme to base the configuration on.
+    pub(crate) base: Option<String>,
+    pub(crate) selected_marker: Option<String>,
+    pub(crate) done_marker: Option<String>,
+    pub(crate) editing_marker: Option<String>,
+    pub(crate) done: Option<String>,
+    pub(crate) normal: Option<String>,
+    pub(crate) not_done: Option<String>,
+    pub(crate) dim: Option<String>,
+    pub(crate) header: Option<String>,
+    pub(crate) editing: Option<String>,
+    pub(crate) warning: Option<String>,
+}
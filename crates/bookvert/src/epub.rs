@@ -0,0 +1,373 @@
+use core::fmt::{self, Write as _};
+use core::str::FromStr;
+
+use std::io::{Cursor, Write};
+use std::time::SystemTime;
+
+use anyhow::{Context, Error, Result, anyhow};
+use image::ImageReader;
+use jiff::Timestamp;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::cli::xml_escape;
+use crate::pipeline::OutputPage;
+use crate::state::PageType;
+
+/// Style shared by all pages, which makes the image fill the page.
+const STYLE: &str = "\
+html, body { margin: 0; padding: 0; width: 100%; height: 100%; }
+img { display: block; width: 100%; height: 100%; object-fit: contain; }
+div#book-inner { margin-top: 0; margin-bottom: 0; }
+";
+
+/// The container format books are written in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BookFormat {
+    /// A zip archive of pages with a `ComicInfo.xml`.
+    #[default]
+    Cbz,
+    /// A fixed-layout EPUB with one page per image.
+    Epub,
+    /// A fixed-layout EPUB with the markup Kobo readers use to track reading
+    /// progress.
+    Kepub,
+}
+
+impl BookFormat {
+    /// The extension of books in this format.
+    pub(crate) fn ext(&self) -> &'static str {
+        match self {
+            BookFormat::Cbz => "cbz",
+            BookFormat::Epub => "epub",
+            BookFormat::Kepub => "kepub.epub",
+        }
+    }
+}
+
+impl FromStr for BookFormat {
+    type Err = Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "cbz" => Ok(BookFormat::Cbz),
+            "epub" => Ok(BookFormat::Epub),
+            "kepub" => Ok(BookFormat::Kepub),
+            _ => Err(anyhow!("Invalid book format '{s}'")),
+        }
+    }
+}
+
+impl fmt::Display for BookFormat {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BookFormat::Cbz => write!(f, "cbz"),
+            BookFormat::Epub => write!(f, "epub"),
+            BookFormat::Kepub => write!(f, "kepub"),
+        }
+    }
+}
+
+/// Metadata of a book written as an EPUB.
+pub(crate) struct Metadata<'a> {
+    pub(crate) title: &'a str,
+    pub(crate) series: &'a str,
+    pub(crate) number: u32,
+    pub(crate) author: Option<&'a str>,
+    pub(crate) publisher: Option<&'a str>,
+    pub(crate) language: Option<String>,
+    pub(crate) summary: Option<&'a str>,
+    pub(crate) rtl: bool,
+    /// When the sources of the book were last modified, which is used instead
+    /// of the current time so that writing the same book twice produces the
+    /// same file.
+    pub(crate) modified: SystemTime,
+}
+
+/// A page which has been added to the book.
+struct Item {
+    image: String,
+    media_type: &'static str,
+}
+
+/// Write pages into a fixed-layout EPUB, where `kobo` adds the markup used by
+/// Kobo readers.
+///
+/// Pages marked as deleted are left out, since EPUB readers have no way of
+/// hiding them.
+pub(crate) fn write(pages: &[OutputPage], meta: &Metadata<'_>, kobo: bool) -> Result<Vec<u8>> {
+    let mut w = ZipWriter::new(Cursor::new(Vec::new()));
+
+    // The mimetype has to be the first entry, and can't be compressed.
+    let stored = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Stored)
+        .unix_permissions(0o755);
+
+    w.start_file("mimetype", stored)?;
+    w.write_all(b"application/epub+zip")?;
+
+    w.start_file("META-INF/container.xml", stored)?;
+    w.write_all(container().as_bytes())?;
+
+    w.start_file("OEBPS/style.css", stored)?;
+    w.write_all(STYLE.as_bytes())?;
+
+    let pages = pages
+        .iter()
+        .filter(|p| p.page_type != Some(PageType::Deleted))
+        .collect::<Vec<_>>();
+
+    let cover = pages
+        .iter()
+        .position(|p| p.page_type == Some(PageType::FrontCover))
+        .unwrap_or_default();
+
+    let mut items = Vec::with_capacity(pages.len());
+
+    for (n, page) in pages.iter().enumerate() {
+        let (_, ext) = page.stem_and_ext();
+
+        let media_type =
+            media_type(ext).with_context(|| anyhow!("{}: Unsupported image in EPUB", page.name))?;
+
+        let (width, height) = ImageReader::new(Cursor::new(&page.content))
+            .with_guessed_format()?
+            .into_dimensions()
+            .with_context(|| anyhow!("{}: Failed to read image dimensions", page.name))?;
+
+        let image = format!("image{n:04}.{ext}");
+
+        w.start_file(format!("OEBPS/images/{image}"), stored)?;
+        w.write_all(&page.content)?;
+
+        w.start_file(format!("OEBPS/page{n:04}.xhtml"), stored)?;
+        w.write_all(page_xhtml(meta.title, &image, width, height, kobo)?.as_bytes())?;
+
+        items.push(Item { image, media_type });
+    }
+
+    w.start_file("OEBPS/nav.xhtml", stored)?;
+    w.write_all(nav(meta.title, !items.is_empty())?.as_bytes())?;
+
+    w.start_file("OEBPS/content.opf", stored)?;
+    w.write_all(package(meta, &items, cover)?.as_bytes())?;
+
+    Ok(w.finish()?.into_inner())
+}
+
+fn container() -> &'static str {
+    concat!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n",
+        "<container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">\n",
+        "  <rootfiles>\n",
+        "    <rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\" />\n",
+        "  </rootfiles>\n",
+        "</container>\n",
+    )
+}
+
+fn package(meta: &Metadata<'_>, items: &[Item], cover: usize) -> Result<String> {
+    let mut o = String::new();
+
+    let modified = Timestamp::try_from(meta.modified)
+        .unwrap_or(Timestamp::UNIX_EPOCH)
+        .strftime("%Y-%m-%dT%H:%M:%SZ");
+
+    let series = xml_escape(meta.series);
+
+    writeln!(o, "<?xml version=\"1.0\" encoding=\"utf-8\"?>")?;
+    writeln!(
+        o,
+        "<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"id\" prefix=\"rendition: http://www.idpf.org/vocab/rendition/#\">"
+    )?;
+    writeln!(
+        o,
+        "  <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\" xmlns:opf=\"http://www.idpf.org/2007/opf\">"
+    )?;
+    writeln!(
+        o,
+        "    <dc:identifier id=\"id\">bookvert:{series}:{}</dc:identifier>",
+        meta.number
+    )?;
+    writeln!(o, "    <dc:title>{}</dc:title>", xml_escape(meta.title))?;
+
+    let language = meta.language.as_deref().unwrap_or("und");
+    writeln!(o, "    <dc:language>{}</dc:language>", xml_escape(language))?;
+
+    if let Some(author) = meta.author {
+        writeln!(o, "    <dc:creator>{}</dc:creator>", xml_escape(author))?;
+    }
+
+    if let Some(publisher) = meta.publisher {
+        writeln!(
+            o,
+            "    <dc:publisher>{}</dc:publisher>",
+            xml_escape(publisher)
+        )?;
+    }
+
+    if let Some(summary) = meta.summary {
+        writeln!(
+            o,
+            "    <dc:description>{}</dc:description>",
+            xml_escape(summary)
+        )?;
+    }
+
+    writeln!(
+        o,
+        "    <meta property=\"dcterms:modified\">{modified}</meta>"
+    )?;
+    writeln!(
+        o,
+        "    <meta property=\"belongs-to-collection\" id=\"series\">{series}</meta>"
+    )?;
+    writeln!(
+        o,
+        "    <meta refines=\"#series\" property=\"collection-type\">series</meta>"
+    )?;
+    writeln!(
+        o,
+        "    <meta refines=\"#series\" property=\"group-position\">{}</meta>",
+        meta.number
+    )?;
+    // Kobo readers pick up series from the metadata written by calibre.
+    writeln!(
+        o,
+        "    <meta name=\"calibre:series\" content=\"{series}\" />"
+    )?;
+    writeln!(
+        o,
+        "    <meta name=\"calibre:series_index\" content=\"{}\" />",
+        meta.number
+    )?;
+    writeln!(
+        o,
+        "    <meta property=\"rendition:layout\">pre-paginated</meta>"
+    )?;
+    writeln!(o, "    <meta property=\"rendition:spread\">none</meta>")?;
+
+    if !items.is_empty() {
+        writeln!(o, "    <meta name=\"cover\" content=\"image{cover:04}\" />")?;
+    }
+
+    writeln!(o, "  </metadata>")?;
+    writeln!(o, "  <manifest>")?;
+    writeln!(
+        o,
+        "    <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\" />"
+    )?;
+    writeln!(
+        o,
+        "    <item id=\"style\" href=\"style.css\" media-type=\"text/css\" />"
+    )?;
+
+    for (n, item) in items.iter().enumerate() {
+        let properties = if n == cover {
+            " properties=\"cover-image\""
+        } else {
+            ""
+        };
+
+        writeln!(
+            o,
+            "    <item id=\"image{n:04}\" href=\"images/{}\" media-type=\"{}\"{properties} />",
+            item.image, item.media_type
+        )?;
+        writeln!(
+            o,
+            "    <item id=\"page{n:04}\" href=\"page{n:04}.xhtml\" media-type=\"application/xhtml+xml\" />"
+        )?;
+    }
+
+    writeln!(o, "  </manifest>")?;
+
+    let direction = if meta.rtl { "rtl" } else { "ltr" };
+    writeln!(o, "  <spine page-progression-direction=\"{direction}\">")?;
+
+    for n in 0..items.len() {
+        writeln!(o, "    <itemref idref=\"page{n:04}\" />")?;
+    }
+
+    writeln!(o, "  </spine>")?;
+    writeln!(o, "</package>")?;
+    Ok(o)
+}
+
+fn nav(title: &str, has_pages: bool) -> Result<String> {
+    let mut o = String::new();
+    let title = xml_escape(title);
+
+    writeln!(o, "<?xml version=\"1.0\" encoding=\"utf-8\"?>")?;
+    writeln!(o, "<!DOCTYPE html>")?;
+    writeln!(
+        o,
+        "<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\">"
+    )?;
+    writeln!(o, "<head><title>{title}</title></head>")?;
+    writeln!(o, "<body>")?;
+    writeln!(o, "  <nav epub:type=\"toc\">")?;
+    writeln!(o, "    <ol>")?;
+
+    if has_pages {
+        writeln!(o, "      <li><a href=\"page0000.xhtml\">{title}</a></li>")?;
+    }
+
+    writeln!(o, "    </ol>")?;
+    writeln!(o, "  </nav>")?;
+    writeln!(o, "</body>")?;
+    writeln!(o, "</html>")?;
+    Ok(o)
+}
+
+fn page_xhtml(title: &str, image: &str, width: u32, height: u32, kobo: bool) -> Result<String> {
+    let mut o = String::new();
+
+    writeln!(o, "<?xml version=\"1.0\" encoding=\"utf-8\"?>")?;
+    writeln!(o, "<!DOCTYPE html>")?;
+    writeln!(
+        o,
+        "<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\">"
+    )?;
+    writeln!(o, "<head>")?;
+    writeln!(o, "  <title>{}</title>", xml_escape(title))?;
+    writeln!(
+        o,
+        "  <meta name=\"viewport\" content=\"width={width}, height={height}\" />"
+    )?;
+    writeln!(
+        o,
+        "  <link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\" />"
+    )?;
+    writeln!(o, "</head>")?;
+    writeln!(o, "<body>")?;
+
+    let img = format!("<img src=\"images/{image}\" alt=\"\" />");
+
+    if kobo {
+        // Kobo readers count reading progress and turn pages by the spans
+        // that kepubify and the Kobo store wrap content in.
+        writeln!(o, "<div id=\"book-columns\"><div id=\"book-inner\">")?;
+        writeln!(o, "  <span class=\"koboSpan\" id=\"kobo.1.1\">{img}</span>")?;
+        writeln!(o, "</div></div>")?;
+    } else {
+        writeln!(o, "  {img}")?;
+    }
+
+    writeln!(o, "</body>")?;
+    writeln!(o, "</html>")?;
+    Ok(o)
+}
+
+/// The media type of an image with the given extension.
+fn media_type(ext: &str) -> Option<&'static str> {
+    match ext.to_ascii_lowercase().as_str() {
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "png" => Some("image/png"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
@@ -2,17 +2,21 @@ use std::path::Path;
 
 use anyhow::Result;
 use ratatui::Frame;
-use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
-use ratatui::layout::{Constraint, Layout};
+use ratatui::crossterm::event::{self, Event, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{
-    List, ListItem, ListState, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState,
+    Block, Clear, List, ListItem, ListState, Paragraph, Scrollbar, ScrollbarOrientation,
+    ScrollbarState,
 };
 use tui_input::Input;
 use tui_input::backend::crossterm::EventHandler;
 
-use crate::state::State;
-use crate::styles::STYLES;
+use crate::cli::To;
+use crate::keymap::{self, Action, Keymap};
+use crate::preview::{self, BookStats};
+use crate::state::{Catalog, PageType, State};
+use crate::styles::Styles;
 
 enum ViewEvent {
     PushView(View),
@@ -38,18 +42,16 @@ impl Default for CatalogsView {
 
 impl CatalogsView {
     fn update(&mut self, key: KeyEvent, state: &mut State) -> ViewEvent {
-        use KeyCode::{Backspace, Char, Down, Enter, Esc, Right, Up};
-
         let max_index = state.catalogs.len().saturating_add(1);
 
-        match key.code {
-            Up | Char('k') => {
+        match keymap::CATALOGS.action(key.code) {
+            Some(Action::Up) => {
                 self.index = self.index.saturating_sub(1);
             }
-            Down | Char('j') => {
+            Some(Action::Down) => {
                 self.index = self.index.saturating_add(1).min(max_index);
             }
-            Right | Enter | Char('l' | 'o' | ' ') => {
+            Some(Action::Open) => {
                 if self.index == 0 {
                     let n = state.picked();
 
@@ -72,13 +74,16 @@ impl CatalogsView {
                     return ViewEvent::PushView(View::Books(BooksView::new(category, index)));
                 }
             }
-            Esc | Char('q') => {
+            Some(Action::Back) => {
                 return ViewEvent::PopView;
             }
-            Char('x') => {
+            Some(Action::Bulk) => {
+                return ViewEvent::PushView(View::Bulk(BulkView::default()));
+            }
+            Some(Action::Finish) => {
                 return ViewEvent::Finish;
             }
-            Backspace | Char('c') if self.index >= 2 => {
+            Some(Action::Clear) if self.index >= 2 => {
                 let category = self.index.saturating_sub(2);
 
                 if let Some(c) = state.catalogs.get_mut(category) {
@@ -91,7 +96,7 @@ impl CatalogsView {
         ViewEvent::None
     }
 
-    fn draw(&mut self, state: &State, frame: &mut Frame) {
+    fn draw(&mut self, state: &State, styles: &Styles, frame: &mut Frame) {
         let mut selected = None;
 
         let sub_header = {
@@ -100,8 +105,8 @@ impl CatalogsView {
             let total_count = state.catalogs.len();
             let all_picked = picked_count == total_count;
 
-            let marker = STYLES.selected(is_selected);
-            let style = STYLES.normal_item_style(is_selected, all_picked);
+            let marker = styles.selected(is_selected);
+            let style = styles.normal_item_style(is_selected, all_picked);
 
             Line::from(vec![
                 Span::styled(format!("{marker} "), style),
@@ -116,14 +121,14 @@ impl CatalogsView {
             let is_selected = self.index == 1;
             let has_name = state.name.is_some();
 
-            let marker = STYLES.selected(is_selected);
-            let style = STYLES.item_style(is_selected, has_name);
+            let marker = styles.selected(is_selected);
+            let style = styles.item_style(is_selected, has_name);
 
             let name_display = state
                 .name
                 .as_deref()
                 .map(|n| format!("Name: {}", n))
-                .unwrap_or_else(|| format!("Name: {}", STYLES.no_name()));
+                .unwrap_or_else(|| format!("Name: {}", styles.no_name()));
 
             Line::from(vec![
                 Span::styled(format!("{marker} "), style),
@@ -141,8 +146,8 @@ impl CatalogsView {
                 selected = Some(items.len());
             }
 
-            let marker = STYLES.selected(is_selected);
-            let style = STYLES.item_style(is_selected, is_picked);
+            let marker = styles.selected(is_selected);
+            let style = styles.item_style(is_selected, is_picked);
 
             let picked_info = if let Some(picked) = catalog.picked {
                 if let Some(book) = catalog.books.get(picked) {
@@ -160,7 +165,7 @@ impl CatalogsView {
             )]);
 
             if is_picked {
-                line.push_span(format!(" {}", STYLES.done()));
+                line.push_span(format!(" {}", styles.done()));
             }
 
             line.push_span(Span::styled(
@@ -169,7 +174,7 @@ impl CatalogsView {
                     catalog.books.len(),
                     pluralize(catalog.books.len(), "book", "books")
                 ),
-                STYLES.dim_style(),
+                styles.dim_style(),
             ));
 
             items.push(ListItem::new(line));
@@ -185,10 +190,10 @@ impl CatalogsView {
             .position(self.list_state.selected().unwrap_or_default());
 
         let header = Line::from(vec![
-            Span::styled("Catalogs", STYLES.header_style()),
+            Span::styled("Catalogs", styles.header_style()),
             Span::styled(
-                " (Enter/o/→ to select, Delete/c to clear, Esc/q to quit)",
-                STYLES.header_hint_style(),
+                keymap::CATALOGS.hint(&keymap::GLOBAL),
+                styles.header_hint_style(),
             ),
         ]);
 
@@ -197,7 +202,7 @@ impl CatalogsView {
 
         let separator = Line::from(Span::styled(
             "─".repeat(frame.area().width as usize),
-            STYLES.dim_style(),
+            styles.dim_style(),
         ));
 
         let area = frame.area();
@@ -223,6 +228,8 @@ impl CatalogsView {
 struct BooksView {
     category: usize,
     index: usize,
+    /// Books marked for comparison.
+    marked: Vec<usize>,
     list_state: ListState,
 }
 
@@ -231,18 +238,17 @@ impl BooksView {
         Self {
             category,
             index,
+            marked: Vec::new(),
             list_state: ListState::default(),
         }
     }
 
     fn update(&mut self, key: KeyEvent, state: &mut State) -> ViewEvent {
-        use KeyCode::{Char, Down, Enter, Esc, Left, Up};
-
-        match key.code {
-            Up | Char('k') => {
+        match keymap::BOOKS.action(key.code) {
+            Some(Action::Up) => {
                 self.index = self.index.saturating_sub(1);
             }
-            Down | Char('j') => {
+            Some(Action::Down) => {
                 if let Some(catalog) = state.catalogs.get(self.category) {
                     self.index = self
                         .index
@@ -250,23 +256,56 @@ impl BooksView {
                         .min(catalog.books.len().saturating_sub(1));
                 }
             }
-            Left | Char('h') | Esc | Char('q') => {
+            Some(Action::Back) => {
                 return ViewEvent::PopView;
             }
-            Enter | Char('o') => {
+            Some(Action::Open) => {
                 if let Some(c) = state.catalogs.get_mut(self.category) {
                     c.picked = Some(self.index);
                 }
 
                 return ViewEvent::PopAndSelectNext;
             }
+            Some(Action::Mark) => {
+                if let Some(n) = self.marked.iter().position(|&i| i == self.index) {
+                    self.marked.remove(n);
+                } else {
+                    // Only two books can be compared, so marking a third book
+                    // replaces the oldest mark.
+                    if self.marked.len() == 2 {
+                        self.marked.remove(0);
+                    }
+
+                    self.marked.push(self.index);
+                }
+            }
+            Some(Action::Compare) => {
+                // Compare the marked books, or the marked book against the
+                // highlighted one.
+                let (a, b) = match self.marked[..] {
+                    [a, b] => (a, b),
+                    [a] if a != self.index => (a, self.index),
+                    _ => return ViewEvent::None,
+                };
+
+                if let Some(catalog) = state.catalogs.get(self.category) {
+                    return ViewEvent::PushView(View::Compare(CompareView::new(
+                        catalog,
+                        self.category,
+                        [a, b],
+                    )));
+                }
+            }
+            Some(Action::Pages) => {
+                return ViewEvent::PushView(View::Pages(PagesView::new(self.category, self.index)));
+            }
             _ => {}
         }
 
         ViewEvent::None
     }
 
-    fn draw(&mut self, state: &State, frame: &mut Frame) {
+    fn draw(&mut self, state: &State, styles: &Styles, frame: &mut Frame) {
         let Some(catalog) = state.catalogs.get(self.category) else {
             return;
         };
@@ -282,29 +321,32 @@ impl BooksView {
                 selected = Some(items.len());
             }
 
-            let marker = STYLES.selected(is_selected);
-            let style = STYLES.normal_item_style(is_selected, is_picked);
+            let marker = styles.selected(is_selected);
+            let style = styles.normal_item_style(is_selected, is_picked);
 
             let dir = book.dir.parent().unwrap_or(Path::new("."));
 
-            items.push(ListItem::new(Span::styled(
-                format!("{marker} {}", book.name),
-                style,
-            )));
+            let mut line = Line::from(Span::styled(format!("{marker} {}", book.name), style));
+
+            if self.marked.contains(&i) {
+                line.push_span(Span::styled(" (marked)", styles.header_hint_style()));
+            }
+
+            items.push(ListItem::new(line));
 
             items.push(ListItem::new(Span::styled(
                 format!("    pages: {}", book.pages.len()),
-                STYLES.dim_style(),
+                styles.dim_style(),
             )));
 
             items.push(ListItem::new(Span::styled(
                 format!("    bytes: {}", book.bytes()),
-                STYLES.dim_style(),
+                styles.dim_style(),
             )));
 
             items.push(ListItem::new(Span::styled(
                 format!("    from {}", dir.display()),
-                STYLES.dim_style(),
+                styles.dim_style(),
             )));
         }
 
@@ -315,10 +357,10 @@ impl BooksView {
 
         let line = format!("Catalog {:03} - Select book", catalog.number);
         let line = Line::from(vec![
-            Span::styled(line, STYLES.header_style()),
+            Span::styled(line, styles.header_style()),
             Span::styled(
-                " (Enter/o to pick, Esc/q/← to go back, i/I to show paths)",
-                STYLES.header_hint_style(),
+                keymap::BOOKS.hint(&keymap::GLOBAL),
+                styles.header_hint_style(),
             ),
         ]);
 
@@ -334,100 +376,420 @@ impl BooksView {
     }
 }
 
-struct NameView {
+struct CompareView {
+    category: usize,
+    books: [usize; 2],
+    stats: [BookStats; 2],
+    focus: usize,
+}
+
+impl CompareView {
+    fn new(catalog: &Catalog, category: usize, books: [usize; 2]) -> Self {
+        let stats = books.map(|i| BookStats::new(&catalog.books[i]));
+
+        Self {
+            category,
+            books,
+            stats,
+            focus: 0,
+        }
+    }
+
+    fn update(&mut self, key: KeyEvent, state: &mut State) -> ViewEvent {
+        match keymap::COMPARE.action(key.code) {
+            Some(Action::Left) => {
+                self.focus = 0;
+            }
+            Some(Action::Right) => {
+                self.focus = 1;
+            }
+            Some(Action::Back) => {
+                return ViewEvent::PopView;
+            }
+            Some(Action::Open) => {
+                if let Some(c) = state.catalogs.get_mut(self.category) {
+                    c.picked = Some(self.books[self.focus]);
+                }
+
+                return ViewEvent::PopAndSelectNext;
+            }
+            _ => {}
+        }
+
+        ViewEvent::None
+    }
+
+    fn draw(&mut self, state: &State, styles: &Styles, frame: &mut Frame) {
+        let Some(catalog) = state.catalogs.get(self.category) else {
+            return;
+        };
+
+        let header = Line::from(vec![
+            Span::styled(
+                format!("Catalog {:03} - Compare books", catalog.number),
+                styles.header_style(),
+            ),
+            Span::styled(
+                keymap::COMPARE.hint(&keymap::GLOBAL),
+                styles.header_hint_style(),
+            ),
+        ]);
+
+        let area = frame.area();
+        let layout = Layout::vertical([Constraint::Length(1), Constraint::Min(1)]).split(area);
+        let columns = Layout::horizontal([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
+            .spacing(1)
+            .split(layout[1]);
+
+        frame.render_widget(header, layout[0]);
+
+        for (n, (&index, stats)) in self.books.iter().zip(&self.stats).enumerate() {
+            let Some(book) = catalog.books.get(index) else {
+                continue;
+            };
+
+            let is_selected = n == self.focus;
+            let is_picked = catalog.picked == Some(index);
+            let marker = styles.selected(is_selected);
+            let style = styles.normal_item_style(is_selected, is_picked);
+
+            let dir = book.dir.parent().unwrap_or(Path::new("."));
+
+            let resolution = match stats.resolution {
+                Some((w, h)) => format!("{w}x{h}"),
+                None => String::from("unknown"),
+            };
+
+            let mut lines = vec![
+                Line::from(Span::styled(format!("{marker} {}", book.name), style)),
+                Line::from(Span::styled(
+                    format!("    pages: {}", stats.pages),
+                    styles.dim_style(),
+                )),
+                Line::from(Span::styled(
+                    format!("    bytes: {}", stats.bytes),
+                    styles.dim_style(),
+                )),
+                Line::from(Span::styled(
+                    format!("    average resolution: {resolution}"),
+                    styles.dim_style(),
+                )),
+                Line::from(Span::styled(
+                    format!("    from {}", dir.display()),
+                    styles.dim_style(),
+                )),
+                Line::default(),
+            ];
+
+            let column = columns[n];
+            let rows = column.height.saturating_sub(lines.len() as u16);
+
+            match &stats.preview {
+                Some(image) => lines.extend(preview::render(image, column.width, rows)),
+                None => lines.push(Line::from(Span::styled("(no preview)", styles.dim_style()))),
+            }
+
+            frame.render_widget(Paragraph::new(lines), column);
+        }
+    }
+}
+
+struct PagesView {
+    category: usize,
+    book: usize,
     index: usize,
-    input: Input,
-    editing: bool,
     list_state: ListState,
 }
 
-impl NameView {
-    fn new(current_name: Option<&str>) -> Self {
+impl PagesView {
+    fn new(category: usize, book: usize) -> Self {
         Self {
+            category,
+            book,
             index: 0,
-            input: Input::new(current_name.unwrap_or_default().to_string()),
-            editing: false,
             list_state: ListState::default(),
         }
     }
 
     fn update(&mut self, key: KeyEvent, state: &mut State) -> ViewEvent {
-        use KeyCode::{Char, Down, Enter, Esc, Left, Up};
-
-        let editing = self.editing && self.index == 0;
+        let Some(book) = state
+            .catalogs
+            .get(self.category)
+            .and_then(|c| c.books.get(self.book))
+        else {
+            return ViewEvent::PopView;
+        };
 
-        match key.code {
-            Up if !editing => {
+        match keymap::PAGES.action(key.code) {
+            Some(Action::Up) => {
                 self.index = self.index.saturating_sub(1);
             }
-            Char('k') if !editing => {
-                self.index = self.index.saturating_sub(1);
+            Some(Action::Down) => {
+                self.index = self
+                    .index
+                    .saturating_add(1)
+                    .min(book.pages.len().saturating_sub(1));
             }
-            Down if !editing => {
-                self.index = self.index.saturating_add(1).min(state.names.len());
+            Some(Action::Cycle) => {
+                if let Some(page) = book.pages.get(self.index) {
+                    let current = page.page_type.get();
+
+                    let next = PageType::CYCLE
+                        .iter()
+                        .position(|t| *t == current)
+                        .map_or(0, |n| (n + 1) % PageType::CYCLE.len());
+
+                    page.page_type.set(PageType::CYCLE[next]);
+                }
             }
-            Char('j') if !editing => {
-                self.index = self.index.saturating_add(1).min(state.names.len());
+            Some(Action::Clear) => {
+                if let Some(page) = book.pages.get(self.index) {
+                    page.page_type.set(None);
+                }
+            }
+            Some(Action::Back) => {
+                return ViewEvent::PopView;
+            }
+            _ => {}
+        }
+
+        ViewEvent::None
+    }
+
+    fn draw(&mut self, state: &State, styles: &Styles, frame: &mut Frame) {
+        let Some(catalog) = state.catalogs.get(self.category) else {
+            return;
+        };
+
+        let Some(book) = catalog.books.get(self.book) else {
+            return;
+        };
+
+        let mut items = Vec::new();
+
+        for (i, page) in book.pages.iter().enumerate() {
+            let is_selected = i == self.index;
+            let marker = styles.selected(is_selected);
+            let style = styles.normal_item_style(is_selected, false);
+
+            let file_name = page
+                .path
+                .file_name()
+                .unwrap_or(page.path.as_os_str())
+                .to_string_lossy();
+
+            let mut line = Line::from(Span::styled(format!("{marker} {file_name}"), style));
+
+            if let Some(page_type) = page.page_type.get() {
+                line.push_span(Span::styled(
+                    format!(" ({page_type})"),
+                    styles.header_hint_style(),
+                ));
+            }
+
+            items.push(ListItem::new(line));
+        }
+
+        self.list_state.select(Some(self.index));
+
+        let mut scrollbar_state = ScrollbarState::new(items.len()).position(self.index);
+
+        let line = format!("Catalog {:03} - Pages of {}", catalog.number, book.name);
+        let line = Line::from(vec![
+            Span::styled(line, styles.header_style()),
+            Span::styled(
+                keymap::PAGES.hint(&keymap::GLOBAL),
+                styles.header_hint_style(),
+            ),
+        ]);
+
+        let list = List::new(items);
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight);
+
+        let area = frame.area();
+        let layout = Layout::vertical([Constraint::Length(1), Constraint::Min(1)]).split(area);
+
+        frame.render_widget(line, layout[0]);
+        frame.render_stateful_widget(list, layout[1], &mut self.list_state);
+        frame.render_stateful_widget(scrollbar, layout[1], &mut scrollbar_state);
+    }
+}
+
+/// Strategies available for bulk picking, and their descriptions.
+const STRATEGIES: &[(To, &str)] = &[
+    (To::First, "the first book"),
+    (To::Last, "the last book"),
+    (To::MostPages, "the book with the most pages"),
+    (To::Largest, "the largest book, usually the best quality"),
+    (To::Smallest, "the smallest book"),
+];
+
+#[derive(Default)]
+struct BulkView {
+    index: usize,
+    list_state: ListState,
+}
+
+impl BulkView {
+    fn update(&mut self, key: KeyEvent, state: &mut State) -> ViewEvent {
+        match keymap::BULK.action(key.code) {
+            Some(Action::Up) => {
+                self.index = self.index.saturating_sub(1);
             }
-            Left if !editing => {
+            Some(Action::Down) => {
+                self.index = self
+                    .index
+                    .saturating_add(1)
+                    .min(STRATEGIES.len().saturating_sub(1));
+            }
+            Some(Action::Back) => {
                 return ViewEvent::PopView;
             }
-            Char('h') if !editing => {
+            Some(Action::Open) => {
+                if let Some((to, _)) = STRATEGIES.get(self.index) {
+                    for c in &mut state.catalogs {
+                        if c.picked.is_none() {
+                            c.picked = to.pick(&c.books);
+                        }
+                    }
+                }
+
                 return ViewEvent::PopView;
             }
-            Esc => {
-                if !editing {
+            _ => {}
+        }
+
+        ViewEvent::None
+    }
+
+    fn draw(&mut self, state: &State, styles: &Styles, frame: &mut Frame) {
+        let unpicked = state.catalogs.len().saturating_sub(state.picked());
+
+        let header = Line::from(vec![
+            Span::styled(
+                format!(
+                    "Pick {unpicked} unselected {}",
+                    pluralize(unpicked, "catalog", "catalogs")
+                ),
+                styles.header_style(),
+            ),
+            Span::styled(
+                keymap::BULK.hint(&keymap::GLOBAL),
+                styles.header_hint_style(),
+            ),
+        ]);
+
+        let mut items = Vec::new();
+
+        for (i, (to, description)) in STRATEGIES.iter().enumerate() {
+            let is_selected = i == self.index;
+            let marker = styles.selected(is_selected);
+            let style = styles.normal_item_style(is_selected, false);
+
+            items.push(ListItem::new(Line::from(vec![
+                Span::styled(format!("{marker} {to}"), style),
+                Span::styled(format!(" - {description}"), styles.dim_style()),
+            ])));
+        }
+
+        self.list_state.select(Some(self.index));
+
+        let area = frame.area();
+        let layout = Layout::vertical([Constraint::Length(1), Constraint::Min(1)]).split(area);
+
+        frame.render_widget(header, layout[0]);
+        frame.render_stateful_widget(List::new(items), layout[1], &mut self.list_state);
+    }
+}
+
+struct NameView {
+    index: usize,
+    input: Input,
+    editing: bool,
+    list_state: ListState,
+}
+
+impl NameView {
+    fn new(current_name: Option<&str>) -> Self {
+        Self {
+            index: 0,
+            input: Input::new(current_name.unwrap_or_default().to_string()),
+            editing: false,
+            list_state: ListState::default(),
+        }
+    }
+
+    fn update(&mut self, key: KeyEvent, state: &mut State) -> ViewEvent {
+        if self.is_editing() {
+            match keymap::NAME_EDITING.action(key.code) {
+                Some(Action::Open) => {
+                    let trimmed = self.input.value().trim();
+
+                    state.name = if trimmed.is_empty() {
+                        None
+                    } else {
+                        Some(trimmed.to_string())
+                    };
+
                     return ViewEvent::PopView;
                 }
+                Some(Action::Back) => {
+                    self.editing = false;
+                }
+                _ => {
+                    self.input.handle_event(&Event::Key(key));
+                }
+            }
+
+            return ViewEvent::None;
+        }
 
-                self.editing = false;
+        match keymap::NAME.action(key.code) {
+            Some(Action::Up) => {
+                self.index = self.index.saturating_sub(1);
+            }
+            Some(Action::Down) => {
+                self.index = self.index.saturating_add(1).min(state.names.len());
             }
-            Char('q') if !editing => {
+            Some(Action::Back) => {
                 return ViewEvent::PopView;
             }
-            Enter | Char('o') => {
+            Some(Action::Open) => {
                 if self.index == 0 {
-                    if editing {
-                        let trimmed = self.input.value().trim();
-
-                        state.name = if trimmed.is_empty() {
-                            None
-                        } else {
-                            Some(trimmed.to_string())
-                        };
-
-                        return ViewEvent::PopView;
-                    } else {
-                        self.editing = true;
-                    }
-                } else if let Some(name) = state.names.iter().nth(self.index.saturating_sub(1)) {
+                    self.editing = true;
+                } else if let Some(name) = state.names.get(self.index.saturating_sub(1)) {
                     state.name = Some(name.clone());
                     return ViewEvent::PopView;
                 }
             }
-            _ if editing => {
-                self.input.handle_event(&Event::Key(key));
-            }
             _ => {}
         }
 
         ViewEvent::None
     }
 
-    fn draw(&mut self, state: &State, frame: &mut Frame) {
-        let editing = self.editing && self.index == 0;
+    /// Test if the custom name is being edited.
+    fn is_editing(&self) -> bool {
+        self.editing && self.index == 0
+    }
+
+    fn draw(&mut self, state: &State, styles: &Styles, frame: &mut Frame) {
+        let editing = self.is_editing();
+
+        let keymap = if editing {
+            &keymap::NAME_EDITING
+        } else {
+            &keymap::NAME
+        };
 
         let header = Line::from(vec![
-            Span::styled("Set Name", STYLES.header_style()),
-            Span::styled(
-                " (Enter to select, Esc/q/← to go back)",
-                STYLES.header_hint_style(),
-            ),
+            Span::styled("Set Name", styles.header_style()),
+            Span::styled(keymap.hint(&keymap::GLOBAL), styles.header_hint_style()),
         ]);
 
         let is_custom_selected = self.index == 0;
-        let input_marker = STYLES.input_marker(is_custom_selected, editing);
-        let input_style = STYLES.input_style(is_custom_selected, editing);
+        let input_marker = styles.input_marker(is_custom_selected, editing);
+        let input_style = styles.input_style(is_custom_selected, editing);
 
         let input_text = if self.input.value().is_empty() && !editing {
             "(enter custom name)".to_string()
@@ -442,7 +804,7 @@ impl NameView {
 
         let separator = Line::from(Span::styled(
             "─".repeat(frame.area().width as usize),
-            STYLES.dim_style(),
+            styles.dim_style(),
         ));
 
         let mut items = Vec::new();
@@ -456,8 +818,8 @@ impl NameView {
                 selected = Some(items.len());
             }
 
-            let marker = STYLES.selected(is_selected);
-            let style = STYLES.normal_item_style(is_selected, is_current);
+            let marker = styles.selected(is_selected);
+            let style = styles.normal_item_style(is_selected, is_current);
 
             items.push(ListItem::new(Span::styled(
                 format!("{marker} {name}"),
@@ -504,26 +866,24 @@ struct ConfirmView {
 
 impl ConfirmView {
     fn update(&mut self, key: KeyEvent, _state: &mut State) -> ViewEvent {
-        use KeyCode::{Char, Enter, Esc, Left, Right};
-
-        match key.code {
-            Left | Char('h') => {
+        match keymap::CONFIRM.action(key.code) {
+            Some(Action::Left) => {
                 self.selected = false;
             }
-            Right | Char('l') => {
+            Some(Action::Right) => {
                 self.selected = true;
             }
-            Enter | Char(' ') | Char('o') => {
+            Some(Action::Open) => {
                 if self.selected {
                     return ViewEvent::Finish;
                 } else {
                     return ViewEvent::PopView;
                 }
             }
-            Esc | Char('q' | 'n') => {
+            Some(Action::Back) => {
                 return ViewEvent::PopView;
             }
-            Char('y') => {
+            Some(Action::Finish) => {
                 return ViewEvent::Finish;
             }
             _ => {}
@@ -532,7 +892,7 @@ impl ConfirmView {
         ViewEvent::None
     }
 
-    fn draw(&mut self, state: &State, frame: &mut Frame) {
+    fn draw(&mut self, state: &State, styles: &Styles, frame: &mut Frame) {
         let picked_count = state.picked();
         let total_count = state.catalogs.len();
         let missing = total_count.saturating_sub(picked_count);
@@ -546,17 +906,17 @@ impl ConfirmView {
         ])
         .split(area);
 
-        let header = Line::from(vec![Span::styled("⚠ Warning", STYLES.warning_style())]);
+        let header = Line::from(vec![Span::styled("⚠ Warning", styles.warning_style())]);
 
         let message = Line::from(vec![Span::styled(
             format!("Selection incomplete: {missing} catalog(s) not selected."),
-            STYLES.warning_text_style(),
+            styles.warning_text_style(),
         )]);
 
         let prompt = Line::from("Continue anyway? ");
 
-        let no_style = STYLES.button_style(!self.selected, false);
-        let yes_style = STYLES.button_style(self.selected, true);
+        let no_style = styles.button_style(!self.selected, false);
+        let yes_style = styles.button_style(self.selected, true);
 
         let buttons = Line::from(vec![
             Span::styled("[No/n]", no_style),
@@ -574,21 +934,59 @@ impl ConfirmView {
 enum View {
     Catalogs(CatalogsView),
     Books(BooksView),
+    Bulk(BulkView),
+    Compare(CompareView),
+    Pages(PagesView),
     Name(NameView),
     Confirm(ConfirmView),
 }
 
+impl View {
+    /// Test if the view currently captures all key presses.
+    fn is_capturing(&self) -> bool {
+        matches!(self, View::Name(v) if v.is_editing())
+    }
+
+    /// The keymaps which are active in the view.
+    fn keymaps(&self) -> &'static [&'static Keymap] {
+        match self {
+            View::Catalogs(..) => &[&keymap::CATALOGS],
+            View::Books(..) => &[&keymap::BOOKS],
+            View::Bulk(..) => &[&keymap::BULK],
+            View::Compare(..) => &[&keymap::COMPARE],
+            View::Pages(..) => &[&keymap::PAGES],
+            View::Name(..) => &[&keymap::NAME, &keymap::NAME_EDITING],
+            View::Confirm(..) => &[&keymap::CONFIRM],
+        }
+    }
+}
+
 /// The interactive application of bookvert.
-#[derive(Default)]
 pub struct App {
     views: Vec<View>,
+    help: bool,
+    styles: Styles,
+    /// Snapshots of picks prior to each change, used to undo changes.
+    undo: Vec<Vec<Option<usize>>>,
 }
 
 impl App {
+    /// Construct a new interactive application using the given styles.
+    pub(crate) fn new(styles: Styles) -> Self {
+        Self {
+            views: Vec::new(),
+            help: false,
+            styles,
+            undo: Vec::new(),
+        }
+    }
+
     /// Run the interactive application.
     pub fn run(&mut self, state: &mut State) -> Result<bool> {
         self.views.clear();
         self.views.push(View::Catalogs(CatalogsView::default()));
+        self.help = false;
+        self.undo.clear();
 
         let mut terminal = ratatui::init();
 
@@ -597,11 +995,22 @@ impl App {
                 break false;
             };
 
-            terminal.draw(|frame| match view {
-                View::Catalogs(v) => v.draw(state, frame),
-                View::Books(v) => v.draw(state, frame),
-                View::Name(v) => v.draw(state, frame),
-                View::Confirm(v) => v.draw(state, frame),
+            let help = self.help;
+
+            terminal.draw(|frame| {
+                match view {
+                    View::Catalogs(v) => v.draw(state, &self.styles, frame),
+                    View::Books(v) => v.draw(state, &self.styles, frame),
+                    View::Bulk(v) => v.draw(state, &self.styles, frame),
+                    View::Compare(v) => v.draw(state, &self.styles, frame),
+                    View::Pages(v) => v.draw(state, &self.styles, frame),
+                    View::Name(v) => v.draw(state, &self.styles, frame),
+                    View::Confirm(v) => v.draw(state, &self.styles, frame),
+                }
+
+                if help {
+                    draw_help(view.keymaps(), &self.styles, frame);
+                }
             })?;
 
             let e = event::read()?;
@@ -614,13 +1023,42 @@ impl App {
                 continue;
             }
 
+            // Any key closes the help overlay.
+            if self.help {
+                self.help = false;
+                continue;
+            }
+
+            if !view.is_capturing() {
+                match keymap::GLOBAL.action(key.code) {
+                    Some(Action::Help) => {
+                        self.help = true;
+                        continue;
+                    }
+                    Some(Action::Undo) => {
+                        self.undo(state);
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+
+            let before = picks(state);
+
             let ev = match view {
                 View::Catalogs(v) => v.update(key, state),
                 View::Books(v) => v.update(key, state),
+                View::Bulk(v) => v.update(key, state),
+                View::Compare(v) => v.update(key, state),
+                View::Pages(v) => v.update(key, state),
                 View::Name(v) => v.update(key, state),
                 View::Confirm(v) => v.update(key, state),
             };
 
+            if picks(state) != before {
+                self.undo.push(before);
+            }
+
             match ev {
                 ViewEvent::PushView(view) => {
                     self.views.push(view);
@@ -629,7 +1067,7 @@ impl App {
                     self.views.pop();
                 }
                 ViewEvent::PopAndSelectNext => {
-                    self.views.pop();
+                    self.views.truncate(1);
 
                     if let Some(View::Catalogs(v)) = self.views.last_mut()
                         && let Some(category) =
@@ -649,6 +1087,94 @@ impl App {
         ratatui::restore();
         Ok(outcome)
     }
+
+    /// Undo the last change to picks.
+    ///
+    /// This returns to the catalog which was changed, and if the change was a
+    /// pick the book selection is re-opened so that another book can be
+    /// picked.
+    fn undo(&mut self, state: &mut State) {
+        let Some(picks) = self.undo.pop() else {
+            return;
+        };
+
+        let changed = picks
+            .iter()
+            .zip(&state.catalogs)
+            .position(|(p, c)| *p != c.picked);
+
+        let reopen =
+            changed.and_then(|category| Some((category, state.catalogs[category].picked?)));
+
+        for (catalog, picked) in state.catalogs.iter_mut().zip(picks) {
+            catalog.picked = picked;
+        }
+
+        let Some(category) = changed else {
+            return;
+        };
+
+        self.views.truncate(1);
+
+        if let Some(View::Catalogs(v)) = self.views.last_mut() {
+            v.index = category.saturating_add(2);
+        }
+
+        if let Some((category, index)) = reopen {
+            self.views
+                .push(View::Books(BooksView::new(category, index)));
+        }
+    }
+}
+
+/// Snapshot the picks of all catalogs.
+fn picks(state: &State) -> Vec<Option<usize>> {
+    state.catalogs.iter().map(|c| c.picked).collect()
+}
+
+/// Draw a help overlay listing the key bindings of the given keymaps.
+fn draw_help(keymaps: &[&Keymap], styles: &Styles, frame: &mut Frame) {
+    let mut lines = Vec::new();
+
+    for keymap in keymaps.iter().copied().chain([&keymap::GLOBAL]) {
+        if !lines.is_empty() {
+            lines.push(Line::default());
+        }
+
+        lines.push(Line::from(Span::styled(
+            keymap.title,
+            styles.header_style(),
+        )));
+
+        for (keys, description) in keymap.entries() {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {keys:<20}"), styles.header_hint_style()),
+                Span::raw(description),
+            ]));
+        }
+    }
+
+    lines.push(Line::default());
+    lines.push(Line::from(Span::styled(
+        "Press any key to close",
+        styles.dim_style(),
+    )));
+
+    let area = frame.area();
+    let width = area.width.min(60);
+    let height = area.height.min(lines.len() as u16 + 2);
+
+    let area = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    let help = Paragraph::new(lines).block(Block::bordered().title(" Help "));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(help, area);
 }
 
 fn pluralize<'a>(count: usize, singular: &'a str, plural: &'a str) -> &'a str {
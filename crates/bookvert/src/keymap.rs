@@ -0,0 +1,327 @@
+use mediavert_core::keymap;
+use ratatui::crossterm::event::KeyCode::{
+    Backspace, Char, Delete, Down, Enter, Esc, Left, Right, Up,
+};
+
+/// Actions which can be bound to keys in the interactive application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Open,
+    Back,
+    Clear,
+    Finish,
+    Help,
+    Undo,
+    Bulk,
+    Mark,
+    Compare,
+    Pages,
+    Cycle,
+}
+
+/// A single key binding.
+pub(crate) type Binding = keymap::Binding<Action>;
+
+/// The key bindings of a single view.
+pub(crate) type Keymap = keymap::Keymap<Action>;
+
+/// Bindings which are available in every view.
+pub(crate) const GLOBAL: Keymap = Keymap {
+    title: "Global",
+    bindings: &[
+        Binding {
+            codes: &[Char('?')],
+            action: Action::Help,
+            description: "help",
+            hint: true,
+        },
+        Binding {
+            codes: &[Char('u')],
+            action: Action::Undo,
+            description: "undo the last pick or clear",
+            hint: false,
+        },
+    ],
+};
+
+pub(crate) const CATALOGS: Keymap = Keymap {
+    title: "Catalogs",
+    bindings: &[
+        Binding {
+            codes: &[Up, Char('k')],
+            action: Action::Up,
+            description: "move up",
+            hint: false,
+        },
+        Binding {
+            codes: &[Down, Char('j')],
+            action: Action::Down,
+            description: "move down",
+            hint: false,
+        },
+        Binding {
+            codes: &[Enter, Char('o'), Right, Char('l'), Char(' ')],
+            action: Action::Open,
+            description: "select",
+            hint: true,
+        },
+        Binding {
+            codes: &[Backspace, Delete, Char('c')],
+            action: Action::Clear,
+            description: "clear",
+            hint: true,
+        },
+        Binding {
+            codes: &[Char('b')],
+            action: Action::Bulk,
+            description: "pick all unselected using a strategy",
+            hint: false,
+        },
+        Binding {
+            codes: &[Char('x')],
+            action: Action::Finish,
+            description: "run with the current selection",
+            hint: false,
+        },
+        Binding {
+            codes: &[Esc, Char('q')],
+            action: Action::Back,
+            description: "quit",
+            hint: true,
+        },
+    ],
+};
+
+pub(crate) const BOOKS: Keymap = Keymap {
+    title: "Select book",
+    bindings: &[
+        Binding {
+            codes: &[Up, Char('k')],
+            action: Action::Up,
+            description: "move up",
+            hint: false,
+        },
+        Binding {
+            codes: &[Down, Char('j')],
+            action: Action::Down,
+            description: "move down",
+            hint: false,
+        },
+        Binding {
+            codes: &[Enter, Char('o')],
+            action: Action::Open,
+            description: "pick",
+            hint: true,
+        },
+        Binding {
+            codes: &[Char('m'), Char(' ')],
+            action: Action::Mark,
+            description: "mark for comparison",
+            hint: false,
+        },
+        Binding {
+            codes: &[Char('c')],
+            action: Action::Compare,
+            description: "compare",
+            hint: true,
+        },
+        Binding {
+            codes: &[Char('p')],
+            action: Action::Pages,
+            description: "view and mark pages",
+            hint: false,
+        },
+        Binding {
+            codes: &[Esc, Char('q'), Left, Char('h')],
+            action: Action::Back,
+            description: "go back",
+            hint: true,
+        },
+    ],
+};
+
+pub(crate) const COMPARE: Keymap = Keymap {
+    title: "Compare books",
+    bindings: &[
+        Binding {
+            codes: &[Left, Char('h')],
+            action: Action::Left,
+            description: "focus left",
+            hint: false,
+        },
+        Binding {
+            codes: &[Right, Char('l')],
+            action: Action::Right,
+            description: "focus right",
+            hint: false,
+        },
+        Binding {
+            codes: &[Enter, Char('o')],
+            action: Action::Open,
+            description: "pick",
+            hint: true,
+        },
+        Binding {
+            codes: &[Esc, Char('q')],
+            action: Action::Back,
+            description: "go back",
+            hint: true,
+        },
+    ],
+};
+
+pub(crate) const PAGES: Keymap = Keymap {
+    title: "Pages",
+    bindings: &[
+        Binding {
+            codes: &[Up, Char('k')],
+            action: Action::Up,
+            description: "move up",
+            hint: false,
+        },
+        Binding {
+            codes: &[Down, Char('j')],
+            action: Action::Down,
+            description: "move down",
+            hint: false,
+        },
+        Binding {
+            codes: &[Enter, Char('t'), Char(' ')],
+            action: Action::Cycle,
+            description: "cycle page type",
+            hint: true,
+        },
+        Binding {
+            codes: &[Backspace, Delete, Char('c')],
+            action: Action::Clear,
+            description: "clear page type",
+            hint: true,
+        },
+        Binding {
+            codes: &[Esc, Char('q'), Left, Char('h')],
+            action: Action::Back,
+            description: "go back",
+            hint: true,
+        },
+    ],
+};
+
+pub(crate) const BULK: Keymap = Keymap {
+    title: "Bulk pick",
+    bindings: &[
+        Binding {
+            codes: &[Up, Char('k')],
+            action: Action::Up,
+            description: "move up",
+            hint: false,
+        },
+        Binding {
+            codes: &[Down, Char('j')],
+            action: Action::Down,
+            description: "move down",
+            hint: false,
+        },
+        Binding {
+            codes: &[Enter, Char('o')],
+            action: Action::Open,
+            description: "apply",
+            hint: true,
+        },
+        Binding {
+            codes: &[Esc, Char('q'), Left, Char('h')],
+            action: Action::Back,
+            description: "go back",
+            hint: true,
+        },
+    ],
+};
+
+pub(crate) const NAME: Keymap = Keymap {
+    title: "Set name",
+    bindings: &[
+        Binding {
+            codes: &[Up, Char('k')],
+            action: Action::Up,
+            description: "move up",
+            hint: false,
+        },
+        Binding {
+            codes: &[Down, Char('j')],
+            action: Action::Down,
+            description: "move down",
+            hint: false,
+        },
+        Binding {
+            codes: &[Enter, Char('o')],
+            action: Action::Open,
+            description: "select",
+            hint: true,
+        },
+        Binding {
+            codes: &[Esc, Char('q'), Left, Char('h')],
+            action: Action::Back,
+            description: "go back",
+            hint: true,
+        },
+    ],
+};
+
+/// Bindings used while editing a custom name, all other keys are used to edit
+/// the name.
+pub(crate) const NAME_EDITING: Keymap = Keymap {
+    title: "Editing name",
+    bindings: &[
+        Binding {
+            codes: &[Enter],
+            action: Action::Open,
+            description: "save",
+            hint: true,
+        },
+        Binding {
+            codes: &[Esc],
+            action: Action::Back,
+            description: "stop editing",
+            hint: true,
+        },
+    ],
+};
+
+pub(crate) const CONFIRM: Keymap = Keymap {
+    title: "Confirm",
+    bindings: &[
+        Binding {
+            codes: &[Left, Char('h')],
+            action: Action::Left,
+            description: "focus no",
+            hint: false,
+        },
+        Binding {
+            codes: &[Right, Char('l')],
+            action: Action::Right,
+            description: "focus yes",
+            hint: false,
+        },
+        Binding {
+            codes: &[Enter, Char(' '), Char('o')],
+            action: Action::Open,
+            description: "confirm the focused choice",
+            hint: false,
+        },
+        Binding {
+            codes: &[Char('y')],
+            action: Action::Finish,
+            description: "continue anyway",
+            hint: false,
+        },
+        Binding {
+            codes: &[Esc, Char('q'), Char('n')],
+            action: Action::Back,
+            description: "go back",
+            hint: false,
+        },
+    ],
+};
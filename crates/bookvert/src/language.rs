@@ -0,0 +1,180 @@
+use core::fmt;
+
+use std::fs;
+use std::path::{Component, Path};
+
+use anyhow::{Context, Result, anyhow};
+use language_tags::LanguageTag;
+use regex::Regex;
+
+use crate::state::Book;
+
+/// The name of the sidecar file used to set the language of a book.
+const LANGUAGE_SIDECAR: &str = ".bookvert-language";
+
+/// Languages which are recognized in directory names, by their ISO 639-1 code
+/// followed by other names they're commonly tagged with.
+const LANGUAGES: &[(&str, &[&str])] = &[
+    ("ar", &["ara", "arabic"]),
+    ("de", &["deu", "ger", "german", "deutsch"]),
+    ("en", &["eng", "english"]),
+    ("es", &["spa", "spanish", "español", "espanol"]),
+    ("fr", &["fra", "fre", "french", "français", "francais"]),
+    ("id", &["ind", "indonesian"]),
+    ("it", &["ita", "italian", "italiano"]),
+    ("ja", &["jp", "jpn", "japanese"]),
+    ("ko", &["kor", "korean"]),
+    ("nl", &["nld", "dut", "dutch"]),
+    ("pl", &["pol", "polish", "polski"]),
+    ("pt", &["por", "portuguese", "português", "portugues"]),
+    ("ru", &["rus", "russian"]),
+    ("sv", &["swe", "swedish", "svenska"]),
+    ("th", &["tha", "thai"]),
+    ("tr", &["tur", "turkish"]),
+    ("vi", &["vie", "vietnamese"]),
+    ("zh", &["chi", "zho", "chinese"]),
+];
+
+/// Where the language of a book was found.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Origin {
+    Map,
+    Sidecar,
+    Directory,
+}
+
+impl fmt::Display for Origin {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Map => write!(f, "language map"),
+            Origin::Sidecar => write!(f, "{LANGUAGE_SIDECAR}"),
+            Origin::Directory => write!(f, "directory name"),
+        }
+    }
+}
+
+/// A user-provided mapping from book directories to languages.
+///
+/// Each line is in the form `<pattern>=<language>`, where the pattern is a
+/// regular expression matched against the directory of the book. Empty lines
+/// and lines starting with `#` are ignored.
+pub(crate) struct LanguageMap {
+    entries: Vec<(Regex, LanguageTag)>,
+}
+
+impl LanguageMap {
+    /// Load a language map from the given path.
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| anyhow!("Failed to read {}", path.display()))?;
+
+        let mut entries = Vec::new();
+
+        for (n, line) in contents.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((pattern, language)) = line.rsplit_once('=') else {
+                return Err(anyhow!(
+                    "{}:{}: Expected <pattern>=<language>",
+                    path.display(),
+                    n + 1
+                ));
+            };
+
+            let pattern = Regex::new(pattern.trim())
+                .with_context(|| anyhow!("{}:{}: Invalid pattern", path.display(), n + 1))?;
+
+            let language = LanguageTag::parse(language.trim())
+                .with_context(|| anyhow!("{}:{}: Invalid language", path.display(), n + 1))?;
+
+            entries.push((pattern, language));
+        }
+
+        Ok(Self { entries })
+    }
+}
+
+/// Detect the language of a book, trying the language map first, then the
+/// sidecar file in the directory of the book, and finally language tags like
+/// `[EN]` or `(Japanese)` in the names of its directories.
+pub(crate) fn detect(
+    book: &Book,
+    map: Option<&LanguageMap>,
+) -> Result<Option<(LanguageTag, Origin)>> {
+    if let Some(map) = map {
+        let dir = book.dir.to_string_lossy();
+
+        for (pattern, language) in map.entries.iter().rev() {
+            if pattern.is_match(&dir) {
+                return Ok(Some((language.clone(), Origin::Map)));
+            }
+        }
+    }
+
+    let path = book.dir.join(LANGUAGE_SIDECAR);
+
+    if path.is_file() {
+        let contents = fs::read_to_string(&path)
+            .with_context(|| anyhow!("Failed to read {}", path.display()))?;
+
+        let language = contents.lines().next().unwrap_or_default().trim();
+
+        if !language.is_empty() {
+            let language = LanguageTag::parse(language)
+                .with_context(|| anyhow!("{}: Invalid language", path.display()))?;
+            return Ok(Some((language, Origin::Sidecar)));
+        }
+    }
+
+    for component in book.dir.components().rev() {
+        let Component::Normal(name) = component else {
+            continue;
+        };
+
+        let Some(name) = name.to_str() else {
+            continue;
+        };
+
+        if let Some(code) = from_tags(name) {
+            let language = LanguageTag::parse(code)?;
+            return Ok(Some((language, Origin::Directory)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Find a language among the bracketed tags of a name, like `[EN]` or
+/// `(Japanese)`. Only bracketed tags are considered, since short codes like
+/// `it` would otherwise match ordinary words.
+fn from_tags(name: &str) -> Option<&'static str> {
+    let mut rest = name;
+
+    while let Some(start) = rest.find(['[', '(']) {
+        let close = if rest[start..].starts_with('[') {
+            ']'
+        } else {
+            ')'
+        };
+
+        let Some(len) = rest[start + 1..].find(close) else {
+            break;
+        };
+
+        let tag = rest[start + 1..start + 1 + len].trim().to_lowercase();
+        rest = &rest[start + 1 + len..];
+
+        for (code, names) in LANGUAGES {
+            if tag == *code || names.contains(&tag.as_str()) {
+                return Some(code);
+            }
+        }
+    }
+
+    None
+}
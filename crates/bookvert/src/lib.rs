@@ -7,6 +7,9 @@
 //! This is a .cbz batch conversion tool which scans directories for image
 //! files, groups them by their directory and creates books out of them.
 //!
+//! If any archives are encountered (zip, rar, 7z), they will be treated as-if
+//! they are a directory named the same as the archive.
+//!
 //! You can install bookvert with cargo:
 //!
 //! ```sh
@@ -46,6 +49,59 @@
 //! I sorted books1.cbz` and `That time I sorted books2.cbz` in the specified
 //! output directory.
 //!
+//! Use `--include-name` to only convert books whose names match a regular expression,
+//! like `--include-name omake` to only convert the extras of a series. Catalogs
+//! without any matching books are left out.
+//!
+//! Pages can be prepared for a particular reader with `--profile`, which downscales
+//! them to fit its screen, converts them to grayscale for e-ink, recompresses them,
+//! and splits or rotates double-page spreads. The built-in profiles are
+//! `kobo-libra2`, `kindle-pw5`, and `tablet-10in`. Spreads are split in the order
+//! given by `--reading-direction`:
+//!
+//! ```sh
+//! bookvert --profile kobo-libra2 --reading-direction rtl --out books .
+//! ```
+//!
+//! For collections in mixed languages, `--detect-language` sets the language of each
+//! book instead of using one `--language` for all of them. The language is read from a
+//! `.bookvert-language` file in the directory of the book, or from tags like `[EN]` or
+//! `(Japanese)` in the names of its directories. A file given with `--language-map`
+//! maps book directories to languages using lines like `/Scanlations/=en`, and takes
+//! precedence over both.
+//!
+//! Pages can be marked as a `front-cover`, `inner-cover`, `advertisement`, or
+//! `deleted` with `--page-type`, which is recorded in `ComicInfo.xml` so that readers
+//! like CDisplayEx and Komga can display and filter them. Pages are matched by `first`,
+//! `last`, or a regular expression for their file name. Pages can also be marked by
+//! pressing `p` on a book in the interactive interface:
+//!
+//! ```sh
+//! bookvert --page-type front-cover=first --page-type 'advertisement=^ad_' --out books .
+//! ```
+//!
+//! Books are written as `.cbz` by default. Use `--format epub` to write fixed-layout
+//! EPUBs instead, or `--format kepub` to write them with the markup Kobo readers use
+//! for page turns and reading statistics, as `.kepub.epub` files which can be copied
+//! straight onto the reader:
+//!
+//! ```sh
+//! bookvert --format kepub --profile kobo-libra2 --out books .
+//! ```
+//!
+//! For a quick one-off, `--single` packages exactly one directory of pages into the
+//! `.cbz` given with `--output`, without detecting series names or numbers. Every
+//! image in the directory and its subdirectories becomes a page, in the order of their
+//! paths:
+//!
+//! ```sh
+//! bookvert --single scans/oneshot --output "Oneshot.cbz"
+//! ```
+//!
+//! Use `--durable` to sync every written book along with the directory it's in to
+//! disk, so that a power loss right after a run can't leave an empty book behind
+//! which later runs would skip as already written.
+//!
 //! <br>
 //!
 //! ## Policies
@@ -75,13 +131,193 @@
 //!   through 5.
 //! - `-p fix` will match *any* book that contains the string `fix`.
 //!
+//! <br>
+//!
+//! #### Select book numbers with `--number-select`
+//!
+//! By default every number in the name of a directory becomes a book number, so a
+//! name like `2nd Season 03` ends up as both book 2 and book 3. `--number-select`
+//! picks a single number instead, either the `first`, the `last`, the `largest`,
+//! or the one captured by a regular expression:
+//!
+//! ```sh
+//! bookvert --number-select last .
+//! bookvert --number-select 'Season (\d+)' .
+//! ```
+//!
+//! <br>
+//!
+//! ## Configuration
+//!
+//! bookvert reads an optional configuration file from `mediavert/config.toml` in
+//! the user's configuration directory, such as `~/.config/mediavert/config.toml`
+//! on Linux. A different file can be specified with `--config`.
+//!
+//! Settings shared by all tools, like `color` which is one of `auto`, `always`,
+//! or `never`, can be specified at the top level and be overridden for bookvert in
+//! the `[bookvert]` section. Options specified on the command line take precedence
+//! over both.
+//!
+//! The colors and markers of the interactive interface can be customized in the
+//! `[bookvert.theme]` section. The `base` theme can be `default`,
+//! `high-contrast`, or `no-color`, and can also be selected with `--theme`:
+//!
+//! ```toml
+//! [bookvert.theme]
+//! base = "high-contrast"
+//! selected-marker = ">"
+//! done = "light-blue"
+//! not-done = "#ffaf00"
+//! ```
+//!
+//! Pages can be extracted from files bookvert doesn't support by an external
+//! command configured as a plugin in the `[bookvert.plugins]` section. In its
+//! `command` `{from}` is replaced with the source file and `{to}` with a temporary
+//! directory the pages should be extracted into. The pages are treated as if the
+//! file was a directory with the same name:
+//!
+//! ```toml
+//! [bookvert.plugins.djvu]
+//! command = ["ddjvu", "-format=tiff", "-eachpage", "{from}", "{to}/p%03d.tif"]
+//! ```
+//!
+//! Profiles for `--profile` are defined in the `[bookvert.profiles]` section, where
+//! a profile with the same name as a built-in one replaces it. Pages are downscaled
+//! to fit within `width` and `height`, `format` is either `jpeg` or `png`, `quality`
+//! is used for pages stored as jpeg, and `spreads` is one of `keep`, `split`, or
+//! `rotate`:
+//!
+//! ```toml
+//! [bookvert.profiles.my-reader]
+//! width = 1072
+//! height = 1448
+//! grayscale = true
+//! format = "jpeg"
+//! quality = 80
+//! spreads = "split"
+//! ```
+//!
+//! Every run stores its temporary files in a directory of its own inside of
+//! `--tmp-dir`, the `tmp-dir` setting, or the system temporary directory, which is
+//! removed once the run finishes. Directories left behind by runs which crashed
+//! are removed with `--clean-temp`. This is only supported on Linux, since
+//! elsewhere it can't be told whether the run a directory belongs to is still
+//! going.
+//!
+//! <br>
+//!
+//! ## Reports
+//!
+//! A self-contained HTML report of a run can be written with
+//! `--report html:<path>`. It lists every book with a thumbnail of its cover, the
+//! number of pages, and its size before and after conversion, along with books
+//! which weren't picked and any errors which aborted the run. Use
+//! `--report text:<path>` for a plain text report without thumbnails.
+//!
+//! <br>
+//!
+//! ## Collection statistics
+//!
+//! Use `--stats` to see what bookvert makes of a collection before converting
+//! anything. It scans the specified paths and reports the number of series names,
+//! catalogs and books, how many candidates each catalog has, the total number of
+//! pages and bytes, a breakdown of image formats, and the largest books. Nothing is
+//! written.
+//!
+//! ```sh
+//! bookvert --stats -n ~/comics/series
+//! ```
+//!
+//! <br>
+//!
+//! ## Stamps
+//!
+//! With `--stamp` the Notes field of ComicInfo.xml in each book is stamped with the
+//! version of bookvert, a hash of the names and sizes of its source pages, and the
+//! date it was packaged, like `bookvert 0.0.8; source 3f1a9c0b2d4e6f80; packaged
+//! 2025-01-31`.
+//!
+//! Stamped libraries can be kept up to date with `--on-conflict refresh`, which only
+//! rewrites books whose source pages have changed since they were stamped. Stamps are
+//! kept by `--update-metadata`, and `--check --stamp` reports books which are missing a
+//! stamp or were stamped by another version of bookvert.
+//!
+//! ```sh
+//! bookvert --on-conflict refresh -n ~/comics/series
+//! ```
+//!
+//! <br>
+//!
+//! ## Library usage
+//!
+//! bookvert can also be driven as a library. The options are the same as the
+//! ones used on the command line, and the individual steps are exposed as
+//! [`cli::scan`], [`cli::pick`], and [`cli::write`] so that a different
+//! interface can inspect and modify the [`State`] in between:
+//!
+//! ```no_run
+//! use bookvert::cli::{self, Bookvert};
+//! use clap::Parser;
+//! use mediavert_core::cancel::Cancel;
+//! use termcolor::{ColorChoice, StandardStream};
+//!
+//! let opts = Bookvert::try_parse_from(["bookvert", "--name", "Series", "books"])?;
+//! let mut o = StandardStream::stdout(ColorChoice::Auto);
+//!
+//! let mut state = cli::scan(&opts, &mut o)?;
+//! cli::pick(&opts, &mut state)?;
+//!
+//! for catalog in &mut state.catalogs {
+//!     if catalog.picked.is_none() {
+//!         catalog.picked = Some(0);
+//!     }
+//! }
+//!
+//! cli::write(&opts, &state, &Cancel::new(), &mut o)?;
+//! # Ok::<_, anyhow::Error>(())
+//! ```
+//!
+//! Options are constructed by parsing arguments with `Parser::try_parse_from`,
+//! which is the supported way to build them programmatically. Most options have
+//! types which are private to bookvert, so a graphical interface or a server
+//! passes the same arguments it would use on the command line. Arguments can be
+//! `OsString`s, so paths don't have to be valid UTF-8.
+//!
+//! The `Cancel` token passed to `cli::write` stops writing books when cancelled,
+//! which is what Ctrl-C does on the command line.
+//!
 //! [examples]: https://github.com/udoprog/bookvert/tree/main/examples
 
+mod archive;
+
+mod config;
+
+mod epub;
+
 mod interactive;
 use self::interactive::App;
 
+mod keymap;
+
+mod language;
+
+mod magic;
+
+mod numerals;
+
+mod pipeline;
+use self::pipeline::OutputPage;
+
+mod plugin;
+
+mod preview;
+
+mod profile;
+
 mod state;
-use self::state::{Book, Catalog, Page, State};
+use self::state::Source;
+pub use self::state::{Book, Catalog, Page, State};
 
 pub mod cli;
+mod strips;
 mod styles;
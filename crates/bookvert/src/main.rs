@@ -22,5 +22,6 @@ struct Opts {
 
 fn main() -> Result<()> {
     let opts = Opts::parse();
+    mediavert_core::log::init(opts.inner.verbosity());
     bookvert::cli::entry(&opts.inner)
 }
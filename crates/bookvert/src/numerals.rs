@@ -0,0 +1,140 @@
+/// Words which commonly precede the number of a book.
+const KEYWORDS: &[&str] = &[
+    "book", "ch", "chapter", "issue", "no", "part", "tome", "vol", "volume",
+];
+
+const UNITS: &[&str] = &[
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+const TENS: &[&str] = &[
+    "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+/// Extract numbers written as Roman numerals or spelled-out English words,
+/// such as `Volume IV` or `Book Two`.
+///
+/// To avoid picking up ordinary words, a numeral is only considered if it's
+/// preceded by a keyword like `Volume` or if it's the last word in the input.
+pub(crate) fn numerals(input: &str) -> Vec<u32> {
+    let words = input
+        .split(|c: char| !c.is_alphanumeric() && c != '-')
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>();
+
+    let mut out = Vec::new();
+
+    for (n, word) in words.iter().enumerate() {
+        let after_keyword = n
+            .checked_sub(1)
+            .and_then(|n| words.get(n))
+            .is_some_and(|w| KEYWORDS.contains(&w.to_lowercase().as_str()));
+
+        if !after_keyword && n + 1 != words.len() {
+            continue;
+        }
+
+        if let Some(number) = roman(word).or_else(|| spelled(word)) {
+            out.push(number);
+        }
+    }
+
+    out
+}
+
+const ROMAN: &[(&str, u32)] = &[
+    ("M", 1000),
+    ("CM", 900),
+    ("D", 500),
+    ("CD", 400),
+    ("C", 100),
+    ("XC", 90),
+    ("L", 50),
+    ("XL", 40),
+    ("X", 10),
+    ("IX", 9),
+    ("V", 5),
+    ("IV", 4),
+    ("I", 1),
+];
+
+/// Parse an upper-case Roman numeral in its canonical form.
+fn roman(word: &str) -> Option<u32> {
+    if word.is_empty() || word.len() > 15 {
+        return None;
+    }
+
+    let mut rest = word;
+    let mut number = 0;
+
+    for &(symbol, value) in ROMAN {
+        while let Some(tail) = rest.strip_prefix(symbol) {
+            rest = tail;
+            number += value;
+        }
+    }
+
+    // Reject non-canonical forms like `IIII` or `VIV` by formatting the number
+    // back and comparing.
+    if !rest.is_empty() || to_roman(number) != word {
+        return None;
+    }
+
+    Some(number)
+}
+
+/// Format a number as a canonical Roman numeral.
+fn to_roman(mut number: u32) -> String {
+    let mut out = String::new();
+
+    for &(symbol, value) in ROMAN {
+        while number >= value {
+            out.push_str(symbol);
+            number -= value;
+        }
+    }
+
+    out
+}
+
+/// Parse a spelled-out English number between zero and ninety-nine.
+fn spelled(word: &str) -> Option<u32> {
+    let word = word.to_lowercase();
+
+    let (tens, unit) = match word.split_once('-') {
+        Some((tens, unit)) => (tens, Some(unit)),
+        None => (word.as_str(), None),
+    };
+
+    if let Some(n) = UNITS.iter().position(|w| *w == tens) {
+        return unit.is_none().then_some(n as u32);
+    }
+
+    let tens = TENS.iter().position(|w| *w == tens)? as u32 * 10 + 20;
+
+    let Some(unit) = unit else {
+        return Some(tens);
+    };
+
+    let unit = UNITS[1..10].iter().position(|w| *w == unit)? as u32 + 1;
+    Some(tens + unit)
+}
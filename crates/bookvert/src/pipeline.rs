@@ -0,0 +1,271 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io::Cursor;
+
+use anyhow::{Context, Result, anyhow};
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, ImageFormat, imageops};
+
+use crate::state::PageType;
+
+/// The quality used when re-encoding JPEG images.
+pub(crate) const DEFAULT_JPEG_QUALITY: u8 = 90;
+
+/// The lowest quality considered when fitting a book into a size budget.
+const MIN_JPEG_QUALITY: u8 = 10;
+
+/// A page which is about to be written to a book.
+pub(crate) struct OutputPage {
+    /// The name of the page inside of the book.
+    pub(crate) name: String,
+    /// The contents of the page.
+    pub(crate) content: Vec<u8>,
+    /// The type of the page, where pages inserted with `--insert-page` have
+    /// the type `Other`.
+    pub(crate) page_type: Option<PageType>,
+}
+
+impl OutputPage {
+    /// Construct a new page.
+    pub(crate) fn new(name: String, content: Vec<u8>) -> Self {
+        Self {
+            name,
+            content,
+            page_type: None,
+        }
+    }
+
+    /// Split the name of the page into its stem and extension.
+    pub(crate) fn stem_and_ext(&self) -> (&str, &str) {
+        self.name.rsplit_once('.').unwrap_or((&self.name, ""))
+    }
+
+    /// Test if the page is in a format which can be processed.
+    pub(crate) fn is_supported(&self) -> bool {
+        let (_, ext) = self.stem_and_ext();
+        ImageFormat::from_extension(ext).is_some_and(|f| f.can_read() && f.can_write())
+    }
+
+    /// Decode the image of the page.
+    pub(crate) fn decode(&self) -> Result<DynamicImage> {
+        image::load_from_memory(&self.content)
+            .with_context(|| anyhow!("{}: Failed to decode image", self.name))
+    }
+}
+
+/// Encode an image using the format associated with the given extension.
+pub(crate) fn encode(image: &DynamicImage, ext: &str, quality: u8) -> Result<Vec<u8>> {
+    let format = ImageFormat::from_extension(ext)
+        .with_context(|| anyhow!("Unsupported image format `{ext}`"))?;
+
+    let mut out = Cursor::new(Vec::new());
+
+    if format == ImageFormat::Jpeg {
+        let encoder = JpegEncoder::new_with_quality(&mut out, quality);
+
+        // Grayscale images are kept as a single channel, which is smaller.
+        if image.color().has_color() {
+            DynamicImage::ImageRgb8(image.to_rgb8()).write_with_encoder(encoder)?;
+        } else {
+            DynamicImage::ImageLuma8(image.to_luma8()).write_with_encoder(encoder)?;
+        }
+    } else {
+        image.write_to(&mut out, format)?;
+    }
+
+    Ok(out.into_inner())
+}
+
+/// Recompress pages as JPEG using the highest quality which makes their
+/// combined size fit within `budget` bytes.
+///
+/// Pages in formats which cannot be processed are left as-is. Returns the
+/// quality that was used, or `None` if the pages already fit the budget. If
+/// the budget cannot be met, the lowest quality considered is used.
+pub(crate) fn fit_to_size(
+    pages: Vec<OutputPage>,
+    budget: u64,
+) -> Result<(Vec<OutputPage>, Option<u8>)> {
+    if total_size(&pages) <= budget {
+        return Ok((pages, None));
+    }
+
+    let mut images = Vec::with_capacity(pages.len());
+
+    for page in &pages {
+        images.push(if page.is_supported() {
+            Some(page.decode()?)
+        } else {
+            None
+        });
+    }
+
+    let mut lo = MIN_JPEG_QUALITY;
+    let mut hi = DEFAULT_JPEG_QUALITY;
+    let mut best = None;
+
+    while lo <= hi {
+        let quality = lo + (hi - lo) / 2;
+        let candidate = recompress(&pages, &images, quality)?;
+
+        if total_size(&candidate) <= budget {
+            best = Some((candidate, quality));
+            lo = quality + 1;
+        } else {
+            hi = quality - 1;
+        }
+    }
+
+    let (pages, quality) = match best {
+        Some(best) => best,
+        None => (
+            recompress(&pages, &images, MIN_JPEG_QUALITY)?,
+            MIN_JPEG_QUALITY,
+        ),
+    };
+
+    Ok((pages, Some(quality)))
+}
+
+/// Recompress the decoded images as JPEG with the given quality.
+///
+/// Pages which change extension are renamed, like `x (2).jpg` if a page named
+/// `x.jpg` already exists.
+fn recompress(
+    pages: &[OutputPage],
+    images: &[Option<DynamicImage>],
+    quality: u8,
+) -> Result<Vec<OutputPage>> {
+    let mut output = Vec::with_capacity(pages.len());
+
+    // Names are compared case-insensitively like in the rest of the book.
+    let mut names = pages
+        .iter()
+        .zip(images)
+        .filter(|(_, image)| image.is_none())
+        .map(|(page, _)| page.name.to_lowercase())
+        .collect::<HashSet<_>>();
+
+    for (page, image) in pages.iter().zip(images) {
+        let Some(image) = image else {
+            output.push(OutputPage {
+                name: page.name.clone(),
+                content: page.content.clone(),
+                page_type: page.page_type,
+            });
+            continue;
+        };
+
+        let (stem, _) = page.stem_and_ext();
+
+        let mut name = format!("{stem}.jpg");
+        let mut n = 2usize;
+
+        while !names.insert(name.to_lowercase()) {
+            name = format!("{stem} ({n}).jpg");
+            n += 1;
+        }
+
+        output.push(OutputPage {
+            name,
+            content: encode(image, "jpg", quality)?,
+            page_type: page.page_type,
+        });
+    }
+
+    Ok(output)
+}
+
+/// The combined size of the given pages.
+pub(crate) fn total_size(pages: &[OutputPage]) -> u64 {
+    pages.iter().map(|p| p.content.len() as u64).sum()
+}
+
+/// Reverse the order of pages, and swap the halves of any double-page spreads
+/// so that they read correctly in the opposite direction.
+///
+/// Pages have to be renumbered to reflect their new order, see [`renumber`].
+pub(crate) fn reverse(mut pages: Vec<OutputPage>) -> Result<Vec<OutputPage>> {
+    pages.reverse();
+
+    for page in &mut pages {
+        if page.is_supported() {
+            let image = page.decode()?;
+
+            if image.width() > image.height() {
+                let (_, ext) = page.stem_and_ext();
+                page.content = encode(&swap_halves(&image), ext, DEFAULT_JPEG_QUALITY)?;
+            }
+        }
+    }
+
+    Ok(pages)
+}
+
+/// Insert pages at the given zero-based positions among the existing pages,
+/// or at the end if the position is `None`.
+///
+/// Positions refer to the pages before anything is inserted, and pages
+/// inserted at the same position keep the order they are given in. Pages
+/// have to be renumbered to reflect their new order, see [`renumber`].
+pub(crate) fn insert(
+    pages: Vec<OutputPage>,
+    inserts: impl IntoIterator<Item = (Option<usize>, OutputPage)>,
+) -> Vec<OutputPage> {
+    let len = pages.len();
+    let mut before = BTreeMap::<usize, Vec<OutputPage>>::new();
+
+    for (position, page) in inserts {
+        let index = position.map_or(len, |n| n.min(len));
+        before.entry(index).or_default().push(page);
+    }
+
+    let mut output = Vec::with_capacity(len + before.values().map(Vec::len).sum::<usize>());
+
+    for (n, page) in pages.into_iter().enumerate() {
+        if let Some(inserted) = before.remove(&n) {
+            output.extend(inserted);
+        }
+
+        output.push(page);
+    }
+
+    output.extend(before.into_values().flatten());
+    output
+}
+
+/// Rename pages so that their names reflect their order, since readers order
+/// pages by name.
+///
+/// If `keep_names` is set the original name is preserved but prefixed with its
+/// position.
+pub(crate) fn renumber(pages: &mut [OutputPage], keep_names: bool) {
+    for (n, page) in pages.iter_mut().enumerate() {
+        page.name = if keep_names {
+            format!("{n:03} - {}", page.name)
+        } else {
+            let (_, ext) = page.stem_and_ext();
+            format!("p{n:03}.{ext}")
+        };
+    }
+}
+
+/// Swap the left and right halves of an image.
+fn swap_halves(image: &DynamicImage) -> DynamicImage {
+    let left = image.width() / 2;
+    let right = image.width() - left;
+
+    let mut out = DynamicImage::new(image.width(), image.height(), image.color());
+    imageops::overlay(
+        &mut out,
+        &image.crop_imm(left, 0, right, image.height()),
+        0,
+        0,
+    );
+    imageops::overlay(
+        &mut out,
+        &image.crop_imm(0, 0, left, image.height()),
+        i64::from(right),
+        0,
+    );
+    out
+}
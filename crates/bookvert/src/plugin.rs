@@ -0,0 +1,73 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use anyhow::{Context, Result, anyhow, bail};
+use mediavert_core::plugin::{self, Plugin};
+use mediavert_core::temp::RunDir;
+use relative_path::RelativePath;
+
+/// Pages extracted by a plugin into a temporary directory, which is removed
+/// once dropped.
+pub(crate) struct Extracted {
+    /// The directory the pages were extracted into.
+    pub(crate) dir: PathBuf,
+    /// The temporary directory of the run, which is kept around for as long
+    /// as there are extracted pages in it.
+    _run_dir: Rc<RunDir>,
+}
+
+impl Extracted {
+    /// Get the contents of an extracted page.
+    pub(crate) fn contents(&self, path: &RelativePath) -> Result<Vec<u8>> {
+        let path = path.to_path(&self.dir);
+        fs::read(&path).with_context(|| anyhow!("Failed to read file {}", path.display()))
+    }
+}
+
+impl Drop for Extracted {
+    fn drop(&mut self) {
+        _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Extract the pages of `path` into `dir` using a plugin.
+///
+/// Pages are extracted into a partial directory which is renamed to `dir` once
+/// the plugin has finished successfully.
+pub(crate) fn extract(
+    plugin: &Plugin,
+    path: &Path,
+    dir: PathBuf,
+    run_dir: Rc<RunDir>,
+) -> Result<Extracted> {
+    let part = dir.with_added_extension("part");
+
+    if part.exists() {
+        fs::remove_dir_all(&part)
+            .with_context(|| anyhow!("Failed to remove {}", part.display()))?;
+    }
+
+    fs::create_dir_all(&part).with_context(|| anyhow!("Failed to create {}", part.display()))?;
+
+    let status = plugin
+        .command(path.as_os_str(), &part)?
+        .status()
+        .with_context(|| anyhow!("Failed to run {}", plugin.program()))?;
+
+    if !status.success() {
+        _ = fs::remove_dir_all(&part);
+        bail!("{} exited with {status}", plugin.program());
+    }
+
+    if let Err(e) = plugin::verify(&part) {
+        _ = fs::remove_dir_all(&part);
+        return Err(e);
+    }
+
+    fs::rename(&part, &dir).with_context(|| anyhow!("Failed to rename {}", part.display()))?;
+    Ok(Extracted {
+        dir,
+        _run_dir: run_dir,
+    })
+}
@@ -0,0 +1,116 @@
+use std::io::Cursor;
+
+use image::imageops::FilterType;
+use image::{ImageReader, RgbImage};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+
+use crate::state::{Book, Page, Source};
+
+/// The largest dimension of a stored preview image.
+const PREVIEW_SIZE: u32 = 256;
+
+/// Statistics about a book used when comparing candidates.
+pub(crate) struct BookStats {
+    /// The number of pages in the book.
+    pub(crate) pages: usize,
+    /// The total size of the book in bytes.
+    pub(crate) bytes: u64,
+    /// The average width and height of pages whose dimensions could be read.
+    pub(crate) resolution: Option<(u32, u32)>,
+    /// A downscaled preview of the first page.
+    pub(crate) preview: Option<RgbImage>,
+}
+
+impl BookStats {
+    /// Collect statistics for a book.
+    ///
+    /// This reads every page, so it might take a while for large books or books
+    /// stored in archives.
+    pub(crate) fn new(book: &Book) -> Self {
+        let mut width = 0u64;
+        let mut height = 0u64;
+        let mut count = 0u64;
+
+        for page in &book.pages {
+            if let Some((w, h)) = dimensions(page) {
+                width += u64::from(w);
+                height += u64::from(h);
+                count += 1;
+            }
+        }
+
+        let resolution = (count > 0).then(|| ((width / count) as u32, (height / count) as u32));
+
+        Self {
+            pages: book.pages.len(),
+            bytes: book.bytes(),
+            resolution,
+            preview: book.pages.first().and_then(preview),
+        }
+    }
+}
+
+/// Read the dimensions of a page.
+fn dimensions(page: &Page) -> Option<(u32, u32)> {
+    match &page.source {
+        Source::File => image::image_dimensions(&page.path).ok(),
+        Source::Archive { .. } | Source::Extracted { .. } => {
+            let contents = page.read().ok()?;
+
+            ImageReader::new(Cursor::new(contents))
+                .with_guessed_format()
+                .ok()?
+                .into_dimensions()
+                .ok()
+        }
+    }
+}
+
+/// Construct a downscaled preview of a page.
+pub(crate) fn preview(page: &Page) -> Option<RgbImage> {
+    let contents = page.read().ok()?;
+    let image = image::load_from_memory(&contents).ok()?;
+    Some(image.thumbnail(PREVIEW_SIZE, PREVIEW_SIZE).to_rgb8())
+}
+
+/// Render an image into lines of half-block characters which fit within the
+/// given number of columns and rows.
+pub(crate) fn render(image: &RgbImage, columns: u16, rows: u16) -> Vec<Line<'static>> {
+    let (w, h) = image.dimensions();
+
+    if w == 0 || h == 0 || columns == 0 || rows == 0 {
+        return Vec::new();
+    }
+
+    // Each character cell covers one pixel horizontally and two vertically.
+    let max_w = u32::from(columns);
+    let max_h = u32::from(rows) * 2;
+    let scale = f64::min(max_w as f64 / w as f64, max_h as f64 / h as f64);
+    let width = ((w as f64 * scale) as u32).max(1);
+    let height = ((h as f64 * scale) as u32).max(2);
+
+    let image = image::imageops::resize(image, width, height, FilterType::Triangle);
+
+    let mut lines = Vec::new();
+
+    for y in (0..height.saturating_sub(1)).step_by(2) {
+        let mut spans = Vec::with_capacity(width as usize);
+
+        for x in 0..width {
+            let [tr, tg, tb] = image.get_pixel(x, y).0;
+            let [br, bg, bb] = image.get_pixel(x, y + 1).0;
+
+            spans.push(Span::styled(
+                "▀",
+                Style::default()
+                    .fg(Color::Rgb(tr, tg, tb))
+                    .bg(Color::Rgb(br, bg, bb)),
+            ));
+        }
+
+        lines.push(Line::from(spans));
+    }
+
+    lines
+}
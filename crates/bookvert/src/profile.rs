@@ -0,0 +1,218 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Result, bail};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat};
+use serde::Deserialize;
+
+use crate::pipeline::{self, OutputPage};
+
+/// Profiles which are built in, and can be overridden in the configuration.
+const BUILTIN: &[(&str, Profile)] = &[
+    (
+        "kobo-libra2",
+        Profile {
+            width: Some(1264),
+            height: Some(1680),
+            grayscale: true,
+            format: Some(PageFormat::Jpeg),
+            quality: Some(85),
+            spreads: Spreads::Split,
+        },
+    ),
+    (
+        "kindle-pw5",
+        Profile {
+            width: Some(1236),
+            height: Some(1648),
+            grayscale: true,
+            format: Some(PageFormat::Jpeg),
+            quality: Some(85),
+            spreads: Spreads::Split,
+        },
+    ),
+    (
+        "tablet-10in",
+        Profile {
+            width: Some(1600),
+            height: Some(2560),
+            grayscale: false,
+            format: Some(PageFormat::Jpeg),
+            quality: Some(90),
+            spreads: Spreads::Keep,
+        },
+    ),
+];
+
+/// The format pages are stored in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum PageFormat {
+    Jpeg,
+    Png,
+}
+
+impl PageFormat {
+    fn ext(&self) -> &'static str {
+        match self {
+            PageFormat::Jpeg => "jpg",
+            PageFormat::Png => "png",
+        }
+    }
+}
+
+/// How double-page spreads, which are wider than they are tall, are handled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Spreads {
+    /// Keep spreads as they are.
+    #[default]
+    Keep,
+    /// Split spreads into two pages in reading order.
+    Split,
+    /// Rotate spreads so that they fill a portrait screen.
+    Rotate,
+}
+
+/// A bundle of settings which prepare pages for a particular reader or device.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub(crate) struct Profile {
+    /// The width pages are downscaled to fit within.
+    pub(crate) width: Option<u32>,
+    /// The height pages are downscaled to fit within.
+    pub(crate) height: Option<u32>,
+    /// Whether pages are converted to grayscale.
+    pub(crate) grayscale: bool,
+    /// The format pages are stored in, or the format they're already in if
+    /// unset.
+    pub(crate) format: Option<PageFormat>,
+    /// The quality pages stored as JPEG are recompressed with.
+    pub(crate) quality: Option<u8>,
+    /// How double-page spreads are handled.
+    pub(crate) spreads: Spreads,
+}
+
+impl Profile {
+    /// Find a profile by name, where profiles in the configuration take
+    /// precedence over the built-in ones.
+    pub(crate) fn find(name: &str, configured: &BTreeMap<String, Profile>) -> Result<Self> {
+        if let Some(profile) = configured.get(name) {
+            return Ok(profile.clone());
+        }
+
+        if let Some((_, profile)) = BUILTIN.iter().find(|(n, _)| *n == name) {
+            return Ok(profile.clone());
+        }
+
+        let names = BUILTIN
+            .iter()
+            .map(|(n, _)| *n)
+            .chain(configured.keys().map(String::as_str))
+            .collect::<Vec<_>>();
+
+        bail!(
+            "Unknown profile '{name}', expected one of: {}",
+            names.join(", ")
+        )
+    }
+
+    /// Apply the profile to pages, where `rtl` indicates that spreads are read
+    /// from right to left.
+    pub(crate) fn apply(&self, pages: Vec<OutputPage>, rtl: bool) -> Result<Vec<OutputPage>> {
+        let mut output = Vec::with_capacity(pages.len());
+
+        for page in pages {
+            if !page.is_supported() {
+                output.push(page);
+                continue;
+            }
+
+            let image = page.decode()?;
+
+            if image.width() <= image.height() {
+                output.push(self.process(page, image, false)?);
+                continue;
+            }
+
+            match self.spreads {
+                Spreads::Keep => {
+                    output.push(self.process(page, image, false)?);
+                }
+                Spreads::Rotate => {
+                    output.push(self.process(page, image.rotate90(), true)?);
+                }
+                Spreads::Split => {
+                    let left = image.width() / 2;
+                    let right = image.width() - left;
+
+                    let mut halves = [
+                        image.crop_imm(0, 0, left, image.height()),
+                        image.crop_imm(left, 0, right, image.height()),
+                    ];
+
+                    if rtl {
+                        halves.reverse();
+                    }
+
+                    let (stem, ext) = page.stem_and_ext();
+
+                    for (n, half) in halves.into_iter().enumerate() {
+                        let mut half_page =
+                            OutputPage::new(format!("{stem}-{n:02}.{ext}"), Vec::new());
+                        half_page.page_type = page.page_type;
+                        output.push(self.process(half_page, half, true)?);
+                    }
+                }
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Process a single page, where `changed` indicates that the image no
+    /// longer matches the contents of the page.
+    fn process(
+        &self,
+        mut page: OutputPage,
+        mut image: DynamicImage,
+        mut changed: bool,
+    ) -> Result<OutputPage> {
+        let width = self.width.unwrap_or(u32::MAX);
+        let height = self.height.unwrap_or(u32::MAX);
+
+        if image.width() > width || image.height() > height {
+            image = image.resize(width, height, FilterType::Lanczos3);
+            changed = true;
+        }
+
+        if self.grayscale && image.color().has_color() {
+            image = DynamicImage::ImageLuma8(image.to_luma8());
+            changed = true;
+        }
+
+        let (stem, ext) = page.stem_and_ext();
+        let current = ImageFormat::from_extension(ext);
+
+        let ext = match self.format {
+            Some(format) => format.ext(),
+            None => ext,
+        };
+
+        let format = ImageFormat::from_extension(ext);
+
+        if format != current || (self.quality.is_some() && format == Some(ImageFormat::Jpeg)) {
+            changed = true;
+        }
+
+        if !changed {
+            return Ok(page);
+        }
+
+        let quality = self.quality.unwrap_or(pipeline::DEFAULT_JPEG_QUALITY);
+        let name = format!("{stem}.{ext}");
+        page.content = pipeline::encode(&image, ext, quality)?;
+        page.name = name;
+        Ok(page)
+    }
+}
@@ -1,8 +1,20 @@
+use core::cell::Cell;
+use core::fmt;
+use core::str::FromStr;
+
 use std::collections::BTreeSet;
-use std::fs::Metadata;
+use std::fs::{self, File};
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
+use anyhow::{Context, Result, anyhow};
+use relative_path::RelativePathBuf;
+
+use crate::archive::SourceArchive;
+use crate::magic;
+use crate::plugin::Extracted;
+
 /// The state of a bookvert session.
 #[derive(Default)]
 pub struct State {
@@ -10,7 +22,9 @@ pub struct State {
     pub name: Option<String>,
     /// The filesystem detected name of the series. These can be used to support
     /// an interactive session where you can for example pick names from a list.
-    pub names: BTreeSet<String>,
+    ///
+    /// These are unique and sorted in the order they should be listed in.
+    pub names: Vec<String>,
     /// The detected catalogs in the session.
     pub catalogs: Vec<Catalog>,
 }
@@ -41,14 +55,153 @@ impl Catalog {
     }
 }
 
+/// Where the contents of a page are read from.
+#[derive(Clone)]
+pub(crate) enum Source {
+    /// A regular file in the filesystem.
+    File,
+    /// A file inside of an archive.
+    Archive {
+        /// The archive the page is stored in.
+        archive: Rc<SourceArchive>,
+        /// Path inside the archive.
+        path: RelativePathBuf,
+    },
+    /// A file extracted by a plugin.
+    Extracted {
+        /// The directory the page was extracted into.
+        extracted: Rc<Extracted>,
+        /// Path inside the extracted directory.
+        path: RelativePathBuf,
+    },
+}
+
+impl Source {
+    /// Read the header of the page used to detect its format.
+    pub(crate) fn header(&self, path: &Path) -> Result<Vec<u8>> {
+        match self {
+            Source::File => magic::read_header(path)
+                .with_context(|| anyhow!("Failed to read file {}", path.display())),
+            Source::Archive { archive, path } => archive.header(path),
+            Source::Extracted { extracted, path } => {
+                let path = path.to_path(&extracted.dir);
+                magic::read_header(&path)
+                    .with_context(|| anyhow!("Failed to read file {}", path.display()))
+            }
+        }
+    }
+}
+
+/// The type of a page, as understood by ComicRack and readers which honor
+/// the `Pages` section of `ComicInfo.xml`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PageType {
+    FrontCover,
+    InnerCover,
+    Advertisement,
+    Deleted,
+    Other,
+}
+
+impl PageType {
+    /// The types which can be cycled through in the interactive page view.
+    pub(crate) const CYCLE: [Option<PageType>; 5] = [
+        None,
+        Some(PageType::FrontCover),
+        Some(PageType::InnerCover),
+        Some(PageType::Advertisement),
+        Some(PageType::Deleted),
+    ];
+
+    /// The name of the type in `ComicInfo.xml`.
+    pub(crate) fn comic_info(&self) -> &'static str {
+        match self {
+            PageType::FrontCover => "FrontCover",
+            PageType::InnerCover => "InnerCover",
+            PageType::Advertisement => "Advertisement",
+            PageType::Deleted => "Deleted",
+            PageType::Other => "Other",
+        }
+    }
+}
+
+impl FromStr for PageType {
+    type Err = anyhow::Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "front-cover" => Ok(PageType::FrontCover),
+            "inner-cover" => Ok(PageType::InnerCover),
+            "advertisement" => Ok(PageType::Advertisement),
+            "deleted" => Ok(PageType::Deleted),
+            "other" => Ok(PageType::Other),
+            _ => Err(anyhow!("Invalid page type '{s}'")),
+        }
+    }
+}
+
+impl fmt::Display for PageType {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PageType::FrontCover => write!(f, "front-cover"),
+            PageType::InnerCover => write!(f, "inner-cover"),
+            PageType::Advertisement => write!(f, "advertisement"),
+            PageType::Deleted => write!(f, "deleted"),
+            PageType::Other => write!(f, "other"),
+        }
+    }
+}
+
 /// Data about a page.
 pub struct Page {
-    /// The filesystem name of the page.
+    /// The filesystem name of the page. For pages inside of archives this is
+    /// the path the archive would have if it was extracted into a directory.
     pub path: PathBuf,
     /// The name of the page.
     pub name: String,
-    /// The filesystem metadata of the page.
-    pub metadata: Metadata,
+    /// The size of the page in bytes.
+    pub size: u64,
+    /// Where the contents of the page are read from.
+    pub(crate) source: Source,
+    /// The type of the page, set with `--page-type` or in the interactive
+    /// page view.
+    pub(crate) page_type: Cell<Option<PageType>>,
+}
+
+impl Page {
+    /// Read the contents of the page.
+    pub(crate) fn read(&self) -> Result<Vec<u8>> {
+        match &self.source {
+            Source::File => fs::read(&self.path)
+                .with_context(|| anyhow!("Failed to read file {}", self.path.display())),
+            Source::Archive { archive, path } => archive.contents(path),
+            Source::Extracted { extracted, path } => extracted.contents(path),
+        }
+    }
+
+    /// Copy the contents of the page into the given writer.
+    ///
+    /// Pages in the filesystem are streamed in fixed-size chunks instead of
+    /// being read into memory, while pages inside of archives have to be
+    /// decompressed in full.
+    pub(crate) fn copy_to(&self, w: &mut impl Write) -> Result<u64> {
+        let path = match &self.source {
+            Source::File => self.path.clone(),
+            Source::Extracted { extracted, path } => path.to_path(&extracted.dir),
+            Source::Archive { archive, path } => {
+                let contents = archive.contents(path)?;
+                w.write_all(&contents)?;
+                return Ok(contents.len() as u64);
+            }
+        };
+
+        let mut file =
+            File::open(&path).with_context(|| anyhow!("Failed to open {}", path.display()))?;
+
+        io::copy(&mut file, w).with_context(|| anyhow!("Failed to copy {}", path.display()))
+    }
 }
 
 /// Data about a book.
@@ -73,6 +226,6 @@ impl Book {
     /// Returns the total size of all pages in bytes.
     #[inline]
     pub fn bytes(&self) -> u64 {
-        self.pages.iter().map(|page| page.metadata.len()).sum()
+        self.pages.iter().map(|page| page.size).sum()
     }
 }
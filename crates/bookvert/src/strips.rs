@@ -0,0 +1,162 @@
+use anyhow::Result;
+use image::{DynamicImage, GrayImage, Rgba, RgbaImage, imageops};
+
+use crate::pipeline::{self, OutputPage};
+
+/// The maximum difference in luminance for a row to be considered blank.
+const BLANK_TOLERANCE: u8 = 8;
+
+/// Split pages taller than `max_height` into multiple pages.
+///
+/// Cuts are preferably made at blank rows, so that panels are not sliced in
+/// half.
+pub(crate) fn split(pages: Vec<OutputPage>, max_height: u32) -> Result<Vec<OutputPage>> {
+    let mut output = Vec::with_capacity(pages.len());
+
+    for page in pages {
+        if !page.is_supported() {
+            output.push(page);
+            continue;
+        }
+
+        let image = page.decode()?;
+
+        if image.height() <= max_height {
+            output.push(page);
+            continue;
+        }
+
+        let luma = image.to_luma8();
+        let (stem, ext) = page.stem_and_ext();
+
+        let mut start = 0;
+        let mut n = 0usize;
+
+        while start < image.height() {
+            let end = if image.height() - start <= max_height {
+                image.height()
+            } else {
+                find_cut(&luma, start, max_height)
+            };
+
+            let segment = image.crop_imm(0, start, image.width(), end - start);
+
+            let mut segment_page = OutputPage::new(
+                format!("{stem}-{n:02}.{ext}"),
+                pipeline::encode(&segment, ext, pipeline::DEFAULT_JPEG_QUALITY)?,
+            );
+
+            segment_page.page_type = page.page_type;
+            output.push(segment_page);
+
+            start = end;
+            n += 1;
+        }
+    }
+
+    Ok(output)
+}
+
+/// Stitch consecutive pages together vertically into strips which are at most
+/// `max_height` pixels tall.
+///
+/// Pages which are narrower than the widest page in a strip are centered on a
+/// white background. Pages with a type, like covers, are kept as they are.
+pub(crate) fn stitch(pages: Vec<OutputPage>, max_height: u32) -> Result<Vec<OutputPage>> {
+    let mut output = Vec::with_capacity(pages.len());
+    let mut group = Vec::<(OutputPage, DynamicImage)>::new();
+    let mut height = 0u32;
+
+    for page in pages {
+        if !page.is_supported() || page.page_type.is_some() {
+            flush(&mut group, &mut output)?;
+            height = 0;
+            output.push(page);
+            continue;
+        }
+
+        let image = page.decode()?;
+
+        if !group.is_empty() && height.saturating_add(image.height()) > max_height {
+            flush(&mut group, &mut output)?;
+            height = 0;
+        }
+
+        height = height.saturating_add(image.height());
+        group.push((page, image));
+    }
+
+    flush(&mut group, &mut output)?;
+    Ok(output)
+}
+
+/// Flush a group of pages into a single stitched page.
+fn flush(group: &mut Vec<(OutputPage, DynamicImage)>, output: &mut Vec<OutputPage>) -> Result<()> {
+    if group.len() <= 1 {
+        output.extend(group.drain(..).map(|(page, _)| page));
+        return Ok(());
+    }
+
+    let width = group
+        .iter()
+        .map(|(_, i)| i.width())
+        .max()
+        .unwrap_or_default();
+    let height = group.iter().map(|(_, i)| i.height()).sum();
+
+    let mut canvas = RgbaImage::from_pixel(width, height, Rgba([255, 255, 255, 255]));
+    let mut y = 0u32;
+
+    for (_, image) in group.iter() {
+        let x = (width - image.width()) / 2;
+        imageops::overlay(&mut canvas, &image.to_rgba8(), i64::from(x), i64::from(y));
+        y += image.height();
+    }
+
+    let (first, _) = &group[0];
+    let (stem, ext) = first.stem_and_ext();
+
+    output.push(OutputPage::new(
+        format!("{stem}.{ext}"),
+        pipeline::encode(
+            &DynamicImage::ImageRgba8(canvas),
+            ext,
+            pipeline::DEFAULT_JPEG_QUALITY,
+        )?,
+    ));
+
+    group.clear();
+    Ok(())
+}
+
+/// Find where to cut a segment starting at `start`.
+///
+/// This searches backwards from the maximum height for a blank row, but no
+/// further than half of the maximum height.
+fn find_cut(luma: &GrayImage, start: u32, max_height: u32) -> u32 {
+    let end = start + max_height;
+    let min = start + max_height / 2;
+
+    for y in (min..end).rev() {
+        if is_blank_row(luma, y) {
+            return y.max(start + 1);
+        }
+    }
+
+    end
+}
+
+/// Test if a row of the image is blank, which means that it only contains a
+/// single color.
+fn is_blank_row(luma: &GrayImage, y: u32) -> bool {
+    let mut min = u8::MAX;
+    let mut max = u8::MIN;
+
+    for x in 0..luma.width() {
+        let [value] = luma.get_pixel(x, y).0;
+        min = min.min(value);
+        max = max.max(value);
+    }
+
+    max.saturating_sub(min) <= BLANK_TOLERANCE
+}
@@ -1,10 +1,50 @@
+use core::fmt;
+use core::str::FromStr;
+
+use anyhow::{Context, Result, anyhow};
 use ratatui::style::{Color, Modifier, Style, Stylize};
 
+use crate::config::ThemeConfig;
+
+/// Built-in color themes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Theme {
+    #[default]
+    Default,
+    HighContrast,
+    NoColor,
+}
+
+impl FromStr for Theme {
+    type Err = anyhow::Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "default" => Ok(Theme::Default),
+            "high-contrast" => Ok(Theme::HighContrast),
+            "no-color" => Ok(Theme::NoColor),
+            _ => Err(anyhow!("Invalid theme '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for Theme {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Theme::Default => write!(f, "default"),
+            Theme::HighContrast => write!(f, "high-contrast"),
+            Theme::NoColor => write!(f, "no-color"),
+        }
+    }
+}
+
 /// Centralized styling configuration for the TUI.
 pub(crate) struct Styles {
-    selected_marker: &'static str,
-    done_marker: &'static str,
-    editing_marker: &'static str,
+    selected_marker: String,
+    done_marker: String,
+    editing_marker: String,
     color_done: Color,
     color_normal: Color,
     color_not_done: Color,
@@ -15,12 +55,103 @@ pub(crate) struct Styles {
 }
 
 impl Styles {
-    pub(crate) fn selected(&self, selected: bool) -> &'static str {
-        if selected { self.selected_marker } else { " " }
+    /// Construct styles from a built-in theme.
+    pub(crate) fn new(theme: Theme) -> Self {
+        let mut styles = Self {
+            selected_marker: String::from("*"),
+            done_marker: String::from("✓"),
+            editing_marker: String::from(">"),
+            color_done: Color::Green,
+            color_normal: Color::Reset,
+            color_not_done: Color::Red,
+            color_dim: Color::DarkGray,
+            color_header: Color::Cyan,
+            color_editing: Color::Cyan,
+            color_warning: Color::Yellow,
+        };
+
+        match theme {
+            Theme::Default => {}
+            Theme::HighContrast => {
+                styles.selected_marker = String::from(">");
+                styles.editing_marker = String::from("»");
+                styles.color_done = Color::LightBlue;
+                styles.color_not_done = Color::LightYellow;
+                styles.color_dim = Color::Gray;
+                styles.color_header = Color::White;
+                styles.color_editing = Color::LightMagenta;
+                styles.color_warning = Color::LightYellow;
+            }
+            Theme::NoColor => {
+                styles.color_done = Color::Reset;
+                styles.color_not_done = Color::Reset;
+                styles.color_dim = Color::Reset;
+                styles.color_header = Color::Reset;
+                styles.color_editing = Color::Reset;
+                styles.color_warning = Color::Reset;
+            }
+        }
+
+        styles
+    }
+
+    /// Construct styles from the theme section of the configuration file,
+    /// where `theme` overrides the base theme specified in it.
+    pub(crate) fn from_config(theme: Option<Theme>, config: &ThemeConfig) -> Result<Self> {
+        let base = match (theme, &config.base) {
+            (Some(theme), _) => theme,
+            (None, Some(base)) => base.parse()?,
+            (None, None) => Theme::default(),
+        };
+
+        let mut styles = Self::new(base);
+
+        let markers = [
+            (&config.selected_marker, &mut styles.selected_marker),
+            (&config.done_marker, &mut styles.done_marker),
+            (&config.editing_marker, &mut styles.editing_marker),
+        ];
+
+        for (value, marker) in markers {
+            if let Some(value) = value {
+                marker.clone_from(value);
+            }
+        }
+
+        // Explicitly disabling colors takes precedence over configured
+        // colors.
+        if base == Theme::NoColor {
+            return Ok(styles);
+        }
+
+        let colors = [
+            ("done", &config.done, &mut styles.color_done),
+            ("normal", &config.normal, &mut styles.color_normal),
+            ("not-done", &config.not_done, &mut styles.color_not_done),
+            ("dim", &config.dim, &mut styles.color_dim),
+            ("header", &config.header, &mut styles.color_header),
+            ("editing", &config.editing, &mut styles.color_editing),
+            ("warning", &config.warning, &mut styles.color_warning),
+        ];
+
+        for (key, value, color) in colors {
+            if let Some(value) = value {
+                *color = value
+                    .parse()
+                    .ok()
+                    .with_context(|| anyhow!("Invalid color '{value}' for theme {key}"))?;
+            }
+        }
+
+        Ok(styles)
+    }
+
+    pub(crate) fn selected(&self, selected: bool) -> &str {
+        if selected { &self.selected_marker } else { " " }
     }
 
-    pub(crate) fn done(&self) -> &'static str {
-        self.done_marker
+    pub(crate) fn done(&self) -> &str {
+        &self.done_marker
     }
 
     pub(crate) fn no_name(&self) -> &'static str {
@@ -109,27 +240,13 @@ impl Styles {
         s
     }
 
-    pub(crate) fn input_marker(&self, selected: bool, editing: bool) -> &'static str {
+    pub(crate) fn input_marker(&self, selected: bool, editing: bool) -> &str {
         if editing {
-            self.editing_marker
+            &self.editing_marker
         } else if selected {
-            self.selected_marker
+            &self.selected_marker
         } else {
             " "
         }
     }
 }
-
-/// Global styles instance.
-pub(crate) const STYLES: Styles = Styles {
-    selected_marker: "*",
-    done_marker: "✓",
-    editing_marker: ">",
-    color_done: Color::Green,
-    color_normal: Color::Reset,
-    color_not_done: Color::Red,
-    color_dim: Color::DarkGray,
-    color_header: Color::Cyan,
-    color_editing: Color::Cyan,
-    color_warning: Color::Yellow,
-};
@@ -0,0 +1,157 @@
+mod _7z;
+mod rar;
+mod zip;
+
+use core::fmt;
+use core::str::FromStr;
+
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::Result;
+use relative_path::RelativePath;
+
+/// Error raised when parsing an unsupported archive kind.
+#[derive(Debug)]
+pub struct ArchiveErr;
+
+impl fmt::Display for ArchiveErr {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unsupported archive kind")
+    }
+}
+
+impl core::error::Error for ArchiveErr {}
+
+/// The kind of an archive which can be treated as a directory of files.
+#[derive(Debug, Clone, Copy)]
+pub enum Archive {
+    Zip,
+    Rar,
+    _7z,
+}
+
+impl Archive {
+    /// Detect an archive from its extension.
+    #[inline]
+    pub fn from_ext(ext: &str) -> Option<Self> {
+        match ext {
+            "zip" => Some(Archive::Zip),
+            "rar" => Some(Archive::Rar),
+            "7z" => Some(Archive::_7z),
+            _ => None,
+        }
+    }
+
+    /// Enumerate an archive of the current type.
+    ///
+    /// The callback receives the path of each file entry and its uncompressed
+    /// size, directories are skipped.
+    pub fn enumerate(
+        &self,
+        path: &Path,
+        entries: &mut dyn FnMut(&RelativePath, u64) -> Result<()>,
+    ) -> Result<()> {
+        match self {
+            Self::Rar => self::rar::enumerate(path, entries),
+            Self::Zip => self::zip::enumerate(path, entries),
+            Self::_7z => self::_7z::enumerate(path, entries),
+        }
+    }
+
+    /// Extract the contents of a file inside the archive.
+    pub fn contents(&self, archive_path: &Path, path: &RelativePath) -> Result<Option<Vec<u8>>> {
+        match self {
+            Archive::Rar => self::rar::contents(archive_path, path),
+            Archive::Zip => self::zip::contents(archive_path, path),
+            Archive::_7z => self::_7z::contents(archive_path, path),
+        }
+    }
+
+    /// Open the archive to read the contents of several files inside of it.
+    ///
+    /// The archive and an index of its entries are kept open between reads,
+    /// so that each read doesn't have to open and scan the archive again.
+    pub fn open(&self, archive_path: &Path) -> Result<OpenArchive> {
+        let inner = match self {
+            Archive::Rar => Inner::Rar(self::rar::Open::new(archive_path)),
+            Archive::Zip => Inner::Zip(self::zip::Open::new(archive_path)?),
+            Archive::_7z => Inner::_7z(Box::new(self::_7z::Open::new(archive_path)?)),
+        };
+
+        Ok(OpenArchive { inner })
+    }
+
+    /// Extract every file inside the archive in a single pass.
+    ///
+    /// The callback receives the path of each file entry and a reader of its
+    /// contents, directories are skipped. This avoids re-scanning the archive
+    /// for each entry, which is expensive for solid archives.
+    pub fn extract(
+        &self,
+        archive_path: &Path,
+        entries: &mut dyn FnMut(&RelativePath, &mut dyn Read) -> Result<()>,
+    ) -> Result<()> {
+        match self {
+            Archive::Rar => self::rar::extract(archive_path, entries),
+            Archive::Zip => self::zip::extract(archive_path, entries),
+            Archive::_7z => self::_7z::extract(archive_path, entries),
+        }
+    }
+}
+
+/// An archive opened with [`Archive::open`].
+pub struct OpenArchive {
+    inner: Inner,
+}
+
+enum Inner {
+    Rar(self::rar::Open),
+    Zip(self::zip::Open),
+    _7z(Box<self::_7z::Open>),
+}
+
+impl OpenArchive {
+    /// Extract the contents of a file inside the archive.
+    pub fn contents(&mut self, path: &RelativePath) -> Result<Option<Vec<u8>>> {
+        self.read(path, u64::MAX)
+    }
+
+    /// Extract at most the first `len` bytes of a file inside the archive,
+    /// like the header used to detect its format.
+    ///
+    /// Decompression stops once enough has been read, except for rar archives
+    /// where entries can only be read in full.
+    pub fn header(&mut self, path: &RelativePath, len: usize) -> Result<Option<Vec<u8>>> {
+        self.read(path, len as u64)
+    }
+
+    fn read(&mut self, path: &RelativePath, limit: u64) -> Result<Option<Vec<u8>>> {
+        match &mut self.inner {
+            Inner::Rar(open) => open.read(path, limit),
+            Inner::Zip(open) => open.read(path, limit),
+            Inner::_7z(open) => open.read(path, limit),
+        }
+    }
+}
+
+impl fmt::Display for Archive {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Archive::Zip => write!(f, "zip"),
+            Archive::Rar => write!(f, "rar"),
+            Archive::_7z => write!(f, "7z"),
+        }
+    }
+}
+
+impl FromStr for Archive {
+    type Err = ArchiveErr;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_ext(s).ok_or(ArchiveErr)
+    }
+}
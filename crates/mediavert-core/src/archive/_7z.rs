@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use relative_path::RelativePath;
+use sevenz_rust2::{Archive, BlockDecoder, Password};
+
+/// An open 7z archive, with an index of which block each entry is stored in
+/// so that only that block has to be decoded to read it.
+pub(super) struct Open {
+    file: File,
+    password: Password,
+    archive: Archive,
+    blocks: HashMap<String, usize>,
+}
+
+impl Open {
+    pub(super) fn new(archive_path: &Path) -> Result<Self> {
+        let mut file = File::open(archive_path)?;
+        let password = Password::empty();
+
+        let archive = Archive::read(&mut file, &password).context("opening archive")?;
+
+        let mut blocks = HashMap::new();
+
+        for block_index in 0..archive.blocks.len() {
+            let dec = BlockDecoder::new(1, block_index, &archive, &password, &mut file);
+
+            for entry in dec.entries() {
+                blocks.insert(entry.name().to_owned(), block_index);
+            }
+        }
+
+        Ok(Self {
+            file,
+            password,
+            archive,
+            blocks,
+        })
+    }
+
+    pub(super) fn read(&mut self, path: &RelativePath, limit: u64) -> Result<Option<Vec<u8>>> {
+        let Some(&block_index) = self.blocks.get(path.as_str()) else {
+            return Ok(None);
+        };
+
+        let dec = BlockDecoder::new(
+            1,
+            block_index,
+            &self.archive,
+            &self.password,
+            &mut self.file,
+        );
+
+        let mut contents = Vec::new();
+
+        dec.for_each_entries(&mut |entry, reader| {
+            if entry.name() == path {
+                io::copy(&mut reader.take(limit), &mut contents)?;
+                Ok(false)
+            } else {
+                io::copy(reader, &mut io::sink())?;
+                Ok(true)
+            }
+        })?;
+
+        Ok(Some(contents))
+    }
+}
+
+pub(super) fn enumerate(
+    archive_path: &Path,
+    entries: &mut dyn FnMut(&RelativePath, u64) -> Result<()>,
+) -> Result<()> {
+    let mut file = File::open(archive_path)?;
+    let password = Password::empty();
+
+    let archive = Archive::read(&mut file, &password).context("opening archive")?;
+
+    let block_count = archive.blocks.len();
+
+    for block_index in 0..block_count {
+        let dec = BlockDecoder::new(1, block_index, &archive, &password, &mut file);
+
+        for entry in dec.entries() {
+            if entry.is_directory() {
+                continue;
+            }
+
+            entries(RelativePath::new(entry.name()), entry.size())?;
+        }
+    }
+
+    Ok(())
+}
+
+pub(super) fn contents(archive_path: &Path, path: &RelativePath) -> Result<Option<Vec<u8>>> {
+    let mut file = File::open(archive_path)?;
+    let password = Password::empty();
+
+    let archive = Archive::read(&mut file, &password).context("opening archive")?;
+
+    let block_count = archive.blocks.len();
+
+    for block_index in 0..block_count {
+        let dec = BlockDecoder::new(1, block_index, &archive, &password, &mut file);
+
+        let found = dec.entries().iter().any(|e| e.name() == path);
+
+        if !found {
+            continue;
+        }
+
+        let mut contents = Vec::new();
+
+        dec.for_each_entries(&mut |entry, reader| {
+            if entry.name() == path {
+                io::copy(reader, &mut contents)?;
+                Ok(false)
+            } else {
+                io::copy(reader, &mut io::sink())?;
+                Ok(true)
+            }
+        })?;
+
+        return Ok(Some(contents));
+    }
+
+    Ok(None)
+}
+
+pub(super) fn extract(
+    archive_path: &Path,
+    entries: &mut dyn FnMut(&RelativePath, &mut dyn Read) -> Result<()>,
+) -> Result<()> {
+    let mut file = File::open(archive_path)?;
+    let password = Password::empty();
+
+    let archive = Archive::read(&mut file, &password).context("opening archive")?;
+
+    let block_count = archive.blocks.len();
+    let mut error = None;
+
+    for block_index in 0..block_count {
+        let dec = BlockDecoder::new(1, block_index, &archive, &password, &mut file);
+
+        // Each block is decoded once, with its entries handed out in order.
+        dec.for_each_entries(&mut |entry, reader| {
+            if entry.is_directory() {
+                return Ok(true);
+            }
+
+            if let Err(e) = entries(RelativePath::new(entry.name()), reader) {
+                error = Some(e);
+                return Ok(false);
+            }
+
+            Ok(true)
+        })?;
+
+        if let Some(e) = error {
+            return Err(e);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use relative_path::RelativePath;
+use unrar::Archive;
+
+/// An open rar archive.
+///
+/// Rar archives can only be processed from the start, so each read scans the
+/// headers up until the entry being read.
+pub(super) struct Open {
+    path: PathBuf,
+}
+
+impl Open {
+    pub(super) fn new(archive_path: &Path) -> Self {
+        Self {
+            path: archive_path.to_owned(),
+        }
+    }
+
+    pub(super) fn read(&mut self, path: &RelativePath, limit: u64) -> Result<Option<Vec<u8>>> {
+        let Some(mut contents) = contents(&self.path, path)? else {
+            return Ok(None);
+        };
+
+        contents.truncate(usize::try_from(limit).unwrap_or(usize::MAX));
+        Ok(Some(contents))
+    }
+}
+
+pub(super) fn enumerate(
+    archive_path: &Path,
+    entries: &mut dyn FnMut(&RelativePath, u64) -> Result<()>,
+) -> Result<()> {
+    let archive = Archive::new(archive_path);
+    let open_archive = archive.open_for_listing()?;
+
+    for e in open_archive {
+        let e = e?;
+
+        if e.is_directory() {
+            continue;
+        }
+
+        let Some(name) = e.filename.as_os_str().to_str() else {
+            continue;
+        };
+
+        entries(RelativePath::new(name), e.unpacked_size)?;
+    }
+
+    Ok(())
+}
+
+pub(super) fn contents(archive_path: &Path, path: &RelativePath) -> Result<Option<Vec<u8>>> {
+    let archive = Archive::new(archive_path);
+    let mut archive = archive.open_for_processing()?;
+
+    while let Some(a) = archive.read_header()? {
+        if a.entry().filename.to_str() == Some(path.as_str()) {
+            let (contents, _) = a.read()?;
+            return Ok(Some(contents));
+        }
+
+        archive = a.skip()?;
+    }
+
+    Ok(None)
+}
+
+pub(super) fn extract(
+    archive_path: &Path,
+    entries: &mut dyn FnMut(&RelativePath, &mut dyn Read) -> Result<()>,
+) -> Result<()> {
+    let archive = Archive::new(archive_path);
+    let mut archive = archive.open_for_processing()?;
+
+    while let Some(a) = archive.read_header()? {
+        let name = a
+            .entry()
+            .filename
+            .to_str()
+            .filter(|_| !a.entry().is_directory())
+            .map(str::to_owned);
+
+        let Some(name) = name else {
+            archive = a.skip()?;
+            continue;
+        };
+
+        let (contents, next) = a.read()?;
+        entries(RelativePath::new(&name), &mut &contents[..])?;
+        archive = next;
+    }
+
+    Ok(())
+}
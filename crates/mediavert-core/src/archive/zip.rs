@@ -0,0 +1,93 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use relative_path::RelativePath;
+use zip::ZipArchive;
+use zip::result::ZipError;
+
+/// An open zip archive, which looks up entries through its central directory.
+pub(super) struct Open {
+    archive: ZipArchive<File>,
+}
+
+impl Open {
+    pub(super) fn new(archive_path: &Path) -> Result<Self> {
+        let reader = File::open(archive_path)?;
+        let archive = ZipArchive::new(reader).context("opening archive")?;
+        Ok(Self { archive })
+    }
+
+    pub(super) fn read(&mut self, path: &RelativePath, limit: u64) -> Result<Option<Vec<u8>>> {
+        let file = match self.archive.by_name(path.as_str()) {
+            Ok(file) => file,
+            Err(ZipError::FileNotFound) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut contents = Vec::with_capacity(file.size().min(limit) as usize);
+        file.take(limit).read_to_end(&mut contents)?;
+        Ok(Some(contents))
+    }
+}
+
+pub(super) fn enumerate(
+    archive_path: &Path,
+    entries: &mut dyn FnMut(&RelativePath, u64) -> Result<()>,
+) -> Result<()> {
+    let reader = File::open(archive_path)?;
+    let mut archive = ZipArchive::new(reader)?;
+
+    for i in 0..archive.len() {
+        let file = archive.by_index(i)?;
+
+        if file.is_dir() {
+            continue;
+        }
+
+        entries(RelativePath::new(file.name()), file.size())?;
+    }
+
+    Ok(())
+}
+
+pub(super) fn contents(archive_path: &Path, path: &RelativePath) -> Result<Option<Vec<u8>>> {
+    let reader = File::open(archive_path)?;
+    let mut archive = ZipArchive::new(reader).context("opening archive")?;
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+
+        if file.name() != path.as_str() {
+            continue;
+        }
+
+        let mut contents = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut contents)?;
+        return Ok(Some(contents));
+    }
+
+    Ok(None)
+}
+
+pub(super) fn extract(
+    archive_path: &Path,
+    entries: &mut dyn FnMut(&RelativePath, &mut dyn Read) -> Result<()>,
+) -> Result<()> {
+    let reader = File::open(archive_path)?;
+    let mut archive = ZipArchive::new(reader).context("opening archive")?;
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+
+        if file.is_dir() {
+            continue;
+        }
+
+        let name = file.name().to_owned();
+        entries(RelativePath::new(&name), &mut file)?;
+    }
+
+    Ok(())
+}
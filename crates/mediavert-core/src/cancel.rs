@@ -0,0 +1,94 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use anyhow::Result;
+
+const RUNNING: u8 = 0;
+const CANCELLED: u8 = 1;
+const ABORTED: u8 = 2;
+
+/// A token used to cancel a run.
+///
+/// A cancelled run finishes the task it is currently working on before
+/// stopping, while an aborted run stops immediately and cleans up after the
+/// task it was working on. Clones of a token share the same state.
+#[derive(Default, Clone)]
+pub struct Cancel {
+    state: Arc<AtomicU8>,
+}
+
+impl Cancel {
+    /// Construct a token which is only cancelled through [`Cancel::cancel`] or
+    /// [`Cancel::abort`].
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Construct a token which is cancelled by the first Ctrl-C and aborted by
+    /// the second, or aborted right away when the process is asked to
+    /// terminate.
+    ///
+    /// This replaces the default handling of Ctrl-C and `SIGTERM` for the
+    /// remainder of the process.
+    pub fn ctrl_c() -> Result<Self> {
+        let cancel = Self::new();
+        listen(cancel.clone())?;
+        Ok(cancel)
+    }
+
+    /// Cancel the run, letting the current task finish.
+    #[inline]
+    pub fn cancel(&self) {
+        self.state.fetch_max(CANCELLED, Ordering::SeqCst);
+    }
+
+    /// Abort the run immediately.
+    #[inline]
+    pub fn abort(&self) {
+        self.state.store(ABORTED, Ordering::SeqCst);
+    }
+
+    /// Test if the run has been cancelled or aborted.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.state.load(Ordering::SeqCst) != RUNNING
+    }
+
+    /// Test if the run has been aborted.
+    #[inline]
+    pub fn is_aborted(&self) -> bool {
+        self.state.load(Ordering::SeqCst) == ABORTED
+    }
+}
+
+#[cfg(unix)]
+fn listen(cancel: Cancel) -> Result<()> {
+    use std::thread;
+
+    use signal_hook::consts::{SIGINT, SIGTERM};
+    use signal_hook::iterator::Signals;
+
+    let mut signals = Signals::new([SIGINT, SIGTERM])?;
+
+    thread::spawn(move || {
+        for signal in signals.forever() {
+            // Nobody is around to press Ctrl-C again when the process is
+            // terminated, like when a system shuts down.
+            if signal == SIGTERM || cancel.is_cancelled() {
+                eprintln!("Aborting");
+                cancel.abort();
+            } else {
+                eprintln!("Stopping after the current task, press Ctrl-C again to abort");
+                cancel.cancel();
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn listen(_: Cancel) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use core::cmp::Ordering;
+use core::fmt;
+use core::str::FromStr;
+
+use anyhow::{Error, Result, anyhow};
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+/// How names are ordered in listings shown to the user.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Collation {
+    /// Compare letters without regard to case or accents and runs of digits by
+    /// their numeric value.
+    #[default]
+    Natural,
+    /// Compare the bytes of names.
+    Bytewise,
+}
+
+impl Collation {
+    /// Compare two names.
+    pub fn compare(self, a: &str, b: &str) -> Ordering {
+        match self {
+            Collation::Natural => natural(a, b),
+            Collation::Bytewise => a.cmp(b),
+        }
+    }
+}
+
+impl FromStr for Collation {
+    type Err = Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "natural" => Ok(Collation::Natural),
+            "bytewise" => Ok(Collation::Bytewise),
+            _ => Err(anyhow!("Invalid sort order '{s}'")),
+        }
+    }
+}
+
+impl fmt::Display for Collation {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Collation::Natural => write!(f, "natural"),
+            Collation::Bytewise => write!(f, "bytewise"),
+        }
+    }
+}
+
+/// Compare two names the way a person would expect them to be listed.
+///
+/// Letters are compared without regard to case or accents and runs of digits
+/// by their numeric value, so `Vol 2` comes before `Vol 10` and `Émile` sorts
+/// next to `Emile`. Names which are otherwise equal are compared bytewise so
+/// that the ordering is total.
+pub fn natural(a: &str, b: &str) -> Ordering {
+    compare_folded(&fold(a), &fold(b)).then_with(|| a.cmp(b))
+}
+
+/// Strip accents from and lowercase a name.
+fn fold(s: &str) -> String {
+    s.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn compare_folded(mut a: &str, mut b: &str) -> Ordering {
+    loop {
+        let (Some(x), Some(y)) = (a.chars().next(), b.chars().next()) else {
+            return a.len().cmp(&b.len());
+        };
+
+        if x.is_ascii_digit() && y.is_ascii_digit() {
+            let (n, rest_a) = digits(a);
+            let (m, rest_b) = digits(b);
+
+            let ordering = n.len().cmp(&m.len()).then_with(|| n.cmp(m));
+
+            if ordering.is_ne() {
+                return ordering;
+            }
+
+            a = rest_a;
+            b = rest_b;
+            continue;
+        }
+
+        let ordering = x.cmp(&y);
+
+        if ordering.is_ne() {
+            return ordering;
+        }
+
+        a = &a[x.len_utf8()..];
+        b = &b[y.len_utf8()..];
+    }
+}
+
+/// Split off a leading run of digits without leading zeros.
+fn digits(s: &str) -> (&str, &str) {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, rest) = s.split_at(end);
+    (number.trim_start_matches('0'), rest)
+}
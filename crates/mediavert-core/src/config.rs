@@ -0,0 +1,142 @@
+use core::fmt;
+use core::num::NonZeroUsize;
+use core::str::FromStr;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use termcolor::ColorChoice;
+
+use crate::plugin::Plugin;
+
+/// When to use colored output.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorMode {
+    /// Use colors if the output supports it.
+    #[default]
+    Auto,
+    /// Always use colors.
+    Always,
+    /// Never use colors.
+    Never,
+}
+
+impl ColorMode {
+    /// Convert into the corresponding [`ColorChoice`].
+    pub fn choice(self) -> ColorChoice {
+        match self {
+            ColorMode::Auto => ColorChoice::Auto,
+            ColorMode::Always => ColorChoice::Always,
+            ColorMode::Never => ColorChoice::Never,
+        }
+    }
+}
+
+impl FromStr for ColorMode {
+    type Err = anyhow::Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            "never" => Ok(ColorMode::Never),
+            _ => Err(anyhow!("Invalid color mode '{s}'")),
+        }
+    }
+}
+
+impl fmt::Display for ColorMode {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorMode::Auto => write!(f, "auto"),
+            ColorMode::Always => write!(f, "always"),
+            ColorMode::Never => write!(f, "never"),
+        }
+    }
+}
+
+/// Settings shared by all tools.
+///
+/// These can be specified at the top level of the configuration file, and be
+/// overridden in the section of an individual tool like `[audiovert]`.
+#[derive(Default, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Shared {
+    /// The directory files are moved to when trashed.
+    pub trash: Option<PathBuf>,
+    /// When to use colored output.
+    pub color: Option<ColorMode>,
+    /// The number of jobs to run in parallel.
+    pub jobs: Option<NonZeroUsize>,
+    /// The directory to store temporary files in.
+    pub tmp_dir: Option<PathBuf>,
+    /// Helpers used to fetch remote sources by URL scheme.
+    pub remote: BTreeMap<String, Plugin>,
+}
+
+impl Shared {
+    /// Combine two sets of settings, where settings in `self` take precedence
+    /// over the ones in `other`.
+    pub fn or(self, other: Shared) -> Shared {
+        Shared {
+            trash: self.trash.or(other.trash),
+            color: self.color.or(other.color),
+            jobs: self.jobs.or(other.jobs),
+            tmp_dir: self.tmp_dir.or(other.tmp_dir),
+            remote: {
+                let mut remote = other.remote;
+                remote.extend(self.remote);
+                remote
+            },
+        }
+    }
+}
+
+/// The default location of the configuration file, which is
+/// `mediavert/config.toml` in the user's configuration directory.
+pub fn default_path() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("mediavert").join("config.toml"))
+}
+
+/// The directory cached data is stored in, which is `mediavert` in the user's
+/// cache directory.
+pub fn cache_dir() -> Option<PathBuf> {
+    Some(dirs::cache_dir()?.join("mediavert"))
+}
+
+/// Load the configuration file.
+///
+/// If `path` is not specified the default location is used, and a missing
+/// file at the default location results in the default configuration.
+pub fn load<T>(path: Option<&Path>) -> Result<T>
+where
+    T: Default + DeserializeOwned,
+{
+    let (path, required) = match path {
+        Some(path) => (path.to_path_buf(), true),
+        None => match default_path() {
+            Some(path) => (path, false),
+            None => return Ok(T::default()),
+        },
+    };
+
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound && !required => {
+            return Ok(T::default());
+        }
+        Err(e) => {
+            return Err(e).with_context(|| anyhow!("Failed to read {}", path.display()));
+        }
+    };
+
+    toml::from_str(&contents).with_context(|| anyhow!("Failed to parse {}", path.display()))
+}
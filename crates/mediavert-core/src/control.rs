@@ -0,0 +1,219 @@
+use core::time::Duration;
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use anyhow::{Context, Result, anyhow};
+use serde::Serialize;
+
+use crate::cancel::Cancel;
+
+/// How often a paused run checks whether it has been cancelled.
+const POLL: Duration = Duration::from_millis(250);
+
+/// The state of a run as reported through the control socket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RunState {
+    /// Tasks are being processed.
+    Running,
+    /// The run will pause, or has paused, after the current task.
+    Paused,
+    /// The run will stop after the current task.
+    Stopping,
+}
+
+#[derive(Serialize)]
+struct Status {
+    state: RunState,
+    completed: usize,
+    total: usize,
+    current: Option<String>,
+}
+
+struct Shared {
+    status: Mutex<Status>,
+    changed: Condvar,
+}
+
+/// A control socket which lets external clients query the progress of a run,
+/// pause or resume it, or request that it stops gracefully.
+///
+/// Clients connect to the socket and send one command per line, which is one
+/// of `status`, `pause`, `resume`, or `stop`. Every command is answered with
+/// the status of the run as a single line of JSON.
+pub struct Control {
+    shared: Arc<Shared>,
+    path: Option<PathBuf>,
+}
+
+impl Control {
+    /// Construct a control which can't be reached from the outside.
+    pub fn disabled() -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                status: Mutex::new(Status {
+                    state: RunState::Running,
+                    completed: 0,
+                    total: 0,
+                    current: None,
+                }),
+                changed: Condvar::new(),
+            }),
+            path: None,
+        }
+    }
+
+    /// Bind a control socket to the given path, or construct a disabled
+    /// control if `None`.
+    pub fn bind(path: Option<&Path>) -> Result<Self> {
+        let mut this = Self::disabled();
+
+        let Some(path) = path else {
+            return Ok(this);
+        };
+
+        listen(path, this.shared.clone())
+            .with_context(|| anyhow!("Binding control socket {}", path.display()))?;
+
+        this.path = Some(path.to_owned());
+        Ok(this)
+    }
+
+    /// Reset the progress before starting a new run, like when watching for
+    /// new files. A stop which was requested for the previous run doesn't
+    /// carry over, while a pause does.
+    pub fn reset(&self) {
+        let mut status = self.shared.status.lock().unwrap();
+
+        if status.state == RunState::Stopping {
+            status.state = RunState::Running;
+        }
+
+        status.completed = 0;
+        status.total = 0;
+        status.current = None;
+    }
+
+    /// Set the total number of tasks in the run.
+    pub fn set_total(&self, total: usize) {
+        self.shared.status.lock().unwrap().total = total;
+    }
+
+    /// Mark that the given task is being processed.
+    pub fn start_task(&self, current: String) {
+        self.shared.status.lock().unwrap().current = Some(current);
+    }
+
+    /// Mark that the current task has finished.
+    pub fn finish_task(&self) {
+        let mut status = self.shared.status.lock().unwrap();
+        status.completed += 1;
+        status.current = None;
+    }
+
+    /// Wait while the run is paused.
+    ///
+    /// This should be called in between tasks, and returns `false` if the run
+    /// should stop, either because it was requested through the socket or
+    /// because the given token was cancelled while paused.
+    pub fn checkpoint(&self, cancel: &Cancel) -> bool {
+        let mut status = self.shared.status.lock().unwrap();
+
+        while status.state == RunState::Paused {
+            if cancel.is_cancelled() {
+                return false;
+            }
+
+            (status, _) = self.shared.changed.wait_timeout(status, POLL).unwrap();
+        }
+
+        status.state != RunState::Stopping
+    }
+}
+
+impl Drop for Control {
+    fn drop(&mut self) {
+        if let Some(path) = &self.path {
+            _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Handle a single command, returning the status to respond with.
+fn command(shared: &Shared, command: &str) -> Result<String> {
+    let mut status = shared.status.lock().unwrap();
+
+    match command {
+        "status" => {}
+        "pause" if status.state == RunState::Running => {
+            status.state = RunState::Paused;
+        }
+        "resume" if status.state == RunState::Paused => {
+            status.state = RunState::Running;
+        }
+        "stop" => {
+            status.state = RunState::Stopping;
+        }
+        "pause" | "resume" => {}
+        _ => return Err(anyhow!("Unknown command '{command}'")),
+    }
+
+    shared.changed.notify_all();
+    Ok(serde_json::to_string(&*status)?)
+}
+
+fn serve<S>(shared: &Shared, stream: S) -> Result<()>
+where
+    S: std::io::Read + Write,
+{
+    let mut stream = BufReader::new(stream);
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+
+        if stream.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+
+        let response = match command(shared, line.trim()) {
+            Ok(status) => status,
+            Err(e) => serde_json::json!({ "error": e.to_string() }).to_string(),
+        };
+
+        let stream = stream.get_mut();
+        stream.write_all(response.as_bytes())?;
+        stream.write_all(b"\n")?;
+        stream.flush()?;
+    }
+}
+
+#[cfg(unix)]
+fn listen(path: &Path, shared: Arc<Shared>) -> Result<()> {
+    use std::os::unix::net::UnixListener;
+
+    let listener = UnixListener::bind(path)?;
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let shared = shared.clone();
+
+            thread::spawn(move || {
+                _ = serve(&shared, stream);
+            });
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn listen(_: &Path, _: Arc<Shared>) -> Result<()> {
+    Err(anyhow!(
+        "Control sockets are not supported on this platform"
+    ))
+}
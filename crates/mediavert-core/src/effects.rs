@@ -0,0 +1,194 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus};
+
+/// The single layer through which operations that modify the filesystem are
+/// performed.
+///
+/// In read-only mode every operation fails with an error of the kind
+/// [`io::ErrorKind::ReadOnlyFilesystem`] instead of being performed. This is
+/// used to make sure that a dry run doesn't change anything, even if a check
+/// for it has been missed somewhere. In strict mode, which is always enabled in
+/// the tests of this crate, a refused operation panics instead so that a test
+/// exercising a missed check fails.
+///
+/// In durable mode files which are written, copied, or renamed into place are
+/// synced to disk along with the directory they're in, so that a power loss
+/// can't leave an empty file behind which looks like it has been completed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Effects {
+    read_only: bool,
+    durable: bool,
+    strict: bool,
+}
+
+impl Effects {
+    /// Construct a new effects layer, which refuses to perform any operations
+    /// if `read_only` is set.
+    #[inline]
+    pub fn new(read_only: bool) -> Self {
+        Self {
+            read_only,
+            durable: false,
+            strict: false,
+        }
+    }
+
+    /// Set whether completed files should be synced to disk.
+    #[inline]
+    pub fn with_durable(self, durable: bool) -> Self {
+        Self { durable, ..self }
+    }
+
+    /// Set whether refused operations panic instead of returning an error,
+    /// which is intended for tests.
+    #[inline]
+    pub fn with_strict(self, strict: bool) -> Self {
+        Self { strict, ..self }
+    }
+
+    /// Test if the layer is read-only.
+    #[inline]
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Check that an operation which isn't performed through this layer, like
+    /// `what` being done to `path` by a library, is permitted.
+    pub fn check(&self, what: &str, path: &Path) -> io::Result<()> {
+        if !self.read_only {
+            return Ok(());
+        }
+
+        let message = format!("Refusing to {what} {} in read-only mode", path.display());
+
+        if cfg!(test) || self.strict {
+            panic!("{message}");
+        }
+
+        Err(io::Error::new(io::ErrorKind::ReadOnlyFilesystem, message))
+    }
+
+    /// Guarded version of [`fs::create_dir_all`].
+    pub fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.check("create directory", path)?;
+        fs::create_dir_all(path)
+    }
+
+    /// Guarded version of [`fs::write`].
+    pub fn write(&self, path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+        self.check("write", path)?;
+
+        if !self.durable {
+            return fs::write(path, contents);
+        }
+
+        let mut file = File::create(path)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        sync_parent(path)
+    }
+
+    /// Guarded version of [`File::create`].
+    pub fn create(&self, path: &Path) -> io::Result<File> {
+        self.check("create", path)?;
+        File::create(path)
+    }
+
+    /// Open a file for appending, creating it if it doesn't exist.
+    pub fn append(&self, path: &Path) -> io::Result<File> {
+        self.check("append to", path)?;
+        OpenOptions::new().create(true).append(true).open(path)
+    }
+
+    /// Guarded version of [`fs::rename`].
+    pub fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.check("move", from)?;
+        self.check("replace", to)?;
+
+        if !self.durable {
+            return fs::rename(from, to);
+        }
+
+        // The file might have been written by another process, so its
+        // contents have to be synced before it's moved into place.
+        if from.is_file() {
+            File::open(from)?.sync_all()?;
+        }
+
+        fs::rename(from, to)?;
+        sync_parent(to)
+    }
+
+    /// Guarded version of [`fs::copy`].
+    pub fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        self.check("copy to", to)?;
+        let n = fs::copy(from, to)?;
+
+        if self.durable {
+            File::open(to)?.sync_all()?;
+            sync_parent(to)?;
+        }
+
+        Ok(n)
+    }
+
+    /// Guarded version of [`fs::hard_link`].
+    pub fn hard_link(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.check("link to", to)?;
+        fs::hard_link(from, to)
+    }
+
+    /// Guarded version of [`fs::remove_file`].
+    pub fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.check("remove", path)?;
+        fs::remove_file(path)
+    }
+
+    /// Guarded version of [`fs::remove_dir`].
+    pub fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        self.check("remove directory", path)?;
+        fs::remove_dir(path)
+    }
+
+    /// Guarded version of [`fs::remove_dir_all`].
+    pub fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.check("remove directory", path)?;
+        fs::remove_dir_all(path)
+    }
+
+    /// Spawn a command which is expected to write to the filesystem.
+    pub fn spawn(&self, command: &mut Command) -> io::Result<Child> {
+        self.check("run", Path::new(command.get_program()))?;
+        command.spawn()
+    }
+
+    /// Run a command which is expected to write to the filesystem to
+    /// completion.
+    pub fn status(&self, command: &mut Command) -> io::Result<ExitStatus> {
+        self.check("run", Path::new(command.get_program()))?;
+        command.status()
+    }
+}
+
+/// Sync the directory containing the given path, so that a new entry in it
+/// survives a power loss.
+fn sync_parent(path: &Path) -> io::Result<()> {
+    // Directories can't be opened for syncing on Windows.
+    if cfg!(windows) {
+        return Ok(());
+    }
+
+    let Some(parent) = path.parent() else {
+        return Ok(());
+    };
+
+    let parent = if parent.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        parent
+    };
+
+    File::open(parent)?.sync_all()
+}
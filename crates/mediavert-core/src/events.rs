@@ -0,0 +1,213 @@
+use core::fmt;
+use core::str::FromStr;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result, anyhow};
+use serde::Serialize;
+
+/// The version of the event protocol.
+///
+/// This is included in every event and is incremented whenever an existing
+/// event changes in an incompatible way. New events and fields may be added
+/// without changing the version.
+pub const VERSION: u32 = 1;
+
+/// How the progress of a run is written to stdout.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Indented and colored output meant to be read by a human.
+    #[default]
+    Human,
+    /// Structured events as JSON, one per line, instead of human output.
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(OutputFormat::Human),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(anyhow!("Invalid output format '{s}'")),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputFormat::Human => write!(f, "human"),
+            OutputFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// An event in the structured event stream.
+///
+/// Events are written as JSON, one per line, with a `type` field identifying
+/// the event and a `version` field with the protocol [`VERSION`].
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Event<'a> {
+    /// The tasks which will be performed have been planned.
+    Plan {
+        /// The number of tasks which will be performed.
+        tasks: usize,
+    },
+    /// A single task has been planned, which is emitted for every task after
+    /// [`Event::Plan`].
+    TaskPlanned {
+        /// The zero-based index of the task.
+        index: usize,
+        /// What the task does, like `converting flac to mp3`.
+        kind: &'a str,
+        /// The source of the task.
+        from: &'a str,
+        /// The destination of the task.
+        to: &'a str,
+    },
+    /// A task has started.
+    TaskStart {
+        /// The zero-based index of the task.
+        index: usize,
+        /// What the task does, like `converting flac to mp3`.
+        kind: &'a str,
+        /// The source of the task.
+        from: &'a str,
+        /// The destination of the task.
+        to: &'a str,
+    },
+    /// A task has reached a new step.
+    TaskProgress {
+        /// The zero-based index of the task.
+        index: usize,
+        /// The step, like `convert`, `tag`, `rename`, or `transfer`.
+        step: &'a str,
+    },
+    /// A task has finished.
+    TaskDone {
+        /// The zero-based index of the task.
+        index: usize,
+        /// Whether the task completed successfully.
+        ok: bool,
+    },
+    /// A file has been moved to the trash.
+    Trashed {
+        /// What was trashed, like `source file`.
+        what: &'a str,
+        /// Where the file was.
+        from: &'a str,
+        /// Where the file was moved to in the trash.
+        to: &'a str,
+    },
+    /// A warning which doesn't prevent the run from continuing.
+    Warning {
+        /// The category of the warning, if it has one.
+        code: Option<&'a str>,
+        /// The path the warning concerns, if any.
+        path: Option<&'a str>,
+        /// The warning message.
+        message: &'a str,
+    },
+    /// An error, either for a single task or one which aborts the run.
+    Error {
+        /// The zero-based index of the task the error concerns, if any.
+        index: Option<usize>,
+        /// The path the error concerns, if any.
+        path: Option<&'a str>,
+        /// The error message.
+        message: &'a str,
+    },
+    /// The run has finished, with a summary specific to the tool.
+    Summary {
+        /// The summary of the run.
+        summary: serde_json::Value,
+    },
+}
+
+/// Where events are written to.
+type Writer = BufWriter<Box<dyn Write + Send>>;
+
+#[derive(Serialize)]
+struct Line<'a> {
+    version: u32,
+    #[serde(flatten)]
+    event: &'a Event<'a>,
+}
+
+/// A destination for structured events.
+///
+/// Clones write to the same destination, so that events can be emitted from
+/// multiple threads.
+#[derive(Clone)]
+pub struct Events {
+    out: Option<Arc<Mutex<Writer>>>,
+}
+
+impl Events {
+    /// Construct a destination which discards all events.
+    pub fn disabled() -> Self {
+        Self { out: None }
+    }
+
+    /// Open the given path to write events to, or discard events if `None`.
+    ///
+    /// The path can be a named pipe, or a file descriptor like `/dev/fd/3`.
+    pub fn open(path: Option<&Path>) -> Result<Self> {
+        let Some(path) = path else {
+            return Ok(Self::disabled());
+        };
+
+        let file =
+            File::create(path).with_context(|| anyhow!("Opening events {}", path.display()))?;
+
+        Ok(Self::new(Box::new(file)))
+    }
+
+    /// Write events to stdout, which is used when they replace the human
+    /// output of a tool.
+    pub fn stdout() -> Self {
+        Self::new(Box::new(io::stdout()))
+    }
+
+    fn new(out: Box<dyn Write + Send>) -> Self {
+        Self {
+            out: Some(Arc::new(Mutex::new(BufWriter::new(out)))),
+        }
+    }
+
+    /// Test if events are enabled.
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.out.is_some()
+    }
+
+    /// Emit an event.
+    ///
+    /// Each event is flushed immediately so that it can be observed while the
+    /// run is in progress.
+    pub fn emit(&mut self, event: &Event<'_>) -> Result<()> {
+        let Some(out) = &self.out else {
+            return Ok(());
+        };
+
+        let mut out = out.lock().unwrap_or_else(|e| e.into_inner());
+
+        let line = Line {
+            version: VERSION,
+            event,
+        };
+
+        serde_json::to_writer(&mut *out, &line)?;
+        out.write_all(b"\n")?;
+        out.flush()?;
+        Ok(())
+    }
+}
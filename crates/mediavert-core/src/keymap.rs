@@ -0,0 +1,84 @@
+use ratatui::crossterm::event::KeyCode::{
+    self, Backspace, Char, Delete, Down, Enter, Esc, Left, Right, Up,
+};
+
+/// A single key binding which performs an action of type `A`.
+pub struct Binding<A: 'static> {
+    /// The key codes which trigger the binding.
+    pub codes: &'static [KeyCode],
+    /// The action performed.
+    pub action: A,
+    /// What the action does in the view it is bound in.
+    pub description: &'static str,
+    /// Whether the binding is included in the header hint of a view.
+    pub hint: bool,
+}
+
+impl<A> Binding<A> {
+    /// Human readable description of the keys, like `Enter/o`.
+    pub fn keys(&self) -> String {
+        let keys = self
+            .codes
+            .iter()
+            .map(|&code| label(code))
+            .collect::<Vec<_>>();
+        keys.join("/")
+    }
+}
+
+/// The key bindings of a single view.
+pub struct Keymap<A: 'static> {
+    /// The title of the keymap as shown in the help overlay.
+    pub title: &'static str,
+    /// The bindings of the view.
+    pub bindings: &'static [Binding<A>],
+}
+
+impl<A> Keymap<A>
+where
+    A: Copy,
+{
+    /// Look up the action bound to the given key code.
+    pub fn action(&self, code: KeyCode) -> Option<A> {
+        let binding = self.bindings.iter().find(|b| b.codes.contains(&code))?;
+        Some(binding.action)
+    }
+
+    /// Construct the hint shown in the header of a view, followed by the
+    /// hinted bindings of `global` which are available in every view.
+    pub fn hint(&self, global: &Keymap<A>) -> String {
+        let mut parts = Vec::new();
+
+        for b in self.bindings.iter().filter(|b| b.hint) {
+            parts.push(format!("{} to {}", b.keys(), b.description));
+        }
+
+        for b in global.bindings.iter().filter(|b| b.hint) {
+            parts.push(format!("{} for {}", b.keys(), b.description));
+        }
+
+        format!(" ({})", parts.join(", "))
+    }
+
+    /// Iterate over the keys and descriptions of all bindings.
+    pub fn entries(&self) -> impl Iterator<Item = (String, &'static str)> {
+        self.bindings.iter().map(|b| (b.keys(), b.description))
+    }
+}
+
+/// The label of a key code as shown to the user.
+fn label(code: KeyCode) -> String {
+    match code {
+        Up => "↑".to_string(),
+        Down => "↓".to_string(),
+        Left => "←".to_string(),
+        Right => "→".to_string(),
+        Enter => "Enter".to_string(),
+        Esc => "Esc".to_string(),
+        Backspace => "Backspace".to_string(),
+        Delete => "Delete".to_string(),
+        Char(' ') => "Space".to_string(),
+        Char(c) => c.to_string(),
+        code => code.to_string(),
+    }
+}
@@ -0,0 +1,54 @@
+//! [<img alt="github" src="https://img.shields.io/badge/github-udoprog/mediavert-8da0cb?style=for-the-badge&logo=github" height="20">](https://github.com/udoprog/mediavert)
+//! [<img alt="crates.io" src="https://img.shields.io/crates/v/mediavert-core.svg?style=for-the-badge&color=fc8d62&logo=rust" height="20">](https://crates.io/crates/mediavert-core)
+//! [<img alt="docs.rs" src="https://img.shields.io/badge/docs.rs-mediavert--core-66c2a5?style=for-the-badge&logoColor=white&logo=data:image/svg+xml;base64,PHN2ZyByb2xlPSJpbWciIHhtbG5zPSJodHRwOi8vd3d3LnczLm9yZy8yMDAwL3N2ZyIgdmlld0JveD0iMCAwIDUxMiA1MTIiPjxwYXRoIGZpbGw9IiNmNWY1ZjUiIGQ9Ik00ODguNiAyNTAuMkwzOTIgMjE0VjEwNS41YzAtMTUtOS4zLTI4LjQtMjMuNC0zMy43bC0xMDAtMzcuNWMtOC4xLTMuMS0xNy4xLTMuMS0yNS4zIDBsLTEwMCAzNy41Yy0xNC4xIDUuMy0yMy40IDE4LjctMjMuNCAzMy43VjIxNGwtOTYuNiAzNi4yQzkuMyAyNTUuNSAwIDI2OC45IDAgMjgzLjlWMzk0YzAgMTMuNiA3LjcgMjYuMSAxOS45IDMyLjJsMTAwIDUwYzEwLjEgNS4xIDIyLjEgNS4xIDMyLjIgMGwxMDMuOS01MiAxMDMuOSA1MmMxMC4xIDUuMSAyMi4xIDUuMSAzMi4yIDBsMTAwLTUwYzEyLjItNi4xIDE5LjktMTguNiAxOS45LTMyLjJWMjgzLjljMC0xNS05LjMtMjguNC0yMy40LTMzLjd6TTM1OCAyMTQuOGwtODUgMzEuOXYtNjguMmw4NS0zN3Y3My4zek0xNTQgMTA0LjFsMTAyLTM4LjIgMTAyIDM4LjJ2LjZsLTEwMiA0MS40LTEwMi00MS40di0uNnptODQgMjkxLjFsLTg1IDQyLjV2LTc5LjFsODUtMzguOHY3NS40em0wLTExMmwtMTAyIDQxLjQtMTAyLTQxLjR2LS42bDEwMi0zOC4yIDEwMiAzOC4ydi42em0yNDAgMTEybC04NSA0Mi41di03OS4xbDg1LTM4Ljh2NzUuNHptMC0xMTJsLTEwMiA0MS40LTEwMi00MS40di0uNmwxMDItMzguMiAxMDIgMzguMnYuNnoiPjwvcGF0aD48L3N2Zz4K" height="20">](https://docs.rs/mediavert-core)
+//!
+//! Shared functionality for the mediavert family of tools.
+//!
+//! This includes:
+//! * Indented and colored output through `out::Out`.
+//! * Escaping of paths and commands so that they can be pasted into a shell
+//!   through `shell`.
+//! * In-memory enumeration and extraction of archives (zip, rar, 7z) through
+//!   `archive::Archive`.
+//! * Cancellation of runs through Ctrl-C or a token through `cancel`.
+//! * Natural ordering of names in listings through `collate`.
+//! * Loading of the configuration file shared by all tools through `config`.
+//! * A control socket to pause, resume, or stop long runs through `control`.
+//! * A single guarded layer for operations which modify the filesystem, which
+//!   refuses to perform them in read-only mode, through `effects::Effects`.
+//! * A versioned stream of JSON events for frontends through `events`.
+//! * Key bindings of interactive views, with labels derived from the bound
+//!   keys, through `keymap`.
+//! * Diagnostics controlled through `RUST_LOG` through `log`.
+//! * Paths which can be hyperlinked in supported terminals through
+//!   `link::Link` and `link::MaybeLink`.
+//! * Notifications sent to a webhook or a command when a run finishes through
+//!   `notify`.
+//! * External commands used to convert unsupported files through `plugin`.
+//! * Self-contained HTML reports of a run through `report`.
+//! * The free space of the filesystem a path is on through `space`.
+//! * Downloading of remote sources to a local spool through `remote`.
+//! * A temporary directory per run which is removed once it finishes, and
+//!   cleanup of directories left behind by earlier runs through `temp`.
+//! * Helpers to locate the trash directory and clean up after trashing files
+//!   through `trash`.
+
+pub mod archive;
+pub mod cancel;
+pub mod collate;
+pub mod config;
+pub mod control;
+pub mod effects;
+pub mod events;
+pub mod keymap;
+pub mod link;
+pub mod log;
+pub mod notify;
+pub mod out;
+pub mod plugin;
+pub mod remote;
+pub mod report;
+pub mod shell;
+pub mod space;
+pub mod temp;
+pub mod trash;
@@ -5,7 +5,7 @@ use std::path::{Path, PathBuf};
 use anyhow::Result;
 
 /// An element that might be linkable.
-pub(crate) trait Linkable {
+pub trait Linkable {
     /// Get the path of the linkable element.
     fn path(&self) -> &Path;
 
@@ -15,14 +15,15 @@ pub(crate) trait Linkable {
 
 /// A path that is guaranteed to be linkable.
 #[derive(Clone)]
-pub(crate) struct Link {
+pub struct Link {
     path: PathBuf,
     abs: PathBuf,
 }
 
 impl Link {
+    /// Construct a new link, failing if the path can't be canonicalized.
     #[inline]
-    pub(crate) fn new(path: impl AsRef<Path>) -> Result<Self> {
+    pub fn new(path: impl AsRef<Path>) -> Result<Self> {
         let path = path.as_ref();
         let abs = path.canonicalize()?;
         Ok(Self {
@@ -62,13 +63,14 @@ impl Linkable for Link {
 
 /// A path that might be linkable.
 #[derive(Clone)]
-pub(crate) struct MaybeLink {
+pub struct MaybeLink {
     path: PathBuf,
     abs: Option<PathBuf>,
 }
 
 impl MaybeLink {
-    pub(crate) fn new(path: PathBuf) -> Self {
+    /// Construct a new path which is linked if it can be canonicalized.
+    pub fn new(path: PathBuf) -> Self {
         let abs = path.canonicalize().ok();
         Self { path, abs }
     }
@@ -0,0 +1,64 @@
+use std::env;
+use std::io;
+
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::EnvFilter;
+
+/// The diagnostics enabled by `-vvv` when `RUST_LOG` isn't set.
+const TRACE_DIRECTIVES: &str = "audiovert=trace,bookvert=trace,mediavert_core=trace";
+
+/// How much output to produce, as set by stacking `-v` flags.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[non_exhaustive]
+pub struct Verbosity {
+    level: u8,
+}
+
+impl Verbosity {
+    /// Construct a verbosity from the number of times `-v` was specified.
+    #[inline]
+    pub fn new(level: u8) -> Self {
+        Self { level }
+    }
+
+    /// Test if details like matching conversions should be shown (`-v`).
+    #[inline]
+    pub fn is_verbose(self) -> bool {
+        self.level >= 1
+    }
+
+    /// Test if full command lines and how paths were resolved should be shown
+    /// (`-vv`).
+    #[inline]
+    pub fn is_detailed(self) -> bool {
+        self.level >= 2
+    }
+
+    /// Test if traces like tag probing and archive enumeration should be shown
+    /// (`-vvv`).
+    #[inline]
+    pub fn is_trace(self) -> bool {
+        self.level >= 3
+    }
+}
+
+/// Install a diagnostics subscriber which writes to stderr.
+///
+/// Diagnostics are disabled by default and enabled through the `RUST_LOG`
+/// environment variable, like `RUST_LOG=audiovert=debug`. If `RUST_LOG` isn't
+/// set and the verbosity asks for traces, tracing diagnostics of the tools are
+/// enabled instead.
+pub fn init(verbosity: Verbosity) {
+    let builder = EnvFilter::builder().with_default_directive(LevelFilter::OFF.into());
+
+    let filter = if verbosity.is_trace() && env::var_os(EnvFilter::DEFAULT_ENV).is_none() {
+        builder.parse_lossy(TRACE_DIRECTIVES)
+    } else {
+        builder.from_env_lossy()
+    };
+
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(io::stderr)
+        .try_init();
+}
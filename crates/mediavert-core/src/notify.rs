@@ -0,0 +1,109 @@
+use core::fmt;
+use core::str::FromStr;
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Serialize;
+use serde_json::json;
+
+/// The payload sent when notifying a URL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifyFormat {
+    /// The summary serialized as JSON.
+    Json,
+    /// A plain text message with a `Title` header, as expected by ntfy.
+    Ntfy,
+    /// A JSON message with a `content` field, as expected by Discord webhooks.
+    Discord,
+    /// A JSON `m.text` message, as expected by Matrix webhooks.
+    Matrix,
+}
+
+impl FromStr for NotifyFormat {
+    type Err = anyhow::Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(NotifyFormat::Json),
+            "ntfy" => Ok(NotifyFormat::Ntfy),
+            "discord" => Ok(NotifyFormat::Discord),
+            "matrix" => Ok(NotifyFormat::Matrix),
+            _ => Err(anyhow!("Invalid notify format '{s}'")),
+        }
+    }
+}
+
+impl fmt::Display for NotifyFormat {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotifyFormat::Json => write!(f, "json"),
+            NotifyFormat::Ntfy => write!(f, "ntfy"),
+            NotifyFormat::Discord => write!(f, "discord"),
+            NotifyFormat::Matrix => write!(f, "matrix"),
+        }
+    }
+}
+
+/// Send a summary to the given URL using a POST request.
+///
+/// The summary is serialized as JSON for [`NotifyFormat::Json`], for every
+/// other format its [`fmt::Display`] implementation is used as the message.
+pub fn url<T>(url: &str, format: NotifyFormat, title: &str, summary: &T) -> Result<()>
+where
+    T: Serialize + fmt::Display,
+{
+    let request = ureq::post(url);
+
+    let result = match format {
+        NotifyFormat::Json => request.send_json(summary),
+        NotifyFormat::Ntfy => request.header("Title", title).send(summary.to_string()),
+        NotifyFormat::Discord => request.send_json(json!({
+            "content": format!("**{title}**\n{summary}"),
+        })),
+        NotifyFormat::Matrix => request.send_json(json!({
+            "msgtype": "m.text",
+            "body": format!("{title}\n{summary}"),
+        })),
+    };
+
+    result.with_context(|| anyhow!("POST {url}"))?;
+    Ok(())
+}
+
+/// Run the given shell command, writing the summary serialized as JSON to its
+/// stdin.
+pub fn command<T>(command: &str, summary: &T) -> Result<()>
+where
+    T: Serialize,
+{
+    let json = serde_json::to_vec(summary)?;
+
+    let mut command = if cfg!(windows) {
+        let mut c = Command::new("cmd");
+        c.arg("/C").arg(command);
+        c
+    } else {
+        let mut c = Command::new("sh");
+        c.arg("-c").arg(command);
+        c
+    };
+
+    command.stdin(Stdio::piped());
+
+    let mut child = command.spawn().context("spawning process")?;
+    let mut stdin = child.stdin.take().context("missing stdin")?;
+    stdin.write_all(&json).context("writing to stdin")?;
+    drop(stdin);
+
+    let status = child.wait().context("waiting for process")?;
+
+    if !status.success() {
+        bail!("Notify command failed: {status}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,243 @@
+use core::cell::Cell;
+use core::fmt;
+use core::time::Duration;
+
+use std::io;
+
+use jiff::Zoned;
+use termcolor::Buffer;
+use termcolor::ColorSpec;
+use termcolor::HyperlinkSpec;
+use termcolor::WriteColor;
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log {
+    ($log:ident, $o:ident $(, $($tt:tt)*)?) => {
+        $( $o.$log(format_args!($($tt)*))?; )*
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __blank { ($($tt:tt)*) => { $crate::__log!(blank, $($tt)*) }; }
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __info { ($($tt:tt)*) => { $crate::__log!(info, $($tt)*) }; }
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __warn { ($($tt:tt)*) => { $crate::__log!(warn, $($tt)*) }; }
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __error { ($($tt:tt)*) => { $crate::__log!(error, $($tt)*) }; }
+
+/// Write a blank line prefixed by the current indentation.
+pub use crate::__blank as blank;
+/// Write an error message.
+pub use crate::__error as error;
+/// Write an informational message.
+pub use crate::__info as info;
+/// Write a warning message.
+pub use crate::__warn as warn;
+
+use crate::link::Linkable;
+use crate::shell;
+
+/// Colors used when writing output.
+pub struct Colors {
+    info: ColorSpec,
+    warn: ColorSpec,
+    error: ColorSpec,
+}
+
+impl Colors {
+    /// Construct the default set of colors.
+    pub fn new() -> Self {
+        let mut info = ColorSpec::new();
+        info.set_fg(Some(termcolor::Color::Green)).set_bold(true);
+
+        let mut warn = ColorSpec::new();
+        warn.set_fg(Some(termcolor::Color::Yellow)).set_bold(true);
+
+        let mut error = ColorSpec::new();
+        error.set_fg(Some(termcolor::Color::Red)).set_bold(true);
+
+        Colors { info, warn, error }
+    }
+}
+
+impl Default for Colors {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A duration formatted for humans, like `1h02m03s` or `4.5s`.
+pub struct Elapsed(pub Duration);
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.0.as_secs();
+
+        match (secs / 3600, secs / 60 % 60, secs % 60) {
+            (0, 0, _) => write!(f, "{:.1}s", self.0.as_secs_f64()),
+            (0, m, s) => write!(f, "{m}m{s:02}s"),
+            (h, m, s) => write!(f, "{h}h{m:02}m{s:02}s"),
+        }
+    }
+}
+
+/// An indented and colored output.
+pub struct Out<'a> {
+    change: isize,
+    timestamps: bool,
+    indent: &'a Cell<usize>,
+    c: &'a Colors,
+    o: &'a mut dyn WriteColor,
+}
+
+impl Out<'_> {
+    /// Construct a new output writing to the given stream.
+    pub fn new<'a>(indent: &'a Cell<usize>, c: &'a Colors, o: &'a mut dyn WriteColor) -> Out<'a> {
+        Out {
+            change: 0,
+            timestamps: false,
+            indent,
+            c,
+            o,
+        }
+    }
+}
+
+impl<'a> Out<'a> {
+    /// Prefix every line with the current wall-clock time.
+    pub fn with_timestamps(mut self, timestamps: bool) -> Self {
+        self.timestamps = timestamps;
+        self
+    }
+
+    /// Test if lines are prefixed with timestamps.
+    #[inline]
+    pub fn timestamps(&self) -> bool {
+        self.timestamps
+    }
+
+    /// Test if the output is colored.
+    #[inline]
+    pub fn supports_color(&self) -> bool {
+        self.o.supports_color()
+    }
+
+    /// Construct a buffer to write output to from another thread, which is
+    /// colored if this output is.
+    ///
+    /// The buffer is written to this output with [`Out::write_buffer`].
+    pub fn buffer(&self) -> Buffer {
+        if self.supports_color() {
+            Buffer::ansi()
+        } else {
+            Buffer::no_color()
+        }
+    }
+
+    /// Write output which was collected in a buffer.
+    pub fn write_buffer(&mut self, buffer: &Buffer) -> io::Result<()> {
+        self.o.write_all(buffer.as_slice())?;
+        self.o.flush()?;
+        Ok(())
+    }
+
+    /// Indent all output written through the returned handle, the indentation
+    /// is restored when it is dropped.
+    pub fn indent(&mut self, change: isize) -> Out<'_> {
+        let indent = self.indent.get().saturating_add_signed(change);
+        self.indent.set(indent);
+
+        Out {
+            change,
+            timestamps: self.timestamps,
+            indent: self.indent,
+            c: self.c,
+            o: self.o,
+        }
+    }
+
+    /// Write an uncolored line.
+    pub fn blank(&mut self, m: impl fmt::Display) -> io::Result<()> {
+        self.prefix()?;
+        writeln!(self.o, "{m}")?;
+        self.o.flush()?;
+        Ok(())
+    }
+
+    /// Write a path, linking it to the file when possible.
+    pub fn link(&mut self, header: impl fmt::Display, link: &dyn Linkable) -> io::Result<()> {
+        self.prefix()?;
+        write!(self.o, "{header}: ")?;
+
+        if let Some(to) = link.link() {
+            let mut link = Vec::from(b"file://");
+            link.extend_from_slice(to.as_os_str().as_encoded_bytes());
+            let open = HyperlinkSpec::open(&link);
+            self.o.set_hyperlink(&open)?;
+        }
+
+        writeln!(self.o, "{}", shell::path(link.path()))?;
+
+        if link.link().is_some() {
+            let close = HyperlinkSpec::close();
+            self.o.set_hyperlink(&close)?;
+        }
+
+        self.o.reset()?;
+        self.o.flush()?;
+        Ok(())
+    }
+
+    /// Write an informational line.
+    pub fn info(&mut self, m: impl fmt::Display) -> io::Result<()> {
+        self.colorize(&self.c.info, m)
+    }
+
+    /// Write a warning line.
+    pub fn warn(&mut self, m: impl fmt::Display) -> io::Result<()> {
+        self.colorize(&self.c.warn, m)
+    }
+
+    /// Write an error line.
+    pub fn error(&mut self, m: impl fmt::Display) -> io::Result<()> {
+        self.colorize(&self.c.error, m)
+    }
+
+    fn prefix(&mut self) -> io::Result<()> {
+        if self.timestamps {
+            write!(self.o, "{} ", Zoned::now().strftime("%Y-%m-%d %H:%M:%S"))?;
+        }
+
+        let n = self.indent.get();
+
+        for _ in 0..n {
+            self.o.write_all(b"  ")?;
+        }
+
+        Ok(())
+    }
+
+    fn colorize(&mut self, c: &ColorSpec, m: impl fmt::Display) -> io::Result<()> {
+        self.prefix()?;
+        self.o.set_color(c)?;
+        writeln!(self.o, "{m}")?;
+        self.o.reset()?;
+        self.o.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for Out<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        let indent = self.indent.get().saturating_sub_signed(self.change);
+        self.indent.set(indent);
+    }
+}
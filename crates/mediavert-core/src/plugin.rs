@@ -0,0 +1,91 @@
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Deserialize;
+
+/// An external command used to convert files with an extension which isn't
+/// supported natively.
+///
+/// In the arguments of the command `{from}` is replaced with the path of the
+/// file being converted, and `{to}` with the path the command should write its
+/// output to.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Plugin {
+    /// The program to run followed by its arguments.
+    pub command: Vec<String>,
+}
+
+impl Plugin {
+    /// The name of the program run by the plugin.
+    pub fn program(&self) -> &str {
+        self.command.first().map(String::as_str).unwrap_or_default()
+    }
+
+    /// Construct the command to convert `from` into `to`.
+    pub fn command(&self, from: &OsStr, to: &Path) -> Result<Command> {
+        let Some((program, args)) = self.command.split_first() else {
+            bail!("Plugin command is empty");
+        };
+
+        let mut command = Command::new(program);
+
+        for arg in args {
+            command.arg(substitute(arg, from, to.as_os_str()));
+        }
+
+        Ok(command)
+    }
+}
+
+/// Verify the output of a plugin, which must be a non-empty file or
+/// directory.
+pub fn verify(path: &Path) -> Result<()> {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            return Err(e).with_context(|| anyhow!("Plugin did not produce {}", path.display()));
+        }
+    };
+
+    if metadata.is_dir() {
+        let mut entries = fs::read_dir(path)
+            .with_context(|| anyhow!("Failed to read directory {}", path.display()))?;
+
+        if entries.next().is_none() {
+            bail!("Plugin produced an empty directory {}", path.display());
+        }
+    } else if metadata.len() == 0 {
+        bail!("Plugin produced an empty file {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Replace the `{from}` and `{to}` placeholders in an argument.
+fn substitute(arg: &str, from: &OsStr, to: &OsStr) -> OsString {
+    let mut out = OsString::new();
+    let mut rest = arg;
+
+    while let Some(start) = rest.find('{') {
+        let (value, len) = if rest[start..].starts_with("{from}") {
+            (from, "{from}".len())
+        } else if rest[start..].starts_with("{to}") {
+            (to, "{to}".len())
+        } else {
+            out.push(&rest[..=start]);
+            rest = &rest[start + 1..];
+            continue;
+        };
+
+        out.push(&rest[..start]);
+        out.push(value);
+        rest = &rest[start + len..];
+    }
+
+    out.push(rest);
+    out
+}
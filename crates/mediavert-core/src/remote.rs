@@ -0,0 +1,293 @@
+use core::time::Duration;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use anyhow::{Context, Result, anyhow, bail};
+
+use crate::cancel::Cancel;
+use crate::plugin::{self, Plugin};
+use crate::temp::RunDir;
+
+/// The maximum depth of directory listings to follow.
+const MAX_DEPTH: usize = 32;
+/// How often a running helper is checked for whether the run was aborted.
+const POLL: Duration = Duration::from_millis(100);
+
+/// Test if the given source is a URL, like `https://example.com/music/`.
+pub fn is_url(source: &str) -> bool {
+    scheme(source).is_some()
+}
+
+/// A directory remote sources are downloaded into before they are converted,
+/// which is removed once dropped.
+pub struct Spool {
+    dir: PathBuf,
+    count: usize,
+    helpers: BTreeMap<String, Plugin>,
+    cancel: Cancel,
+}
+
+impl Spool {
+    /// Construct a new spool in the temporary directory of the current run.
+    ///
+    /// URLs with a scheme other than `http` and `https` are fetched with the
+    /// given helpers, where `{from}` is the URL and `{to}` the directory to
+    /// download into.
+    ///
+    /// A cancelled token stops fetching in between files, and an aborted one
+    /// stops the file currently being downloaded.
+    pub fn new(
+        run_dir: &RunDir,
+        helpers: BTreeMap<String, Plugin>,
+        cancel: Cancel,
+    ) -> Result<Self> {
+        Ok(Self {
+            dir: run_dir.allocate("spool")?,
+            count: 0,
+            helpers,
+            cancel,
+        })
+    }
+
+    /// Fetch the given URL into the spool, returning the local directory it
+    /// was downloaded into.
+    ///
+    /// URLs ending with a `/` are treated as directory listings, which are
+    /// downloaded recursively.
+    pub fn fetch(&mut self, url: &str) -> Result<PathBuf> {
+        let Some(scheme) = scheme(url) else {
+            bail!("Not a URL: {url}");
+        };
+
+        let to = self.dir.join(self.count.to_string());
+        self.count += 1;
+
+        fs::create_dir_all(&to).with_context(|| anyhow!("Failed to create {}", to.display()))?;
+
+        match scheme {
+            "http" | "https" => {
+                if url.ends_with('/') {
+                    fetch_listing(&self.cancel, url, &to, 0)?;
+                } else {
+                    let name = entry_name(url.rsplit('/').next().unwrap_or_default())
+                        .with_context(|| anyhow!("Invalid file name in {url}"))?;
+
+                    download(&self.cancel, url, &to.join(name))?;
+                }
+            }
+            scheme => {
+                let Some(helper) = self.helpers.get(scheme) else {
+                    bail!("No helper configured to fetch '{scheme}' URLs");
+                };
+
+                let mut child = helper
+                    .command(url.as_ref(), &to)?
+                    .spawn()
+                    .with_context(|| anyhow!("Failed to run {}", helper.program()))?;
+
+                let status = loop {
+                    if let Some(status) = child.try_wait()? {
+                        break status;
+                    }
+
+                    if self.cancel.is_aborted() {
+                        _ = child.kill();
+                        _ = child.wait();
+                        bail!("Aborted fetching {url}");
+                    }
+
+                    thread::sleep(POLL);
+                };
+
+                if !status.success() {
+                    bail!("{} exited with {status}", helper.program());
+                }
+
+                plugin::verify(&to)?;
+            }
+        }
+
+        Ok(to)
+    }
+}
+
+impl Drop for Spool {
+    fn drop(&mut self) {
+        _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Recursively download the files in a directory listing.
+fn fetch_listing(cancel: &Cancel, url: &str, to: &Path, depth: usize) -> Result<()> {
+    if depth > MAX_DEPTH {
+        bail!("Directory listing too deep: {url}");
+    }
+
+    let listing = ureq::get(url)
+        .call()
+        .and_then(|mut response| response.body_mut().read_to_string())
+        .with_context(|| anyhow!("Failed to fetch listing {url}"))?;
+
+    for href in hrefs(&listing) {
+        if cancel.is_cancelled() {
+            bail!("Cancelled fetching {url}");
+        }
+
+        // Only follow relative links to entries in the listed directory.
+        if href.starts_with(['/', '?', '#', '.']) || href.contains(['?', '#', ':']) {
+            continue;
+        }
+
+        let (name, is_dir) = match href.strip_suffix('/') {
+            Some(name) => (name, true),
+            None => (href, false),
+        };
+
+        if name.is_empty() || name.contains('/') {
+            continue;
+        }
+
+        let name =
+            entry_name(name).with_context(|| anyhow!("Invalid entry '{href}' in listing {url}"))?;
+        let entry_url = format!("{url}{href}");
+
+        if is_dir {
+            fetch_listing(cancel, &entry_url, &to.join(name), depth + 1)?;
+        } else {
+            download(cancel, &entry_url, &to.join(name))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Download a single file, which is written to a partial file first.
+///
+/// The partial file is removed if the download is aborted.
+fn download(cancel: &Cancel, url: &str, path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| anyhow!("Failed to create {}", parent.display()))?;
+    }
+
+    let part = path.with_added_extension("part");
+
+    let mut response = ureq::get(url)
+        .call()
+        .with_context(|| anyhow!("Failed to fetch {url}"))?;
+
+    let mut file =
+        File::create(&part).with_context(|| anyhow!("Failed to create {}", part.display()))?;
+
+    let mut reader = response.body_mut().as_reader();
+    let mut buf = vec![0; 1 << 16];
+
+    loop {
+        if cancel.is_aborted() {
+            drop(file);
+            _ = fs::remove_file(&part);
+            bail!("Aborted downloading {url}");
+        }
+
+        let n = reader
+            .read(&mut buf)
+            .with_context(|| anyhow!("Failed to download {url}"))?;
+
+        if n == 0 {
+            break;
+        }
+
+        file.write_all(&buf[..n])
+            .with_context(|| anyhow!("Failed to write {}", part.display()))?;
+    }
+
+    fs::rename(&part, path).with_context(|| anyhow!("Failed to rename {}", part.display()))?;
+    Ok(())
+}
+
+/// Decode the name of a downloaded file or directory, making sure that it
+/// can't escape the directory it's joined to.
+fn entry_name(encoded: &str) -> Result<String> {
+    let name = decode(encoded);
+
+    if name.is_empty() {
+        bail!("Missing name");
+    }
+
+    if name.contains(['/', '\\'])
+        || matches!(name.as_str(), "." | "..")
+        || Path::new(&name).is_absolute()
+    {
+        bail!("Name '{name}' isn't a single path component");
+    }
+
+    Ok(name)
+}
+
+/// Get the scheme of a URL.
+fn scheme(source: &str) -> Option<&str> {
+    let (scheme, _) = source.split_once("://")?;
+
+    let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+
+    valid.then_some(scheme)
+}
+
+/// Extract the unique links in an HTML document.
+fn hrefs(html: &str) -> BTreeSet<&str> {
+    let lower = html.to_ascii_lowercase();
+    let mut hrefs = BTreeSet::new();
+    let mut offset = 0;
+
+    while let Some(n) = lower[offset..].find("href=") {
+        let start = offset + n + "href=".len();
+        offset = start;
+
+        let Some(quote) = html[start..]
+            .chars()
+            .next()
+            .filter(|c| matches!(c, '"' | '\''))
+        else {
+            continue;
+        };
+
+        let Some(len) = html[start + 1..].find(quote) else {
+            break;
+        };
+
+        hrefs.insert(&html[start + 1..start + 1 + len]);
+        offset = start + 1 + len;
+    }
+
+    hrefs
+}
+
+/// Decode a percent-encoded URL component.
+fn decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut n = 0;
+
+    while n < bytes.len() {
+        if bytes[n] == b'%'
+            && let Some(hex) = s.get(n + 1..n + 3)
+            && let Ok(b) = u8::from_str_radix(hex, 16)
+        {
+            out.push(b);
+            n += 3;
+            continue;
+        }
+
+        out.push(bytes[n]);
+        n += 1;
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
@@ -0,0 +1,419 @@
+use core::fmt::{self, Write as _};
+use core::str::FromStr;
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, anyhow};
+
+/// The format of a report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// A self-contained HTML document.
+    Html,
+    /// Plain text with aligned columns, suitable for reading in a terminal.
+    Text,
+}
+
+/// Where and in which format to write a report, like `html:report.html` or
+/// `text:report.txt`.
+#[derive(Debug, Clone)]
+pub struct ReportTarget {
+    /// The format of the report.
+    pub format: ReportFormat,
+    /// The path to write the report to.
+    pub path: PathBuf,
+}
+
+impl FromStr for ReportTarget {
+    type Err = anyhow::Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((format, path)) = s.split_once(':') else {
+            return Err(anyhow!("Invalid report '{s}', expected <format>:<path>"));
+        };
+
+        let format = match format {
+            "html" => ReportFormat::Html,
+            "text" => ReportFormat::Text,
+            _ => return Err(anyhow!("Invalid report format '{format}'")),
+        };
+
+        if path.is_empty() {
+            return Err(anyhow!("Invalid report '{s}', missing path"));
+        }
+
+        Ok(ReportTarget {
+            format,
+            path: PathBuf::from(path),
+        })
+    }
+}
+
+impl fmt::Display for ReportTarget {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.format {
+            ReportFormat::Html => write!(f, "html:{}", self.path.display()),
+            ReportFormat::Text => write!(f, "text:{}", self.path.display()),
+        }
+    }
+}
+
+/// A cell in a report table.
+pub enum Cell {
+    /// Plain text.
+    Text(String),
+    /// An embedded image.
+    Image {
+        /// The mime type of the image, like `image/png`.
+        mime: &'static str,
+        /// The contents of the image.
+        data: Vec<u8>,
+    },
+}
+
+impl From<String> for Cell {
+    #[inline]
+    fn from(value: String) -> Self {
+        Cell::Text(value)
+    }
+}
+
+impl From<&str> for Cell {
+    #[inline]
+    fn from(value: &str) -> Self {
+        Cell::Text(value.to_owned())
+    }
+}
+
+/// A titled table in a report.
+pub struct Section {
+    heading: String,
+    headers: Vec<String>,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Section {
+    /// Add a row to the table.
+    pub fn row(&mut self, row: Vec<Cell>) {
+        self.rows.push(row);
+    }
+}
+
+/// A report of a planned or executed run.
+pub struct Report {
+    title: String,
+    summary: Vec<(String, String)>,
+    sections: Vec<Section>,
+    warnings: Vec<String>,
+    errors: Vec<String>,
+}
+
+impl Report {
+    /// Construct a new empty report.
+    pub fn new(title: impl fmt::Display) -> Self {
+        Self {
+            title: title.to_string(),
+            summary: Vec::new(),
+            sections: Vec::new(),
+            warnings: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Add a summary entry to the report.
+    pub fn summary(&mut self, key: impl fmt::Display, value: impl fmt::Display) {
+        self.summary.push((key.to_string(), value.to_string()));
+    }
+
+    /// Add a table to the report.
+    pub fn section(&mut self, heading: impl fmt::Display, headers: &[&str]) -> &mut Section {
+        self.sections.push(Section {
+            heading: heading.to_string(),
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows: Vec::new(),
+        });
+
+        self.sections.last_mut().expect("section was just pushed")
+    }
+
+    /// Add a warning to the report.
+    pub fn warning(&mut self, message: impl fmt::Display) {
+        self.warnings.push(message.to_string());
+    }
+
+    /// Add an error to the report.
+    pub fn error(&mut self, message: impl fmt::Display) {
+        self.errors.push(message.to_string());
+    }
+
+    /// Write the report to the given target.
+    pub fn write(&self, target: &ReportTarget) -> Result<()> {
+        let contents = match target.format {
+            ReportFormat::Html => self.html()?,
+            ReportFormat::Text => self.text()?,
+        };
+
+        fs::write(&target.path, contents)
+            .with_context(|| anyhow!("Failed to write report {}", target.path.display()))
+    }
+
+    /// Render the report as a self-contained HTML document.
+    fn html(&self) -> Result<String, fmt::Error> {
+        let mut o = String::new();
+
+        writeln!(o, "<!DOCTYPE html>")?;
+        writeln!(o, "<html>")?;
+        writeln!(o, "<head>")?;
+        writeln!(o, "<meta charset=\"utf-8\">")?;
+        writeln!(o, "<title>{}</title>", Escape(&self.title))?;
+        writeln!(o, "<style>{STYLE}</style>")?;
+        writeln!(o, "</head>")?;
+        writeln!(o, "<body>")?;
+        writeln!(o, "<h1>{}</h1>", Escape(&self.title))?;
+
+        if !self.summary.is_empty() {
+            writeln!(o, "<table class=\"summary\">")?;
+
+            for (key, value) in &self.summary {
+                writeln!(
+                    o,
+                    "<tr><th>{}</th><td>{}</td></tr>",
+                    Escape(key),
+                    Escape(value)
+                )?;
+            }
+
+            writeln!(o, "</table>")?;
+        }
+
+        for (class, heading, messages) in [
+            ("error", "Errors", &self.errors),
+            ("warning", "Warnings", &self.warnings),
+        ] {
+            if messages.is_empty() {
+                continue;
+            }
+
+            writeln!(o, "<h2>{heading}</h2>")?;
+            writeln!(o, "<ul class=\"{class}\">")?;
+
+            for message in messages {
+                writeln!(o, "<li>{}</li>", Escape(message))?;
+            }
+
+            writeln!(o, "</ul>")?;
+        }
+
+        for section in &self.sections {
+            writeln!(o, "<h2>{}</h2>", Escape(&section.heading))?;
+            writeln!(o, "<table>")?;
+            write!(o, "<tr>")?;
+
+            for header in &section.headers {
+                write!(o, "<th>{}</th>", Escape(header))?;
+            }
+
+            writeln!(o, "</tr>")?;
+
+            for row in &section.rows {
+                write!(o, "<tr>")?;
+
+                for cell in row {
+                    match cell {
+                        Cell::Text(text) => write!(o, "<td>{}</td>", Escape(text))?,
+                        Cell::Image { mime, data } => {
+                            write!(o, "<td><img src=\"data:{mime};base64,")?;
+                            base64(&mut o, data);
+                            write!(o, "\"></td>")?;
+                        }
+                    }
+                }
+
+                writeln!(o, "</tr>")?;
+            }
+
+            writeln!(o, "</table>")?;
+        }
+
+        writeln!(o, "</body>")?;
+        writeln!(o, "</html>")?;
+        Ok(o)
+    }
+
+    /// Render the report as plain text, where the columns of each table are
+    /// aligned and images are left out.
+    fn text(&self) -> Result<String, fmt::Error> {
+        let mut o = String::new();
+
+        writeln!(o, "{}", self.title)?;
+        writeln!(o, "{}", "=".repeat(self.title.chars().count()))?;
+
+        if !self.summary.is_empty() {
+            let width = self
+                .summary
+                .iter()
+                .map(|(key, _)| key.chars().count())
+                .max()
+                .unwrap_or_default();
+
+            writeln!(o)?;
+
+            for (key, value) in &self.summary {
+                writeln!(o, "{key:width$}  {value}")?;
+            }
+        }
+
+        for (heading, messages) in [("Errors", &self.errors), ("Warnings", &self.warnings)] {
+            if messages.is_empty() {
+                continue;
+            }
+
+            writeln!(o)?;
+            writeln!(o, "{heading}:")?;
+
+            for message in messages {
+                writeln!(o, "- {message}")?;
+            }
+        }
+
+        for section in &self.sections {
+            let rows = section
+                .rows
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|cell| match cell {
+                            Cell::Text(text) => text.as_str(),
+                            Cell::Image { .. } => "[image]",
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+
+            let mut widths = section
+                .headers
+                .iter()
+                .map(|h| h.chars().count())
+                .collect::<Vec<_>>();
+
+            for row in &rows {
+                for (n, cell) in row.iter().enumerate() {
+                    if let Some(width) = widths.get_mut(n) {
+                        *width = (*width).max(cell.chars().count());
+                    }
+                }
+            }
+
+            writeln!(o)?;
+            writeln!(o, "{}", section.heading)?;
+            writeln!(o, "{}", "-".repeat(section.heading.chars().count()))?;
+
+            let headers = section.headers.iter().map(String::as_str);
+            text_row(&mut o, headers, &widths)?;
+
+            for row in rows {
+                text_row(&mut o, row.into_iter(), &widths)?;
+            }
+        }
+
+        Ok(o)
+    }
+}
+
+/// Write a row of a plain text table, padding each cell to the width of its
+/// column.
+fn text_row<'a>(
+    o: &mut String,
+    cells: impl Iterator<Item = &'a str>,
+    widths: &[usize],
+) -> fmt::Result {
+    let mut line = String::new();
+
+    for (n, cell) in cells.enumerate() {
+        if n > 0 {
+            line.push_str("  ");
+        }
+
+        let width = widths.get(n).copied().unwrap_or_default();
+        write!(line, "{cell:width$}")?;
+    }
+
+    writeln!(o, "{}", line.trim_end())
+}
+
+/// Format a size in bytes for humans.
+pub fn size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = UNITS[0];
+
+    for next in &UNITS[1..] {
+        if value < 1024.0 {
+            break;
+        }
+
+        value /= 1024.0;
+        unit = next;
+    }
+
+    format!("{value:.1} {unit}")
+}
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin-bottom:2em}\
+th,td{border:1px solid #ccc;padding:.3em .6em;text-align:left;vertical-align:top}\
+th{background:#f0f0f0}\
+table.summary th{width:12em}\
+ul.error{color:#a00}\
+ul.warning{color:#a60}\
+img{max-width:128px;max-height:128px}";
+
+/// Escape text for inclusion in HTML.
+struct Escape<'a>(&'a str);
+
+impl fmt::Display for Escape<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '<' => f.write_str("&lt;")?,
+                '>' => f.write_str("&gt;")?,
+                '&' => f.write_str("&amp;")?,
+                '"' => f.write_str("&quot;")?,
+                c => f.write_char(c)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Append the base64 encoding of `data`.
+fn base64(o: &mut String, data: &[u8]) {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                o.push(TABLE[(n >> (18 - 6 * i)) as usize & 0x3f] as char);
+            } else {
+                o.push('=');
+            }
+        }
+    }
+}
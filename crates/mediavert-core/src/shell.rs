@@ -6,7 +6,8 @@ use std::ffi::OsStr;
 use std::path::Path;
 use std::process::Command;
 
-pub(crate) fn path(path: &Path) -> impl fmt::Display + '_ {
+/// Display a path, escaping any invalid UTF-8.
+pub fn path(path: &Path) -> impl fmt::Display + '_ {
     #[repr(transparent)]
     struct Format(OsStr);
 
@@ -34,7 +35,8 @@ pub(crate) fn path(path: &Path) -> impl fmt::Display + '_ {
     Format::new(path.as_os_str())
 }
 
-pub(crate) fn escape(s: &OsStr) -> Cow<'_, str> {
+/// Escape an argument so that it can be pasted into a shell.
+pub fn escape(s: &OsStr) -> Cow<'_, str> {
     let Some(s) = s.to_str() else {
         return Cow::Borrowed("<non-utf8>");
     };
@@ -42,7 +44,8 @@ pub(crate) fn escape(s: &OsStr) -> Cow<'_, str> {
     escape_str(s)
 }
 
-pub(crate) fn escape_str(s: &str) -> Cow<'_, str> {
+/// Escape a string so that it can be pasted into a shell.
+pub fn escape_str(s: &str) -> Cow<'_, str> {
     let mut o = String::new();
 
     let s = 'escape: {
@@ -67,7 +70,8 @@ pub(crate) fn escape_str(s: &str) -> Cow<'_, str> {
     Cow::Owned(o)
 }
 
-pub(crate) fn escape_in_bash(c: char) -> Option<&'static str> {
+/// Get the escape sequence of a character in bash, if it needs one.
+pub fn escape_in_bash(c: char) -> Option<&'static str> {
     match c {
         ' ' => Some("\\ "),
         '"' => Some("\\\""),
@@ -90,13 +94,14 @@ pub(crate) fn escape_in_bash(c: char) -> Option<&'static str> {
 }
 
 /// Helper type to format a commands with argument substitutions.
-pub(crate) struct FormatCommand<'a> {
+pub struct FormatCommand<'a> {
     cmd: &'a Command,
     replacements: HashMap<&'a OsStr, Cow<'a, str>>,
 }
 
 impl<'a> FormatCommand<'a> {
-    pub(crate) fn new(cmd: &'a Command) -> Self {
+    /// Construct a new formatter for the given command.
+    pub fn new(cmd: &'a Command) -> Self {
         Self {
             cmd,
             replacements: HashMap::new(),
@@ -104,7 +109,7 @@ impl<'a> FormatCommand<'a> {
     }
 
     /// Insert a replacement for a given argument.
-    pub(crate) fn replace(
+    pub fn replace(
         &mut self,
         key: &'a (impl AsRef<OsStr> + ?Sized),
         value: impl Into<Cow<'a, str>>,
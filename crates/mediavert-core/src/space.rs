@@ -0,0 +1,40 @@
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+
+/// Get the number of bytes available to unprivileged users on the filesystem
+/// which holds `path`, or `None` if it can't be determined on this platform.
+///
+/// Since the path might not have been created yet, the nearest ancestor which
+/// exists is used.
+pub fn available(path: &Path) -> Result<Option<u64>> {
+    // The last ancestor of a relative path is empty, which is the current
+    // directory.
+    let existing = path
+        .ancestors()
+        .map(|p| {
+            if p.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                p
+            }
+        })
+        .find(|p| p.exists());
+
+    let Some(existing) = existing else {
+        return Ok(None);
+    };
+
+    statvfs(existing).with_context(|| anyhow!("Checking free space on {}", existing.display()))
+}
+
+#[cfg(unix)]
+fn statvfs(path: &Path) -> Result<Option<u64>> {
+    let stat = rustix::fs::statvfs(path)?;
+    Ok(Some(stat.f_bavail.saturating_mul(stat.f_frsize)))
+}
+
+#[cfg(not(unix))]
+fn statvfs(_: &Path) -> Result<Option<u64>> {
+    Ok(None)
+}
@@ -0,0 +1,111 @@
+use core::cell::Cell;
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use anyhow::{Context, Result, anyhow};
+
+/// The prefix of the temporary directories of runs.
+const PREFIX: &str = "mediavert-";
+
+/// The temporary directory of a run, which holds every temporary artifact
+/// produced by it and is removed once dropped.
+///
+/// The directory is named `mediavert-<tool>-<pid>` and is only created once
+/// something is allocated in it.
+pub struct RunDir {
+    path: PathBuf,
+    count: Cell<usize>,
+}
+
+impl RunDir {
+    /// Construct the temporary directory of a run of the given tool inside of
+    /// `tmp_dir`.
+    pub fn new(tmp_dir: &Path, tool: &str) -> Self {
+        Self {
+            path: tmp_dir.join(format!("{PREFIX}{tool}-{}", process::id())),
+            count: Cell::new(0),
+        }
+    }
+
+    /// The path of the directory.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Allocate a unique path for an artifact in the directory, like
+    /// `spool-0`.
+    ///
+    /// The path itself isn't created, but the directory of the run is.
+    pub fn allocate(&self, name: &str) -> Result<PathBuf> {
+        fs::create_dir_all(&self.path)
+            .with_context(|| anyhow!("Failed to create {}", self.path.display()))?;
+
+        let n = self.count.get();
+        self.count.set(n + 1);
+        Ok(self.path.join(format!("{name}-{n}")))
+    }
+}
+
+impl Drop for RunDir {
+    fn drop(&mut self) {
+        _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Remove the temporary directories left behind in `tmp_dir` by runs which
+/// are no longer running, returning the directories which were removed.
+///
+/// On platforms where it can't be determined if a run is still going, the
+/// directories are left alone since they might belong to live runs.
+pub fn clean(tmp_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+
+    let entries = match fs::read_dir(tmp_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(removed),
+        Err(e) => return Err(anyhow!("Failed to read {}: {e}", tmp_dir.display())),
+    };
+
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name();
+
+        let Some(pid) = name
+            .to_str()
+            .and_then(|name| name.strip_prefix(PREFIX))
+            .and_then(|name| name.rsplit_once('-'))
+            .and_then(|(_, pid)| pid.parse::<u32>().ok())
+        else {
+            continue;
+        };
+
+        if pid == process::id() || is_running(pid) != Some(false) {
+            continue;
+        }
+
+        let path = entry.path();
+
+        fs::remove_dir_all(&path)
+            .with_context(|| anyhow!("Failed to remove {}", path.display()))?;
+
+        removed.push(path);
+    }
+
+    Ok(removed)
+}
+
+/// Test if the process with the given id is running, or `None` if it can't be
+/// determined.
+#[cfg(target_os = "linux")]
+fn is_running(pid: u32) -> Option<bool> {
+    Some(Path::new("/proc").join(pid.to_string()).exists())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_running(_: u32) -> Option<bool> {
+    None
+}
@@ -0,0 +1,36 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+/// Get the default trash directory.
+///
+/// This is `~/trash` or `~/Trash` if either exists, otherwise it is `~/trash`.
+pub fn default_dir() -> Result<PathBuf> {
+    let mut trash = env::home_dir().context("Get home directory")?;
+
+    for d in ["trash", "Trash"] {
+        trash.push(d);
+
+        if trash.is_dir() {
+            return Ok(trash);
+        }
+
+        trash.pop();
+    }
+
+    trash.push("trash");
+    Ok(trash)
+}
+
+/// Test if the given path is an empty directory.
+///
+/// Returns `false` if the directory can't be read.
+pub fn is_empty_dir(path: &Path) -> bool {
+    let Ok(mut entries) = fs::read_dir(path) else {
+        return false;
+    };
+
+    entries.next().is_none()
+}
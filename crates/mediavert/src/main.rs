@@ -10,6 +10,8 @@
 //! * [`audiovert`] ([git][audiovert-git]) - `convert music` which is a tool to convert
 //!   tagged or untagged music from one format and directory structure to
 //!   another.
+//! * [`videovert`] ([git][videovert-git]) - `convert video` which is a tool to
+//!   convert video libraries between container formats and codecs.
 //!
 //! <br>
 //!
@@ -25,14 +27,18 @@
 //! [bookvert-git]: https://github.com/udoprog/mediavert/tree/main/crates/bookvert
 //! [`audiovert`]: https://crates.io/crates/audiovert
 //! [audiovert-git]: https://github.com/udoprog/mediavert/tree/main/crates/audiovert
+//! [`videovert`]: https://crates.io/crates/videovert
+//! [videovert-git]: https://github.com/udoprog/mediavert/tree/main/crates/videovert
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use mediavert_core::log::Verbosity;
 
 #[derive(Subcommand)]
 enum Command {
-    Books(bookvert::cli::Bookvert),
-    Audio(audiovert::cli::Audiovert),
+    Books(Box<bookvert::cli::Bookvert>),
+    Audio(Box<audiovert::cli::Audiovert>),
+    Video(Box<videovert::cli::Videovert>),
 }
 
 const VERSION: &str = match option_env!("MEDIAVERT_VERSION") {
@@ -51,8 +57,17 @@ struct Opts {
 fn main() -> Result<()> {
     let opts = Opts::parse();
 
+    let verbosity = match &opts.command {
+        Command::Books(opts) => opts.verbosity(),
+        Command::Audio(opts) => opts.verbosity(),
+        Command::Video(..) => Verbosity::default(),
+    };
+
+    mediavert_core::log::init(verbosity);
+
     match opts.command {
         Command::Books(opts) => bookvert::cli::entry(&opts),
         Command::Audio(opts) => audiovert::cli::entry(&opts),
+        Command::Video(opts) => videovert::cli::entry(&opts),
     }
 }
@@ -0,0 +1,510 @@
+use core::cell::Cell;
+
+use std::ffi::{OsStr, OsString};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus, Stdio};
+
+use anyhow::{self, Context, Result};
+use clap::Parser;
+use mediavert_core::effects::Effects;
+use mediavert_core::link::MaybeLink;
+use mediavert_core::out::{Colors, Out, blank, error, info, warn};
+use mediavert_core::shell::{self, FormatCommand};
+use mediavert_core::trash;
+use relative_path::RelativePath;
+use termcolor::{ColorChoice, StandardStream};
+use tracing::{debug, info_span};
+
+use crate::codec::{AudioCodec, VideoCodec};
+use crate::condition::{Condition, FromCondition, ToCondition};
+use crate::config::{ArchiveId, Config, Db, Source};
+use crate::format::Format;
+use crate::tasks::{
+    Exists, MatchingConversion, TaskKind, Tasks, TransferKind, Trash, TrashWhat, Unsupported,
+};
+
+const PART: &str = "part";
+
+/// A tool to perform batch conversion of video.
+#[derive(Parser)]
+pub struct Videovert {
+    /// If set, forces overwriting of existing files if a source file exists and
+    /// the destination file also exists.
+    #[arg(short = 'f', long)]
+    force: bool,
+    /// If set, enables verbose output.
+    #[arg(short = 'v', long)]
+    verbose: bool,
+    /// Removed files will be moved to this location instead of being
+    /// deleted [default: ~/trash].
+    #[arg(long)]
+    trash: Option<PathBuf>,
+    /// Prefix every line of output with the wall-clock time.
+    #[arg(long)]
+    timestamps: bool,
+    /// If set, source files are trashed after successful conversion.
+    #[arg(short = 'r', long)]
+    trash_source: bool,
+    /// Conversion pairs to perform, like avi=mkv which would mean converting
+    /// from avi to mkv. This also takes special values like legacy=<format>,
+    /// modern=<format> or same.
+    ///
+    /// Legacy formats are avi, flv and wmv, all other formats are modern.
+    ///
+    /// The target <format> can also specify an exact format or the special
+    /// keyword same. With this a flexible rules of conversions can be defined.
+    ///
+    /// By default, conversions are performed from legacy formats to mkv, and
+    /// to link modern formats.
+    ///
+    /// Note that multiple matching conversions can be specified, in which case
+    /// multiple target files will be produced.
+    #[arg(short = 'c', long)]
+    conversion: Vec<Condition>,
+    /// If set, performs a dry run without making any changes. This also implies
+    /// verbose.
+    #[arg(short = 'D', long)]
+    dry_run: bool,
+    /// If set, performs a dry run where any attempt to modify the filesystem
+    /// is refused with an error. This implies `--dry-run`.
+    #[arg(long)]
+    read_only: bool,
+    /// Sync converted and transferred files along with the directory they're
+    /// in to disk once they're complete.
+    #[arg(long)]
+    durable: bool,
+    /// Output base directory for converted files.
+    #[arg(short = 'o', long)]
+    to: Option<PathBuf>,
+    /// If set, moves files instead of creating hard links when transferring.
+    #[arg(long)]
+    r#move: bool,
+    /// The video codec to use when performing conversions, either `h264`,
+    /// `h265`, `av1`, or `copy` to keep the existing video stream.
+    #[arg(long, default_value_t = VideoCodec::H265)]
+    video_codec: VideoCodec,
+    /// The audio codec to use when performing conversions, either `aac`,
+    /// `opus`, or `copy` to keep the existing audio streams.
+    #[arg(long, default_value_t = AudioCodec::Copy)]
+    audio_codec: AudioCodec,
+    /// The constant rate factor to use when encoding video, where lower values
+    /// mean higher quality. If not set, the default of the encoder is used.
+    #[arg(long)]
+    crf: Option<u32>,
+    /// If set, re-encodes files which are already in the target format
+    /// instead of transferring them.
+    #[arg(long)]
+    reencode: bool,
+    /// Path to ffmpeg binary to use when performing conversions.
+    #[arg(long, default_value = "ffmpeg")]
+    ffmpeg_bin: PathBuf,
+    /// The extension to use for partial conversion files.
+    ///
+    /// These are used in place of the target file during conversion, and
+    /// renamed once conversion has been verified to be successful.
+    ///
+    /// If these files are encountered during future conversions, they will be
+    /// removed.
+    #[arg(long, default_value = PART)]
+    part_ext: String,
+    /// Paths to process.
+    paths: Vec<PathBuf>,
+}
+
+/// Entry for `videovert`.
+///
+/// See [`crate`] documentation.
+pub fn entry(opts: &Videovert) -> Result<()> {
+    // Current indentation level for output.
+    let indent = Cell::new(0);
+
+    let trash = match &opts.trash {
+        Some(p) => p.clone(),
+        None => trash::default_dir()?,
+    };
+
+    let mut config = Config {
+        audio_codec: opts.audio_codec,
+        conversion: opts.conversion.clone(),
+        crf: opts.crf,
+        dry_run: opts.dry_run || opts.read_only,
+        effects: Effects::new(opts.read_only).with_durable(opts.durable),
+        ffmpeg: opts.ffmpeg_bin.clone(),
+        force: opts.force,
+        part_ext: opts.part_ext.clone(),
+        paths: opts.paths.clone(),
+        r#move: opts.r#move,
+        reencode: opts.reencode,
+        to_dir: opts.to.clone(),
+        trash_source: opts.trash_source,
+        trash,
+        verbose: opts.verbose,
+        video_codec: opts.video_codec,
+    };
+
+    if config.paths.is_empty() {
+        config.paths.push(PathBuf::from("."));
+    }
+
+    if config.conversion.is_empty() {
+        config.conversion.push(Condition::FromTo {
+            from: FromCondition::Legacy,
+            to: ToCondition::Exact(Format::Mkv),
+        });
+
+        config.conversion.push(Condition::FromTo {
+            from: FromCondition::Modern,
+            to: ToCondition::Same,
+        });
+    }
+
+    let cols = Colors::new();
+
+    let o = StandardStream::stdout(ColorChoice::Auto);
+    let mut o = o.lock();
+    let mut o = Out::new(&indent, &cols, &mut o).with_timestamps(opts.timestamps);
+    run(&mut o, &config)
+}
+
+fn run(o: &mut Out<'_>, config: &Config) -> Result<()> {
+    let mut tasks = Tasks::new();
+
+    config.populate(&mut tasks)?;
+
+    for Unsupported { source, ext } in tasks.unsupported.drain(..) {
+        warn!(o, "Unsupported extension: {ext}");
+        let mut o = o.indent(1);
+        tasks.db.dump(&mut o, &source)?;
+    }
+
+    if config.verbose {
+        for Exists { source, path } in tasks.already_exists.drain(..) {
+            warn!(o, "already exists (--force to remove):");
+            let mut o = o.indent(1);
+            tasks.db.dump(&mut o, &source)?;
+            o.link("to", &path)?;
+        }
+    }
+
+    if config.verbose {
+        for MatchingConversion {
+            source,
+            from,
+            to_formats,
+        } in tasks.matching_conversions.drain(..)
+        {
+            let to_formats = to_formats
+                .iter()
+                .map(|f| f.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            info!(o, "Found matching conversions: {from} -> {to_formats}");
+            let mut o = o.indent(1);
+            tasks.db.dump(&mut o, &source)?;
+        }
+    }
+
+    let total = tasks.tasks.len();
+
+    for c in &mut tasks.tasks {
+        if c.is_completed() {
+            continue;
+        }
+
+        info!(
+            o,
+            "Task #{}/#{total}: {}",
+            c.index.saturating_add(1),
+            c.kind
+        );
+        let mut o = o.indent(1);
+
+        let span =
+            info_span!("task", index = c.index, kind = %c.kind, to = %shell::path(&c.to_path));
+        let _enter = span.enter();
+
+        tasks.db.dump(&mut o, &c.source)?;
+        o.link("to", &c.to_path)?;
+
+        for (reason, path) in c.pre_remove.drain(..) {
+            info!(o, "removing {reason}");
+            let mut o = o.indent(1);
+
+            if config.verbose {
+                blank!(o, "rm {}", shell::path(&path));
+            } else {
+                blank!(o, "rm <to>.{}", config.part_ext);
+            }
+
+            if !config.dry_run
+                && let Err(e) = config.effects.remove_file(&path)
+            {
+                error!(o, "{e}");
+            }
+        }
+
+        match c.kind {
+            TaskKind::Convert {
+                ref part_path,
+                to,
+                ref mut converted,
+                ..
+            } => {
+                if !*converted {
+                    let (argument, archive) = match &c.source {
+                        Source::File { file } => {
+                            let file = tasks.db.file(*file)?;
+                            (file.as_os_str(), None)
+                        }
+                        Source::Archive { archive, path } => {
+                            (OsStr::new("pipe:"), Some((*archive, path)))
+                        }
+                    };
+
+                    let mut command = Command::new(&config.ffmpeg);
+                    command.args(["-hide_banner", "-loglevel", "error"]);
+                    command.args([OsStr::new("-i"), argument]);
+                    command.args(["-map_metadata", "0"]);
+                    config.codecs(to, &mut command);
+                    command.args(["-f", to.ffmpeg_format()]);
+                    command.arg(part_path);
+
+                    debug!(command = %FormatCommand::new(&command), "converting");
+
+                    let mut f = FormatCommand::new(&command);
+
+                    if !config.verbose {
+                        f.replace(config.ffmpeg.as_os_str(), "<ffmpeg>");
+
+                        if archive.is_none() {
+                            f.replace(argument, "<from>");
+                        }
+
+                        f.replace(part_path.as_os_str(), format!("<to>.{}", config.part_ext));
+                    }
+
+                    if !config.make_dir(&mut o, "partial", part_path)? {
+                        continue;
+                    }
+
+                    {
+                        blank!(o, "{f}");
+                        let mut o = o.indent(1);
+
+                        if !config.dry_run {
+                            if let Some((archive, path)) = archive {
+                                command.stdin(Stdio::piped());
+
+                                let status = match write_source_to_stdin(
+                                    &mut command,
+                                    &config.effects,
+                                    &tasks.db,
+                                    archive,
+                                    path,
+                                ) {
+                                    Ok(status) => status,
+                                    Err(e) => {
+                                        error!(o, "{e}");
+                                        continue;
+                                    }
+                                };
+
+                                debug!(%status, "conversion finished");
+                                *converted = status.success();
+                            } else {
+                                let status = match config.effects.status(&mut command) {
+                                    Ok(s) => s,
+                                    Err(e) => {
+                                        error!(o, "{e}");
+                                        continue;
+                                    }
+                                };
+
+                                debug!(%status, "conversion finished");
+                                *converted = status.success();
+                            }
+                        } else {
+                            *converted = true;
+                        }
+                    }
+
+                    if *converted && !c.moved {
+                        if !config.make_dir(&mut o, "rename", &c.to_path)? {
+                            continue;
+                        }
+
+                        blank!(o, "mv <to>.{} <to>", config.part_ext);
+                        let mut o = o.indent(1);
+
+                        if config.verbose {
+                            o.link("from", part_path)?;
+                            o.link("to", &c.to_path)?;
+                        }
+
+                        debug!(from = %shell::path(part_path), "renaming partial file");
+
+                        if !config.dry_run {
+                            if let Err(e) = config.effects.rename(part_path, &c.to_path) {
+                                error!(o, "{e}");
+                            } else {
+                                c.moved = true;
+                            }
+                        } else {
+                            c.moved = true;
+                        }
+                    }
+                }
+            }
+            TaskKind::Transfer { kind } => {
+                if !c.moved {
+                    if !config.make_dir(&mut o, kind, &c.to_path)? {
+                        continue;
+                    }
+
+                    if config.verbose {
+                        tasks.db.dump(&mut o, &c.source)?;
+                        o.link("to", &c.to_path)?;
+                    } else {
+                        blank!(o, "{} <from> <to>", kind.symbolic_command());
+                    }
+
+                    debug!(kind = kind.symbolic_command(), "transferring");
+
+                    if !config.dry_run {
+                        let result = tasks
+                            .db
+                            .move_to(&config.effects, &c.source, &c.to_path, kind);
+
+                        if let Err(e) = result {
+                            error!(o, "{e}");
+                        } else {
+                            c.moved = true;
+                        }
+                    } else {
+                        c.moved = true;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut n = 0u32;
+
+    for c in tasks.tasks.iter().filter(|c| c.is_completed()) {
+        if !config.trash_source {
+            continue;
+        }
+
+        // NB: Trashing is meaningless for moved files.
+        if matches!(
+            c.kind,
+            TaskKind::Transfer {
+                kind: TransferKind::Move
+            }
+        ) {
+            continue;
+        }
+
+        let path = match &c.source {
+            Source::Archive { .. } => continue,
+            Source::File { file } => tasks.db.file(*file)?,
+        };
+
+        let new;
+
+        let file_name = match path.file_name() {
+            Some(name) => name,
+            None => {
+                new = OsString::from(format!("file{}", n));
+                n += 1;
+                &new
+            }
+        };
+
+        tasks.to_trash.push(Trash {
+            what: TrashWhat::SourceFile,
+            path: path.clone(),
+            name: file_name.to_owned(),
+        });
+    }
+
+    // Ensure trash directory exists.
+    if !tasks.to_trash.is_empty() && !config.trash.is_dir() {
+        info!(o, "Creating trash directory");
+
+        let mut o = o.indent(1);
+
+        blank!(o, "path: {}", shell::path(&config.trash));
+
+        if !config.dry_run
+            && let Err(e) = config.effects.create_dir_all(&config.trash)
+        {
+            error!(o, "{e}");
+        }
+    }
+
+    let mut check_empty = Vec::new();
+
+    // Move files to trash.
+    for Trash { what, path, name } in tasks.to_trash.drain(..) {
+        let trash_path = MaybeLink::new(config.trash.join(&name));
+
+        info!(o, "Trashing {what}");
+        let mut o = o.indent(1);
+        o.link("from", &path)?;
+        o.link("to", &trash_path)?;
+
+        if !config.dry_run
+            && let Err(e) = config.effects.rename(&path, &trash_path)
+        {
+            error!(o, "{e}");
+
+            if let Some(path) = path.parent() {
+                check_empty.push(path.to_path_buf());
+            }
+        }
+    }
+
+    // Recursively check for empty directories and remove them.
+    for mut path in check_empty {
+        if !trash::is_empty_dir(&path) {
+            continue;
+        }
+
+        info!(o, "removing empty directory:");
+        let mut o = o.indent(1);
+        blank!(o, "path: {}", shell::path(&path));
+
+        if !config.dry_run {
+            if let Err(e) = config.effects.remove_dir(&path) {
+                error!(o, "{e}");
+            }
+
+            path.pop();
+        } else {
+            continue;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_source_to_stdin(
+    command: &mut Command,
+    effects: &Effects,
+    archives: &Db,
+    archive: ArchiveId,
+    path: &RelativePath,
+) -> Result<ExitStatus> {
+    let mut child = effects.spawn(command).context("spawning process")?;
+    let contents = archives
+        .archive_contents(archive, path)
+        .context("reading source contents")?;
+    let mut stdin = child.stdin.take().context("missing stdin")?;
+    stdin.write_all(&contents).context("writing to stdin")?;
+    stdin.flush().context("flushing stdin")?;
+    drop(stdin);
+    child.wait().context("waiting for process")
+}
@@ -0,0 +1,109 @@
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+
+#[derive(Debug)]
+pub(crate) struct CodecErr;
+
+impl fmt::Display for CodecErr {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unsupported codec")
+    }
+}
+
+impl Error for CodecErr {}
+
+/// The codec used for video streams when converting.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum VideoCodec {
+    /// Copy the video stream as-is.
+    Copy,
+    H264,
+    H265,
+    Av1,
+}
+
+impl VideoCodec {
+    /// The ffmpeg encoder used for the codec.
+    pub(crate) fn encoder(&self) -> &'static str {
+        match self {
+            VideoCodec::Copy => "copy",
+            VideoCodec::H264 => "libx264",
+            VideoCodec::H265 => "libx265",
+            VideoCodec::Av1 => "libsvtav1",
+        }
+    }
+}
+
+impl fmt::Display for VideoCodec {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VideoCodec::Copy => write!(f, "copy"),
+            VideoCodec::H264 => write!(f, "h264"),
+            VideoCodec::H265 => write!(f, "h265"),
+            VideoCodec::Av1 => write!(f, "av1"),
+        }
+    }
+}
+
+impl FromStr for VideoCodec {
+    type Err = CodecErr;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "copy" => Ok(VideoCodec::Copy),
+            "h264" => Ok(VideoCodec::H264),
+            "h265" | "hevc" => Ok(VideoCodec::H265),
+            "av1" => Ok(VideoCodec::Av1),
+            _ => Err(CodecErr),
+        }
+    }
+}
+
+/// The codec used for audio streams when converting.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum AudioCodec {
+    /// Copy the audio stream as-is.
+    Copy,
+    Aac,
+    Opus,
+}
+
+impl AudioCodec {
+    /// The ffmpeg encoder used for the codec.
+    pub(crate) fn encoder(&self) -> &'static str {
+        match self {
+            AudioCodec::Copy => "copy",
+            AudioCodec::Aac => "aac",
+            AudioCodec::Opus => "libopus",
+        }
+    }
+}
+
+impl fmt::Display for AudioCodec {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AudioCodec::Copy => write!(f, "copy"),
+            AudioCodec::Aac => write!(f, "aac"),
+            AudioCodec::Opus => write!(f, "opus"),
+        }
+    }
+}
+
+impl FromStr for AudioCodec {
+    type Err = CodecErr;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "copy" => Ok(AudioCodec::Copy),
+            "aac" => Ok(AudioCodec::Aac),
+            "opus" => Ok(AudioCodec::Opus),
+            _ => Err(CodecErr),
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+
+#[derive(Debug)]
+pub(crate) struct FormatErr;
+
+impl fmt::Display for FormatErr {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unsupported format")
+    }
+}
+
+impl Error for FormatErr {}
+
+/// A video container format.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) enum Format {
+    Avi,
+    Flv,
+    Mkv,
+    Mov,
+    Mp4,
+    Webm,
+    Wmv,
+}
+
+impl Format {
+    /// Legacy formats are old container formats which are poorly supported by
+    /// modern players and typically contain outdated codecs.
+    pub(crate) fn is_legacy(&self) -> bool {
+        matches!(self, Format::Avi | Format::Flv | Format::Wmv)
+    }
+
+    /// Whether the container can hold any kind of stream, in which case all
+    /// streams including subtitles are preserved during conversion.
+    pub(crate) fn keeps_all_streams(&self) -> bool {
+        matches!(self, Format::Mkv)
+    }
+
+    pub(crate) fn ext(&self) -> &'static str {
+        match self {
+            Format::Avi => "avi",
+            Format::Flv => "flv",
+            Format::Mkv => "mkv",
+            Format::Mov => "mov",
+            Format::Mp4 => "mp4",
+            Format::Webm => "webm",
+            Format::Wmv => "wmv",
+        }
+    }
+
+    pub(crate) fn ffmpeg_format(&self) -> &'static str {
+        match self {
+            Format::Avi => "avi",
+            Format::Flv => "flv",
+            Format::Mkv => "matroska",
+            Format::Mov => "mov",
+            Format::Mp4 => "mp4",
+            Format::Webm => "webm",
+            Format::Wmv => "asf",
+        }
+    }
+
+    pub(crate) fn from_ext(ext: &str) -> Option<Format> {
+        match ext {
+            "avi" => Some(Format::Avi),
+            "flv" => Some(Format::Flv),
+            "mkv" => Some(Format::Mkv),
+            "mov" => Some(Format::Mov),
+            "mp4" | "m4v" => Some(Format::Mp4),
+            "webm" => Some(Format::Webm),
+            "wmv" => Some(Format::Wmv),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.ext().fmt(f)
+    }
+}
+
+impl FromStr for Format {
+    type Err = FormatErr;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_ext(s).ok_or(FormatErr)
+    }
+}
@@ -0,0 +1,28 @@
+//! A tool to perform batch conversion of video.
+//!
+//! See [`videovert`] documentation for more information.
+//!
+//! [`videovert`]: https://crates.io/crates/videovert
+
+use anyhow::Result;
+use clap::Parser;
+use mediavert_core::log::Verbosity;
+
+const VERSION: &str = match option_env!("MEDIAVERT_VERSION") {
+    Some(v) => v,
+    None => env!("CARGO_PKG_VERSION"),
+};
+
+/// A tool to perform batch conversion of video.
+#[derive(Parser)]
+#[command(author, version, about, max_term_width = 80, version = VERSION)]
+pub struct Opts {
+    #[command(flatten)]
+    inner: videovert::cli::Videovert,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    mediavert_core::log::init(Verbosity::default());
+    videovert::cli::entry(&opts.inner)
+}